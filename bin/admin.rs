@@ -0,0 +1,575 @@
+use anyhow::{Result, anyhow, bail};
+use clap::{Args, Parser, Subcommand};
+use dotenvy::dotenv;
+use pico_proving_service::{
+    CancelTaskRequest, DrainQueueRequest, EventKind, GetAppRequest, GetGpuStatsRequest,
+    GetQuotaRequest, GetUsageRequest, ListAppsRequest, ListDeadLettersRequest, ListTasksRequest,
+    ListWorkersRequest, RequeueDeadLetterRequest, SubscribeEventsRequest, TaskKey, TaskState,
+    client::{NAMESPACE_METADATA_KEY, connect_channel},
+    config,
+    error::ServiceError,
+    prover_network_client::ProverNetworkClient,
+    service_event::Event,
+    utils::{auth::ApiKeyInterceptor, tls::TlsConfig},
+};
+use std::{path::PathBuf, time::Duration};
+use tokio::time::sleep;
+use tonic::{Request, Response, Status, metadata::AsciiMetadataValue, service::Interceptor};
+
+// operator CLI of the service, the results are printed as the tab separated rows for the shell
+// tools instead of the logs of test-client
+#[derive(Parser)]
+#[clap(name = "pico-prover-admin", about = "Operate the pico proving service")]
+struct Cli {
+    #[clap(
+        long,
+        env = "CONFIG_FILE",
+        help = "TOML config file, overridden by the flags and env"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "GRPC_ADDR",
+        default_value = "http://[::]:50052",
+        help = "gRPC address of the service"
+    )]
+    pub grpc_addr: String,
+
+    #[clap(
+        long,
+        env = "API_KEY",
+        help = "API key if the server authenticates by API keys"
+    )]
+    pub api_key: Option<String>,
+
+    #[clap(
+        long,
+        env = "BEARER_TOKEN",
+        help = "Bearer token or admin JWT if the server authenticates by bearer tokens or JWTs"
+    )]
+    pub bearer_token: Option<String>,
+
+    #[clap(
+        long,
+        env = "NAMESPACE",
+        help = "Namespace of the apps and tasks (default: the one bound to the JWT or default)"
+    )]
+    pub namespace: Option<String>,
+
+    #[clap(flatten)]
+    pub tls: TlsConfig,
+
+    #[command(subcommand)]
+    pub cmd: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "List the tracked proving tasks")]
+    Tasks(TasksCommand),
+
+    #[command(about = "Cancel a queued or in-progress proving task")]
+    Cancel(TaskCommand),
+
+    #[command(about = "List the registered applications")]
+    Apps,
+
+    #[command(about = "Show the metadata of a registered application")]
+    App {
+        #[arg(long, help = "Application ID")]
+        app_id: String,
+    },
+
+    #[command(about = "Show the remote workers in coordinator mode")]
+    Workers,
+
+    #[command(about = "Show the stats of the GPU devices")]
+    Gpus,
+
+    #[command(about = "Stop accepting the new tasks and wait for the queue to drain")]
+    Drain(DrainCommand),
+
+    #[command(about = "Dump the usage of the apps in a time window")]
+    Usage(UsageCommand),
+
+    #[command(about = "Show the quotas of an application with their usage")]
+    Quota {
+        #[arg(long, help = "Application ID")]
+        app_id: String,
+    },
+
+    #[command(about = "List the permanently failed tasks")]
+    DeadLetters {
+        #[arg(long, help = "Filter by application ID")]
+        app_id: Option<String>,
+    },
+
+    #[command(about = "Queue a permanently failed task again")]
+    Requeue(TaskCommand),
+
+    #[command(about = "Follow the task, worker and GC events until interrupted")]
+    Events {
+        #[arg(long, help = "Filter the task events by application ID")]
+        app_id: Option<String>,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Kinds of the events (task_state, worker_health, task_collected), all if not set"
+        )]
+        kinds: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct TasksCommand {
+    #[arg(long, help = "Filter by application ID")]
+    app_id: Option<String>,
+
+    #[arg(
+        long,
+        help = "Filter by state (queued, executing, proving, completed, failed, cancelled)"
+    )]
+    state: Option<String>,
+
+    #[arg(
+        long,
+        help = "Filter by unix timestamp (seconds) of queueing, inclusive"
+    )]
+    created_after: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Filter by unix timestamp (seconds) of queueing, exclusive"
+    )]
+    created_before: Option<u64>,
+}
+
+#[derive(Args)]
+struct TaskCommand {
+    #[arg(long, help = "Application ID")]
+    app_id: String,
+
+    #[arg(long, help = "Task ID")]
+    task_id: String,
+}
+
+#[derive(Args)]
+struct DrainCommand {
+    #[arg(long, help = "Accept the new tasks again instead of draining")]
+    resume: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "resume",
+        help = "Wait until the queued and active tasks finish"
+    )]
+    wait: bool,
+
+    #[arg(
+        long,
+        default_value = "10",
+        help = "Interval (seconds) to check the queue while waiting"
+    )]
+    interval_secs: u64,
+}
+
+#[derive(Args)]
+struct UsageCommand {
+    #[arg(long, help = "Filter by application ID")]
+    app_id: Option<String>,
+
+    #[arg(long, help = "Filter by signer of the signed requests")]
+    signer: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Unix timestamp (seconds) of the window start, inclusive"
+    )]
+    start_time: u64,
+
+    #[arg(
+        long,
+        help = "Unix timestamp (seconds) of the window end, exclusive (default: now)"
+    )]
+    end_time: Option<u64>,
+}
+
+// the failed status is printed as the typed error of the service
+fn check<T>(res: Result<Response<T>, Status>) -> Result<T> {
+    res.map(Response::into_inner)
+        .map_err(|status| ServiceError::from(status).into())
+}
+
+fn task_name(task: &TaskKey) -> String {
+    format!("{}/{}/{}", task.namespace, task.app_id, task.task_id)
+}
+
+fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+
+    let cli: Cli = config::file::parse()?;
+    let channel = connect_channel(cli.grpc_addr, cli.tls.client_tls_config()?).await?;
+    let mut api_key = ApiKeyInterceptor::new(cli.api_key.as_deref())?;
+    let bearer_token: Option<AsciiMetadataValue> = cli
+        .bearer_token
+        .map(|token| format!("Bearer {token}").parse())
+        .transpose()?;
+    let namespace: Option<AsciiMetadataValue> = cli
+        .namespace
+        .map(|namespace| namespace.parse())
+        .transpose()?;
+    let interceptor = move |mut req: Request<()>| {
+        if let Some(token) = &bearer_token {
+            req.metadata_mut().insert("authorization", token.clone());
+        }
+        if let Some(namespace) = &namespace {
+            req.metadata_mut()
+                .insert(NAMESPACE_METADATA_KEY, namespace.clone());
+        }
+        api_key.call(req)
+    };
+    let mut client = ProverNetworkClient::with_interceptor(channel, interceptor);
+
+    match cli.cmd {
+        Command::Tasks(cmd) => {
+            let state = cmd
+                .state
+                .map(|state| {
+                    TaskState::from_str_name(&state.to_uppercase())
+                        .ok_or_else(|| anyhow!("invalid task state {state}"))
+                })
+                .transpose()?;
+            println!("APP_ID\tTASK_ID\tSTATE\tCREATED_AT\tSTARTED_AT\tFINISHED_AT\tFAILURE_REASON");
+            let mut page_token = String::new();
+            loop {
+                let req = ListTasksRequest {
+                    app_id: cmd.app_id.clone(),
+                    state: state.map(Into::into),
+                    created_after: cmd.created_after,
+                    created_before: cmd.created_before,
+                    page_size: 0,
+                    page_token,
+                };
+                let res = check(client.list_tasks(req).await)?;
+                for task in &res.tasks {
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                        task.app_id,
+                        task.task_id,
+                        task.state().as_str_name(),
+                        task.created_at,
+                        opt(task.started_at),
+                        opt(task.finished_at),
+                        opt(task.failure_reason.as_deref()),
+                    );
+                }
+                if res.next_page_token.is_empty() {
+                    break;
+                }
+                page_token = res.next_page_token;
+            }
+        }
+        Command::Cancel(cmd) => {
+            let req = CancelTaskRequest {
+                app_id: cmd.app_id.clone(),
+                task_id: cmd.task_id.clone(),
+            };
+            check(client.cancel_task(req).await)?;
+            println!("cancelled task {} of app {}", cmd.task_id, cmd.app_id);
+        }
+        Command::Apps => {
+            println!("APP_ID\tELF_HASH\tCREATED_AT\tINFO");
+            let mut page_token = String::new();
+            loop {
+                let req = ListAppsRequest {
+                    page_token,
+                    ..Default::default()
+                };
+                let res = check(client.list_apps(req).await)?;
+                for app in &res.apps {
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        app.app_id,
+                        app.elf_hash,
+                        app.created_at,
+                        opt(app.info.as_deref()),
+                    );
+                }
+                if res.next_page_token.is_empty() {
+                    break;
+                }
+                page_token = res.next_page_token;
+            }
+        }
+        Command::App { app_id } => {
+            let res = check(client.get_app(GetAppRequest { app_id }).await)?;
+            let Some(app) = res.app else {
+                bail!("no app metadata returned");
+            };
+            println!("app_id\t{}", app.app_id);
+            println!("elf_hash\t{}", app.elf_hash);
+            println!("created_at\t{}", app.created_at);
+            println!("info\t{}", opt(app.info.as_deref()));
+            if let Some(info) = app.app_info {
+                println!("app_info\t{info:?}");
+            }
+            if let Some(warm_up) = res.warm_up {
+                println!("warm_up\t{:?}", warm_up.state());
+                if let Some(reason) = warm_up.failure_reason {
+                    println!("warm_up_failure\t{reason}");
+                }
+            }
+        }
+        Command::Workers => {
+            let res = check(client.list_workers(ListWorkersRequest {}).await)?;
+            println!("WORKER_ID\tHEARTBEAT_AGE_SECS\tTASKS");
+            for worker in &res.workers {
+                let tasks: Vec<_> = worker.tasks.iter().map(task_name).collect();
+                println!(
+                    "{}\t{}\t{}",
+                    worker.worker_id,
+                    worker.heartbeat_age_secs,
+                    tasks.join(",")
+                );
+            }
+        }
+        Command::Gpus => {
+            let res = check(client.get_gpu_stats(GetGpuStatsRequest {}).await)?;
+            println!(
+                "INDEX\tNAME\tMEMORY_USED\tMEMORY_TOTAL\tUTILIZATION\tHEALTHY\tRUNNING\tQUEUED\tFAILURES\tQUARANTINED_UNTIL"
+            );
+            for device in &res.devices {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    device.index,
+                    device.name,
+                    device.memory_used,
+                    device.memory_total,
+                    device.utilization,
+                    device.healthy,
+                    device.running_tasks,
+                    device.queued_tasks,
+                    device.consecutive_failures,
+                    device
+                        .quarantined_until
+                        .map_or("-".to_string(), |until| until.to_string()),
+                );
+            }
+            println!("cpu_fallbacks={}", res.cpu_fallbacks);
+        }
+        Command::Drain(cmd) => loop {
+            let req = DrainQueueRequest { resume: cmd.resume };
+            let res = check(client.drain_queue(req).await)?;
+            println!(
+                "draining={}, queued_tasks={}, active_tasks={}",
+                res.draining, res.queued_tasks, res.active_tasks
+            );
+            if !cmd.wait || res.queued_tasks + res.active_tasks == 0 {
+                break;
+            }
+            sleep(Duration::from_secs(cmd.interval_secs)).await;
+        },
+        Command::Usage(cmd) => {
+            let req = GetUsageRequest {
+                app_id: cmd.app_id,
+                signer: cmd.signer,
+                start_time: cmd.start_time,
+                end_time: cmd.end_time,
+            };
+            let res = check(client.get_usage(req).await)?;
+            println!("APP_ID\tTASKS\tCYCLES\tCHUNKS\tGPU_SECONDS\tSTORED_BYTES");
+            // the total is the row of the empty app ID
+            for app in res.apps.iter().chain(res.total.as_ref()) {
+                println!(
+                    "{}\t{}\t{}\t{}\t{:.1}\t{}",
+                    if app.app_id.is_empty() {
+                        "TOTAL"
+                    } else {
+                        &app.app_id
+                    },
+                    app.tasks,
+                    app.cycles,
+                    app.chunks,
+                    app.gpu_seconds,
+                    app.stored_bytes,
+                );
+            }
+        }
+        Command::Quota { app_id } => {
+            let res = check(client.get_quota(GetQuotaRequest { app_id }).await)?;
+            println!("QUOTA\tLIMIT\tUSED\tREMAINING");
+            for (name, usage) in [
+                ("daily_cycles", res.daily_cycles),
+                ("stored_bytes", res.stored_bytes),
+                ("concurrent_tasks", res.concurrent_tasks),
+            ] {
+                let usage = usage.unwrap_or_default();
+                println!(
+                    "{name}\t{}\t{}\t{}",
+                    opt(usage.limit),
+                    usage.used,
+                    opt(usage.remaining)
+                );
+            }
+            println!("daily cycles reset at {}", res.daily_resets_at);
+        }
+        Command::DeadLetters { app_id } => {
+            println!("APP_ID\tTASK_ID\tATTEMPTS\tFAILED_AT\tFAILURE_REASON");
+            let mut page_token = String::new();
+            loop {
+                let req = ListDeadLettersRequest {
+                    app_id: app_id.clone(),
+                    page_size: 0,
+                    page_token,
+                };
+                let res = check(client.list_dead_letters(req).await)?;
+                for dead_letter in &res.dead_letters {
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}",
+                        dead_letter.app_id,
+                        dead_letter.task_id,
+                        dead_letter.attempts,
+                        dead_letter.failed_at,
+                        dead_letter.failure_reason,
+                    );
+                }
+                if res.next_page_token.is_empty() {
+                    break;
+                }
+                page_token = res.next_page_token;
+            }
+        }
+        Command::Requeue(cmd) => {
+            let req = RequeueDeadLetterRequest {
+                app_id: cmd.app_id.clone(),
+                task_id: cmd.task_id.clone(),
+            };
+            check(client.requeue_dead_letter(req).await)?;
+            println!("requeued task {} of app {}", cmd.task_id, cmd.app_id);
+        }
+        Command::Events { app_id, kinds } => {
+            let kinds = kinds
+                .iter()
+                .map(|kind| {
+                    EventKind::from_str_name(&kind.to_uppercase())
+                        .map(Into::into)
+                        .ok_or_else(|| anyhow!("invalid event kind {kind}"))
+                })
+                .collect::<Result<_>>()?;
+            let mut events = check(
+                client
+                    .subscribe_events(SubscribeEventsRequest { app_id, kinds })
+                    .await,
+            )?;
+            println!("TIMESTAMP\tKIND\tSUBJECT\tDETAIL");
+            while let Some(event) = events.message().await.map_err(ServiceError::from)? {
+                let (kind, subject, detail) = match event.event {
+                    Some(Event::TaskState(event)) => (
+                        EventKind::TaskState,
+                        event.task.as_ref().map(task_name),
+                        format!(
+                            "{} {}",
+                            event.state().as_str_name(),
+                            event.failure_reason.as_deref().unwrap_or_default()
+                        ),
+                    ),
+                    Some(Event::WorkerHealth(event)) => (
+                        EventKind::WorkerHealth,
+                        Some(event.worker_id),
+                        if event.alive { "ALIVE" } else { "LAPSED" }.to_string(),
+                    ),
+                    Some(Event::TaskCollected(event)) => (
+                        EventKind::TaskCollected,
+                        event.task.as_ref().map(task_name),
+                        String::new(),
+                    ),
+                    None => continue,
+                };
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    event.timestamp,
+                    kind.as_str_name(),
+                    opt(subject),
+                    detail.trim_end(),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(["pico-prover-admin"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_tasks() {
+        let cli = parse(&[
+            "--namespace",
+            "tenant",
+            "tasks",
+            "--app-id",
+            "0xabc",
+            "--state",
+            "failed",
+            "--created-after",
+            "100",
+        ])
+        .unwrap();
+        assert_eq!(cli.namespace.as_deref(), Some("tenant"));
+        let Command::Tasks(cmd) = cli.cmd else {
+            panic!("expected the tasks command");
+        };
+        assert_eq!(cmd.app_id.as_deref(), Some("0xabc"));
+        assert_eq!(cmd.state.as_deref(), Some("failed"));
+        assert_eq!(cmd.created_after, Some(100));
+        assert_eq!(cmd.created_before, None);
+    }
+
+    #[test]
+    fn test_parse_drain() {
+        let Command::Drain(cmd) = parse(&["drain", "--wait"]).unwrap().cmd else {
+            panic!("expected the drain command");
+        };
+        assert!(cmd.wait);
+        assert!(!cmd.resume);
+        assert_eq!(cmd.interval_secs, 10);
+
+        // resuming never waits for the queue
+        assert!(parse(&["drain", "--resume", "--wait"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let Command::Events { app_id, kinds } =
+            parse(&["events", "--kinds", "task_state,worker_health"])
+                .unwrap()
+                .cmd
+        else {
+            panic!("expected the events command");
+        };
+        assert_eq!(app_id, None);
+        assert_eq!(kinds, vec!["task_state", "worker_health"]);
+
+        assert!(parse(&["cancel", "--app-id", "0xabc"]).is_err());
+    }
+}
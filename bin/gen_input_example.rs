@@ -1,11 +1,6 @@
-use pico_vm::{
-    compiler::riscv::program::Program, configs::stark_config::KoalaBearPoseidon2,
-    emulator::stdin::EmulatorStdin,
-};
+use pico_proving_service::types::build_inputs;
 use std::fs;
 
-type SC = KoalaBearPoseidon2;
-
 fn main() {
     // generate and dump a boolean input
     generate_bool_input();
@@ -21,60 +16,36 @@ fn main() {
 }
 
 fn generate_bool_input() {
-    // initialize a stdin builder
-    let mut stdin_builder = EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>();
-
-    // save a boolean to stdin
+    // save a boolean to stdin and serialize it
     let arg = false;
-    stdin_builder.write(&arg);
-
-    // serialize and dump the serialized stdin builder
-    let input = bincode::serialize(&stdin_builder).unwrap();
+    let input = build_inputs(&[arg]).unwrap();
     fs::write("bool_input.bin", &input).unwrap();
 
     println!("Generated bool input: 0x{}", hex::encode(input));
 }
 
 fn generate_u32_input() {
-    // initialize a stdin builder
-    let mut stdin_builder = EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>();
-
-    // save an unsigned integer to stdin
+    // save an unsigned integer to stdin and serialize it
     let arg = 100_u32;
-    stdin_builder.write(&arg);
-
-    // serialize and dump the serialized stdin builder
-    let input = bincode::serialize(&stdin_builder).unwrap();
+    let input = build_inputs(&[arg]).unwrap();
     fs::write("u32_input.bin", &input).unwrap();
 
     println!("Generated u32 input: 0x{}", hex::encode(input));
 }
 
 fn generate_string_input() {
-    // initialize a stdin builder
-    let mut stdin_builder = EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>();
-
-    // save a string to stdin
+    // save a string to stdin and serialize it
     let arg = "dummy string";
-    stdin_builder.write(&arg);
-
-    // serialize and dump the serialized stdin builder
-    let input = bincode::serialize(&stdin_builder).unwrap();
+    let input = build_inputs(&[arg]).unwrap();
     fs::write("str_input.bin", &input).unwrap();
 
     println!("Generated string input: 0x{}", hex::encode(input));
 }
 
 fn generate_bytes_input() {
-    // initialize a stdin builder
-    let mut stdin_builder = EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>();
-
-    // save bytes to stdin
+    // save bytes to stdin and serialize it
     let arg = vec![1, 2, 3, 4];
-    stdin_builder.write(&arg);
-
-    // serialize and dump the serialized stdin builder
-    let input = bincode::serialize(&stdin_builder).unwrap();
+    let input = build_inputs(&[arg]).unwrap();
     fs::write("bytes_input.bin", &input).unwrap();
 
     println!("Generated bytes input: 0x{}", hex::encode(input));
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use clap::Parser;
+use dotenvy::dotenv;
+use pico_proving_service::utils::jwt::JwtAuthority;
+use pico_vm::machine::logger::setup_logger;
+use std::time::Duration;
+use tracing::info;
+
+// mint a token offline, e.g. the first admin token to issue the others by IssueToken
+#[derive(Parser)]
+struct Cli {
+    #[arg(long, env = "JWT_SECRET", help = "HMAC secret of the server")]
+    secret: String,
+
+    #[arg(long, help = "Subject of the token")]
+    subject: String,
+
+    #[arg(
+        long,
+        help = "Namespace bound to the token, all the namespaces if not set"
+    )]
+    namespace: Option<String>,
+
+    #[arg(
+        long = "scope",
+        required = true,
+        help = "Scope as ACTION or ACTION:APP_ID, repeatable"
+    )]
+    scopes: Vec<String>,
+
+    #[arg(
+        long,
+        default_value = "2592000",
+        help = "Lifetime of the token in seconds"
+    )]
+    ttl_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    setup_logger();
+
+    let cli = Cli::parse();
+    let authority = JwtAuthority::new(cli.secret.as_bytes());
+    let (token, claims) = authority.issue(
+        &cli.subject,
+        cli.namespace,
+        cli.scopes,
+        Duration::from_secs(cli.ttl_secs),
+    )?;
+
+    info!("Generated token {} expiring at {}", claims.jti, claims.exp);
+    println!("{token}");
+
+    Ok(())
+}
@@ -1,27 +1,56 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use dotenvy::dotenv;
-use pico_proving_service::{app_manager::App, cost_estimation::estimate_cost, types::SC};
-use pico_vm::{
-    compiler::riscv::program::Program, emulator::stdin::EmulatorStdin,
-    machine::logger::setup_logger,
+use futures::{StreamExt, stream};
+use pico_proving_service::{
+    app_manager::App, config, cost_estimation::estimate_cost, metrics, types::build_inputs,
 };
+use pico_vm::machine::logger::setup_logger;
+use prometheus::{Histogram, HistogramOpts, IntCounter, IntGauge, Registry, exponential_buckets};
 use rsp_host_executor::EthHostExecutor;
 use rsp_primitives::{chain_spec, genesis::Genesis};
 use rsp_provider::create_provider;
+use serde::Serialize;
 use std::{
     fs,
+    io::ErrorKind,
+    net::SocketAddr,
     path::{Path, PathBuf},
 };
+use tokio::signal::ctrl_c;
+use tracing::{info, warn};
 use url::Url;
 
 #[derive(Parser)]
 struct Cli {
+    #[clap(
+        long,
+        env = "CONFIG_FILE",
+        help = "TOML config file, overridden by the flags and env"
+    )]
+    config: Option<PathBuf>,
+
     #[arg(
         long,
+        required_unless_present = "start_block",
         help = "Block number to generate reth inputs and public values digest"
     )]
-    block_number: u64,
+    block_number: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with = "block_number",
+        requires = "end_block",
+        help = "First block number of the range to generate sequentially, e.g. for a backfill"
+    )]
+    start_block: Option<u64>,
+
+    #[arg(
+        long,
+        requires = "start_block",
+        help = "Last block number (inclusive) of the range to generate"
+    )]
+    end_block: Option<u64>,
 
     #[arg(long, default_value = "fixtures/reth-elf", help = "reth ELF file path")]
     elf: PathBuf,
@@ -34,8 +63,94 @@ reth_input_BLOCK_NUMBER.bin and reth_pv_digest_BLOCK_NUMBER.bin"
     )]
     dump_dir: PathBuf,
 
+    #[clap(
+        long,
+        help = "Directory for saving the opcode tracking output of the blocks: \
+reth_opcode_tracking_BLOCK_NUMBER.json, the dump dir if not set"
+    )]
+    cache_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        conflicts_with = "opcode_tracking",
+        help = "Dump the reth inputs without emulating, the public values digest is not generated"
+    )]
+    skip_client_execution: bool,
+
+    #[clap(
+        long,
+        help = "Track the cycles by opcodes in the client execution for cycle profiling"
+    )]
+    opcode_tracking: bool,
+
+    #[clap(
+        long,
+        help = "File recording the last generated block, the range resumes after it if it exists"
+    )]
+    checkpoint: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "Maximum blocks of which the inputs are fetched concurrently, they're dumped in order"
+    )]
+    max_in_flight: usize,
+
     #[clap(long, env = "PICO_RPC_URL", help = "HTTP RPC URL")]
     rpc_url: Url,
+
+    #[clap(
+        long,
+        env = "METRICS_ADDR",
+        help = "HTTP listen address of the Prometheus metrics endpoint /metrics, disabled if not set"
+    )]
+    metrics_addr: Option<SocketAddr>,
+}
+
+// metrics of the generation for the long runs, e.g. the soak tests
+struct GenMetrics {
+    registry: Registry,
+    // blocks of which the inputs are being fetched
+    blocks_seen: IntCounter,
+    // blocks of which the inputs are emulated and dumped
+    inputs_generated: IntCounter,
+    // size of the serialized inputs of a block
+    inputs_bytes: Histogram,
+    // last dumped block
+    last_block: IntGauge,
+}
+
+impl GenMetrics {
+    fn new() -> prometheus::Result<Self> {
+        let metrics = Self {
+            registry: Registry::new(),
+            blocks_seen: IntCounter::new(
+                "pico_reth_blocks_seen_total",
+                "Blocks of which the reth inputs are fetched",
+            )?,
+            inputs_generated: IntCounter::new(
+                "pico_reth_inputs_generated_total",
+                "Blocks of which the reth inputs are emulated and dumped",
+            )?,
+            // from 64KiB to 1GiB
+            inputs_bytes: Histogram::with_opts(
+                HistogramOpts::new(
+                    "pico_reth_inputs_bytes",
+                    "Size of the serialized reth inputs of a block",
+                )
+                .buckets(exponential_buckets(65536.0, 2.0, 15)?),
+            )?,
+            last_block: IntGauge::new("pico_reth_last_block", "Last block of the dumped inputs")?,
+        };
+
+        let registry = &metrics.registry;
+        registry.register(Box::new(metrics.blocks_seen.clone()))?;
+        registry.register(Box::new(metrics.inputs_generated.clone()))?;
+        registry.register(Box::new(metrics.inputs_bytes.clone()))?;
+        registry.register(Box::new(metrics.last_block.clone()))?;
+
+        Ok(metrics)
+    }
 }
 
 #[tokio::main]
@@ -45,33 +160,193 @@ async fn main() -> Result<()> {
     setup_logger();
 
     // parse cli
-    let cli = Cli::parse();
-    let block_number = cli.block_number;
-    let elf = cli.elf;
+    let cli: Cli = config::file::parse()?;
+    let mut blocks = match (cli.block_number, cli.start_block, cli.end_block) {
+        (Some(block_number), _, _) => block_number..=block_number,
+        (None, Some(start_block), Some(end_block)) => start_block..=end_block,
+        _ => bail!("either --block-number or --start-block and --end-block is required"),
+    };
+    if blocks.is_empty() {
+        bail!("--end-block must not be less than --start-block");
+    }
+    if let Some(last) = cli
+        .checkpoint
+        .as_deref()
+        .map(read_checkpoint)
+        .transpose()?
+        .flatten()
+    {
+        info!("resuming after block {last} of the checkpoint");
+        let next = last
+            .checked_add(1)
+            .with_context(|| format!("no block after block {last} of the checkpoint"))?;
+        blocks = next.max(*blocks.start())..=*blocks.end();
+        if blocks.is_empty() {
+            info!("all blocks are already generated");
+            return Ok(());
+        }
+    }
+    let cache_dir = cli.cache_dir.unwrap_or_else(|| cli.dump_dir.clone());
     let dump_dir = cli.dump_dir;
     let rpc_url = cli.rpc_url;
+    let opts = DumpOpts {
+        skip_client_execution: cli.skip_client_execution,
+        cache_dir: cli.opcode_tracking.then_some(cache_dir),
+    };
+    let gen_metrics = GenMetrics::new()?;
+    if let Some(addr) = cli.metrics_addr {
+        metrics::serve_registry(addr, gen_metrics.registry.clone()).await?;
+    }
 
     // create the dump parent dir
     fs::create_dir_all(&dump_dir)?;
+    if let Some(cache_dir) = &opts.cache_dir {
+        fs::create_dir_all(cache_dir)?;
+    }
 
-    // generate inputs
-    let inputs = generate_inputs(block_number, rpc_url).await?;
+    // compile the reth elf once for the blocks
+    let elf = fs::read(&cli.elf)?;
+    let app = App::new(&elf, None);
 
-    // save `reth_input_BLOCK_NUMBER.bin`
+    // the inputs of the following blocks are fetched while emulating the current one
+    let opcode_tracking = cli.opcode_tracking;
+    let mut generated = stream::iter(blocks.clone())
+        .inspect(|_| gen_metrics.blocks_seen.inc())
+        .map(|block_number| {
+            let handle = tokio::spawn(generate_inputs(
+                block_number,
+                rpc_url.clone(),
+                opcode_tracking,
+            ));
+            async move { anyhow::Ok((block_number, handle.await??)) }
+        })
+        .buffered(cli.max_in_flight.max(1));
+    let total = blocks.end() - blocks.start() + 1;
+    let mut dumped = 0;
+    // an interrupt stops after the block being dumped, the checkpoint is its block
+    let interrupt = ctrl_c();
+    tokio::pin!(interrupt);
+    loop {
+        let res = tokio::select! {
+            res = generated.next() => res,
+            _ = &mut interrupt => {
+                warn!("interrupted, the blocks being fetched are dropped");
+                break;
+            }
+        };
+        let Some(res) = res else {
+            break;
+        };
+        let (block_number, inputs) = res?;
+        dump_block(&app, block_number, &inputs, &dump_dir, &opts)?;
+        if let Some(checkpoint) = &cli.checkpoint {
+            write_checkpoint(checkpoint, block_number)?;
+        }
+        gen_metrics.inputs_generated.inc();
+        gen_metrics.inputs_bytes.observe(inputs.len() as f64);
+        gen_metrics.last_block.set(block_number as i64);
+        dumped += 1;
+        info!("generated reth inputs of block {block_number}");
+    }
+    info!(
+        "generated {dumped} blocks, {} blocks skipped of {}..={}",
+        total - dumped,
+        blocks.start(),
+        blocks.end()
+    );
+
+    Ok(())
+}
+
+// last generated block of the checkpoint, none if it's not created yet
+fn read_checkpoint(path: &Path) -> Result<Option<u64>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content.trim().parse()?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// replace the checkpoint by renaming, it's not corrupted if interrupted
+fn write_checkpoint(path: &Path, block_number: u64) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, block_number.to_string())?;
+    fs::rename(tmp_path, path)?;
+
+    Ok(())
+}
+
+struct DumpOpts {
+    // dump the inputs only without emulating
+    skip_client_execution: bool,
+    // directory of the opcode tracking output, none if the tracking is disabled
+    cache_dir: Option<PathBuf>,
+}
+
+// opcode tracking output of a block for the cycle profiling
+#[derive(Serialize)]
+struct OpcodeTracking {
+    block_number: u64,
+    cycles: u64,
+    // estimated cost by the opcodes of the emulated chips
+    cost: u64,
+}
+
+// the inputs are validated by emulating before saved, the ones failing in emulation (e.g. by a
+// genesis mismatch) are not saved to fail in proving later
+fn dump_block(
+    app: &App,
+    block_number: u64,
+    inputs: &[u8],
+    dump_dir: &Path,
+    opts: &DumpOpts,
+) -> Result<()> {
+    // save `reth_input_BLOCK_NUMBER.bin` only, it's validated in proving
     let input_path = dump_dir.join(format!("reth_input_{block_number}.bin"));
-    fs::write(input_path, &inputs)?;
+    if opts.skip_client_execution {
+        fs::write(input_path, inputs)?;
+        return Ok(());
+    }
 
-    // generate public values digest
-    let pv_digest = generate_pv_digest(&elf, &inputs)?;
+    // generate public values digest, the cost is estimated by the opcodes if tracking
+    let info = estimate_cost(
+        app.program.clone(),
+        app.pk.clone(),
+        app.vk.clone(),
+        Some(inputs),
+        None,
+        opts.cache_dir.is_some(),
+    )
+    .with_context(|| format!("failed to emulate reth inputs of block {block_number}"))?;
+    let cycles = info.total_cycles;
+    info!("emulated block {block_number} in {cycles} cycles");
+
+    // save `reth_input_BLOCK_NUMBER.bin`
+    fs::write(input_path, inputs)?;
 
     // save `reth_pv_digest_BLOCK_NUMBER.bin`
     let pv_digest_path = dump_dir.join(format!("reth_pv_digest_{block_number}.bin"));
-    fs::write(pv_digest_path, pv_digest)?;
+    fs::write(pv_digest_path, format!("0x{:064x}", info.pv_digest))?;
+
+    // save `reth_opcode_tracking_BLOCK_NUMBER.json`
+    if let Some(cache_dir) = &opts.cache_dir {
+        let tracking = OpcodeTracking {
+            block_number,
+            cycles,
+            cost: info.cost,
+        };
+        let tracking_path = cache_dir.join(format!("reth_opcode_tracking_{block_number}.json"));
+        fs::write(tracking_path, serde_json::to_vec_pretty(&tracking)?)?;
+    }
 
     Ok(())
 }
 
-async fn generate_inputs(block_number: u64, rpc_url: Url) -> Result<Vec<u8>> {
+async fn generate_inputs(
+    block_number: u64,
+    rpc_url: Url,
+    opcode_tracking: bool,
+) -> Result<Vec<u8>> {
     // create the rpc provider
     let rpc_provider = create_provider(rpc_url);
 
@@ -86,27 +361,11 @@ async fn generate_inputs(block_number: u64, rpc_url: Url) -> Result<Vec<u8>> {
             &rpc_provider,
             Genesis::Mainnet,
             None,
-            false,
+            opcode_tracking,
             &None,
         )
         .await?;
 
-    // write the input into stdin builder
-    let mut stdin_builder = EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>();
-    stdin_builder.write(&input);
-
-    // serialize the stdin builder
-    Ok(bincode::serialize(&stdin_builder)?)
-}
-
-fn generate_pv_digest(elf_file_path: &Path, inputs: &[u8]) -> Result<String> {
-    // read reth elf
-    let elf = fs::read(elf_file_path)?;
-
-    let app = App::new(&elf, None);
-
-    let info = estimate_cost(app.program, app.pk, app.vk, Some(inputs), None, false)?;
-    let pv_digest = info.pv_digest;
-
-    Ok(format!("0x{pv_digest:064x}"))
+    // write the input into stdin and serialize it
+    build_inputs(&[input])
 }
@@ -1,26 +1,36 @@
 use anyhow::Result;
-use clap::Parser;
 use dotenvy::dotenv;
 use pico_proving_service::{
-    config::ServiceConfig,
+    artifact_store,
+    config::{self, ServiceConfig},
+    gpu_pool::GpuPool,
     grpc::GrpcService,
+    metrics,
     proving::onchain::start_onchain_daemon,
-    proving_queue::{ProvingOutputs, ProvingQueue},
+    proving_queue::{ProvingOutputs, ProvingQueue, TaskStatuses},
+    shutdown::{self, Shutdown},
+    storage,
+    utils::telemetry::setup_tracing,
 };
 use pico_vm::{
     iter::{ThreadPoolBuilder, current_num_threads},
-    machine::logger::setup_logger,
     thread::channel::SingleUnboundedChannel,
 };
-use sqlx::sqlite::SqlitePoolOptions;
-use std::{process::exit, sync::Arc};
-use tokio::signal::ctrl_c;
+use std::{
+    process::exit,
+    sync::{Arc, atomic::AtomicBool},
+    time::Duration,
+};
+use tokio::time::timeout;
 use tracing::info;
 
+// timeout to close the open gRPC calls, e.g. the progress streams
+const SERVER_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    setup_logger();
+    let tracer_provider = setup_tracing("pico-proving-service")?;
     // Start background docker monitor for on-chain prover
     start_onchain_daemon();
 
@@ -30,34 +40,108 @@ async fn main() -> Result<()> {
         .expect("failed to build global Rayon thread pool");
     info!("initialized Rayon with {} threads", current_num_threads());
 
-    let cfg = ServiceConfig::parse();
-    info!("starting with config: {:?}", cfg);
+    let cfg: ServiceConfig = config::file::parse()?;
+    info!("starting with config: {:?}", cfg.redacted());
+    // fail fast on the invalid auth config
+    cfg.validate().map_err(anyhow::Error::msg)?;
+    let retention_policy = cfg.retention_policy();
+    let gc_interval = Duration::from_secs(cfg.gc_interval_secs);
+    let drain_timeout = Duration::from_secs(cfg.drain_timeout_secs);
+    let shutdown = Shutdown::default();
 
-    let db_pool = Arc::new(SqlitePoolOptions::new().connect(&cfg.db_url).await?);
+    let storage = storage::connect(cfg.storage_backend, &cfg.db_url).await?;
     let proving_outputs = Arc::new(ProvingOutputs::default());
+    let task_statuses = Arc::new(TaskStatuses::default());
     let grpc_to_proving_channel = SingleUnboundedChannel::default();
+    let workers_ready = Arc::new(AtomicBool::new(false));
+
+    let gpu_pool = Arc::new(GpuPool::detect(
+        &cfg.gpu_devices,
+        cfg.gpu_tasks_per_device,
+        cfg.gpu_health.clone(),
+    ));
+    let artifacts = artifact_store::connect(&cfg.artifacts).await?;
 
     let mut handles = vec![];
+    if let Some(addr) = cfg.metrics_addr {
+        handles.push(metrics::serve(addr).await?);
+    }
+    if !gpu_pool.is_empty() {
+        handles.push(gpu_pool.start_monitor(Duration::from_secs(cfg.gpu_monitor_interval_secs)));
+    }
 
     let proving_queue = ProvingQueue::new(
         cfg.clone(),
         proving_outputs.clone(),
+        task_statuses.clone(),
         grpc_to_proving_channel.receiver(),
-        db_pool.clone(),
+        grpc_to_proving_channel.sender(),
+        storage.clone(),
+        workers_ready.clone(),
+        gpu_pool.clone(),
+        artifacts.clone(),
+        shutdown.clone(),
     );
-    handles.push(proving_queue.run());
+    // the tasks are proved by the remote workers in coordinator mode
+    let coordinator = if cfg.coordinator_mode {
+        let coordinator = proving_queue.coordinator();
+        handles.push(coordinator.start_reaper());
+        Some(coordinator)
+    } else {
+        handles.push(proving_queue.run());
+        None
+    };
 
     let grpc_service = GrpcService::new(
         cfg,
-        db_pool,
+        storage.clone(),
         proving_outputs,
+        task_statuses.clone(),
         grpc_to_proving_channel.sender(),
+        workers_ready,
+        gpu_pool,
+        artifacts,
+        shutdown.clone(),
     );
-    handles.push(grpc_service.run());
+    if !retention_policy.is_empty() {
+        handles.push(
+            grpc_service
+                .retention()
+                .start_gc(retention_policy, gc_interval),
+        );
+    }
+    // the revoked tokens must be loaded before serving
+    if let Some(handle) = grpc_service.start_revocation_sync().await? {
+        handles.push(handle);
+    }
+    // replay the tasks not finished before the last exit
+    let replayed = grpc_service.replay_tasks().await?;
+    info!("replayed {replayed} unfinished proving tasks");
+    if let Some(handle) = grpc_service.start_key_cache_warming() {
+        handles.push(handle);
+    }
+    let server = grpc_service.run(coordinator);
 
     info!("waiting for stop");
-    ctrl_c().await?;
+    shutdown::wait_for_signal().await?;
+
+    // reject the new tasks and let the in-flight ones finish, the status and proofs are still
+    // served meanwhile
+    info!("draining in-flight tasks up to {drain_timeout:?}");
+    shutdown.start_draining();
+    let interrupted = shutdown::drain_tasks(&task_statuses, &storage, drain_timeout).await;
+    if interrupted > 0 {
+        info!("{interrupted} interrupted tasks will be replayed after restart");
+    }
+    shutdown.stop();
+    if timeout(SERVER_STOP_TIMEOUT, server).await.is_err() {
+        info!("grpc server stop timed out");
+    }
 
     info!("server exits");
+    // flush the pending spans
+    if let Some(provider) = tracer_provider {
+        let _ = provider.shutdown();
+    }
     exit(0);
 }
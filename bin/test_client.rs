@@ -1,22 +1,59 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
 use dotenvy::dotenv;
 use pico_proving_service::{
-    EstimateCostRequest, GetProvingResultRequest, ProveTaskRequest, RegisterAppRequest,
+    AppInfo, BatchProveTaskRequest, BenchmarkWorkload, CancelTaskRequest, DeleteProofRequest,
+    DeregisterAppRequest, ErrCode, EstimateCostRequest, GetAppAnalysisRequest, GetAppRequest,
+    GetGpuStatsRequest, GetOnchainVerifierRequest, GetProofRequest, GetProvingResultRequest,
+    GetServiceKeyRequest, GetTaskStatusRequest, GetUsageRequest, InputsCompression,
+    IssueTokenRequest, ListAppVersionsRequest, ListAppsRequest, ListDeadLettersRequest,
+    ListTasksRequest, ProofEnvelope, ProofType, ProveTaskRequest, RegisterAppRequest,
+    RequeueDeadLetterRequest, RevokeTokenRequest, RunBenchmarkRequest, SignatureScheme,
+    TaskPriority, TaskState, VerifyProofRequest, WarmUpOptions,
+    app_manager::elf_hash,
+    client::{
+        DEFAULT_HEALTH_CHECK_INTERVAL, DEFAULT_UPLOAD_CHUNK_SIZE, NAMESPACE_METADATA_KEY,
+        REQUEST_ID_METADATA_KEY, connect_balanced, connect_channel, existing_app_id,
+    },
+    config,
+    get_onchain_verifier_request::Proof as OnchainProof,
     prover_network_client::ProverNetworkClient,
+    utils::{
+        auth::ApiKeyInterceptor,
+        compression::compress_inputs,
+        signing::{RequestSigner, verify_proof_signature},
+        telemetry::setup_tracing,
+        time::unix_timestamp,
+        tls::TlsConfig,
+    },
+    verify_proof_request::{Claim, Verifier},
 };
-use pico_vm::machine::logger::setup_logger;
-use std::{fs, path::PathBuf};
-use tonic::codec::CompressionEncoding;
-use tracing::info;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::time::sleep;
+use tonic::{
+    Request, Status, codec::CompressionEncoding, metadata::AsciiMetadataValue, service::Interceptor,
+};
+use tracing::{info, warn};
 
 #[derive(Parser)]
 struct Cli {
+    #[clap(
+        long,
+        env = "CONFIG_FILE",
+        help = "TOML config file, overridden by the flags and env"
+    )]
+    pub config: Option<PathBuf>,
+
     #[clap(
         long,
         env = "GRPC_ADDR",
         default_value = "http://[::]:50052",
-        help = "gRPC address"
+        help = "gRPC address, or the addresses of the replicas separated by comma to balance the requests"
     )]
     pub grpc_addr: String,
 
@@ -28,6 +65,30 @@ struct Cli {
     )]
     pub max_grpc_msg_size: usize,
 
+    #[clap(
+        long,
+        env = "API_KEY",
+        help = "API key if the server authenticates by API keys"
+    )]
+    pub api_key: Option<String>,
+
+    #[clap(
+        long,
+        env = "BEARER_TOKEN",
+        help = "Bearer token or JWT if the server authenticates by bearer tokens or JWTs"
+    )]
+    pub bearer_token: Option<String>,
+
+    #[clap(
+        long,
+        env = "NAMESPACE",
+        help = "Namespace of the apps and tasks (default: the one bound to the JWT or default)"
+    )]
+    pub namespace: Option<String>,
+
+    #[clap(flatten)]
+    pub tls: TlsConfig,
+
     #[command(subcommand)]
     pub cmd: Command,
 }
@@ -43,17 +104,164 @@ enum Command {
     #[command(about = "Add a proving task")]
     ProveTask(ProveTaskCommand),
 
+    #[command(about = "Upload the inputs once to be referenced by their hash")]
+    UploadInputs(UploadInputsCommand),
+
+    #[command(about = "Add a batch of proving tasks")]
+    BatchProveTask(BatchProveTaskCommand),
+
     #[command(about = "Fetch the proving result if complete")]
     GetProvingResult(GetProvingResultCommand),
+
+    #[command(about = "Query the lifecycle state of a proving task")]
+    GetTaskStatus(GetTaskStatusCommand),
+
+    #[command(about = "Cancel a queued or in-progress proving task")]
+    CancelTask(CancelTaskCommand),
+
+    #[command(about = "Download a completed proof")]
+    GetProof(GetProofCommand),
+
+    #[command(about = "Download a proof by streaming to a file")]
+    DownloadProof(DownloadProofCommand),
+
+    #[command(about = "Delete the proof of a finished task")]
+    DeleteProof(DeleteProofCommand),
+
+    #[command(about = "List the tracked proving tasks")]
+    ListTasks(ListTasksCommand),
+
+    #[command(about = "Get the metadata of a registered application")]
+    GetApp(GetAppCommand),
+
+    #[command(about = "Get the static analysis of the ELF of a registered application")]
+    GetAppAnalysis(GetAppCommand),
+
+    #[command(about = "List the registered applications")]
+    ListApps(ListAppsCommand),
+
+    #[command(about = "List the ELF versions of a named application")]
+    ListAppVersions(ListAppVersionsCommand),
+
+    #[command(about = "Remove a registered application and its proofs")]
+    DeregisterApp(DeregisterAppCommand),
+
+    #[command(about = "Verify an on-chain proof by the server")]
+    VerifyProof(VerifyProofCommand),
+
+    #[command(about = "Get the on-chain verifier contract and the calldata of a proof")]
+    GetOnchainVerifier(GetOnchainVerifierCommand),
+
+    #[command(about = "Get the public key of the service signing the proofs")]
+    GetServiceKey,
+
+    #[command(about = "Get the stats of the GPU devices")]
+    GetGpuStats,
+
+    #[command(about = "Get the usage of the apps in a time window")]
+    GetUsage(GetUsageCommand),
+
+    #[command(about = "Issue a JWT with the scopes (admin)")]
+    IssueToken(IssueTokenCommand),
+
+    #[command(about = "Revoke an issued JWT (admin)")]
+    RevokeToken(RevokeTokenCommand),
+
+    #[command(about = "List the permanently failed tasks")]
+    ListDeadLetters(ListDeadLettersCommand),
+
+    #[command(about = "Queue a permanently failed task again (admin)")]
+    RequeueDeadLetter(RequeueDeadLetterCommand),
+
+    #[command(about = "Prove a bundled workload on CPU and each GPU device (admin)")]
+    RunBenchmark(RunBenchmarkCommand),
 }
 
 #[derive(Args)]
 struct RegisterAppCommand {
-    #[arg(long, help = "Application ELF file path")]
-    elf: PathBuf,
+    #[arg(
+        long,
+        required_unless_present = "elf_uri",
+        help = "Application ELF file path"
+    )]
+    elf: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["elf", "stream"],
+        requires = "elf_sha256",
+        help = "URI (https://, s3:// or ipfs://) of the ELF fetched by the server"
+    )]
+    elf_uri: Option<String>,
+
+    #[arg(
+        long,
+        requires = "elf_uri",
+        help = "Hex of the sha256 of the ELF at the URI"
+    )]
+    elf_sha256: Option<String>,
 
     #[arg(long, help = "Application information")]
     info: Option<String>,
+
+    #[arg(
+        long,
+        help = "Register the ELF as the next version of the named application"
+    )]
+    name: Option<String>,
+
+    #[arg(long, help = "Display name in the application info")]
+    display_name: Option<String>,
+
+    #[arg(long, help = "Description in the application info")]
+    description: Option<String>,
+
+    #[arg(long, help = "Owner in the application info")]
+    owner: Option<String>,
+
+    #[arg(long = "tag", help = "Tag in the application info, could be repeated")]
+    tags: Vec<String>,
+
+    #[arg(long, help = "Guest toolchain version in the application info")]
+    toolchain_version: Option<String>,
+
+    #[arg(long, help = "Upload the ELF by streaming in chunks")]
+    stream: bool,
+
+    #[arg(
+        long,
+        help = "Warm up the application in background after registration"
+    )]
+    warm_up: bool,
+
+    #[arg(
+        long,
+        requires = "warm_up",
+        help = "Serialized inputs file of the smoke proof run by the warm-up"
+    )]
+    smoke_inputs: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_UPLOAD_CHUNK_SIZE,
+        help = "Chunk size (bytes) of the streaming upload"
+    )]
+    chunk_size: usize,
+}
+
+impl RegisterAppCommand {
+    // the structured app info if any field is provided
+    fn app_info(&self) -> Option<AppInfo> {
+        let info = AppInfo {
+            name: self.display_name.clone().unwrap_or_default(),
+            description: self.description.clone().unwrap_or_default(),
+            owner: self.owner.clone().unwrap_or_default(),
+            tags: self.tags.clone(),
+            toolchain_version: self.toolchain_version.clone().unwrap_or_default(),
+        };
+
+        (info != AppInfo::default()).then_some(info)
+    }
 }
 
 #[derive(Args)]
@@ -63,12 +271,47 @@ struct EstimateCostCommand {
 
     #[arg(long, help = "Input file paths")]
     inputs: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with = "inputs",
+        help = "Directory of the saved inputs (e.g. by gen-reth-inputs) to estimate each .bin file without proving"
+    )]
+    inputs_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "inputs_dir"],
+        help = "Hash of the inputs uploaded by upload-inputs"
+    )]
+    inputs_hash: Option<String>,
+
+    #[arg(long, help = "Emulate again instead of returning the cached estimate")]
+    bypass_cache: bool,
 }
 
 #[derive(Args)]
 struct ProveTaskCommand {
-    #[arg(long, help = "Application unique ID")]
-    app_id: String,
+    #[arg(
+        long,
+        required_unless_present = "app_name",
+        help = "Application unique ID"
+    )]
+    app_id: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["app_id", "upload_stream"],
+        help = "Name of the versioned application"
+    )]
+    app_name: Option<String>,
+
+    #[arg(
+        long,
+        requires = "app_name",
+        help = "Pinned version of the named application (default: the latest)"
+    )]
+    app_version: Option<u32>,
 
     #[arg(long, help = "Proving task unique ID")]
     task_id: String,
@@ -76,8 +319,216 @@ struct ProveTaskCommand {
     #[arg(long, help = "Input file paths")]
     inputs: Option<PathBuf>,
 
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "upload_stream"],
+        help = "Hash of the inputs uploaded by upload-inputs"
+    )]
+    inputs_hash: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "inputs_hash", "upload_stream"],
+        requires = "inputs_sha256",
+        help = "URI (https://, s3:// or ipfs://) of the inputs fetched by the server"
+    )]
+    inputs_uri: Option<String>,
+
+    #[arg(
+        long,
+        requires = "inputs_uri",
+        help = "Hex of the sha256 of the inputs at the URI"
+    )]
+    inputs_sha256: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs_hash", "inputs_uri", "upload_stream"],
+        help = "Compress the inputs by zstd at the level before submitting (0 for the default level)"
+    )]
+    compress: Option<i32>,
+
+    #[arg(long, help = "Use GPU for proving (default: false, use CPU)")]
+    use_gpu: bool,
+
+    #[arg(
+        long,
+        requires = "use_gpu",
+        conflicts_with = "upload_stream",
+        help = "Prove on CPU if no GPU device is healthy"
+    )]
+    allow_cpu_fallback: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "Public values digest in hex the task must produce, it's checked before proving"
+    )]
+    expected_pv_digest: Option<String>,
+
+    #[arg(long, help = "Scheduling priority (normal, high, low)")]
+    priority: Option<String>,
+
+    #[arg(long, help = "Stream the proving progress until the task is finished")]
+    stream: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "Prove again even if the same inputs are proved"
+    )]
+    force: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "URL to post the signed result once the task is finished"
+    )]
+    callback_url: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "Verify the proof before completing the task"
+    )]
+    verify_proof: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "Type of the final proof: evm, core or compressed (default: evm)"
+    )]
+    proof_type: Option<String>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with = "upload_stream",
+        help = "Task IDs to prove first, their proofs are appended to the inputs"
+    )]
+    depends_on: Vec<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "Unix timestamp (seconds) before which the task is held in the queue"
+    )]
+    not_before: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with = "upload_stream",
+        help = "Unix timestamp (seconds) by which the task must complete"
+    )]
+    deadline: Option<u64>,
+
+    #[arg(long, help = "Upload the inputs by streaming in chunks")]
+    upload_stream: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_UPLOAD_CHUNK_SIZE,
+        help = "Chunk size (bytes) of the streaming upload"
+    )]
+    chunk_size: usize,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["stream", "upload_stream"],
+        help = "Hex of the private key signing the request by ProveTaskSigned"
+    )]
+    signing_key: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "secp256k1",
+        help = "Scheme of the signing key (secp256k1, ed25519)"
+    )]
+    signature_scheme: String,
+
+    #[arg(
+        long,
+        requires = "signing_key",
+        help = "Nonce of the signed request, unique per signer (default: now in milliseconds)"
+    )]
+    nonce: Option<u64>,
+
+    #[arg(
+        long,
+        default_value = "300",
+        help = "Seconds before the signed request expires"
+    )]
+    signature_ttl_secs: u64,
+}
+
+#[derive(Args)]
+struct UploadInputsCommand {
+    #[arg(long, help = "Input file path")]
+    inputs: PathBuf,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_UPLOAD_CHUNK_SIZE,
+        help = "Chunk size (bytes) of the streaming upload"
+    )]
+    chunk_size: usize,
+}
+
+#[derive(Args)]
+struct BatchProveTaskCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(
+        long = "task",
+        required_unless_present = "inputs_dir",
+        help = "Proving task as TASK_ID or TASK_ID=INPUTS_PATH, could be repeated"
+    )]
+    tasks: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Directory of the saved inputs (e.g. by gen-reth-inputs), each .bin file is proved as a task named by its file name without the extension"
+    )]
+    inputs_dir: Option<PathBuf>,
+
     #[arg(long, help = "Use GPU for proving (default: false, use CPU)")]
     use_gpu: bool,
+
+    #[arg(long, help = "Prove again even if the same inputs are proved")]
+    force: bool,
+
+    #[arg(
+        long,
+        help = "Poll the status of the queued tasks until finished and log their latency"
+    )]
+    wait: bool,
+
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Interval (seconds) of polling the task status"
+    )]
+    poll_interval_secs: u64,
+
+    #[arg(
+        long,
+        requires = "wait",
+        help = "CSV file to write the latency, proof size and failure of the finished tasks"
+    )]
+    report: Option<PathBuf>,
+}
+
+// end-to-end result of a waited task
+struct TaskReport {
+    task_id: String,
+    state: TaskState,
+    // from queued to finished
+    latency_secs: u64,
+    proof_size: usize,
+    attempts: u32,
+    failure_code: Option<ErrCode>,
+    failure_reason: Option<String>,
 }
 
 #[derive(Args)]
@@ -89,15 +540,289 @@ struct GetProvingResultCommand {
     task_id: String,
 }
 
+#[derive(Args)]
+struct GetTaskStatusCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "Proving task unique ID")]
+    task_id: String,
+}
+
+#[derive(Args)]
+struct CancelTaskCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "Proving task unique ID")]
+    task_id: String,
+}
+
+#[derive(Args)]
+struct GetProofCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "Proving task unique ID")]
+    task_id: String,
+
+    #[arg(long, help = "Output file path to save the proof")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DownloadProofCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "Proving task unique ID")]
+    task_id: String,
+
+    #[arg(long, help = "Output file path to save the proof")]
+    output: PathBuf,
+
+    #[arg(long, help = "Chunk size (bytes) of the streaming download")]
+    chunk_size: Option<u32>,
+}
+
+#[derive(Args)]
+struct DeleteProofCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "Proving task unique ID")]
+    task_id: String,
+}
+
+#[derive(Args)]
+struct ListTasksCommand {
+    #[arg(long, help = "Filter by application unique ID")]
+    app_id: Option<String>,
+
+    #[arg(
+        long,
+        help = "Filter by task state (queued, executing, proving, completed, failed, cancelled)"
+    )]
+    state: Option<String>,
+
+    #[arg(long, help = "Filter by unix timestamp of queueing (inclusive)")]
+    created_after: Option<u64>,
+
+    #[arg(long, help = "Filter by unix timestamp of queueing (exclusive)")]
+    created_before: Option<u64>,
+
+    #[arg(long, default_value = "100", help = "Maximum number of tasks per page")]
+    page_size: u32,
+
+    #[arg(long, help = "Fetch all pages")]
+    all: bool,
+}
+
+#[derive(Args)]
+struct GetAppCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+}
+
+#[derive(Args)]
+struct ListAppsCommand {
+    #[arg(long, help = "Filter by the owner in the application info")]
+    owner: Option<String>,
+
+    #[arg(long, help = "Filter by the display name in the application info")]
+    name: Option<String>,
+
+    #[arg(long, help = "Filter by a tag in the application info")]
+    tag: Option<String>,
+}
+
+#[derive(Args)]
+struct ListAppVersionsCommand {
+    #[arg(long, help = "Name of the versioned application")]
+    name: String,
+}
+
+#[derive(Args)]
+struct DeregisterAppCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+}
+
+#[derive(Args)]
+struct VerifyProofCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "On-chain or STARK proof file path")]
+    proof: PathBuf,
+
+    #[arg(long, help = "Claimed public values digest in hex")]
+    pv_digest: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "pv_digest",
+        help = "Claimed raw public values file path"
+    )]
+    public_values: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Type of the proof: evm, core or compressed (default: evm)"
+    )]
+    proof_type: Option<String>,
+}
+
+#[derive(Args)]
+struct GetOnchainVerifierCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(
+        long,
+        help = "Proving task unique ID of the EVM proof to encode as calldata"
+    )]
+    task_id: Option<String>,
+
+    #[arg(long, help = "Output file path to save the verifier contract source")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct GetUsageCommand {
+    #[arg(long, help = "Filter by application unique ID")]
+    app_id: Option<String>,
+
+    #[arg(long, help = "Filter by signer of the signed requests")]
+    signer: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Unix timestamp of the window start (inclusive)"
+    )]
+    start_time: u64,
+
+    #[arg(
+        long,
+        help = "Unix timestamp of the window end (exclusive, default: now)"
+    )]
+    end_time: Option<u64>,
+}
+
+#[derive(Args)]
+struct IssueTokenCommand {
+    #[arg(long, help = "Subject of the token")]
+    subject: String,
+
+    #[arg(
+        long = "scope",
+        required = true,
+        help = "Scope as ACTION or ACTION:APP_ID, repeatable"
+    )]
+    scopes: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Lifetime of the token in seconds (default: JWT_TOKEN_TTL_SECS of server)"
+    )]
+    ttl_secs: Option<u64>,
+
+    #[arg(long, help = "Namespace the token is bound to")]
+    namespace: Option<String>,
+}
+
+#[derive(Args)]
+struct RevokeTokenCommand {
+    #[arg(long, help = "Token ID returned by issue-token")]
+    token_id: String,
+}
+
+#[derive(Args)]
+struct ListDeadLettersCommand {
+    #[arg(long, help = "Filter by application unique ID")]
+    app_id: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "100",
+        help = "Maximum number of dead letters per page"
+    )]
+    page_size: u32,
+
+    #[arg(long, help = "Fetch all pages")]
+    all: bool,
+}
+
+#[derive(Args)]
+struct RequeueDeadLetterCommand {
+    #[arg(long, help = "Application unique ID")]
+    app_id: String,
+
+    #[arg(long, help = "Proving task unique ID")]
+    task_id: String,
+}
+
+#[derive(Args)]
+struct RunBenchmarkCommand {
+    #[arg(
+        long,
+        help = "Bundled workload: fibonacci or reth (default: fibonacci)"
+    )]
+    workload: Option<String>,
+
+    #[arg(
+        long,
+        help = "Type of the final proof: evm, core or compressed (default: evm)"
+    )]
+    proof_type: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    setup_logger();
+    setup_tracing("pico-test-client")?;
 
-    let cli = Cli::parse();
+    let cli: Cli = config::file::parse()?;
 
-    let mut client = ProverNetworkClient::connect(cli.grpc_addr.clone())
-        .await?
+    let addrs: Vec<_> = cli.grpc_addr.split(',').map(str::trim).collect();
+    let channel = match addrs[..] {
+        [addr] => connect_channel(addr.to_string(), cli.tls.client_tls_config()?).await?,
+        _ => {
+            let addrs = addrs.into_iter().map(str::to_string).collect();
+            let tls = cli.tls.client_tls_config()?;
+            connect_balanced(addrs, tls, DEFAULT_HEALTH_CHECK_INTERVAL)
+                .await?
+                .0
+        }
+    };
+    let mut api_key = ApiKeyInterceptor::new(cli.api_key.as_deref())?;
+    let bearer_token: Option<AsciiMetadataValue> = cli
+        .bearer_token
+        .map(|token| format!("Bearer {token}").parse())
+        .transpose()?;
+    let namespace: Option<AsciiMetadataValue> = cli
+        .namespace
+        .map(|namespace| namespace.parse())
+        .transpose()?;
+    let interceptor = move |mut req: Request<()>| {
+        if let Some(token) = &bearer_token {
+            req.metadata_mut().insert("authorization", token.clone());
+        }
+        if let Some(namespace) = &namespace {
+            req.metadata_mut()
+                .insert(NAMESPACE_METADATA_KEY, namespace.clone());
+        }
+        // the request ID is kept by the service to correlate the logs of both sides
+        let request_id = hex::encode(rand::random::<[u8; 16]>());
+        info!(request_id, "sending request");
+        let value = request_id
+            .parse()
+            .map_err(|_| Status::internal("invalid request ID"))?;
+        req.metadata_mut().insert(REQUEST_ID_METADATA_KEY, value);
+        api_key.call(req)
+    };
+    let mut client = ProverNetworkClient::with_interceptor(channel, interceptor)
         .max_encoding_message_size(cli.max_grpc_msg_size)
         .max_decoding_message_size(cli.max_grpc_msg_size)
         .accept_compressed(CompressionEncoding::Zstd)
@@ -105,50 +830,372 @@ async fn main() -> Result<()> {
 
     match cli.cmd {
         Command::RegisterApp(cmd) => {
-            let elf = fs::read(cmd.elf)?;
-
-            let req = RegisterAppRequest {
-                elf,
-                info: cmd.info,
+            let app_info = cmd.app_info();
+            let warm_up = if cmd.warm_up {
+                Some(WarmUpOptions {
+                    smoke_inputs: cmd.smoke_inputs.as_ref().map(fs::read).transpose()?,
+                })
+            } else {
+                None
+            };
+            let elf = cmd.elf.as_ref().map(fs::read).transpose()?;
+            let elf_sha256 = cmd
+                .elf_sha256
+                .as_deref()
+                .map(hex::decode)
+                .transpose()
+                .context("invalid elf sha256")?
+                .unwrap_or_default();
+            // the same content hash of the elf kept by the service
+            let elf_hash = match &elf {
+                Some(elf) => elf_hash(elf),
+                None => hex::encode(&elf_sha256),
+            };
+            let res = match cmd.elf {
+                Some(elf_path) if cmd.stream => {
+                    client
+                        .register_app_from_file(
+                            elf_path,
+                            cmd.info,
+                            app_info,
+                            cmd.name,
+                            warm_up,
+                            cmd.chunk_size,
+                        )
+                        .await
+                }
+                _ => {
+                    let req = RegisterAppRequest {
+                        elf: elf.unwrap_or_default(),
+                        info: cmd.info,
+                        name: cmd.name,
+                        app_info,
+                        elf_uri: cmd.elf_uri,
+                        elf_sha256,
+                        warm_up,
+                    };
+                    client
+                        .register_app(req)
+                        .await
+                        .map(|res| res.into_inner())
+                        .map_err(Into::into)
+                }
             };
-            let res = client.register_app(req).await?.into_inner();
 
-            info!("RegisterApp: err={:?}", res.err);
+            match res {
+                Ok(res) => info!(
+                    "RegisterApp: err={:?}, app_id={}, elf_hash={elf_hash}, version={:?}",
+                    res.err, res.app_id, res.version
+                ),
+                // the same elf is registered already, reuse the existing app
+                Err(e) => match e.downcast_ref::<Status>().and_then(existing_app_id) {
+                    Some(app_id) => info!(
+                        "RegisterApp: already registered, app_id={app_id}, elf_hash={elf_hash}"
+                    ),
+                    None => return Err(e),
+                },
+            }
         }
         Command::EstimateCost(cmd) => {
-            let inputs = if let Some(file_path) = cmd.inputs {
-                Some(fs::read(file_path)?)
-            } else {
-                None
+            // the saved inputs of a chain are estimated to size the hardware before proving them
+            let inputs_files = match &cmd.inputs_dir {
+                Some(inputs_dir) => inputs_dir_files(inputs_dir)?,
+                None => vec![(String::new(), cmd.inputs)],
             };
+            let (mut total_cycles, mut total_cost) = (0, 0);
+            for (name, file_path) in &inputs_files {
+                let inputs = file_path.as_ref().map(fs::read).transpose()?;
+                let req = EstimateCostRequest {
+                    app_id: cmd.app_id.clone(),
+                    inputs,
+                    bypass_cache: Some(cmd.bypass_cache),
+                    inputs_hash: cmd.inputs_hash.clone(),
+                };
+                let res = client.estimate_cost(req).await?.into_inner();
+                total_cycles += res.total_cycles;
+                total_cost += res.cost;
 
-            let req = EstimateCostRequest {
-                app_id: cmd.app_id,
-                inputs,
-            };
-            let res = client.estimate_cost(req).await?.into_inner();
+                if !name.is_empty() {
+                    info!("EstimateCost: inputs={name}");
+                }
+                info!(
+                    "EstimateCost: err={:?}, cost={}, pv_digest={:?}, cached={}, total_cycles={}, total_chunks={}",
+                    res.err,
+                    res.cost,
+                    res.pv_digest,
+                    res.cached,
+                    res.total_cycles,
+                    res.total_chunks
+                );
+                info!(
+                    "EstimateCost: cpu_proving_secs={:?}, gpu_proving_secs={:?}, cpu_price={:?}, gpu_price={:?}",
+                    res.cpu_proving_secs, res.gpu_proving_secs, res.cpu_price, res.gpu_price
+                );
+            }
+            if cmd.inputs_dir.is_some() {
+                info!(
+                    "EstimateCost: {} inputs, total_cycles={total_cycles}, total cost={total_cost}",
+                    inputs_files.len()
+                );
+            }
+        }
+        Command::ProveTask(cmd) if cmd.upload_stream => {
+            let priority = cmd.priority.as_deref().map(parse_priority).transpose()?;
+            let res = client
+                .prove_task_from_file(
+                    cmd.app_id.unwrap_or_default(),
+                    cmd.task_id,
+                    cmd.inputs,
+                    Some(cmd.use_gpu),
+                    priority,
+                    cmd.chunk_size,
+                )
+                .await?;
 
-            info!(
-                "EstimateCost: err={:?}, cost={}, pv_digest={:?}",
-                res.err, res.cost, res.pv_digest
-            );
+            info!("ProveTaskUpload: err={:?}", res.err);
         }
         Command::ProveTask(cmd) => {
-            let inputs = if let Some(file_path) = cmd.inputs {
+            let mut inputs = if let Some(file_path) = cmd.inputs {
                 Some(fs::read(file_path)?)
             } else {
                 None
             };
+            if let (Some(level), Some(raw)) = (cmd.compress, &inputs) {
+                let compressed = compress_inputs(raw, level)?;
+                info!(
+                    "ProveTask: compressed inputs from {} to {} bytes",
+                    raw.len(),
+                    compressed.len()
+                );
+                inputs = Some(compressed);
+            }
 
+            let inputs_sha256 = cmd
+                .inputs_sha256
+                .as_deref()
+                .map(hex::decode)
+                .transpose()
+                .context("invalid inputs sha256")?
+                .unwrap_or_default();
+            let expected_pv_digest = cmd
+                .expected_pv_digest
+                .as_deref()
+                .map(|digest| hex::decode(digest.strip_prefix("0x").unwrap_or(digest)))
+                .transpose()
+                .context("invalid expected public values digest")?;
+            let priority = cmd.priority.as_deref().map(parse_priority).transpose()?;
+            let proof_type = cmd
+                .proof_type
+                .as_deref()
+                .map(parse_proof_type)
+                .transpose()?;
             let req = ProveTaskRequest {
-                app_id: cmd.app_id,
+                app_id: cmd.app_id.unwrap_or_default(),
                 task_id: cmd.task_id,
                 inputs,
                 use_gpu: Some(cmd.use_gpu),
+                priority: priority.map(Into::into),
+                app_name: cmd.app_name,
+                app_version: cmd.app_version,
+                force: Some(cmd.force),
+                callback_url: cmd.callback_url,
+                verify_proof: Some(cmd.verify_proof),
+                proof_type: proof_type.map(Into::into),
+                depends_on: cmd.depends_on,
+                not_before: cmd.not_before,
+                deadline: cmd.deadline,
+                inputs_hash: cmd.inputs_hash,
+                inputs_uri: cmd.inputs_uri,
+                inputs_sha256,
+                inputs_compression: cmd.compress.map(|_| InputsCompression::Zstd.into()),
+                allow_cpu_fallback: Some(cmd.allow_cpu_fallback),
+                expected_pv_digest,
             };
-            let res = client.prove_task(req).await?.into_inner();
 
-            info!("ProveTask: err={:?}", res.err);
+            if let Some(key) = &cmd.signing_key {
+                let signer =
+                    RequestSigner::from_hex(parse_signature_scheme(&cmd.signature_scheme)?, key)?;
+                let nonce = cmd.nonce.unwrap_or_else(|| {
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_millis() as u64)
+                });
+                let signed = signer.sign(&req, nonce, unix_timestamp() + cmd.signature_ttl_secs)?;
+                let res = client.prove_task_signed(signed).await?.into_inner();
+
+                info!(
+                    "ProveTaskSigned: err={:?}, app_id={}, deduplicated_by={:?}, signer={}, nonce={nonce}",
+                    res.err, res.app_id, res.deduplicated_by, res.signer
+                );
+            } else if cmd.stream {
+                let mut stream = client.prove_task_stream(req).await?.into_inner();
+                while let Some(progress) = stream.message().await? {
+                    info!(
+                        "ProveTaskStream: event={:?}, state={:?}, chunks={}/{}, combines={}/{}, elapsed={}s, failure_reason={:?}",
+                        progress.event(),
+                        progress.state(),
+                        progress.proved_chunks,
+                        progress.total_chunks,
+                        progress.combined_proofs,
+                        progress.total_combines,
+                        progress.elapsed_secs,
+                        progress.failure_reason,
+                    );
+                }
+            } else {
+                let res = client.prove_task(req).await?.into_inner();
+
+                info!(
+                    "ProveTask: err={:?}, app_id={}, deduplicated_by={:?}",
+                    res.err, res.app_id, res.deduplicated_by
+                );
+            }
+        }
+        Command::UploadInputs(cmd) => {
+            let inputs = fs::read(&cmd.inputs)?;
+            let res = client
+                .upload_inputs_by_chunks(&inputs, cmd.chunk_size)
+                .await?;
+
+            info!(
+                "UploadInputs: err={:?}, inputs_hash={}, size={}",
+                res.err, res.inputs_hash, res.size
+            );
+        }
+        Command::BatchProveTask(cmd) => {
+            let mut tasks: Vec<(String, Option<PathBuf>)> = cmd
+                .tasks
+                .into_iter()
+                .map(|task| match task.split_once('=') {
+                    Some((task_id, file_path)) => (task_id.to_string(), Some(file_path.into())),
+                    None => (task, None),
+                })
+                .collect();
+            // replay the saved inputs without generating them again
+            if let Some(inputs_dir) = &cmd.inputs_dir {
+                tasks.extend(inputs_dir_files(inputs_dir)?);
+            }
+            let tasks = tasks
+                .into_iter()
+                .map(|(task_id, inputs_path)| {
+                    let inputs = inputs_path.map(fs::read).transpose()?;
+
+                    Ok(ProveTaskRequest {
+                        app_id: cmd.app_id.clone(),
+                        task_id,
+                        inputs,
+                        use_gpu: Some(cmd.use_gpu),
+                        priority: None,
+                        app_name: None,
+                        app_version: None,
+                        force: Some(cmd.force),
+                        callback_url: None,
+                        verify_proof: None,
+                        proof_type: None,
+                        depends_on: vec![],
+                        not_before: None,
+                        deadline: None,
+                        inputs_hash: None,
+                        inputs_uri: None,
+                        inputs_sha256: vec![],
+                        inputs_compression: None,
+                        allow_cpu_fallback: None,
+                        expected_pv_digest: None,
+                    })
+                })
+                .collect::<Result<_>>()?;
+
+            let req = BatchProveTaskRequest { tasks };
+            let res = client.batch_prove_task(req).await?.into_inner();
+
+            info!("BatchProveTask: err={:?}", res.err);
+            // the deduplicated tasks are waited by the tasks proving their inputs
+            let mut pending: Vec<_> = res
+                .results
+                .iter()
+                .filter(|result| result.err.is_none())
+                .map(|result| {
+                    result
+                        .deduplicated_by
+                        .clone()
+                        .unwrap_or_else(|| result.task_id.clone())
+                })
+                .collect();
+            pending.sort();
+            pending.dedup();
+            for result in res.results {
+                info!(
+                    "BatchProveTask: task_id={}, err={:?}, deduplicated_by={:?}",
+                    result.task_id, result.err, result.deduplicated_by
+                );
+            }
+            if cmd.wait {
+                let mut reports = vec![];
+                while !pending.is_empty() {
+                    sleep(Duration::from_secs(cmd.poll_interval_secs)).await;
+                    let mut unfinished = vec![];
+                    for task_id in pending {
+                        let req = GetTaskStatusRequest {
+                            app_id: cmd.app_id.clone(),
+                            task_id: task_id.clone(),
+                        };
+                        let status = client.get_task_status(req).await?.into_inner();
+                        let state = status.state();
+                        let finished_at = match (state, status.finished_at) {
+                            (
+                                TaskState::Completed | TaskState::Failed | TaskState::Cancelled,
+                                Some(finished_at),
+                            ) => finished_at,
+                            _ => {
+                                unfinished.push(task_id);
+                                continue;
+                            }
+                        };
+                        let proof_size = if state == TaskState::Completed {
+                            let req = GetProofRequest {
+                                app_id: cmd.app_id.clone(),
+                                task_id: task_id.clone(),
+                            };
+                            client.get_proof(req).await?.into_inner().proof.len()
+                        } else {
+                            0
+                        };
+                        let report = TaskReport {
+                            task_id,
+                            state,
+                            latency_secs: finished_at.saturating_sub(status.created_at),
+                            proof_size,
+                            attempts: status.attempts,
+                            failure_code: status.failure_code.map(|_| status.failure_code()),
+                            failure_reason: status.failure_reason,
+                        };
+                        info!(
+                            "BatchProveTask: task_id={}, state={:?}, latency={}s, proof size={} bytes, attempts={}, failure_code={:?}, failure_reason={:?}",
+                            report.task_id,
+                            report.state,
+                            report.latency_secs,
+                            report.proof_size,
+                            report.attempts,
+                            report.failure_code,
+                            report.failure_reason,
+                        );
+                        reports.push(report);
+                    }
+                    pending = unfinished;
+                }
+                let completed = reports
+                    .iter()
+                    .filter(|report| report.state == TaskState::Completed)
+                    .count();
+                info!(
+                    "BatchProveTask: {completed}/{} tasks completed",
+                    reports.len()
+                );
+                if let Some(file_path) = cmd.report {
+                    write_report(&file_path, &reports)?;
+                    info!("BatchProveTask: report saved to {}", file_path.display());
+                }
+            }
         }
         Command::GetProvingResult(cmd) => {
             let req = GetProvingResultRequest {
@@ -157,9 +1204,486 @@ async fn main() -> Result<()> {
             };
             let res = client.get_proving_result(req).await?.into_inner();
 
-            info!("GetProvingResult: err={:?}, proof={:?}", res.err, res.proof);
+            info!(
+                "GetProvingResult: err={:?}, proof={:?}, timings={:?}",
+                res.err, res.proof, res.timings
+            );
+        }
+        Command::GetTaskStatus(cmd) => {
+            let req = GetTaskStatusRequest {
+                app_id: cmd.app_id,
+                task_id: cmd.task_id,
+            };
+            let res = client.get_task_status(req).await?.into_inner();
+
+            info!(
+                "GetTaskStatus: err={:?}, state={:?}, created_at={}, started_at={:?}, finished_at={:?}, failure_reason={:?}, failure_code={:?}, not_before={:?}, deadline={:?}, timings={:?}",
+                res.err,
+                res.state(),
+                res.created_at,
+                res.started_at,
+                res.finished_at,
+                res.failure_reason,
+                res.failure_code.map(|_| res.failure_code()),
+                res.not_before,
+                res.deadline,
+                res.timings,
+            );
+        }
+        Command::CancelTask(cmd) => {
+            let req = CancelTaskRequest {
+                app_id: cmd.app_id,
+                task_id: cmd.task_id,
+            };
+            let res = client.cancel_task(req).await?.into_inner();
+
+            info!("CancelTask: err={:?}", res.err);
+        }
+        Command::GetProof(cmd) => {
+            let req = GetProofRequest {
+                app_id: cmd.app_id.clone(),
+                task_id: cmd.task_id.clone(),
+            };
+            let res = client.get_proof(req).await?.into_inner();
+
+            info!(
+                "GetProof: err={:?}, proof size={} bytes, pv_digest=0x{}, metadata={:?}, proof_type={:?}, calldata size={} bytes",
+                res.err,
+                res.proof.len(),
+                hex::encode(&res.pv_digest),
+                res.metadata,
+                res.proof_type(),
+                res.calldata.len(),
+            );
+
+            if let Some(signature) = &res.signature {
+                let envelope = ProofEnvelope {
+                    app_id: cmd.app_id.trim_start_matches("0x").to_string(),
+                    task_id: cmd.task_id,
+                    proof_sha256: Sha256::digest(&res.proof).to_vec(),
+                    pv_digest: res.pv_digest.clone(),
+                    proof_type: res.proof_type,
+                    metadata: res.metadata.clone(),
+                };
+                match verify_proof_signature(&envelope, signature) {
+                    Ok(signer) => info!("GetProof: signed by {signer}"),
+                    Err(e) => warn!("GetProof: invalid signature: {e}"),
+                }
+            }
+
+            if let Some(file_path) = cmd.output {
+                fs::write(&file_path, &res.proof)?;
+                info!("GetProof: proof saved to {}", file_path.display());
+            }
+        }
+        Command::DownloadProof(cmd) => {
+            let res = client
+                .download_proof_to_file(cmd.app_id, cmd.task_id, &cmd.output, cmd.chunk_size)
+                .await?;
+
+            info!(
+                "DownloadProof: proof size={} bytes, sha256=0x{}, pv_digest=0x{}, proof_type={:?}, metadata={:?}, saved to {}",
+                res.proof_size,
+                hex::encode(&res.proof_sha256),
+                hex::encode(&res.pv_digest),
+                res.proof_type(),
+                res.metadata,
+                cmd.output.display(),
+            );
+        }
+        Command::DeleteProof(cmd) => {
+            let req = DeleteProofRequest {
+                app_id: cmd.app_id,
+                task_id: cmd.task_id,
+            };
+            let res = client.delete_proof(req).await?.into_inner();
+
+            info!("DeleteProof: err={:?}", res.err);
+        }
+        Command::ListTasks(cmd) => {
+            let state = cmd
+                .state
+                .map(|state| {
+                    TaskState::from_str_name(&state.to_uppercase())
+                        .ok_or_else(|| anyhow::anyhow!("invalid task state {state}"))
+                })
+                .transpose()?;
+
+            let mut page_token = String::new();
+            loop {
+                let req = ListTasksRequest {
+                    app_id: cmd.app_id.clone(),
+                    state: state.map(Into::into),
+                    created_after: cmd.created_after,
+                    created_before: cmd.created_before,
+                    page_size: cmd.page_size,
+                    page_token,
+                };
+                let res = client.list_tasks(req).await?.into_inner();
+
+                info!(
+                    "ListTasks: err={:?}, total_count={}, page_count={}",
+                    res.err,
+                    res.total_count,
+                    res.tasks.len()
+                );
+                for task in &res.tasks {
+                    info!(
+                        "  app_id={}, task_id={}, state={:?}, created_at={}, started_at={:?}, finished_at={:?}, failure_reason={:?}",
+                        task.app_id,
+                        task.task_id,
+                        task.state(),
+                        task.created_at,
+                        task.started_at,
+                        task.finished_at,
+                        task.failure_reason,
+                    );
+                }
+
+                if !cmd.all || res.next_page_token.is_empty() {
+                    break;
+                }
+                page_token = res.next_page_token;
+            }
+        }
+        Command::GetApp(cmd) => {
+            let req = GetAppRequest { app_id: cmd.app_id };
+            let res = client.get_app(req).await?.into_inner();
+
+            info!(
+                "GetApp: err={:?}, app={:?}, warm_up={:?}",
+                res.err, res.app, res.warm_up
+            );
+        }
+        Command::GetAppAnalysis(cmd) => {
+            let req = GetAppAnalysisRequest { app_id: cmd.app_id };
+            let res = client.get_app_analysis(req).await?.into_inner();
+
+            info!(
+                "GetAppAnalysis: err={:?}, analysis={:?}",
+                res.err, res.analysis
+            );
+        }
+        Command::ListApps(cmd) => {
+            let mut page_token = String::new();
+            loop {
+                let req = ListAppsRequest {
+                    page_size: 0,
+                    page_token,
+                    owner: cmd.owner.clone(),
+                    name: cmd.name.clone(),
+                    tag: cmd.tag.clone(),
+                };
+                let res = client.list_apps(req).await?.into_inner();
+
+                info!("ListApps: err={:?}, page_count={}", res.err, res.apps.len());
+                for app in &res.apps {
+                    info!(
+                        "  app_id=0x{}, elf_hash={}, created_at={}, info={:?}, app_info={:?}",
+                        app.app_id, app.elf_hash, app.created_at, app.info, app.app_info,
+                    );
+                }
+
+                if res.next_page_token.is_empty() {
+                    break;
+                }
+                page_token = res.next_page_token;
+            }
+        }
+        Command::ListAppVersions(cmd) => {
+            let req = ListAppVersionsRequest { name: cmd.name };
+            let res = client.list_app_versions(req).await?.into_inner();
+
+            info!("ListAppVersions: err={:?}", res.err);
+            for version in res.versions {
+                info!(
+                    "  version={}, app_id=0x{}, created_at={}",
+                    version.version, version.app_id, version.created_at,
+                );
+            }
+        }
+        Command::DeregisterApp(cmd) => {
+            let req = DeregisterAppRequest { app_id: cmd.app_id };
+            let res = client.deregister_app(req).await?.into_inner();
+
+            info!("DeregisterApp: err={:?}", res.err);
+        }
+        Command::VerifyProof(cmd) => {
+            let claim = match (cmd.pv_digest, cmd.public_values) {
+                (Some(pv_digest), _) => Claim::PvDigest(hex::decode(
+                    pv_digest.strip_prefix("0x").unwrap_or(&pv_digest),
+                )?),
+                (None, Some(file_path)) => Claim::PublicValues(fs::read(file_path)?),
+                (None, None) => anyhow::bail!("pv-digest or public-values is required"),
+            };
+            let proof_type = cmd
+                .proof_type
+                .as_deref()
+                .map(parse_proof_type)
+                .transpose()?
+                .unwrap_or(ProofType::Evm);
+            let req = VerifyProofRequest {
+                verifier: Some(Verifier::AppId(cmd.app_id)),
+                proof: fs::read(cmd.proof)?,
+                claim: Some(claim),
+                proof_type: proof_type.into(),
+            };
+            let res = client.verify_proof(req).await?.into_inner();
+
+            info!(
+                "VerifyProof: err={:?}, valid={}, reason={:?}",
+                res.err, res.valid, res.reason,
+            );
+        }
+        Command::GetOnchainVerifier(cmd) => {
+            let req = GetOnchainVerifierRequest {
+                app_id: cmd.app_id,
+                proof: cmd.task_id.map(OnchainProof::TaskId),
+                claim: None,
+            };
+            let res = client.get_onchain_verifier(req).await?.into_inner();
+
+            info!(
+                "GetOnchainVerifier: err={:?}, verify_function={}, vk_hash=0x{}, source size={} bytes, calldata=0x{}",
+                res.err,
+                res.verify_function,
+                hex::encode(&res.vk_hash),
+                res.verifier_source.len(),
+                hex::encode(&res.calldata),
+            );
+
+            if let Some(file_path) = cmd.output {
+                fs::write(&file_path, &res.verifier_source)?;
+                info!(
+                    "GetOnchainVerifier: verifier saved to {}",
+                    file_path.display()
+                );
+            }
         }
+        Command::GetServiceKey => {
+            let res = client
+                .get_service_key(GetServiceKeyRequest {})
+                .await?
+                .into_inner();
+
+            info!(
+                "GetServiceKey: err={:?}, scheme={:?}, public_key=0x{}, signer={}",
+                res.err,
+                res.scheme(),
+                hex::encode(&res.public_key),
+                res.signer,
+            );
+        }
+        Command::GetGpuStats => {
+            let res = client
+                .get_gpu_stats(GetGpuStatsRequest {})
+                .await?
+                .into_inner();
+
+            info!(
+                "GetGpuStats: err={:?}, cpu_fallbacks={}",
+                res.err, res.cpu_fallbacks
+            );
+            for device in &res.devices {
+                info!(
+                    "  device={}, name={}, memory={}/{}, utilization={}%, healthy={}, running={}, queued={}, failures={}, quarantined_until={:?}",
+                    device.index,
+                    device.name,
+                    device.memory_used,
+                    device.memory_total,
+                    device.utilization,
+                    device.healthy,
+                    device.running_tasks,
+                    device.queued_tasks,
+                    device.consecutive_failures,
+                    device.quarantined_until,
+                );
+            }
+        }
+        Command::GetUsage(cmd) => {
+            let req = GetUsageRequest {
+                app_id: cmd.app_id,
+                signer: cmd.signer,
+                start_time: cmd.start_time,
+                end_time: cmd.end_time,
+            };
+            let res = client.get_usage(req).await?.into_inner();
+
+            info!("GetUsage: err={:?}, total={:?}", res.err, res.total);
+            for app in &res.apps {
+                info!(
+                    "  app_id={}, tasks={}, cycles={}, chunks={}, gpu_seconds={:.1}, stored_bytes={}",
+                    app.app_id,
+                    app.tasks,
+                    app.cycles,
+                    app.chunks,
+                    app.gpu_seconds,
+                    app.stored_bytes,
+                );
+            }
+        }
+        Command::IssueToken(cmd) => {
+            let req = IssueTokenRequest {
+                subject: cmd.subject,
+                scopes: cmd.scopes,
+                ttl_secs: cmd.ttl_secs,
+                namespace: cmd.namespace,
+            };
+            let res = client.issue_token(req).await?.into_inner();
+
+            info!(
+                "IssueToken: err={:?}, token_id={}, expires_at={}",
+                res.err, res.token_id, res.expires_at,
+            );
+            println!("{}", res.token);
+        }
+        Command::RevokeToken(cmd) => {
+            let req = RevokeTokenRequest {
+                token_id: cmd.token_id,
+            };
+            let res = client.revoke_token(req).await?.into_inner();
+
+            info!("RevokeToken: err={:?}", res.err);
+        }
+        Command::ListDeadLetters(cmd) => {
+            let mut page_token = String::new();
+            loop {
+                let req = ListDeadLettersRequest {
+                    app_id: cmd.app_id.clone(),
+                    page_size: cmd.page_size,
+                    page_token,
+                };
+                let res = client.list_dead_letters(req).await?.into_inner();
+
+                info!(
+                    "ListDeadLetters: err={:?}, page_count={}",
+                    res.err,
+                    res.dead_letters.len()
+                );
+                for dead_letter in &res.dead_letters {
+                    info!(
+                        "  app_id={}, task_id={}, attempts={}, failed_at={}, use_gpu={}, priority={:?}, inputs_size={}, failure_reason={}",
+                        dead_letter.app_id,
+                        dead_letter.task_id,
+                        dead_letter.attempts,
+                        dead_letter.failed_at,
+                        dead_letter.use_gpu,
+                        dead_letter.priority(),
+                        dead_letter.inputs_size,
+                        dead_letter.failure_reason,
+                    );
+                }
+
+                if !cmd.all || res.next_page_token.is_empty() {
+                    break;
+                }
+                page_token = res.next_page_token;
+            }
+        }
+        Command::RequeueDeadLetter(cmd) => {
+            let req = RequeueDeadLetterRequest {
+                app_id: cmd.app_id,
+                task_id: cmd.task_id,
+            };
+            let res = client.requeue_dead_letter(req).await?.into_inner();
+
+            info!("RequeueDeadLetter: err={:?}", res.err);
+        }
+        Command::RunBenchmark(cmd) => {
+            let workload = cmd.workload.as_deref().map(parse_workload).transpose()?;
+            let proof_type = cmd
+                .proof_type
+                .as_deref()
+                .map(parse_proof_type)
+                .transpose()?;
+            let req = RunBenchmarkRequest {
+                workload: workload.map(Into::into),
+                proof_type: proof_type.map(Into::into),
+            };
+            let res = client.run_benchmark(req).await?.into_inner();
+
+            info!("RunBenchmark: err={:?}", res.err);
+            for result in res.results {
+                info!(
+                    "backend={}, device={:?}, total_cycles={}, total_chunks={}, proving_secs={:.2}, cycles_per_sec={:.0}, timings={:?}, error={:?}",
+                    result.backend,
+                    result.device,
+                    result.total_cycles,
+                    result.total_chunks,
+                    result.proving_secs,
+                    result.cycles_per_sec,
+                    result.timings,
+                    result.error,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_report(file_path: &Path, reports: &[TaskReport]) -> Result<()> {
+    let mut csv = String::from(
+        "task_id,state,latency_secs,proof_size,attempts,failure_code,failure_reason\n",
+    );
+    for report in reports {
+        // the failure reason is quoted since it may contain commas
+        let failure_reason = report
+            .failure_reason
+            .as_deref()
+            .unwrap_or_default()
+            .replace('"', "\"\"");
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},\"{failure_reason}\"\n",
+            report.task_id,
+            report.state.as_str_name(),
+            report.latency_secs,
+            report.proof_size,
+            report.attempts,
+            report
+                .failure_code
+                .map_or("", |failure_code| failure_code.as_str_name()),
+        ));
     }
+    fs::write(file_path, csv)?;
 
     Ok(())
 }
+
+// the .bin files in the directory sorted by name with their file stems, e.g. as the task IDs
+fn inputs_dir_files(inputs_dir: &Path) -> Result<Vec<(String, Option<PathBuf>)>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(inputs_dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "bin") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        files.push((stem.to_string(), Some(path)));
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn parse_priority(priority: &str) -> Result<TaskPriority> {
+    TaskPriority::from_str_name(&priority.to_uppercase())
+        .ok_or_else(|| anyhow::anyhow!("invalid task priority {priority}"))
+}
+
+fn parse_workload(workload: &str) -> Result<BenchmarkWorkload> {
+    BenchmarkWorkload::from_str_name(&workload.to_uppercase())
+        .ok_or_else(|| anyhow::anyhow!("invalid benchmark workload {workload}"))
+}
+
+fn parse_signature_scheme(scheme: &str) -> Result<SignatureScheme> {
+    SignatureScheme::from_str_name(&scheme.to_uppercase())
+        .ok_or_else(|| anyhow::anyhow!("invalid signature scheme {scheme}"))
+}
+
+fn parse_proof_type(proof_type: &str) -> Result<ProofType> {
+    ProofType::from_str_name(&proof_type.to_uppercase())
+        .ok_or_else(|| anyhow::anyhow!("invalid proof type {proof_type}"))
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use dotenvy::dotenv;
+use pico_proving_service::{
+    config::{self, WorkerConfig},
+    coordinator::worker::run_worker,
+    metrics,
+    proving::onchain::start_onchain_daemon,
+    utils::telemetry::setup_tracing,
+};
+use pico_vm::iter::{ThreadPoolBuilder, current_num_threads};
+use tracing::info;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    // the provider is kept alive to export the spans until exit
+    let _tracer_provider = setup_tracing("pico-proving-worker")?;
+    // Start background docker monitor for on-chain prover
+    start_onchain_daemon();
+
+    ThreadPoolBuilder::new()
+        .num_threads(num_cpus::get())
+        .build_global()
+        .expect("failed to build global Rayon thread pool");
+    info!("initialized Rayon with {} threads", current_num_threads());
+
+    let cfg: WorkerConfig = config::file::parse()?;
+    info!("starting worker with config: {:?}", cfg.redacted());
+    // the proving phases of the claimed tasks are measured by the worker
+    let _metrics = match cfg.metrics_addr {
+        Some(addr) => Some(metrics::serve(addr).await?),
+        None => None,
+    };
+
+    run_worker(cfg).await
+}
@@ -1,4 +1,15 @@
+use std::{env, path::PathBuf};
+
 fn main() {
-    tonic_build::compile_protos("proto/prover_network.proto").unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // the descriptor set is served by the gRPC reflection
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("prover_network_descriptor.bin"))
+        .compile_protos(
+            &["proto/prover_network.proto", "proto/health.proto"],
+            &["proto"],
+        )
+        .unwrap();
     tonic_build::compile_protos("proto/proving.proto").unwrap();
 }
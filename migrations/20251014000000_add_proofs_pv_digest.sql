@@ -0,0 +1 @@
+ALTER TABLE proofs ADD COLUMN pv_digest BLOB;
@@ -0,0 +1,2 @@
+ALTER TABLE apps ADD COLUMN elf_hash TEXT;
+ALTER TABLE apps ADD COLUMN created_at INTEGER;
@@ -0,0 +1,13 @@
+CREATE TABLE tasks (
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    inputs BLOB,
+    use_gpu BOOLEAN NOT NULL DEFAULT FALSE,
+    state INTEGER NOT NULL,
+    failure_reason TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (app_id, task_id)
+);
+
+CREATE INDEX tasks_state ON tasks (state, created_at);
@@ -0,0 +1 @@
+CREATE UNIQUE INDEX apps_elf_hash ON apps (elf_hash);
@@ -0,0 +1,8 @@
+CREATE TABLE app_versions (
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    app_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (name, version),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
@@ -0,0 +1,7 @@
+ALTER TABLE apps ADD COLUMN app_name TEXT;
+ALTER TABLE apps ADD COLUMN description TEXT;
+ALTER TABLE apps ADD COLUMN owner TEXT;
+ALTER TABLE apps ADD COLUMN tags TEXT;
+ALTER TABLE apps ADD COLUMN toolchain_version TEXT;
+
+CREATE INDEX apps_owner ON apps (owner);
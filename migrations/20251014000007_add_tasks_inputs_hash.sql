@@ -0,0 +1,3 @@
+ALTER TABLE tasks ADD COLUMN inputs_hash TEXT;
+
+CREATE INDEX tasks_inputs_hash ON tasks (app_id, inputs_hash);
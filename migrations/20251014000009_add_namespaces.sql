@@ -0,0 +1,77 @@
+-- the apps are registered in the namespaces, and the tasks, proofs and app versions are partitioned
+-- by them. the existing ones belong to the default namespace, and the tables are rebuilt since
+-- sqlite cannot alter the primary keys
+CREATE TABLE app_namespaces (
+    namespace TEXT NOT NULL,
+    app_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (namespace, app_id),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
+
+CREATE INDEX app_namespaces_app_id ON app_namespaces (app_id);
+
+INSERT INTO app_namespaces (namespace, app_id, created_at)
+SELECT 'default', app_id, COALESCE(created_at, 0) FROM apps;
+
+CREATE TABLE tasks_new (
+    namespace TEXT NOT NULL DEFAULT 'default',
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    inputs BLOB,
+    use_gpu BOOLEAN NOT NULL DEFAULT FALSE,
+    state INTEGER NOT NULL,
+    failure_reason TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    inputs_hash TEXT,
+    PRIMARY KEY (namespace, app_id, task_id)
+);
+
+INSERT INTO tasks_new (app_id, task_id, inputs, use_gpu, state, failure_reason, created_at,
+    updated_at, priority, inputs_hash)
+SELECT app_id, task_id, inputs, use_gpu, state, failure_reason, created_at, updated_at, priority,
+    inputs_hash
+FROM tasks ORDER BY rowid;
+
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+
+CREATE INDEX tasks_state ON tasks (state, created_at);
+CREATE INDEX tasks_inputs_hash ON tasks (namespace, app_id, inputs_hash);
+
+CREATE TABLE proofs_new (
+    namespace TEXT NOT NULL DEFAULT 'default',
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    proof BLOB,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    pv_digest BLOB,
+    PRIMARY KEY (namespace, app_id, task_id),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
+
+INSERT INTO proofs_new (app_id, task_id, proof, created_at, pv_digest)
+SELECT app_id, task_id, proof, created_at, pv_digest FROM proofs;
+
+DROP TABLE proofs;
+ALTER TABLE proofs_new RENAME TO proofs;
+
+CREATE TABLE app_versions_new (
+    namespace TEXT NOT NULL DEFAULT 'default',
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    app_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (namespace, name, version),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
+
+INSERT INTO app_versions_new (name, version, app_id, created_at)
+SELECT name, version, app_id, created_at FROM app_versions;
+
+DROP TABLE app_versions;
+ALTER TABLE app_versions_new RENAME TO app_versions;
+
+ALTER TABLE tokens ADD COLUMN namespace TEXT;
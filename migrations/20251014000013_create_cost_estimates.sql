@@ -0,0 +1,11 @@
+-- cached estimates of the inputs of the apps, they're shared by the namespaces since the same elf
+-- and inputs are estimated the same
+CREATE TABLE cost_estimates (
+    app_id TEXT NOT NULL,
+    inputs_hash TEXT NOT NULL,
+    cost INTEGER NOT NULL,
+    total_cycles INTEGER NOT NULL,
+    pv_digest BLOB NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (app_id, inputs_hash)
+);
@@ -0,0 +1,11 @@
+-- proving work accumulated by the completed tasks of each backend (cpu or gpu), it calibrates the
+-- proving time and price of the estimates
+CREATE TABLE calibrations (
+    backend TEXT PRIMARY KEY NOT NULL,
+    tasks INTEGER NOT NULL,
+    cycles INTEGER NOT NULL,
+    chunks INTEGER NOT NULL,
+    proving_seconds REAL NOT NULL,
+    device_seconds REAL NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,2 @@
+-- unix timestamp (seconds) before which the task is held in the queue
+ALTER TABLE tasks ADD COLUMN not_before INTEGER;
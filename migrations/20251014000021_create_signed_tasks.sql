@@ -0,0 +1,13 @@
+-- proving tasks submitted by the signed requests, a nonce is used once by a signer to reject the
+-- replays, and the usage of the tasks is accounted to their signers
+CREATE TABLE signed_tasks (
+    signer TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    namespace TEXT NOT NULL,
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (signer, nonce)
+);
+
+CREATE INDEX signed_tasks_task ON signed_tasks (namespace, app_id, task_id);
@@ -0,0 +1,13 @@
+-- static analysis of the elfs made on registration, it's shared by the namespaces registering the
+-- same elf. `syscalls` is the comma-separated CODE=CALL_SITES of the referenced syscalls in hex
+CREATE TABLE app_analyses (
+    app_id TEXT PRIMARY KEY NOT NULL,
+    text_size INTEGER NOT NULL,
+    data_size INTEGER NOT NULL,
+    bss_size INTEGER NOT NULL,
+    memory_image_size INTEGER NOT NULL,
+    entry INTEGER NOT NULL,
+    syscalls TEXT NOT NULL,
+    baseline_cycles INTEGER,
+    created_at INTEGER NOT NULL
+);
@@ -0,0 +1,3 @@
+-- the task is failed if the public values digest of its emulation mismatches
+ALTER TABLE tasks ADD COLUMN expected_pv_digest BLOB;
+ALTER TABLE dead_letters ADD COLUMN expected_pv_digest BLOB;
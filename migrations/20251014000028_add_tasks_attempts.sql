@@ -0,0 +1,6 @@
+-- unix timestamp (seconds) of starting the last attempt
+ALTER TABLE tasks ADD COLUMN started_at INTEGER;
+-- proving attempts started so far
+ALTER TABLE tasks ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+-- protobuf encoded PhaseTimings once completed
+ALTER TABLE tasks ADD COLUMN timings BLOB;
@@ -0,0 +1,33 @@
+CREATE TABLE apps (
+    app_id TEXT PRIMARY KEY NOT NULL,
+    program BYTEA NOT NULL,
+    pk BYTEA NOT NULL,
+    vk BYTEA NOT NULL,
+    info TEXT,
+    elf_hash TEXT,
+    created_at BIGINT
+);
+
+CREATE TABLE proofs (
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    proof BYTEA,
+    pv_digest BYTEA,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    PRIMARY KEY (app_id, task_id),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
+
+CREATE TABLE tasks (
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    inputs BYTEA,
+    use_gpu BOOLEAN NOT NULL DEFAULT FALSE,
+    state INTEGER NOT NULL,
+    failure_reason TEXT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (app_id, task_id)
+);
+
+CREATE INDEX tasks_state ON tasks (state, created_at);
@@ -0,0 +1,8 @@
+CREATE TABLE app_versions (
+    name TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    app_id TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (name, version),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
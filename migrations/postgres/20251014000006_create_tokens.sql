@@ -0,0 +1,10 @@
+CREATE TABLE tokens (
+    token_id TEXT PRIMARY KEY NOT NULL,
+    subject TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX tokens_revoked ON tokens (revoked, expires_at);
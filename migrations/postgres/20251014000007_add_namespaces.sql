@@ -0,0 +1,31 @@
+-- the apps are registered in the namespaces, and the tasks, proofs and app versions are partitioned
+-- by them. the existing ones belong to the default namespace
+CREATE TABLE app_namespaces (
+    namespace TEXT NOT NULL,
+    app_id TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (namespace, app_id),
+    FOREIGN KEY (app_id) REFERENCES apps (app_id)
+);
+
+CREATE INDEX app_namespaces_app_id ON app_namespaces (app_id);
+
+INSERT INTO app_namespaces (namespace, app_id, created_at)
+SELECT 'default', app_id, COALESCE(created_at, 0) FROM apps;
+
+ALTER TABLE tasks ADD COLUMN namespace TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE tasks DROP CONSTRAINT tasks_pkey;
+ALTER TABLE tasks ADD PRIMARY KEY (namespace, app_id, task_id);
+
+DROP INDEX tasks_inputs_hash;
+CREATE INDEX tasks_inputs_hash ON tasks (namespace, app_id, inputs_hash);
+
+ALTER TABLE proofs ADD COLUMN namespace TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE proofs DROP CONSTRAINT proofs_pkey;
+ALTER TABLE proofs ADD PRIMARY KEY (namespace, app_id, task_id);
+
+ALTER TABLE app_versions ADD COLUMN namespace TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE app_versions DROP CONSTRAINT app_versions_pkey;
+ALTER TABLE app_versions ADD PRIMARY KEY (namespace, name, version);
+
+ALTER TABLE tokens ADD COLUMN namespace TEXT;
@@ -0,0 +1,16 @@
+-- permanently failed tasks with their inputs, they're kept until requeued or the app is deregistered
+CREATE TABLE dead_letters (
+    namespace TEXT NOT NULL,
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    inputs BYTEA,
+    use_gpu BOOLEAN NOT NULL DEFAULT FALSE,
+    priority INTEGER NOT NULL DEFAULT 0,
+    callback_url TEXT,
+    failure_reason TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    failed_at BIGINT NOT NULL,
+    PRIMARY KEY (namespace, app_id, task_id)
+);
+
+CREATE INDEX dead_letters_failed_at ON dead_letters (namespace, failed_at);
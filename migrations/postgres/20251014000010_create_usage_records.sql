@@ -0,0 +1,13 @@
+-- proving work of the completed tasks for billing the apps, a task proved again is recorded again
+CREATE TABLE usage_records (
+    namespace TEXT NOT NULL,
+    app_id TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    cycles BIGINT NOT NULL,
+    chunks BIGINT NOT NULL,
+    gpu_seconds DOUBLE PRECISION NOT NULL,
+    stored_bytes BIGINT NOT NULL,
+    recorded_at BIGINT NOT NULL
+);
+
+CREATE INDEX usage_records_recorded_at ON usage_records (namespace, recorded_at, app_id);
@@ -0,0 +1,11 @@
+-- proving work accumulated by the completed tasks of each backend (cpu or gpu), it calibrates the
+-- proving time and price of the estimates
+CREATE TABLE calibrations (
+    backend TEXT PRIMARY KEY NOT NULL,
+    tasks BIGINT NOT NULL,
+    cycles BIGINT NOT NULL,
+    chunks BIGINT NOT NULL,
+    proving_seconds DOUBLE PRECISION NOT NULL,
+    device_seconds DOUBLE PRECISION NOT NULL,
+    updated_at BIGINT NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE tasks ADD COLUMN verify_proof BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE dead_letters ADD COLUMN verify_proof BOOLEAN NOT NULL DEFAULT FALSE;
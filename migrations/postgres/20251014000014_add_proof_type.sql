@@ -0,0 +1,4 @@
+-- proof type (ProofType) of the tasks and proofs, EVM (0) by default
+ALTER TABLE tasks ADD COLUMN proof_type INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE dead_letters ADD COLUMN proof_type INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE proofs ADD COLUMN proof_type INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- task IDs of the same app separated by comma, their proofs are written to the stdin of the task
+ALTER TABLE tasks ADD COLUMN depends_on TEXT;
+ALTER TABLE dead_letters ADD COLUMN depends_on TEXT;
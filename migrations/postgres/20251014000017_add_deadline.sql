@@ -0,0 +1,2 @@
+-- unix timestamp (seconds) by which the task must complete
+ALTER TABLE tasks ADD COLUMN deadline BIGINT;
@@ -0,0 +1,10 @@
+-- inputs uploaded once to be referenced by their hash in the tasks of the namespace, `inputs` is
+-- NULL if they're offloaded to the artifact store
+CREATE TABLE uploaded_inputs (
+    namespace TEXT NOT NULL,
+    inputs_hash TEXT NOT NULL,
+    inputs BYTEA,
+    size BIGINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (namespace, inputs_hash)
+);
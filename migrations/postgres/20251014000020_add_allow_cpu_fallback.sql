@@ -0,0 +1,3 @@
+-- the GPU task is proved on CPU if no healthy device
+ALTER TABLE tasks ADD COLUMN allow_cpu_fallback BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE dead_letters ADD COLUMN allow_cpu_fallback BOOLEAN NOT NULL DEFAULT FALSE;
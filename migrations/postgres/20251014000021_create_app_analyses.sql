@@ -0,0 +1,13 @@
+-- static analysis of the elfs made on registration, it's shared by the namespaces registering the
+-- same elf. `syscalls` is the comma-separated CODE=CALL_SITES of the referenced syscalls in hex
+CREATE TABLE app_analyses (
+    app_id TEXT PRIMARY KEY NOT NULL,
+    text_size BIGINT NOT NULL,
+    data_size BIGINT NOT NULL,
+    bss_size BIGINT NOT NULL,
+    memory_image_size BIGINT NOT NULL,
+    entry BIGINT NOT NULL,
+    syscalls TEXT NOT NULL,
+    baseline_cycles BIGINT,
+    created_at BIGINT NOT NULL
+);
@@ -0,0 +1,6 @@
+-- provenance of the proofs returned in the proof metadata, null for the proofs stored before
+ALTER TABLE proofs ADD COLUMN prover_version TEXT;
+ALTER TABLE proofs ADD COLUMN service_version TEXT;
+ALTER TABLE proofs ADD COLUMN elf_hash TEXT;
+ALTER TABLE proofs ADD COLUMN inputs_hash TEXT;
+ALTER TABLE proofs ADD COLUMN proving_seconds DOUBLE PRECISION;
@@ -0,0 +1,2 @@
+-- signature of the service over the proof envelope, null if not signed
+ALTER TABLE proofs ADD COLUMN signature BYTEA;
@@ -0,0 +1,18 @@
+-- the info of an app is registered by each namespace, so the namespaces sharing the elf don't see
+-- the info of each other. the existing registrations keep the info of the app
+ALTER TABLE app_namespaces ADD COLUMN info TEXT;
+ALTER TABLE app_namespaces ADD COLUMN app_name TEXT;
+ALTER TABLE app_namespaces ADD COLUMN description TEXT;
+ALTER TABLE app_namespaces ADD COLUMN owner TEXT;
+ALTER TABLE app_namespaces ADD COLUMN tags TEXT;
+ALTER TABLE app_namespaces ADD COLUMN toolchain_version TEXT;
+
+UPDATE app_namespaces SET
+    info = (SELECT info FROM apps WHERE apps.app_id = app_namespaces.app_id),
+    app_name = (SELECT app_name FROM apps WHERE apps.app_id = app_namespaces.app_id),
+    description = (SELECT description FROM apps WHERE apps.app_id = app_namespaces.app_id),
+    owner = (SELECT owner FROM apps WHERE apps.app_id = app_namespaces.app_id),
+    tags = (SELECT tags FROM apps WHERE apps.app_id = app_namespaces.app_id),
+    toolchain_version = (SELECT toolchain_version FROM apps WHERE apps.app_id = app_namespaces.app_id);
+
+CREATE INDEX app_namespaces_owner ON app_namespaces (namespace, owner);
@@ -0,0 +1,70 @@
+use crate::{proving::ProvedInfo, proving_queue::ProvingKey, storage::SharedStorage};
+use anyhow::Result;
+use sqlx::FromRow;
+use tracing::error;
+
+// proving work of a completed task billed to its app
+#[derive(Clone, Debug)]
+pub struct UsageRecord {
+    pub key: ProvingKey,
+    pub cycles: u64,
+    pub chunks: u64,
+    pub gpu_seconds: f64,
+    // bytes of the stored proof
+    pub stored_bytes: u64,
+    // unix timestamp (seconds) of completion
+    pub recorded_at: u64,
+}
+
+impl UsageRecord {
+    pub fn new(key: &ProvingKey, info: &ProvedInfo, recorded_at: u64) -> Self {
+        Self {
+            key: key.clone(),
+            cycles: info.total_cycles,
+            chunks: info.total_chunks,
+            gpu_seconds: info.gpu_seconds,
+            stored_bytes: info.proof.len() as u64,
+            recorded_at,
+        }
+    }
+}
+
+// aggregated usage of an app over a time window
+#[derive(Debug, Default, FromRow)]
+pub struct AppUsageRow {
+    pub app_id: String,
+    pub tasks: i64,
+    pub cycles: i64,
+    pub chunks: i64,
+    pub gpu_seconds: f64,
+    pub stored_bytes: i64,
+}
+
+// record the usage of the completed task, the failure is only logged to not fail the task
+pub async fn record_usage(storage: &SharedStorage, usage: &UsageRecord) {
+    if let Err(e) = storage.insert_usage(usage).await {
+        error!(
+            "[accounting] failed to record usage of task {:?}: {e}",
+            usage.key
+        );
+    }
+}
+
+// usage of the apps in the namespace completed in [start, end), ordered by app ID. only the tasks
+// signed by the signer are counted if set
+pub async fn app_usage(
+    storage: &SharedStorage,
+    namespace: &str,
+    app_id: Option<&str>,
+    signer: Option<&str>,
+    start: u64,
+    end: u64,
+) -> Result<Vec<AppUsageRow>> {
+    if start >= end {
+        return Ok(vec![]);
+    }
+
+    storage
+        .app_usage(namespace, app_id, signer, start, end)
+        .await
+}
@@ -0,0 +1,227 @@
+use crate::{
+    AppAnalysis, SyscallRef,
+    app_manager::{App, ElfError},
+    cost_estimation::estimate_cost,
+};
+use elf::{
+    ElfBytes,
+    abi::{PF_X, PT_LOAD},
+    endian::LittleEndian,
+};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+
+// maximum cycles of the baseline emulation with empty inputs, no baseline if exceeded
+const BASELINE_MAX_CYCLES: u64 = 10_000_000;
+
+// the syscall code is loaded into t0 before ecall
+const T0: u32 = 5;
+const ECALL: u32 = 0x0000_0073;
+
+// names of the syscall codes of the guests
+const SYSCALL_NAMES: &[(u32, &str)] = &[
+    (0x00_00_00_00, "HALT"),
+    (0x00_00_00_02, "WRITE"),
+    (0x00_00_00_03, "ENTER_UNCONSTRAINED"),
+    (0x00_00_00_04, "EXIT_UNCONSTRAINED"),
+    (0x00_30_01_05, "SHA_EXTEND"),
+    (0x00_01_01_06, "SHA_COMPRESS"),
+    (0x00_01_01_07, "ED_ADD"),
+    (0x00_00_01_08, "ED_DECOMPRESS"),
+    (0x00_01_01_09, "KECCAK_PERMUTE"),
+    (0x00_01_01_0A, "SECP256K1_ADD"),
+    (0x00_00_01_0B, "SECP256K1_DOUBLE"),
+    (0x00_00_01_0C, "SECP256K1_DECOMPRESS"),
+    (0x00_01_01_0E, "BN254_ADD"),
+    (0x00_00_01_0F, "BN254_DOUBLE"),
+    (0x00_00_00_10, "COMMIT"),
+    (0x00_00_00_1A, "COMMIT_DEFERRED_PROOFS"),
+    (0x00_00_01_1C, "BLS12381_DECOMPRESS"),
+    (0x00_01_01_1D, "UINT256_MUL"),
+    (0x00_01_01_1E, "BLS12381_ADD"),
+    (0x00_00_01_1F, "BLS12381_DOUBLE"),
+    (0x00_00_00_F0, "HINT_LEN"),
+    (0x00_00_00_F1, "HINT_READ"),
+];
+
+// static analysis of an elf, the sizes are in bytes
+#[derive(Debug, Default)]
+pub struct ElfAnalysis {
+    // loaded bytes of the executable segments
+    pub text_size: u64,
+    // loaded bytes of the other segments
+    pub data_size: u64,
+    // zero-initialized bytes of all the segments
+    pub bss_size: u64,
+    pub entry: u32,
+    // call sites of the syscall codes loaded before ecall
+    pub syscalls: BTreeMap<u32, u32>,
+}
+
+impl ElfAnalysis {
+    // the memory image loaded before running the first instruction
+    pub fn memory_image_size(&self) -> u64 {
+        self.text_size + self.data_size + self.bss_size
+    }
+}
+
+// analyze an elf accepted by `validate_elf`
+pub fn analyze_elf(elf: &[u8]) -> Result<ElfAnalysis, ElfError> {
+    let file = ElfBytes::<LittleEndian>::minimal_parse(elf)
+        .map_err(|e| ElfError::Header(e.to_string()))?;
+    let mut analysis = ElfAnalysis {
+        entry: file.ehdr.e_entry as u32,
+        ..Default::default()
+    };
+    let segments = file
+        .segments()
+        .ok_or(ElfError::NoSegment)?
+        .iter()
+        .filter(|segment| segment.p_type == PT_LOAD);
+    for segment in segments {
+        analysis.bss_size += segment.p_memsz - segment.p_filesz;
+        if segment.p_flags & PF_X == 0 {
+            analysis.data_size += segment.p_filesz;
+            continue;
+        }
+        analysis.text_size += segment.p_filesz;
+        let start = segment.p_offset as usize;
+        let code = &elf[start..start + segment.p_filesz as usize];
+        scan_syscalls(code, &mut analysis.syscalls);
+    }
+
+    Ok(analysis)
+}
+
+// cycles of running the app with empty inputs, none if the guest requires inputs or exceeds
+// `BASELINE_MAX_CYCLES`
+pub fn baseline_cycles(app: &App) -> Option<u64> {
+    estimate_cost(
+        app.program.clone(),
+        app.pk.clone(),
+        app.vk.clone(),
+        None,
+        Some(BASELINE_MAX_CYCLES),
+        false,
+    )
+    .ok()
+    .map(|info| info.total_cycles)
+}
+
+// track the value of t0 by the `lui` and `addi` writing it, and count the known code at each
+// ecall. it's rough since the jumps and the data mixed in the code are not followed
+fn scan_syscalls(code: &[u8], syscalls: &mut BTreeMap<u32, u32>) {
+    let mut t0: Option<u32> = None;
+    for word in code.chunks_exact(4) {
+        let inst = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        if inst == ECALL {
+            if let Some(code) = t0 {
+                *syscalls.entry(code).or_default() += 1;
+            }
+            continue;
+        }
+
+        let opcode = inst & 0x7f;
+        let rd = (inst >> 7) & 0x1f;
+        let funct3 = (inst >> 12) & 0x7;
+        let rs1 = (inst >> 15) & 0x1f;
+        let imm = ((inst as i32) >> 20) as u32;
+        t0 = match opcode {
+            // lui
+            0x37 if rd == T0 => Some(inst & 0xffff_f000),
+            // addi from zero or t0
+            0x13 if rd == T0 && funct3 == 0 && rs1 == 0 => Some(imm),
+            0x13 if rd == T0 && funct3 == 0 && rs1 == T0 => t0.map(|t0| t0.wrapping_add(imm)),
+            // stores and branches don't write rd
+            0x23 | 0x63 => t0,
+            _ if rd == T0 => None,
+            _ => t0,
+        };
+    }
+}
+
+pub fn syscall_name(code: u32) -> Option<&'static str> {
+    SYSCALL_NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| *name)
+}
+
+// the second byte of a syscall code is set if it's proved by a precompile chip
+pub fn is_precompile(code: u32) -> bool {
+    (code >> 8) & 0xff != 0
+}
+
+#[derive(Debug, FromRow)]
+pub struct AppAnalysisRow {
+    pub app_id: String,
+    pub text_size: i64,
+    pub data_size: i64,
+    pub bss_size: i64,
+    pub memory_image_size: i64,
+    pub entry: i64,
+    // comma-separated CODE=CALL_SITES in hex
+    pub syscalls: String,
+    pub baseline_cycles: Option<i64>,
+    // unix timestamp (seconds) of the analysis
+    pub created_at: i64,
+}
+
+impl AppAnalysisRow {
+    pub fn new(
+        app_id: String,
+        analysis: &ElfAnalysis,
+        baseline_cycles: Option<u64>,
+        created_at: u64,
+    ) -> Self {
+        let syscalls = analysis
+            .syscalls
+            .iter()
+            .map(|(code, sites)| format!("{code:08x}={sites:x}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Self {
+            app_id,
+            text_size: analysis.text_size as i64,
+            data_size: analysis.data_size as i64,
+            bss_size: analysis.bss_size as i64,
+            memory_image_size: analysis.memory_image_size() as i64,
+            entry: analysis.entry as i64,
+            syscalls,
+            baseline_cycles: baseline_cycles.map(|cycles| cycles as i64),
+            created_at: created_at as i64,
+        }
+    }
+}
+
+impl From<AppAnalysisRow> for AppAnalysis {
+    fn from(row: AppAnalysisRow) -> Self {
+        let syscalls = row
+            .syscalls
+            .split(',')
+            .filter_map(|syscall| {
+                let (code, sites) = syscall.split_once('=')?;
+                let code = u32::from_str_radix(code, 16).ok()?;
+                Some(SyscallRef {
+                    code,
+                    name: syscall_name(code).map(str::to_string),
+                    precompile: is_precompile(code),
+                    call_sites: u32::from_str_radix(sites, 16).ok()?,
+                })
+            })
+            .collect();
+
+        Self {
+            app_id: row.app_id,
+            text_size: row.text_size as u64,
+            data_size: row.data_size as u64,
+            bss_size: row.bss_size as u64,
+            memory_image_size: row.memory_image_size as u64,
+            entry: row.entry as u32,
+            syscalls,
+            baseline_cycles: row.baseline_cycles.map(|cycles| cycles as u64),
+            analyzed_at: row.created_at as u64,
+        }
+    }
+}
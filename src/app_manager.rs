@@ -1,6 +1,18 @@
-use crate::types::{DbPool, SC, Val};
-use anyhow::{Result, bail};
+use crate::{
+    AppInfo,
+    app_analysis::{AppAnalysisRow, analyze_elf, baseline_cycles},
+    storage::{AppDeletion, SharedStorage},
+    types::{SC, Val},
+    utils::time::unix_timestamp,
+};
+use anyhow::Result;
 use derive_more::Constructor;
+use elf::{
+    ElfBytes,
+    abi::{EM_RISCV, ET_EXEC, PF_X, PT_LOAD},
+    endian::LittleEndian,
+    file::Class,
+};
 use pico_vm::{
     compiler::riscv::{
         compiler::{Compiler, SourceType},
@@ -17,8 +29,10 @@ use pico_vm::{
     },
     primitives::consts::RISCV_NUM_PVS,
 };
+use sha2::{Digest, Sha256};
 use sqlx::FromRow;
 use std::sync::Arc;
+use thiserror::Error;
 use tracing::info;
 
 #[derive(Clone)]
@@ -28,11 +42,14 @@ pub struct App {
     pub pk: BaseProvingKey<SC>,
     pub vk: BaseVerifyingKey<SC>,
     pub info: Option<String>,
+    // hex-encoded sha256 of the elf, it's empty for the apps registered without this field
+    pub elf_hash: String,
 }
 
 impl App {
     // create an app
     pub fn new(elf: &[u8], info: Option<String>) -> Self {
+        let elf_hash = elf_hash(elf);
         info!("compiling elf to program");
         let mut program = Compiler::new(SourceType::RISCV, elf).compile();
 
@@ -65,6 +82,7 @@ impl App {
             pk,
             vk,
             info,
+            elf_hash,
         }
     }
 }
@@ -76,6 +94,7 @@ pub struct AppRow {
     pub pk: Vec<u8>,
     pub vk: Vec<u8>,
     pub info: Option<String>,
+    pub elf_hash: Option<String>,
 }
 
 impl From<App> for AppRow {
@@ -85,6 +104,7 @@ impl From<App> for AppRow {
         let pk = bincode::serialize(&app.pk).unwrap();
         let vk = bincode::serialize(&app.vk).unwrap();
         let info = app.info;
+        let elf_hash = Some(app.elf_hash);
 
         Self {
             app_id,
@@ -92,6 +112,7 @@ impl From<App> for AppRow {
             pk,
             vk,
             info,
+            elf_hash,
         }
     }
 }
@@ -103,6 +124,7 @@ impl From<AppRow> for App {
         let pk = bincode::deserialize(&row.pk).unwrap();
         let vk = bincode::deserialize(&row.vk).unwrap();
         let info = row.info;
+        let elf_hash = row.elf_hash.unwrap_or_default();
 
         Self {
             app_id,
@@ -110,52 +132,390 @@ impl From<AppRow> for App {
             pk,
             vk,
             info,
+            elf_hash,
+        }
+    }
+}
+
+// app metadata without the program and keys
+#[derive(Debug, FromRow)]
+pub struct AppMetaRow {
+    pub app_id: String,
+    pub elf_hash: Option<String>,
+    // unix timestamp (seconds) of registration
+    pub created_at: Option<i64>,
+    pub info: Option<String>,
+    #[sqlx(flatten)]
+    pub app_info: AppInfoRow,
+}
+
+// structured app info, the empty fields are stored as null
+#[derive(Debug, Default, FromRow)]
+pub struct AppInfoRow {
+    pub app_name: Option<String>,
+    pub description: Option<String>,
+    pub owner: Option<String>,
+    // comma-separated tags
+    pub tags: Option<String>,
+    pub toolchain_version: Option<String>,
+}
+
+impl TryFrom<AppInfo> for AppInfoRow {
+    type Error = RegisterAppError;
+
+    fn try_from(info: AppInfo) -> Result<Self, Self::Error> {
+        if let Some(tag) = info
+            .tags
+            .iter()
+            .find(|tag| tag.trim().is_empty() || tag.contains(','))
+        {
+            return Err(RegisterAppError::InvalidInfo(format!(
+                "tag {tag:?} must not be empty or contain comma"
+            )));
+        }
+        let non_empty = |value: String| (!value.is_empty()).then_some(value);
+
+        Ok(Self {
+            app_name: non_empty(info.name),
+            description: non_empty(info.description),
+            owner: non_empty(info.owner),
+            tags: non_empty(info.tags.join(",")),
+            toolchain_version: non_empty(info.toolchain_version),
+        })
+    }
+}
+
+impl From<AppInfoRow> for AppInfo {
+    fn from(row: AppInfoRow) -> Self {
+        let tags = row
+            .tags
+            .map(|tags| tags.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+
+        Self {
+            name: row.app_name.unwrap_or_default(),
+            description: row.description.unwrap_or_default(),
+            owner: row.owner.unwrap_or_default(),
+            tags,
+            toolchain_version: row.toolchain_version.unwrap_or_default(),
+        }
+    }
+}
+
+// filters of listing the apps by the app info, all the set filters must match
+#[derive(Debug, Default)]
+pub struct AppFilter {
+    // the apps of all the namespaces are listed if not set
+    pub namespace: Option<String>,
+    pub owner: Option<String>,
+    pub name: Option<String>,
+    pub tag: Option<String>,
+}
+
+// a version of a named app, the name maps to the history of its elf versions
+#[derive(Debug, FromRow)]
+pub struct AppVersionRow {
+    pub name: String,
+    pub version: i64,
+    pub app_id: String,
+    // unix timestamp (seconds) of registering this version
+    pub created_at: i64,
+}
+
+// result of a registration
+#[derive(Constructor, Debug)]
+pub struct Registration {
+    pub app_id: String,
+    // version of the named app if registered with a name
+    pub version: Option<u32>,
+}
+
+#[derive(Debug, Error)]
+pub enum RegisterAppError {
+    // the same elf is registered, the existing app ID is returned
+    #[error("app already exists {0}")]
+    AlreadyExists(String),
+
+    #[error("invalid elf: {0}")]
+    InvalidElf(#[from] ElfError),
+
+    #[error("invalid app info: {0}")]
+    InvalidInfo(String),
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+// reason of rejecting an elf which couldn't be loaded as a program
+#[derive(Debug, Error)]
+pub enum ElfError {
+    #[error("cannot parse elf header: {0}")]
+    Header(String),
+
+    #[error("expected 32-bit elf, found {0}")]
+    Class(String),
+
+    #[error("expected RISC-V machine ({EM_RISCV}), found {0}")]
+    Machine(u16),
+
+    #[error("expected executable file type ({ET_EXEC}), found {0}")]
+    FileType(u16),
+
+    #[error("no loadable segment")]
+    NoSegment,
+
+    #[error("segment {index} at {vaddr:#x} is not word aligned")]
+    UnalignedSegment { index: usize, vaddr: u64 },
+
+    #[error("segment {index} file size {file_size} exceeds memory size {mem_size}")]
+    SegmentSize {
+        index: usize,
+        file_size: u64,
+        mem_size: u64,
+    },
+
+    #[error("segment {index} data at offset {offset} of {size} bytes exceeds the elf")]
+    SegmentData {
+        index: usize,
+        offset: u64,
+        size: u64,
+    },
+
+    #[error("segment {index} at {vaddr:#x} of {size} bytes exceeds the 32-bit address space")]
+    SegmentRange { index: usize, vaddr: u64, size: u64 },
+
+    #[error("entry point {0:#x} is not word aligned")]
+    UnalignedEntry(u64),
+
+    #[error("entry point {0:#x} is not in any executable segment")]
+    EntryOutOfSegment(u64),
+}
+
+// check the elf is a RISC-V executable loadable by the compiler, which panics on an invalid one
+pub fn validate_elf(elf: &[u8]) -> Result<(), ElfError> {
+    let file = ElfBytes::<LittleEndian>::minimal_parse(elf)
+        .map_err(|e| ElfError::Header(e.to_string()))?;
+    let header = &file.ehdr;
+    if header.class != Class::ELF32 {
+        return Err(ElfError::Class(format!("{:?}", header.class)));
+    }
+    if header.e_machine != EM_RISCV {
+        return Err(ElfError::Machine(header.e_machine));
+    }
+    if header.e_type != ET_EXEC {
+        return Err(ElfError::FileType(header.e_type));
+    }
+
+    let segments = file
+        .segments()
+        .ok_or(ElfError::NoSegment)?
+        .iter()
+        .filter(|segment| segment.p_type == PT_LOAD)
+        .collect::<Vec<_>>();
+    if segments.is_empty() {
+        return Err(ElfError::NoSegment);
+    }
+    for (index, segment) in segments.iter().enumerate() {
+        let (vaddr, file_size, mem_size) = (segment.p_vaddr, segment.p_filesz, segment.p_memsz);
+        if vaddr % 4 != 0 {
+            return Err(ElfError::UnalignedSegment { index, vaddr });
         }
+        if file_size > mem_size {
+            return Err(ElfError::SegmentSize {
+                index,
+                file_size,
+                mem_size,
+            });
+        }
+        let offset = segment.p_offset;
+        if offset
+            .checked_add(file_size)
+            .is_none_or(|end| end > elf.len() as u64)
+        {
+            return Err(ElfError::SegmentData {
+                index,
+                offset,
+                size: file_size,
+            });
+        }
+        if vaddr.checked_add(mem_size).is_none_or(|end| end > 1 << 32) {
+            return Err(ElfError::SegmentRange {
+                index,
+                vaddr,
+                size: mem_size,
+            });
+        }
+    }
+
+    let entry = header.e_entry;
+    if entry % 4 != 0 {
+        return Err(ElfError::UnalignedEntry(entry));
+    }
+    let in_code = segments.iter().any(|segment| {
+        segment.p_flags & PF_X != 0
+            && (segment.p_vaddr..segment.p_vaddr + segment.p_filesz).contains(&entry)
+    });
+    if !in_code {
+        return Err(ElfError::EntryOutOfSegment(entry));
     }
+
+    Ok(())
+}
+
+// hex-encoded sha256 of the elf, the elf is stored once by this content hash
+pub fn elf_hash(elf: &[u8]) -> String {
+    hex::encode(Sha256::digest(elf))
 }
 
 #[derive(Constructor)]
 pub struct AppManager {
-    db_pool: Arc<DbPool>,
+    storage: SharedStorage,
 }
 
 impl AppManager {
-    pub async fn get_app(&self, app_id: &str) -> Result<Option<App>> {
+    // get the app registered in the namespace
+    pub async fn get_app(&self, namespace: &str, app_id: &str) -> Result<Option<App>> {
         // remove the prefix `0x`
         let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
 
-        let row = sqlx::query_as::<_, AppRow>(
-            "SELECT app_id, program, pk, vk, info FROM apps WHERE app_id = ?",
-        )
-        .bind(app_id)
-        .fetch_optional(&*self.db_pool)
-        .await?;
+        let row = self.storage.get_app(Some(namespace), app_id).await?;
 
         Ok(row.map(Into::into))
     }
 
-    pub async fn set_app(&self, elf: &[u8], info: Option<String>) -> Result<App> {
-        let app = App::new(elf, info);
+    // register the elf with its info in the namespace, and append it as the next version of the
+    // name if provided. the elf registered by another namespace is shared without compiling again,
+    // but the info is kept for each namespace
+    pub async fn set_app(
+        &self,
+        namespace: &str,
+        elf: &[u8],
+        info: Option<String>,
+        app_info: Option<AppInfo>,
+        name: Option<&str>,
+    ) -> Result<Registration, RegisterAppError> {
+        let app_info = app_info
+            .map(AppInfoRow::try_from)
+            .transpose()?
+            .unwrap_or_default();
+        // check the duplicate by the content hash before the expensive compiling and key setup
+        let app_id = match self.storage.find_app_by_elf_hash(&elf_hash(elf)).await? {
+            Some(app_id) => {
+                info!("elf is already registered as app {app_id}");
+                app_id
+            }
+            None => {
+                validate_elf(elf)?;
+                let analysis = analyze_elf(elf)?;
+                let app = App::new(elf, info.clone());
 
-        let app_id = &app.app_id;
-        info!("register an new app {app_id}");
+                let app_id = app.app_id.clone();
+                info!("register an new app {app_id}");
 
-        if self.get_app(app_id).await?.is_some() {
-            bail!("app already exists {app_id}");
-        }
+                // the apps registered without the elf hash are checked by the app ID
+                if self.storage.get_app(None, &app_id).await?.is_none() {
+                    info!("running the baseline emulation");
+                    let baseline_cycles = baseline_cycles(&app);
+                    let row = AppRow::from(app);
+
+                    info!("saving app to DB");
+                    self.storage
+                        .insert_app(&row, &app_info, unix_timestamp())
+                        .await?;
+                    let analysis = AppAnalysisRow::new(
+                        app_id.clone(),
+                        &analysis,
+                        baseline_cycles,
+                        unix_timestamp(),
+                    );
+                    self.storage.insert_app_analysis(&analysis).await?;
+                }
+                app_id
+            }
+        };
+        let exists = !self
+            .storage
+            .insert_app_namespace(
+                namespace,
+                &app_id,
+                info.as_deref(),
+                &app_info,
+                unix_timestamp(),
+            )
+            .await?;
 
-        let row = AppRow::from(app.clone());
+        let Some(name) = name else {
+            if exists {
+                return Err(RegisterAppError::AlreadyExists(app_id));
+            }
+            return Ok(Registration::new(app_id, None));
+        };
 
-        info!("saving app to DB");
-        sqlx::query("INSERT INTO apps (app_id, program, pk, vk, info) VALUES (?, ?, ?, ?, ?)")
-            .bind(&row.app_id)
-            .bind(&row.program)
-            .bind(&row.pk)
-            .bind(&row.vk)
-            .bind(&row.info)
-            .execute(&*self.db_pool)
+        // a registered elf is appended again if it's not the latest version, e.g. rolling back
+        let latest = self.storage.get_app_version(namespace, name, None).await?;
+        if exists && latest.is_some_and(|latest| latest.app_id == app_id) {
+            return Err(RegisterAppError::AlreadyExists(app_id));
+        }
+        let version = self
+            .storage
+            .insert_app_version(namespace, name, &app_id, unix_timestamp())
             .await?;
+        info!("registered app {app_id} as version {version} of {namespace}/{name}");
+
+        Ok(Registration::new(app_id, Some(version)))
+    }
+
+    // resolve the pinned version of the name, or the latest version if not pinned
+    pub async fn resolve_version(
+        &self,
+        namespace: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<Option<AppVersionRow>> {
+        self.storage.get_app_version(namespace, name, version).await
+    }
+
+    // list the versions of the name from the oldest to the latest
+    pub async fn list_versions(&self, namespace: &str, name: &str) -> Result<Vec<AppVersionRow>> {
+        self.storage.list_app_versions(namespace, name).await
+    }
+
+    // delete the app and its proofs in the namespace, return false if the app isn't registered in
+    // the namespace
+    pub async fn delete_app(&self, namespace: &str, app_id: &str) -> Result<AppDeletion> {
+        // remove the prefix `0x`
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+
+        info!("deleting app {app_id} of namespace {namespace} from DB");
+        self.storage.delete_app(namespace, app_id).await
+    }
+
+    pub async fn get_app_meta(&self, namespace: &str, app_id: &str) -> Result<Option<AppMetaRow>> {
+        // remove the prefix `0x`
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+
+        self.storage.get_app_meta(namespace, app_id).await
+    }
+
+    // the analysis of the elf made on registration, none for the apps registered before analyzing
+    pub async fn get_app_analysis(
+        &self,
+        namespace: &str,
+        app_id: &str,
+    ) -> Result<Option<AppAnalysisRow>> {
+        // remove the prefix `0x`
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+
+        self.storage.get_app_analysis(namespace, app_id).await
+    }
 
-        Ok(app)
+    // list the app metadata matching the filter ordered by registration time
+    pub async fn list_app_metas(
+        &self,
+        filter: &AppFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AppMetaRow>> {
+        self.storage.list_app_metas(filter, offset, limit).await
     }
 }
@@ -0,0 +1,128 @@
+use super::ArtifactStore;
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use std::{
+    ffi::OsString,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::fs;
+
+// artifact store on the local filesystem, the keys are mapped to the relative paths
+pub struct LocalArtifactStore {
+    root: PathBuf,
+}
+
+impl LocalArtifactStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        // the key must not escape the root directory
+        if key
+            .trim_end_matches('/')
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            bail!("invalid artifact key {key}");
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for LocalArtifactStore {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        // write to a temporary file and rename, a partially written file is never read. It's unique
+        // by the process and a counter in the same directory, the concurrent puts of the same key
+        // or the keys differing by the extension never share it
+        let tmp = tmp_path(&path);
+        fs::write(&tmp, data).await?;
+        fs::rename(tmp, path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        list_files(&self.root, &self.path(prefix)?, &mut keys).await?;
+
+        Ok(keys)
+    }
+
+    fn uri(&self, key: &str) -> String {
+        let path = self.root.join(key);
+        let path = std::path::absolute(&path).unwrap_or(path);
+        format!("file://{}", path.display())
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<()> {
+        match fs::remove_dir_all(self.path(prefix)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// temporary path of a file to put, listing skips it by the `tmp` extension
+fn tmp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+// collect the keys of the files under the directory recursively
+async fn list_files(root: &Path, dir: &Path, keys: &mut Vec<String>) -> Result<()> {
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_none_or(|ext| ext != "tmp") {
+                let key = path
+                    .strip_prefix(root)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                keys.push(key);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use crate::{
+    proving_queue::{DEFAULT_NAMESPACE, ProvingKey},
+    types::PROVER_VERSION,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+use std::{path::PathBuf, sync::Arc};
+
+mod fs;
+mod s3;
+
+pub use fs::LocalArtifactStore;
+pub use s3::S3ArtifactStore;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ArtifactBackend {
+    // the artifacts are kept in the database or not persisted
+    None,
+    // local filesystem for a single host
+    Fs,
+    // S3 or the compatible object storage shared by multiple hosts
+    S3,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct ArtifactConfig {
+    #[clap(
+        long,
+        env = "ARTIFACT_STORE",
+        default_value = "none",
+        value_enum,
+        help = "Store of the proofs, inputs and checkpoints (none, fs, s3)"
+    )]
+    pub artifact_store: ArtifactBackend,
+
+    #[clap(
+        long,
+        env = "ARTIFACT_DIR",
+        default_value = "./artifacts",
+        help = "Root directory of the local artifact store"
+    )]
+    pub artifact_dir: PathBuf,
+
+    #[clap(long, env = "S3_BUCKET", help = "Bucket of the S3 artifact store")]
+    pub s3_bucket: Option<String>,
+
+    #[clap(
+        long,
+        env = "S3_PREFIX",
+        default_value = "",
+        help = "Key prefix of the artifacts in the S3 bucket"
+    )]
+    pub s3_prefix: String,
+
+    #[clap(
+        long,
+        env = "S3_ENDPOINT",
+        help = "Endpoint of the S3 compatible storage (e.g. MinIO), default to AWS"
+    )]
+    pub s3_endpoint: Option<String>,
+
+    #[clap(
+        long,
+        env = "ENABLE_CHECKPOINT",
+        help = "Checkpoint the proofs of the tasks in the artifact store for resuming"
+    )]
+    pub enable_checkpoint: bool,
+}
+
+// store of the binary artifacts by the slash-separated keys
+#[async_trait]
+pub trait ArtifactStore: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+
+    // return none if the artifact doesn't exist
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    // no error if the artifact doesn't exist
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    // list the keys of the artifacts under the prefix
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+
+    // URI of the artifact for the external readers, e.g. the task callbacks
+    fn uri(&self, key: &str) -> String;
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<()> {
+        for key in self.list(prefix).await? {
+            self.delete(&key).await?;
+        }
+
+        Ok(())
+    }
+}
+
+pub type SharedArtifactStore = Arc<dyn ArtifactStore>;
+
+impl ArtifactConfig {
+    // the store to checkpoint the proofs if enabled
+    pub fn checkpoint_store<'a>(
+        &self,
+        store: Option<&'a SharedArtifactStore>,
+    ) -> Option<&'a SharedArtifactStore> {
+        store.filter(|_| self.enable_checkpoint)
+    }
+}
+
+// connect to the configured artifact store, none if not configured
+pub async fn connect(cfg: &ArtifactConfig) -> Result<Option<SharedArtifactStore>> {
+    let store: SharedArtifactStore = match cfg.artifact_store {
+        ArtifactBackend::None if cfg.enable_checkpoint => {
+            anyhow::bail!("ENABLE_CHECKPOINT requires an artifact store")
+        }
+        ArtifactBackend::None => return Ok(None),
+        ArtifactBackend::Fs => Arc::new(LocalArtifactStore::new(cfg.artifact_dir.clone())),
+        ArtifactBackend::S3 => {
+            let bucket = cfg
+                .s3_bucket
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("S3_BUCKET is required for the S3 store"))?;
+            Arc::new(
+                S3ArtifactStore::connect(bucket, cfg.s3_prefix.clone(), cfg.s3_endpoint.as_deref())
+                    .await?,
+            )
+        }
+    };
+
+    Ok(Some(store))
+}
+
+// the task ID is hex encoded to be a safe key component, and the tasks of the default namespace
+// keep the paths before the namespaces
+fn task_path(key: &ProvingKey) -> String {
+    let app_id = key.app_id().strip_prefix("0x").unwrap_or(key.app_id());
+    let path = format!("{app_id}/{}", hex::encode(key.task_id()));
+
+    match key.namespace() {
+        DEFAULT_NAMESPACE => path,
+        namespace => format!("ns/{namespace}/{path}"),
+    }
+}
+
+pub fn proof_key(key: &ProvingKey) -> String {
+    format!("proofs/{}.bin", task_path(key))
+}
+
+// the calldata to verify the EVM proof on-chain is kept next to the proof
+pub fn calldata_key(key: &ProvingKey) -> String {
+    format!("calldata/{}.bin", task_path(key))
+}
+
+pub fn inputs_key(key: &ProvingKey) -> String {
+    format!("inputs/{}.bin", task_path(key))
+}
+
+// the uploaded inputs are shared by the tasks of the namespace referencing their hash
+pub fn uploaded_inputs_key(namespace: &str, inputs_hash: &str) -> String {
+    format!("uploads/{namespace}/{inputs_hash}.bin")
+}
+
+// the inputs of a dead letter are kept apart since the task inputs are dropped once failed
+pub fn dead_letter_inputs_key(key: &ProvingKey) -> String {
+    format!("dead_letters/{}.bin", task_path(key))
+}
+
+// the program and keys of an app are shared by the namespaces registering the same elf
+pub fn app_keys_key(elf_hash: &str) -> String {
+    format!("keys/{PROVER_VERSION}/{elf_hash}.bin")
+}
+
+pub fn checkpoint_prefix(key: &ProvingKey) -> String {
+    format!("checkpoints/{}/", task_path(key))
+}
@@ -0,0 +1,107 @@
+use super::ArtifactStore;
+use anyhow::Result;
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::{Client, config::Builder, error::SdkError, primitives::ByteStream};
+
+// artifact store on S3 or the compatible object storage, the credentials and region are loaded
+// from the standard AWS environment
+pub struct S3ArtifactStore {
+    client: Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl S3ArtifactStore {
+    pub async fn connect(bucket: String, prefix: String, endpoint: Option<&str>) -> Result<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let mut builder = Builder::from(&config);
+        if let Some(endpoint) = endpoint {
+            // the compatible storages are usually addressed by path
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+        let client = Client::from_conf(builder.build());
+        let prefix = match prefix.trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("{prefix}/"),
+        };
+
+        Ok(Self {
+            client,
+            bucket,
+            prefix,
+        })
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for S3ArtifactStore {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .body(ByteStream::from(data.to_vec()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let output = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(SdkError::ServiceError(e)) if e.err().is_no_such_key() => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let data = output.body.collect().await?.into_bytes();
+
+        Ok(Some(data.to_vec()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(self.object_key(prefix))
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            for object in page?.contents() {
+                if let Some(key) = object.key().and_then(|key| key.strip_prefix(&self.prefix)) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
+    fn uri(&self, key: &str) -> String {
+        format!("s3://{}/{}", self.bucket, self.object_key(key))
+    }
+}
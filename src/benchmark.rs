@@ -0,0 +1,243 @@
+use crate::{
+    BenchmarkResult, BenchmarkWorkload, ProofType, TaskPriority,
+    app_manager::App,
+    cost_estimation::{self, CalibrationSample, ProvingBackend},
+    gpu_pool::GpuPool,
+    proving::{
+        ProvedInfo,
+        backend::{ProveOptions, SharedProverBackend},
+        memory::MemoryBudget,
+    },
+    proving_queue::{DEFAULT_NAMESPACE, ProvingKey, ProvingTask},
+    storage::SharedStorage,
+    types::SC,
+};
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use pico_vm::{
+    compiler::riscv::program::Program,
+    machine::keys::{BaseProvingKey, BaseVerifyingKey},
+};
+use std::{fs, sync::Arc};
+use tokio::sync::{Mutex, broadcast};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info, info_span, warn};
+
+// bundled fixtures of the workloads, they're loaded from the working directory the same as the
+// estimator model
+const FIBONACCI_ELF: &str = "fixtures/fib-elf";
+const FIBONACCI_INPUTS: &str = "fixtures/fib-1m.bin";
+const RETH_ELF: &str = "fixtures/reth-elf";
+const RETH_INPUTS: &str = "fixtures/reth-18884864.bin";
+
+// buffered progress messages of a benchmark, they're not subscribed
+const PROGRESS_CHANNEL_CAPACITY: usize = 16;
+
+// program, keys and inputs of a workload compiled once for the following benchmarks
+struct Workload {
+    app_id: String,
+    program: Arc<Program>,
+    pk: Arc<BaseProvingKey<SC>>,
+    vk: Arc<BaseVerifyingKey<SC>>,
+    inputs: Vec<u8>,
+}
+
+// benchmark of the local provers by the bundled workloads, the throughput of each backend is
+// recorded as a calibration sample of the cost estimation
+pub struct Benchmark {
+    storage: SharedStorage,
+    gpu_pool: Arc<GpuPool>,
+    prover_backend: SharedProverBackend,
+    prover_count: usize,
+    memory_budget: Option<MemoryBudget>,
+    workloads: DashMap<BenchmarkWorkload, Arc<Workload>>,
+    // held by the running benchmark, the benchmarks run one at a time not to skew each other
+    running: Mutex<()>,
+}
+
+impl Benchmark {
+    pub fn new(
+        storage: SharedStorage,
+        gpu_pool: Arc<GpuPool>,
+        prover_backend: SharedProverBackend,
+        prover_count: usize,
+        memory_budget: Option<MemoryBudget>,
+    ) -> Self {
+        Self {
+            storage,
+            gpu_pool,
+            prover_backend,
+            prover_count,
+            memory_budget,
+            workloads: DashMap::new(),
+            running: Mutex::new(()),
+        }
+    }
+
+    // prove the workload on CPU and then on each healthy GPU device, none if another benchmark is
+    // running. the proving stops if the returned future is dropped
+    pub async fn run(
+        &self,
+        workload: BenchmarkWorkload,
+        proof_type: ProofType,
+    ) -> Result<Option<Vec<BenchmarkResult>>> {
+        let Ok(_running) = self.running.try_lock() else {
+            return Ok(None);
+        };
+        let loaded = self.load(workload).await?;
+        let cancel_token = CancellationToken::new();
+        let _cancel_guard = cancel_token.clone().drop_guard();
+
+        let mut results = vec![self.prove(&loaded, proof_type, None, &cancel_token).await];
+        for index in self.gpu_pool.healthy_devices() {
+            results.push(
+                self.prove(&loaded, proof_type, Some(index), &cancel_token)
+                    .await,
+            );
+        }
+        info!(
+            "[benchmark] {workload:?} completed on {} backends",
+            results.len()
+        );
+
+        Ok(Some(results))
+    }
+
+    async fn load(&self, workload: BenchmarkWorkload) -> Result<Arc<Workload>> {
+        if let Some(loaded) = self.workloads.get(&workload) {
+            return Ok(loaded.clone());
+        }
+
+        let (elf_path, inputs_path) = match workload {
+            BenchmarkWorkload::Fibonacci => (FIBONACCI_ELF, FIBONACCI_INPUTS),
+            BenchmarkWorkload::Reth => (RETH_ELF, RETH_INPUTS),
+        };
+        let elf = fs::read(elf_path).with_context(|| format!("failed to read {elf_path}"))?;
+        let inputs =
+            fs::read(inputs_path).with_context(|| format!("failed to read {inputs_path}"))?;
+        info!("[benchmark] compiling {workload:?} workload");
+        let app = tokio::task::spawn_blocking(move || App::new(&elf, None)).await?;
+        let loaded = Arc::new(Workload {
+            app_id: app.app_id,
+            program: app.program,
+            pk: Arc::new(app.pk),
+            vk: Arc::new(app.vk),
+            inputs,
+        });
+        self.workloads.insert(workload, loaded.clone());
+
+        Ok(loaded)
+    }
+
+    // prove by GPU if the device is provided, the failure is reported in the result
+    async fn prove(
+        &self,
+        workload: &Workload,
+        proof_type: ProofType,
+        device: Option<u32>,
+        cancel_token: &CancellationToken,
+    ) -> BenchmarkResult {
+        let backend = match device {
+            Some(_) => ProvingBackend::Gpu,
+            None => ProvingBackend::Cpu,
+        };
+        let info = match self
+            .try_prove(workload, proof_type, device, cancel_token)
+            .await
+        {
+            Ok(info) => info,
+            Err(e) => {
+                warn!(
+                    "[benchmark] failed to prove by {} {device:?}: {e}",
+                    backend.name()
+                );
+                return BenchmarkResult {
+                    backend: backend.name().to_string(),
+                    device,
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                };
+            }
+        };
+
+        if let Some(sample) = CalibrationSample::new(&info) {
+            cost_estimation::calibrate(&self.storage, &sample).await;
+        }
+        let cycles_per_sec = if info.proving_seconds > 0.0 {
+            info.total_cycles as f64 / info.proving_seconds
+        } else {
+            0.0
+        };
+        info!(
+            "[benchmark] proved {} cycles by {} {device:?} in {:.2}s, {cycles_per_sec:.0} cycles/s",
+            info.total_cycles,
+            backend.name(),
+            info.proving_seconds,
+        );
+
+        BenchmarkResult {
+            backend: backend.name().to_string(),
+            device,
+            total_cycles: info.total_cycles,
+            total_chunks: info.total_chunks,
+            proving_secs: info.proving_seconds,
+            cycles_per_sec,
+            timings: Some(info.timings),
+            error: None,
+        }
+    }
+
+    async fn try_prove(
+        &self,
+        workload: &Workload,
+        proof_type: ProofType,
+        device: Option<u32>,
+        cancel_token: &CancellationToken,
+    ) -> Result<ProvedInfo> {
+        // wait for the device in its queue behind the proving tasks
+        let gpu_lease = match device {
+            Some(index) => Some(self.gpu_pool.acquire_device(index).await?),
+            None => None,
+        };
+        let gpu_devices = gpu_lease.as_ref().map_or(&[][..], |lease| lease.devices());
+
+        let task_id = match device {
+            Some(index) => format!("benchmark-gpu-{index}"),
+            None => "benchmark-cpu".to_string(),
+        };
+        let key = ProvingKey::new(
+            DEFAULT_NAMESPACE.to_string(),
+            workload.app_id.clone(),
+            task_id,
+        );
+        let span = info_span!("benchmark", app_id = key.app_id(), task_id = key.task_id());
+        let task = ProvingTask::new(
+            key,
+            workload.program.clone(),
+            workload.pk.clone(),
+            workload.vk.clone(),
+            Some(workload.inputs.clone()),
+            device.is_some(),
+            false,
+            TaskPriority::default(),
+            None,
+            proof_type,
+            cancel_token.child_token(),
+            broadcast::channel(PROGRESS_CHANNEL_CAPACITY).0,
+            span.clone(),
+        );
+
+        // no checkpoint since the benchmark is not resumed
+        let opts = ProveOptions {
+            prover_count: self.prover_count,
+            gpu_devices,
+            checkpoint_store: None,
+            memory_budget: self.memory_budget,
+            preempt_token: None,
+        };
+        self.prover_backend
+            .prove(task, opts, Box::new(|| {}))
+            .instrument(span)
+            .await
+    }
+}
@@ -0,0 +1,113 @@
+use super::{ApiKeyClient, connect_channel};
+use crate::{
+    EstimateCostRequest, EstimateCostResponse, GetProofRequest, GetProofResponse,
+    GetProvingResultRequest, GetProvingResultResponse, GetTaskStatusRequest, GetTaskStatusResponse,
+    ProveTaskRequest, ProveTaskResponse, RegisterAppRequest, RegisterAppResponse,
+    UploadInputsResponse, prover_network_client::ProverNetworkClient as AsyncClient,
+    utils::auth::ApiKeyInterceptor,
+};
+use anyhow::Result;
+use std::path::Path;
+use tokio::runtime::{Builder, Runtime};
+use tonic::{Status, transport::ClientTlsConfig};
+
+// blocking client for the non-async callers, the calls of the async client are run on an internal
+// runtime owned by the client. it must not be used inside an async context
+pub struct ProverNetworkClient {
+    inner: ApiKeyClient,
+    runtime: Runtime,
+}
+
+impl ProverNetworkClient {
+    // connect to the service, TLS is enabled if configured and the API key is injected if set
+    pub fn connect(
+        addr: String,
+        tls: Option<ClientTlsConfig>,
+        api_key: Option<&str>,
+    ) -> Result<Self> {
+        let runtime = Builder::new_multi_thread().enable_all().build()?;
+        let channel = runtime.block_on(connect_channel(addr, tls))?;
+        let inner = AsyncClient::with_interceptor(channel, ApiKeyInterceptor::new(api_key)?);
+
+        Ok(Self { inner, runtime })
+    }
+
+    pub fn register_app(&mut self, req: RegisterAppRequest) -> Result<RegisterAppResponse, Status> {
+        let res = self.runtime.block_on(self.inner.register_app(req))?;
+
+        Ok(res.into_inner())
+    }
+
+    // register an application by streaming the elf file in chunks
+    pub fn register_app_from_file(
+        &mut self,
+        elf_path: impl AsRef<Path>,
+        info: Option<String>,
+        name: Option<String>,
+        chunk_size: usize,
+    ) -> Result<RegisterAppResponse> {
+        self.runtime.block_on(
+            self.inner
+                .register_app_from_file(elf_path, info, None, name, None, chunk_size),
+        )
+    }
+
+    pub fn estimate_cost(
+        &mut self,
+        req: EstimateCostRequest,
+    ) -> Result<EstimateCostResponse, Status> {
+        let res = self.runtime.block_on(self.inner.estimate_cost(req))?;
+
+        Ok(res.into_inner())
+    }
+
+    pub fn prove_task(&mut self, req: ProveTaskRequest) -> Result<ProveTaskResponse, Status> {
+        let res = self.runtime.block_on(self.inner.prove_task(req))?;
+
+        Ok(res.into_inner())
+    }
+
+    // add a proving task by streaming its inputs in chunks
+    pub fn prove_task_by_chunks(
+        &mut self,
+        req: ProveTaskRequest,
+        chunk_size: usize,
+    ) -> Result<ProveTaskResponse> {
+        self.runtime
+            .block_on(self.inner.prove_task_by_chunks(req, chunk_size))
+    }
+
+    // upload the inputs in chunks once to be referenced by their hash
+    pub fn upload_inputs(
+        &mut self,
+        inputs: &[u8],
+        chunk_size: usize,
+    ) -> Result<UploadInputsResponse> {
+        self.runtime
+            .block_on(self.inner.upload_inputs_by_chunks(inputs, chunk_size))
+    }
+
+    pub fn get_task_status(
+        &mut self,
+        req: GetTaskStatusRequest,
+    ) -> Result<GetTaskStatusResponse, Status> {
+        let res = self.runtime.block_on(self.inner.get_task_status(req))?;
+
+        Ok(res.into_inner())
+    }
+
+    pub fn get_proving_result(
+        &mut self,
+        req: GetProvingResultRequest,
+    ) -> Result<GetProvingResultResponse, Status> {
+        let res = self.runtime.block_on(self.inner.get_proving_result(req))?;
+
+        Ok(res.into_inner())
+    }
+
+    pub fn get_proof(&mut self, req: GetProofRequest) -> Result<GetProofResponse, Status> {
+        let res = self.runtime.block_on(self.inner.get_proof(req))?;
+
+        Ok(res.into_inner())
+    }
+}
@@ -0,0 +1,829 @@
+use crate::{
+    AppInfo, DownloadProofFinish, DownloadProofRequest, ErrCode, GetTaskStatusRequest,
+    InputsCompression, PhaseTimings, ProofType, ProveTaskChunk, ProveTaskFinish, ProveTaskRequest,
+    ProveTaskResponse, RegisterAppChunk, RegisterAppFinish, RegisterAppResponse, TaskPriority,
+    TaskState, UploadInputsChunk, UploadInputsResponse, WarmUpOptions, download_proof_chunk,
+    error::ServiceError,
+    health::proto::{
+        HealthCheckRequest, health_check_response::ServingStatus, health_client::HealthClient,
+    },
+    prove_task_chunk,
+    prover_network_client::ProverNetworkClient,
+    prover_network_server::SERVICE_NAME,
+    register_app_chunk,
+    retry::RetryPolicy,
+    utils::{
+        auth::ApiKeyInterceptor, compression::compress_inputs, telemetry::TraceInterceptor,
+        time::unix_timestamp,
+    },
+};
+use anyhow::{Result, anyhow, bail};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tonic::{
+    Code, Request, Status,
+    codegen::{Body, Bytes, StdError},
+    service::interceptor::InterceptedService,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+};
+use tower::discover::Change;
+use tracing::{info, warn};
+
+pub mod blocking;
+
+// default chunk size to upload large data by streaming
+pub const DEFAULT_UPLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+
+// metadata key of the existing app ID returned by a duplicate registration
+pub const APP_ID_METADATA_KEY: &str = "x-app-id";
+
+// metadata key of the namespace isolating the apps and tasks of a tenant
+pub const NAMESPACE_METADATA_KEY: &str = "x-namespace";
+
+// metadata key of the request ID, it's generated by the service if not sent and echoed back in the
+// response to correlate the logs
+pub const REQUEST_ID_METADATA_KEY: &str = "x-request-id";
+
+// metadata key of the code of a typed error, it's the name of the `ErrCode`
+pub const ERR_CODE_METADATA_KEY: &str = "x-err-code";
+
+// metadata keys of the queue depth and the estimated wait (seconds) of an overloaded service
+pub const QUEUE_DEPTH_METADATA_KEY: &str = "x-queue-depth";
+pub const ESTIMATED_WAIT_METADATA_KEY: &str = "x-estimated-wait-secs";
+
+// metadata key of the seconds to wait before retrying a rate limited call
+pub const RETRY_AFTER_METADATA_KEY: &str = "retry-after";
+
+// default interval to check the health of the balanced endpoints
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// timeout of a health check of a balanced endpoint
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+// default interval to poll the status of a task waiting for its proof
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// default retries of the transient errors by `ProvingServiceClient`
+const DEFAULT_RETRY_ATTEMPTS: u32 = 5;
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+// default retry budget, about 1 retry per 10 successful calls once the tokens are spent
+const DEFAULT_RETRY_TOKENS: u32 = 10;
+const DEFAULT_RETRY_REFUND: f64 = 0.1;
+
+// the existing app ID if the registration failed since the elf is already registered
+pub fn existing_app_id(status: &Status) -> Option<String> {
+    if status.code() != Code::AlreadyExists {
+        return None;
+    }
+
+    let app_id = status.metadata().get(APP_ID_METADATA_KEY)?.to_str().ok()?;
+
+    Some(app_id.to_string())
+}
+
+// client authenticated by an API key
+pub type ApiKeyClient = ProverNetworkClient<InterceptedService<Channel, ApiKeyInterceptor>>;
+
+// client propagating the trace context of the current span to the service
+pub type TracedClient = ProverNetworkClient<InterceptedService<Channel, TraceInterceptor>>;
+
+// connect to the service, TLS is enabled if configured
+pub async fn connect_channel(addr: String, tls: Option<ClientTlsConfig>) -> Result<Channel> {
+    let mut endpoint = Endpoint::from_shared(addr)?;
+    if let Some(tls) = tls {
+        endpoint = endpoint.tls_config(tls)?;
+    }
+
+    Ok(endpoint.connect().await?)
+}
+
+// a replica of the service balanced by `connect_balanced`
+struct BalancedEndpoint {
+    addr: String,
+    endpoint: Endpoint,
+    health: HealthClient<Channel>,
+    // routed by the balancer since it's serving in the last check
+    routed: bool,
+}
+
+// connect to the replicas of the service, the requests are balanced across the serving replicas by
+// the power of two choices of their pending requests. the replicas are checked by the health
+// service in background, a replica is removed from the balancer once it's not serving or
+// unreachable and added back once it's serving again. the monitor stops once the channel is dropped
+pub async fn connect_balanced(
+    addrs: Vec<String>,
+    tls: Option<ClientTlsConfig>,
+    health_check_interval: Duration,
+) -> Result<(Channel, JoinHandle<()>)> {
+    if addrs.is_empty() {
+        bail!("no endpoint to connect");
+    }
+    let mut endpoints = addrs
+        .into_iter()
+        .map(|addr| {
+            let mut endpoint = Endpoint::from_shared(addr.clone())?;
+            if let Some(tls) = &tls {
+                endpoint = endpoint.tls_config(tls.clone())?;
+            }
+            let health = HealthClient::new(endpoint.connect_lazy());
+            Ok(BalancedEndpoint {
+                addr,
+                endpoint,
+                health,
+                routed: false,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (channel, changes) = Channel::balance_channel(endpoints.len());
+    update_endpoints(&mut endpoints, &changes).await;
+    if !endpoints.iter().any(|endpoint| endpoint.routed) {
+        bail!("no serving endpoint");
+    }
+
+    let monitor = tokio::spawn(async move {
+        while !changes.is_closed() {
+            sleep(health_check_interval).await;
+            update_endpoints(&mut endpoints, &changes).await;
+        }
+    });
+
+    Ok((channel, monitor))
+}
+
+// route to the endpoints serving now and fail over from the others
+async fn update_endpoints(
+    endpoints: &mut [BalancedEndpoint],
+    changes: &mpsc::Sender<Change<String, Endpoint>>,
+) {
+    for balanced in endpoints {
+        let serving = is_serving(&mut balanced.health).await;
+        let change = match (serving, balanced.routed) {
+            (true, false) => {
+                info!(
+                    "[client] endpoint {} is serving, routing to it",
+                    balanced.addr
+                );
+                Change::Insert(balanced.addr.clone(), balanced.endpoint.clone())
+            }
+            (false, true) => {
+                warn!(
+                    "[client] endpoint {} is not serving, failing over",
+                    balanced.addr
+                );
+                Change::Remove(balanced.addr.clone())
+            }
+            _ => continue,
+        };
+        // the balancer is dropped with the channel
+        if changes.send(change).await.is_err() {
+            return;
+        }
+        balanced.routed = serving;
+    }
+}
+
+async fn is_serving(health: &mut HealthClient<Channel>) -> bool {
+    let req = HealthCheckRequest {
+        service: SERVICE_NAME.to_string(),
+    };
+    match timeout(HEALTH_CHECK_TIMEOUT, health.check(req)).await {
+        Ok(Ok(res)) => res.into_inner().status() == ServingStatus::Serving,
+        _ => false,
+    }
+}
+
+impl ProverNetworkClient<Channel> {
+    // connect by TLS, the client certificate is presented if configured for mTLS
+    pub async fn connect_tls(addr: String, tls: ClientTlsConfig) -> Result<Self> {
+        let channel = connect_channel(addr, Some(tls)).await?;
+
+        Ok(Self::new(channel))
+    }
+
+    // connect to the replicas with the requests balanced and failed over by their health, the
+    // health monitor is aborted by the returned handle
+    pub async fn connect_balanced(
+        addrs: Vec<String>,
+        tls: Option<ClientTlsConfig>,
+    ) -> Result<(Self, JoinHandle<()>)> {
+        let (channel, monitor) =
+            connect_balanced(addrs, tls, DEFAULT_HEALTH_CHECK_INTERVAL).await?;
+
+        Ok((Self::new(channel), monitor))
+    }
+
+    // build a client injecting the API key in the metadata of every call
+    pub fn with_api_key(channel: Channel, api_key: &str) -> Result<ApiKeyClient> {
+        let interceptor = ApiKeyInterceptor::new(Some(api_key))?;
+
+        Ok(ProverNetworkClient::with_interceptor(channel, interceptor))
+    }
+
+    // build a client injecting the trace context in the metadata of every call, so the spans of
+    // the service are in the traces of the caller
+    pub fn with_tracing(channel: Channel) -> TracedClient {
+        ProverNetworkClient::with_interceptor(channel, TraceInterceptor)
+    }
+}
+
+impl<T> ProverNetworkClient<T>
+where
+    T: tonic::client::GrpcService<tonic::body::Body>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    // register an application by streaming the elf file in chunks
+    pub async fn register_app_from_file(
+        &mut self,
+        elf_path: impl AsRef<Path>,
+        info: Option<String>,
+        app_info: Option<AppInfo>,
+        name: Option<String>,
+        warm_up: Option<WarmUpOptions>,
+        chunk_size: usize,
+    ) -> Result<RegisterAppResponse> {
+        let elf = fs::read(elf_path)?;
+        let elf_sha256 = Sha256::digest(&elf).to_vec();
+
+        let mut chunks: Vec<_> = elf
+            .chunks(chunk_size.max(1))
+            .map(|chunk| RegisterAppChunk {
+                frame: Some(register_app_chunk::Frame::ElfChunk(chunk.to_vec())),
+            })
+            .collect();
+        chunks.push(RegisterAppChunk {
+            frame: Some(register_app_chunk::Frame::Finish(RegisterAppFinish {
+                elf_sha256,
+                info,
+                name,
+                app_info,
+                warm_up,
+            })),
+        });
+
+        let res = self
+            .register_app_stream(tokio_stream::iter(chunks))
+            .await?
+            .into_inner();
+
+        Ok(res)
+    }
+
+    // add a proving task by streaming the serialized inputs file in chunks
+    pub async fn prove_task_from_file(
+        &mut self,
+        app_id: String,
+        task_id: String,
+        inputs_path: Option<impl AsRef<Path>>,
+        use_gpu: Option<bool>,
+        priority: Option<TaskPriority>,
+        chunk_size: usize,
+    ) -> Result<ProveTaskResponse> {
+        let inputs = inputs_path.map(fs::read).transpose()?;
+        let req = ProveTaskRequest {
+            app_id,
+            task_id,
+            inputs,
+            use_gpu,
+            priority: priority.map(Into::into),
+            ..Default::default()
+        };
+
+        self.prove_task_by_chunks(req, chunk_size).await
+    }
+
+    // add a proving task by streaming its inputs in chunks, for the inputs exceeding the message
+    // size limit
+    pub async fn prove_task_by_chunks(
+        &mut self,
+        req: ProveTaskRequest,
+        chunk_size: usize,
+    ) -> Result<ProveTaskResponse> {
+        // the fetched inputs are checked by the given sha256
+        let inputs_sha256 = match &req.inputs {
+            Some(inputs) => Sha256::digest(inputs).to_vec(),
+            None => req.inputs_sha256.clone(),
+        };
+
+        let mut chunks: Vec<_> = req
+            .inputs
+            .iter()
+            .flat_map(|inputs| inputs.chunks(chunk_size.max(1)))
+            .map(|chunk| ProveTaskChunk {
+                frame: Some(prove_task_chunk::Frame::InputsChunk(chunk.to_vec())),
+            })
+            .collect();
+        chunks.push(ProveTaskChunk {
+            frame: Some(prove_task_chunk::Frame::Finish(ProveTaskFinish {
+                app_id: req.app_id,
+                task_id: req.task_id,
+                use_gpu: req.use_gpu,
+                inputs_sha256,
+                priority: req.priority,
+                app_name: req.app_name,
+                app_version: req.app_version,
+                force: req.force,
+                callback_url: req.callback_url,
+                verify_proof: req.verify_proof,
+                proof_type: req.proof_type,
+                depends_on: req.depends_on,
+                not_before: req.not_before,
+                deadline: req.deadline,
+                inputs_hash: req.inputs_hash,
+                inputs_uri: req.inputs_uri,
+                inputs_compression: req.inputs_compression,
+                allow_cpu_fallback: req.allow_cpu_fallback,
+                expected_pv_digest: req.expected_pv_digest,
+            })),
+        });
+
+        let res = self
+            .prove_task_upload(tokio_stream::iter(chunks))
+            .await?
+            .into_inner();
+
+        Ok(res)
+    }
+
+    // upload the inputs in chunks once, the returned hash is referenced by `inputs_hash` of the
+    // later tasks instead of sending the inputs again
+    pub async fn upload_inputs_by_chunks(
+        &mut self,
+        inputs: &[u8],
+        chunk_size: usize,
+    ) -> Result<UploadInputsResponse> {
+        let chunks: Vec<_> = inputs
+            .chunks(chunk_size.max(1))
+            .map(|chunk| UploadInputsChunk {
+                inputs_chunk: chunk.to_vec(),
+            })
+            .collect();
+
+        let res = self
+            .upload_inputs(tokio_stream::iter(chunks))
+            .await?
+            .into_inner();
+        let inputs_hash = hex::encode(Sha256::digest(inputs));
+        if res.inputs_hash != inputs_hash {
+            bail!(
+                "hash of the uploaded inputs mismatches: expected {inputs_hash}, got {}",
+                res.inputs_hash
+            );
+        }
+
+        Ok(res)
+    }
+
+    // download a proof by streaming to the file, it's removed if the checksum mismatches
+    pub async fn download_proof_to_file(
+        &mut self,
+        app_id: String,
+        task_id: String,
+        output_path: impl AsRef<Path>,
+        chunk_size: Option<u32>,
+    ) -> Result<DownloadProofFinish> {
+        let req = DownloadProofRequest {
+            app_id,
+            task_id,
+            chunk_size,
+        };
+        let output_path = output_path.as_ref();
+        let mut file = File::create(output_path)?;
+        let finish = self
+            .download_proof_with(req, |chunk| Ok(file.write_all(chunk)?))
+            .await;
+        let finish = match finish {
+            Ok(finish) => finish,
+            Err(e) => {
+                fs::remove_file(output_path)?;
+                return Err(e);
+            }
+        };
+        file.flush()?;
+
+        Ok(finish)
+    }
+
+    // download a proof by streaming into memory, the checksum is verified
+    pub async fn download_proof_to_vec(
+        &mut self,
+        app_id: String,
+        task_id: String,
+        chunk_size: Option<u32>,
+    ) -> Result<(Vec<u8>, DownloadProofFinish)> {
+        let req = DownloadProofRequest {
+            app_id,
+            task_id,
+            chunk_size,
+        };
+        let mut proof = vec![];
+        let finish = self
+            .download_proof_with(req, |chunk| {
+                proof.extend_from_slice(chunk);
+                Ok(())
+            })
+            .await?;
+
+        Ok((proof, finish))
+    }
+
+    // write the proof chunks to the sink and verify the checksum by the finish frame
+    async fn download_proof_with(
+        &mut self,
+        req: DownloadProofRequest,
+        mut sink: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<DownloadProofFinish> {
+        let mut stream = self.download_proof(req).await?.into_inner();
+
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        let finish = loop {
+            let chunk = stream
+                .message()
+                .await?
+                .ok_or_else(|| anyhow!("proof download ends without finish frame"))?;
+            match chunk.frame {
+                Some(download_proof_chunk::Frame::ProofChunk(chunk)) => {
+                    hasher.update(&chunk);
+                    size += chunk.len() as u64;
+                    sink(&chunk)?;
+                }
+                Some(download_proof_chunk::Frame::Finish(finish)) => break finish,
+                None => bail!("empty frame of proof download"),
+            }
+        };
+
+        if size != finish.proof_size || hasher.finalize().as_slice() != finish.proof_sha256 {
+            bail!("checksum of the downloaded proof mismatches");
+        }
+
+        Ok(finish)
+    }
+}
+
+// proof of a completed task downloaded by `ProvingServiceClient`
+#[derive(Clone, Debug)]
+pub struct Proof {
+    pub app_id: String,
+    // the task proving the inputs, it's the earlier task if deduplicated
+    pub task_id: String,
+    // the on-chain proof for EVM or the bincode serialized STARK proof
+    pub proof: Vec<u8>,
+    pub pv_digest: Vec<u8>,
+    pub proof_type: ProofType,
+    // time spent in the proving phases, none if proved before a restart of the service
+    pub timings: Option<PhaseTimings>,
+}
+
+// budget of the client retries, a retry spends a token and a successful call refunds a fraction
+// of a token, so the retries stop instead of piling on once most of the calls fail
+#[derive(Clone, Debug)]
+pub struct RetryBudget {
+    tokens: f64,
+    max_tokens: f64,
+    refund: f64,
+}
+
+impl RetryBudget {
+    pub fn new(max_tokens: u32, refund: f64) -> Self {
+        Self {
+            tokens: max_tokens as f64,
+            max_tokens: max_tokens as f64,
+            refund,
+        }
+    }
+
+    fn withdraw(&mut self) -> bool {
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+
+        true
+    }
+
+    fn deposit(&mut self) {
+        self.tokens = (self.tokens + self.refund).min(self.max_tokens);
+    }
+}
+
+// UNAVAILABLE and DEADLINE_EXCEEDED are the transient errors such as a restarting replica or a
+// network blip, the connection errors are also reported as UNAVAILABLE
+fn is_transient(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<Status>()
+        .is_some_and(|status| matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded))
+}
+
+// wait of a call rejected by the rate limit of the service, the other RESOURCE_EXHAUSTED errors
+// such as the memory budget are not retried
+pub fn retry_after(status: &Status) -> Option<Duration> {
+    if status.code() != Code::ResourceExhausted {
+        return None;
+    }
+    status
+        .metadata()
+        .get(RETRY_AFTER_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+}
+
+fn rate_limited_for(e: &anyhow::Error) -> Option<Duration> {
+    e.downcast_ref::<Status>().and_then(retry_after)
+}
+
+// set the gRPC deadline of the call if any
+fn with_deadline<M>(message: M, timeout: Option<Duration>) -> Request<M> {
+    let mut req = Request::new(message);
+    if let Some(timeout) = timeout {
+        req.set_timeout(timeout);
+    }
+
+    req
+}
+
+// full backoff at most, and half of it at least not to retry in sync with the other clients
+fn jitter(backoff: Duration) -> Duration {
+    backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+// high-level client registering the apps, submitting the tasks and waiting for their proofs on top
+// of the raw `ProverNetworkClient`. the transient errors of the calls are retried with jittered
+// exponential backoff
+pub struct ProvingServiceClient<T> {
+    inner: ProverNetworkClient<T>,
+    poll_interval: Duration,
+    // timeout of waiting for a task, no timeout if none
+    timeout: Option<Duration>,
+    // gRPC deadline of each unary call, it's sent to the service as well
+    request_timeout: Option<Duration>,
+    chunk_size: usize,
+    // zstd level to compress the inputs before submitting, not compressed if none
+    compression_level: Option<i32>,
+    retry_policy: RetryPolicy,
+    retry_budget: RetryBudget,
+}
+
+impl<T> ProvingServiceClient<T>
+where
+    T: tonic::client::GrpcService<tonic::body::Body> + Clone,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    pub fn new(inner: ProverNetworkClient<T>) -> Self {
+        Self {
+            inner,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: None,
+            request_timeout: None,
+            chunk_size: DEFAULT_UPLOAD_CHUNK_SIZE,
+            compression_level: None,
+            retry_policy: RetryPolicy::new(
+                DEFAULT_RETRY_ATTEMPTS,
+                DEFAULT_RETRY_BACKOFF,
+                DEFAULT_MAX_RETRY_BACKOFF,
+                &[],
+            ),
+            retry_budget: RetryBudget::new(DEFAULT_RETRY_TOKENS, DEFAULT_RETRY_REFUND),
+        }
+    }
+
+    // attempts and backoff of retrying a call, the error patterns of the policy are ignored since
+    // only the transient gRPC codes are retried. no retry if the max attempts is 1
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_retry_budget(mut self, retry_budget: RetryBudget) -> Self {
+        self.retry_budget = retry_budget;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    // the task is submitted with the deadline by the timeout if it has none, the service fails it
+    // instead of proving after the client gave up
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // the timed out call is retried as transient
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    // chunk size to upload the elfs and the inputs by streaming
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    // compress the inputs by zstd at the level before submitting, 0 for the default level. the
+    // inputs are decompressed by the service
+    pub fn with_inputs_compression(mut self, level: i32) -> Self {
+        self.compression_level = Some(level);
+        self
+    }
+
+    // raw client for the other RPCs
+    pub fn inner(&mut self) -> &mut ProverNetworkClient<T> {
+        &mut self.inner
+    }
+
+    // register the elf file and return its app ID, or the ID of the existing app if it's already
+    // registered
+    pub async fn register_if_missing(
+        &mut self,
+        elf_path: impl AsRef<Path>,
+        info: Option<String>,
+    ) -> Result<String> {
+        let elf_path = elf_path.as_ref().to_path_buf();
+        let chunk_size = self.chunk_size;
+        self.with_retries(|mut client, _| {
+            let (elf_path, info) = (elf_path.clone(), info.clone());
+            async move {
+                let res = client
+                    .register_app_from_file(elf_path, info, None, None, None, chunk_size)
+                    .await;
+                match res {
+                    Ok(res) => Ok(res.app_id),
+                    Err(e) => match e.downcast_ref::<Status>().and_then(existing_app_id) {
+                        Some(app_id) => Ok(app_id),
+                        None => Err(e),
+                    },
+                }
+            }
+        })
+        .await
+    }
+
+    // queue a task, the inputs larger than the chunk size are uploaded by streaming. a retry of the
+    // attempt accepted but its response lost is returned the same task by the service
+    pub async fn submit(&mut self, mut req: ProveTaskRequest) -> Result<ProveTaskResponse> {
+        if let (Some(level), Some(inputs), None) =
+            (self.compression_level, &req.inputs, req.inputs_compression)
+        {
+            req.inputs = Some(compress_inputs(inputs, level)?);
+            req.inputs_compression = Some(InputsCompression::Zstd.into());
+        }
+        let chunk_size = self.chunk_size;
+        let too_large = req
+            .inputs
+            .as_ref()
+            .is_some_and(|inputs| inputs.len() > chunk_size);
+        let request_timeout = self.request_timeout;
+        self.with_retries(|mut client, _| {
+            let req = req.clone();
+            async move {
+                if too_large {
+                    client.prove_task_by_chunks(req, chunk_size).await
+                } else {
+                    client
+                        .prove_task(with_deadline(req, request_timeout))
+                        .await
+                        .map(|res| res.into_inner())
+                        .map_err(Into::into)
+                }
+            }
+        })
+        .await
+    }
+
+    // poll the status of the task until it's completed, and fail by the `ServiceError` of its
+    // failure if it's failed. the timings of the completed task are returned
+    pub async fn wait(&mut self, app_id: &str, task_id: &str) -> Result<Option<PhaseTimings>> {
+        let start = Instant::now();
+        loop {
+            let req = GetTaskStatusRequest {
+                app_id: app_id.to_string(),
+                task_id: task_id.to_string(),
+            };
+            let request_timeout = self.request_timeout;
+            let res = self
+                .with_retries(|mut client, _| {
+                    let req = with_deadline(req.clone(), request_timeout);
+                    async move { Ok(client.get_task_status(req).await?.into_inner()) }
+                })
+                .await?;
+            match res.state() {
+                TaskState::Completed => return Ok(res.timings),
+                TaskState::Failed => {
+                    let code = res
+                        .failure_code
+                        .and_then(|code| ErrCode::try_from(code).ok())
+                        .unwrap_or(ErrCode::ProvingFailed);
+                    let reason = res.failure_reason.unwrap_or_default();
+                    return Err(ServiceError::from_code(code, reason.clone())
+                        .unwrap_or(ServiceError::ProvingFailed(reason))
+                        .into());
+                }
+                TaskState::Cancelled => bail!("task {task_id} is cancelled"),
+                TaskState::Queued | TaskState::Executing | TaskState::Proving => {}
+            }
+            if self
+                .timeout
+                .is_some_and(|timeout| start.elapsed() >= timeout)
+            {
+                bail!("timed out waiting for task {task_id}");
+            }
+            sleep(self.poll_interval).await;
+        }
+    }
+
+    // download the proof of a completed task
+    pub async fn download(&mut self, app_id: &str, task_id: &str) -> Result<Proof> {
+        let (proof, finish) = self
+            .with_retries(|mut client, _| {
+                let (app_id, task_id) = (app_id.to_string(), task_id.to_string());
+                async move { client.download_proof_to_vec(app_id, task_id, None).await }
+            })
+            .await?;
+
+        Ok(Proof {
+            app_id: app_id.to_string(),
+            task_id: task_id.to_string(),
+            proof,
+            proof_type: finish.proof_type(),
+            pv_digest: finish.pv_digest,
+            timings: None,
+        })
+    }
+
+    // submit the task, wait until it's completed and download its proof. the proof of the earlier
+    // task is returned if deduplicated
+    pub async fn prove(&mut self, mut req: ProveTaskRequest) -> Result<Proof> {
+        if let (None, Some(timeout)) = (req.deadline, self.timeout) {
+            req.deadline = Some(unix_timestamp() + timeout.as_secs());
+        }
+        let task_id = req.task_id.clone();
+        let res = self.submit(req).await?;
+        let task_id = res.deduplicated_by.unwrap_or(task_id);
+
+        let timings = self.wait(&res.app_id, &task_id).await?;
+        let mut proof = self.download(&res.app_id, &task_id).await?;
+        proof.timings = timings;
+
+        Ok(proof)
+    }
+
+    // run the call by a clone of the client with the attempt number (starting from 1), the
+    // transient errors are retried while the attempts and the budget last
+    async fn with_retries<R, Fut>(
+        &mut self,
+        mut call: impl FnMut(ProverNetworkClient<T>, u32) -> Fut,
+    ) -> Result<R>
+    where
+        Fut: Future<Output = Result<R>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call(self.inner.clone(), attempt).await {
+                Ok(res) => {
+                    self.retry_budget.deposit();
+                    return Ok(res);
+                }
+                Err(e)
+                    if (is_transient(&e) || rate_limited_for(&e).is_some())
+                        && attempt < self.retry_policy.max_attempts
+                        && self.retry_budget.withdraw() =>
+                {
+                    // not earlier than the rate limit allows
+                    let backoff = jitter(self.retry_policy.backoff(attempt))
+                        .max(rate_limited_for(&e).unwrap_or_default());
+                    warn!("[client] attempt {attempt} failed, retrying in {backoff:?}: {e}");
+                    sleep(backoff).await;
+                    attempt += 1;
+                }
+                // the failed status is surfaced as the typed error
+                Err(e) => {
+                    return Err(match e.downcast::<Status>() {
+                        Ok(status) => ServiceError::from(status).into(),
+                        Err(e) => e,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+// flag and env of the TOML config file
+const CONFIG_FLAG: &str = "--config";
+const CONFIG_ENV: &str = "CONFIG_FILE";
+
+// parse the config with the layered precedence: CLI > env > TOML file > defaults. the file is
+// given by --config or CONFIG_FILE, its keys are the long flags in snake or kebab case (e.g.
+// grpc_addr), and the tables only group the keys
+pub fn parse<T: Parser>() -> Result<T> {
+    if let Some(path) = config_path(env::args_os()) {
+        apply_file::<T>(&path)?;
+    }
+
+    Ok(T::parse())
+}
+
+fn config_path(args: impl Iterator<Item = OsString>) -> Option<PathBuf> {
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == CONFIG_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    env::var_os(CONFIG_ENV).map(PathBuf::from)
+}
+
+// the keys of the file are applied by the env of the flags unless the env is already set, so the
+// CLI and env take precedence
+fn apply_file<T: Parser>(path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read config file {}", path.display()))?;
+    let table: Table = content
+        .parse()
+        .with_context(|| format!("invalid config file {}", path.display()))?;
+    let mut entries = vec![];
+    flatten(&table, true, &mut entries)?;
+
+    let cmd = T::command();
+    for (key, value) in entries {
+        let name = key.replace('_', "-");
+        let arg = cmd
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()))
+            .ok_or_else(|| anyhow!("unknown key {key} in config file"))?;
+        let env_name = arg
+            .get_env()
+            .ok_or_else(|| anyhow!("key {key} cannot be set by config file"))?;
+        if env::var_os(env_name).is_none() {
+            // SAFETY: the config is parsed on startup before the other threads read the env
+            unsafe { env::set_var(env_name, value) };
+        }
+    }
+
+    Ok(())
+}
+
+// the tables of the top level only group the keys, they're not nested further
+fn flatten(table: &Table, top: bool, entries: &mut Vec<(String, String)>) -> Result<()> {
+    for (key, value) in table {
+        match value {
+            Value::Table(table) if top => flatten(table, false, entries)?,
+            Value::Table(_) => bail!("nested table {key} in config file"),
+            value => entries.push((key.clone(), env_value(key, value)?)),
+        }
+    }
+
+    Ok(())
+}
+
+// the arrays are joined by comma as the lists in the env
+fn env_value(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Array(values) => Ok(values
+            .iter()
+            .map(|value| match value {
+                Value::Array(_) | Value::Table(_) => bail!("invalid item of {key} in config file"),
+                value => env_value(key, value),
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join(",")),
+        Value::Table(_) => bail!("invalid value of {key} in config file"),
+        value => Ok(value.to_string()),
+    }
+}
@@ -0,0 +1,492 @@
+use crate::{
+    ClaimTaskRequest, ClaimTaskResponse, ClaimedTask, ErrCode, FetchAppRequest, FetchAppResponse,
+    HeartbeatRequest, HeartbeatResponse, ReportTaskRequest, ReportTaskResponse, TaskKey,
+    WorkerStatus,
+    coordinator_server::Coordinator,
+    error::PicoError,
+    events,
+    proving::ProvedInfo,
+    proving_queue::{ProvingKey, ProvingTask, TaskRunner},
+    report_task_request::Outcome,
+    scheduler::{Scheduler, lane_of},
+    shutdown::Shutdown,
+    storage::SharedStorage,
+    utils::telemetry,
+};
+use alloy_primitives::U256;
+use anyhow::anyhow;
+use crossbeam::channel::Receiver;
+use dashmap::DashMap;
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, sleep},
+};
+use tonic::{Request, Response, Status, async_trait};
+use tracing::{info, warn};
+
+pub mod worker;
+
+// a task claimed by a remote worker
+struct Claim {
+    worker_id: String,
+    task: ProvingTask,
+    attempt: u32,
+    claimed_at: Instant,
+    heartbeat_at: Instant,
+}
+
+// coordinator of the remote workers, it owns the task store and the scheduler, and the workers
+// pull the tasks by claiming, keep them alive by heartbeats and report the results. the task of a
+// lapsed worker is re-queued for the other workers
+#[derive(Clone)]
+pub struct CoordinatorService {
+    runner: Arc<TaskRunner>,
+    receiver: Arc<Receiver<ProvingTask>>,
+    scheduler: Arc<Mutex<Scheduler>>,
+    storage: SharedStorage,
+    claims: Arc<DashMap<ProvingKey, Claim>>,
+    // last heartbeats of the workers
+    workers: Arc<DashMap<String, Instant>>,
+    // true while any worker is alive
+    workers_ready: Arc<AtomicBool>,
+    heartbeat_timeout: Duration,
+    // preempt the lowest-priority claimed task if all the workers are busy
+    preemption: bool,
+    // no task is claimed while draining
+    shutdown: Shutdown,
+}
+
+impl CoordinatorService {
+    pub(crate) fn new(
+        runner: Arc<TaskRunner>,
+        receiver: Arc<Receiver<ProvingTask>>,
+        scheduler: Scheduler,
+        storage: SharedStorage,
+        workers_ready: Arc<AtomicBool>,
+        heartbeat_timeout: Duration,
+        preemption: bool,
+        shutdown: Shutdown,
+    ) -> Self {
+        Self {
+            runner,
+            receiver,
+            scheduler: Arc::new(Mutex::new(scheduler)),
+            storage,
+            claims: Arc::new(DashMap::new()),
+            workers: Arc::new(DashMap::new()),
+            workers_ready,
+            heartbeat_timeout,
+            preemption,
+            shutdown,
+        }
+    }
+
+    // start a background reaper re-queueing the tasks of the lapsed workers
+    pub fn start_reaper(&self) -> JoinHandle<()> {
+        info!("[coordinator] reaper init");
+        let coordinator = self.clone();
+        let interval = (self.heartbeat_timeout / 2).max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                coordinator.reap().await;
+            }
+        })
+    }
+
+    async fn reap(&self) {
+        let now = Instant::now();
+        let lapsed = |at: &Instant| is_lapsed(now, *at, self.heartbeat_timeout);
+
+        self.workers.retain(|worker_id, at| {
+            let alive = !lapsed(at);
+            if !alive {
+                warn!("[coordinator] worker {worker_id} heartbeat lapsed");
+                events::worker_health(worker_id, false);
+            }
+            alive
+        });
+        self.workers_ready
+            .store(!self.workers.is_empty(), Ordering::Release);
+
+        let keys = lapsed_claims(
+            self.claims
+                .iter()
+                .map(|claim| (claim.key().clone(), claim.heartbeat_at)),
+            now,
+            self.heartbeat_timeout,
+        );
+        for key in keys {
+            let Some((_, claim)) = self.claims.remove(&key) else {
+                continue;
+            };
+            self.finish_claim(&key);
+            if claim.task.cancel_token.is_cancelled() {
+                continue;
+            }
+
+            let reason = format!("worker {} heartbeat lapsed", claim.worker_id);
+            warn!("[coordinator] reassigning task {key:?}: {reason}");
+            self.runner.reassign_task(claim.task, reason).await;
+        }
+
+        if self.preemption {
+            self.preempt().await;
+        }
+    }
+
+    // preempt the lowest-priority claimed task for a queued task of a higher priority if all the
+    // workers are busy, the worker aborts it by the next heartbeat and keeps its checkpoint, so
+    // it's resumed by the next worker claiming it
+    async fn preempt(&self) {
+        let busy = self.workers.iter().all(|worker| {
+            self.claims
+                .iter()
+                .any(|claim| claim.worker_id == *worker.key())
+        });
+        if !busy {
+            return;
+        }
+        let lowest = {
+            let mut scheduler = self.scheduler.lock().unwrap();
+            while let Ok(task) = self.receiver.try_recv() {
+                scheduler.push(task);
+            }
+            self.claims
+                .iter()
+                .filter(|claim| scheduler.has_higher_priority(claim.task.priority))
+                .max_by_key(|claim| (lane_of(claim.task.priority), claim.claimed_at))
+                .map(|claim| claim.key().clone())
+        };
+        let Some((key, claim)) = lowest.and_then(|key| self.claims.remove(&key)) else {
+            return;
+        };
+        self.finish_claim(&key);
+        info!(
+            "[coordinator] preempting {:?} task {key:?} of worker {} for a higher-priority task",
+            claim.task.priority, claim.worker_id
+        );
+        self.runner.preempt_task(claim.task).await;
+    }
+
+    // release the running slot of the app in the scheduler
+    fn finish_claim(&self, key: &ProvingKey) {
+        self.scheduler.lock().unwrap().finish(key);
+    }
+
+    // pop the next task not cancelled
+    fn pop_task(&self) -> Option<ProvingTask> {
+        if self.shutdown.is_draining() {
+            return None;
+        }
+        let mut scheduler = self.scheduler.lock().unwrap();
+        while let Ok(task) = self.receiver.try_recv() {
+            scheduler.push(task);
+        }
+        while let Some(task) = scheduler.pop() {
+            if !task.cancel_token.is_cancelled() {
+                return Some(task);
+            }
+            info!("[coordinator] skip cancelled task: {:?}", task.key);
+            scheduler.finish(&task.key);
+        }
+
+        None
+    }
+
+    // the alive workers with their claimed tasks ordered by worker ID
+    pub fn worker_statuses(&self) -> Vec<WorkerStatus> {
+        let now = Instant::now();
+        let mut workers: Vec<_> = self
+            .workers
+            .iter()
+            .map(|worker| WorkerStatus {
+                worker_id: worker.key().clone(),
+                heartbeat_age_secs: now.duration_since(*worker.value()).as_secs(),
+                tasks: self
+                    .claims
+                    .iter()
+                    .filter(|claim| claim.worker_id == *worker.key())
+                    .map(|claim| TaskKey {
+                        app_id: claim.key().app_id().to_string(),
+                        task_id: claim.key().task_id().to_string(),
+                        namespace: claim.key().namespace().to_string(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        workers.sort_by(|a, b| a.worker_id.cmp(&b.worker_id));
+
+        workers
+    }
+
+    fn touch_worker(&self, worker_id: &str) -> Result<(), Status> {
+        if worker_id.is_empty() {
+            return Err(Status::invalid_argument("worker_id is required"));
+        }
+        if self
+            .workers
+            .insert(worker_id.to_string(), Instant::now())
+            .is_none()
+        {
+            info!("[coordinator] worker {worker_id} joined");
+            events::worker_health(worker_id, true);
+        }
+        self.workers_ready.store(true, Ordering::Release);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Coordinator for CoordinatorService {
+    async fn claim_task(
+        &self,
+        req: Request<ClaimTaskRequest>,
+    ) -> Result<Response<ClaimTaskResponse>, Status> {
+        info!("receive ClaimTaskRequest");
+
+        let worker_id = req.into_inner().worker_id;
+        self.touch_worker(&worker_id)?;
+
+        // the tasks reaching their deadlines in the queue are failed instead of claimed
+        let task = loop {
+            match self.pop_task() {
+                Some(task) if self.runner.fail_expired(&task).await => self.finish_claim(&task.key),
+                task => break task,
+            }
+        };
+        let task = match task {
+            Some(task) => {
+                let key = task.key.clone();
+                let attempt = self.runner.start_task(&key).await;
+                info!(
+                    "[coordinator] task {key:?} claimed by worker {worker_id}, attempt {attempt}"
+                );
+                let claimed = ClaimedTask {
+                    namespace: key.namespace().to_string(),
+                    app_id: key.app_id().to_string(),
+                    task_id: key.task_id().to_string(),
+                    inputs: task.inputs.clone(),
+                    use_gpu: task.use_gpu,
+                    allow_cpu_fallback: task.allow_cpu_fallback,
+                    expected_pv_digest: self
+                        .runner
+                        .expected_pv_digest(&key)
+                        .map(|digest| digest.to_be_bytes::<32>().to_vec()),
+                    // the spans of the worker are in the trace of the task
+                    trace_context: telemetry::trace_context(&task.span),
+                    proof_type: task.proof_type.into(),
+                };
+                self.claims.insert(
+                    key,
+                    Claim {
+                        worker_id,
+                        task,
+                        attempt,
+                        claimed_at: Instant::now(),
+                        heartbeat_at: Instant::now(),
+                    },
+                );
+                Some(claimed)
+            }
+            None => None,
+        };
+
+        info!("return ClaimTaskResponse");
+
+        Ok(Response::new(ClaimTaskResponse {
+            err: None,
+            task,
+            heartbeat_timeout_secs: self.heartbeat_timeout.as_secs(),
+        }))
+    }
+
+    async fn fetch_app(
+        &self,
+        req: Request<FetchAppRequest>,
+    ) -> Result<Response<FetchAppResponse>, Status> {
+        info!("receive FetchAppRequest");
+
+        let app_id = req.into_inner().app_id;
+        let normalized_app_id = app_id.strip_prefix("0x").unwrap_or(&app_id);
+        // the app is shared by the namespaces registering it
+        let app = self
+            .storage
+            .get_app(None, normalized_app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
+            .ok_or_else(|| Status::not_found(format!("cannot find app {app_id}")))?;
+
+        info!("return FetchAppResponse");
+
+        Ok(Response::new(FetchAppResponse {
+            err: None,
+            program: app.program,
+            pk: app.pk,
+            vk: app.vk,
+        }))
+    }
+
+    async fn heartbeat(
+        &self,
+        req: Request<HeartbeatRequest>,
+    ) -> Result<Response<HeartbeatResponse>, Status> {
+        let req = req.into_inner();
+        self.touch_worker(&req.worker_id)?;
+
+        // the tasks cancelled or reassigned to the other workers are aborted
+        let mut aborted = vec![];
+        for task in req.tasks {
+            let key = ProvingKey::new(
+                task.namespace.clone(),
+                task.app_id.clone(),
+                task.task_id.clone(),
+            );
+            let cancelled = match self.claims.get_mut(&key) {
+                Some(mut claim) if claim.worker_id == req.worker_id => {
+                    claim.heartbeat_at = Instant::now();
+                    claim.task.cancel_token.is_cancelled()
+                }
+                _ => {
+                    aborted.push(task);
+                    continue;
+                }
+            };
+            if cancelled {
+                self.claims.remove(&key);
+                self.finish_claim(&key);
+                // the cancelled task drops its checkpoint, only the preempted ones keep it for
+                // resuming
+                self.runner.clear_checkpoint(&key).await;
+                aborted.push(task);
+            }
+        }
+
+        Ok(Response::new(HeartbeatResponse { err: None, aborted }))
+    }
+
+    async fn report_task(
+        &self,
+        req: Request<ReportTaskRequest>,
+    ) -> Result<Response<ReportTaskResponse>, Status> {
+        info!("receive ReportTaskRequest");
+
+        let req = req.into_inner();
+        self.touch_worker(&req.worker_id)?;
+
+        let key = ProvingKey::new(req.namespace, req.app_id, req.task_id);
+        let error_code = req.error_code.and_then(|code| ErrCode::try_from(code).ok());
+        let result = match req.outcome {
+            Some(Outcome::Proof(proof)) => {
+                let pv_digest = U256::try_from_be_slice(&proof.pv_digest).ok_or_else(|| {
+                    Status::invalid_argument("pv_digest must be at most 32 bytes")
+                })?;
+                Ok(ProvedInfo {
+                    proof: proof.proof,
+                    pv_digest,
+                    total_cycles: proof.total_cycles,
+                    total_chunks: proof.total_chunks,
+                    gpu_seconds: proof.gpu_seconds,
+                    proving_seconds: proof.proving_seconds,
+                    timings: proof.timings.unwrap_or_default(),
+                })
+            }
+            // keep the structured error reported by the worker
+            Some(Outcome::Error(e)) if error_code == Some(ErrCode::ResourceExhausted) => {
+                Err(PicoError::ResourceExhausted(e).into())
+            }
+            Some(Outcome::Error(e)) if error_code == Some(ErrCode::PvDigestMismatch) => {
+                Err(PicoError::PvDigestMismatch(e).into())
+            }
+            Some(Outcome::Error(e)) => Err(anyhow!(e)),
+            None => return Err(Status::invalid_argument("proof or error is required")),
+        };
+        let (_, claim) = self
+            .claims
+            .remove_if(&key, |_, claim| claim.worker_id == req.worker_id)
+            .ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "task {key:?} is not claimed by worker {}",
+                    req.worker_id
+                ))
+            })?;
+        self.finish_claim(&key);
+        info!(
+            "[coordinator] task {key:?} reported by worker {}, proved={}",
+            req.worker_id,
+            result.is_ok()
+        );
+
+        self.runner
+            .complete_task(&key, claim.attempt, Some(claim.task), result)
+            .await;
+
+        info!("return ReportTaskResponse");
+
+        Ok(Response::new(ReportTaskResponse { err: None }))
+    }
+}
+
+// the heartbeat is lapsed once it's older than the timeout
+fn is_lapsed(now: Instant, heartbeat_at: Instant, timeout: Duration) -> bool {
+    now.duration_since(heartbeat_at) > timeout
+}
+
+// the claims whose workers missed the heartbeats, they're re-queued for the other workers
+fn lapsed_claims(
+    heartbeats: impl Iterator<Item = (ProvingKey, Instant)>,
+    now: Instant,
+    timeout: Duration,
+) -> Vec<ProvingKey> {
+    heartbeats
+        .filter(|(_, heartbeat_at)| is_lapsed(now, *heartbeat_at, timeout))
+        .map(|(key, _)| key)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(task_id: &str) -> ProvingKey {
+        ProvingKey::new(
+            "default".to_string(),
+            "app".to_string(),
+            task_id.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_lapsed_claims() {
+        let timeout = Duration::from_secs(30);
+        let start = Instant::now();
+        let heartbeats = [
+            (key("stale"), start),
+            (key("timeout"), start + Duration::from_secs(10)),
+            (key("fresh"), start + Duration::from_secs(35)),
+        ];
+
+        let now = start + Duration::from_secs(40);
+        let lapsed = lapsed_claims(heartbeats.clone().into_iter(), now, timeout);
+        // the heartbeat exactly at the timeout is still alive
+        assert_eq!(lapsed, vec![key("stale")]);
+
+        let lapsed = lapsed_claims(
+            heartbeats.into_iter(),
+            now + Duration::from_secs(1),
+            timeout,
+        );
+        assert_eq!(lapsed, vec![key("stale"), key("timeout")]);
+
+        // the heartbeat received after the reaping started is never lapsed
+        assert!(!is_lapsed(start, start + Duration::from_secs(1), timeout));
+    }
+}
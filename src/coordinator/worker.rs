@@ -0,0 +1,286 @@
+use crate::{
+    ClaimTaskRequest, ClaimedTask, FetchAppRequest, HeartbeatRequest, ReportTaskRequest, TaskKey,
+    TaskPriority, TaskProof,
+    artifact_store::{self, SharedArtifactStore},
+    client,
+    config::WorkerConfig,
+    coordinator_client::CoordinatorClient,
+    error::{error_code, is_device_fault},
+    gpu_pool::GpuPool,
+    proving::{
+        self,
+        backend::{ProveOptions, SharedProverBackend},
+    },
+    proving_queue::{ProvingKey, ProvingTask},
+    report_task_request::Outcome,
+    types::SC,
+    utils::{auth::AuthConfig, telemetry},
+};
+use alloy_primitives::U256;
+use anyhow::{Result, anyhow};
+use pico_vm::{
+    compiler::riscv::program::Program,
+    machine::keys::{BaseProvingKey, BaseVerifyingKey},
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, time::sleep};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, error, info, info_span, warn};
+
+// buffered progress messages of a task, they're not reported to the coordinator
+const PROGRESS_CHANNEL_CAPACITY: usize = 16;
+
+// program and keys of an app cached for the following tasks
+struct WorkerApp {
+    program: Arc<Program>,
+    pk: Arc<BaseProvingKey<SC>>,
+    vk: Arc<BaseVerifyingKey<SC>>,
+}
+
+// run a remote worker pulling the tasks from the coordinator one by one
+pub async fn run_worker(cfg: WorkerConfig) -> Result<()> {
+    let channel =
+        client::connect_channel(cfg.coordinator_addr.clone(), cfg.tls.client_tls_config()?).await?;
+    let mut client = CoordinatorClient::with_interceptor(channel, cfg.client_auth_interceptor())
+        .max_encoding_message_size(cfg.max_grpc_msg_size)
+        .max_decoding_message_size(cfg.max_grpc_msg_size);
+    let worker_id = cfg.worker_id();
+    let poll_interval = Duration::from_secs(cfg.claim_poll_interval_secs);
+    let gpu_pool = Arc::new(GpuPool::detect(
+        &cfg.gpu_devices,
+        cfg.gpu_tasks_per_device,
+        cfg.gpu_health.clone(),
+    ));
+    // the checkpoints are resumed by the other workers if the store is shared
+    let artifacts = artifact_store::connect(&cfg.artifacts).await?;
+    let prover_backend = cfg.prover.backend();
+    let mut apps = HashMap::new();
+    info!("[worker] worker {worker_id} started");
+
+    loop {
+        let req = ClaimTaskRequest {
+            worker_id: worker_id.clone(),
+        };
+        let res = match client.claim_task(req).await {
+            Ok(res) => res.into_inner(),
+            Err(e) => {
+                warn!("[worker] failed to claim task: {e}");
+                sleep(poll_interval).await;
+                continue;
+            }
+        };
+        let Some(claimed) = res.task else {
+            sleep(poll_interval).await;
+            continue;
+        };
+        let key = ProvingKey::new(
+            claimed.namespace.clone(),
+            claimed.app_id.clone(),
+            claimed.task_id.clone(),
+        );
+        info!("[worker] claimed task {key:?}");
+
+        // send the heartbeats well within the timeout of the coordinator
+        let heartbeat_interval = Duration::from_secs(
+            cfg.heartbeat_interval_secs
+                .min(res.heartbeat_timeout_secs / 3)
+                .max(1),
+        );
+        let cancel_token = CancellationToken::new();
+        let heartbeat = {
+            let mut client = client.clone();
+            let req = HeartbeatRequest {
+                worker_id: worker_id.clone(),
+                tasks: vec![TaskKey {
+                    app_id: claimed.app_id.clone(),
+                    task_id: claimed.task_id.clone(),
+                    namespace: claimed.namespace.clone(),
+                }],
+            };
+            let cancel_token = cancel_token.clone();
+            tokio::spawn(async move {
+                loop {
+                    sleep(heartbeat_interval).await;
+                    match client.heartbeat(req.clone()).await {
+                        // the task is cancelled or reassigned to another worker
+                        Ok(res) if !res.into_inner().aborted.is_empty() => {
+                            cancel_token.cancel();
+                            break;
+                        }
+                        Ok(_) => (),
+                        Err(e) => warn!("[worker] failed to send heartbeat: {e}"),
+                    }
+                }
+            })
+        };
+
+        // fetch the program and keys of the app if not cached
+        if !apps.contains_key(&claimed.app_id) {
+            let req = FetchAppRequest {
+                app_id: claimed.app_id.clone(),
+            };
+            let app = client
+                .fetch_app(req)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|res| {
+                    let res = res.into_inner();
+                    Ok(WorkerApp {
+                        program: Arc::new(bincode::deserialize(&res.program)?),
+                        pk: Arc::new(bincode::deserialize(&res.pk)?),
+                        vk: Arc::new(bincode::deserialize(&res.vk)?),
+                    })
+                });
+            match app {
+                Ok(app) => {
+                    apps.insert(claimed.app_id.clone(), app);
+                }
+                Err(e) => error!("[worker] failed to fetch app {}: {e}", claimed.app_id),
+            }
+        }
+
+        let result = match apps.get(&claimed.app_id) {
+            Some(app) => {
+                prove_claimed(
+                    &cfg,
+                    &gpu_pool,
+                    &prover_backend,
+                    artifacts.as_ref(),
+                    app,
+                    key.clone(),
+                    claimed,
+                    &cancel_token,
+                )
+                .await
+            }
+            None => Err(anyhow!("failed to fetch app")),
+        };
+        let (outcome, error_code) = match result {
+            Ok(proof) => (Outcome::Proof(proof), None),
+            Err(e) => (Outcome::Error(e.to_string()), error_code(&e)),
+        };
+        heartbeat.abort();
+        if cancel_token.is_cancelled() {
+            info!("[worker] task {key:?} aborted by the coordinator");
+            continue;
+        }
+
+        let req = ReportTaskRequest {
+            worker_id: worker_id.clone(),
+            app_id: key.app_id().to_string(),
+            task_id: key.task_id().to_string(),
+            outcome: Some(outcome),
+            namespace: key.namespace().to_string(),
+            error_code: error_code.map(Into::into),
+        };
+        match client.report_task(req).await {
+            Ok(_) => info!("[worker] reported task {key:?}"),
+            // the task is reassigned after the heartbeat timeout
+            Err(e) => error!("[worker] failed to report task {key:?}: {e}"),
+        }
+    }
+}
+
+async fn prove_claimed(
+    cfg: &WorkerConfig,
+    gpu_pool: &Arc<GpuPool>,
+    prover_backend: &SharedProverBackend,
+    artifacts: Option<&SharedArtifactStore>,
+    app: &WorkerApp,
+    key: ProvingKey,
+    claimed: ClaimedTask,
+    cancel_token: &CancellationToken,
+) -> Result<TaskProof> {
+    // the task span continues the trace of the task in the coordinator
+    let span = info_span!(
+        "proving_task",
+        namespace = key.namespace(),
+        app_id = key.app_id(),
+        task_id = key.task_id(),
+    );
+    telemetry::set_remote_parent(&span, &claimed.trace_context);
+    let expected_pv_digest = claimed
+        .expected_pv_digest
+        .as_deref()
+        .and_then(U256::try_from_be_slice);
+    // the priority and the deadline are only used by the scheduler of the coordinator
+    let task = ProvingTask::new(
+        key.clone(),
+        app.program.clone(),
+        app.pk.clone(),
+        app.vk.clone(),
+        claimed.inputs,
+        claimed.use_gpu,
+        claimed.allow_cpu_fallback,
+        TaskPriority::default(),
+        None,
+        claimed.proof_type(),
+        CancellationToken::new(),
+        broadcast::channel(PROGRESS_CHANNEL_CAPACITY).0,
+        span.clone(),
+    );
+
+    // the wrong inputs are failed by the emulation before waiting for the GPU devices
+    if let Some(expected) = expected_pv_digest {
+        proving::check_pv_digest(&task, expected)
+            .instrument(info_span!(parent: &span, "check_pv_digest"))
+            .await?;
+    }
+
+    let gpu_lease = if claimed.use_gpu {
+        match gpu_pool.acquire(cfg.gpu_devices_per_task).await {
+            Ok(lease) => Some(lease),
+            Err(e) if claimed.allow_cpu_fallback => {
+                warn!("[worker] proving task {key:?} on CPU, failed to assign GPU devices: {e}");
+                None
+            }
+            Err(e) => return Err(anyhow!("failed to assign GPU devices: {e}")),
+        }
+    } else {
+        None
+    };
+    let gpu_devices = gpu_lease.as_ref().map_or(&[][..], |lease| lease.devices());
+
+    let checkpoint_store = cfg.artifacts.checkpoint_store(artifacts);
+    let opts = ProveOptions {
+        prover_count: cfg.prover_count,
+        gpu_devices,
+        checkpoint_store,
+        memory_budget: cfg.memory.budget(),
+        // the task aborted by the coordinator may be resumed by another worker, so the checkpoint
+        // is kept as preempted
+        preempt_token: Some(cancel_token.clone()),
+    };
+    let info = prover_backend
+        .prove(
+            task,
+            opts,
+            Box::new(|| {
+                info!("[worker] emulation of task {key:?} completed");
+            }),
+        )
+        .instrument(info_span!(parent: &span, "prove"))
+        .await;
+    // the failures of the devices are counted to quarantine the faulty ones, not the aborted
+    // attempts
+    if let Some(lease) = &gpu_lease {
+        match &info {
+            Ok(_) => gpu_pool.report_success(lease.devices()),
+            Err(e) if is_device_fault(e) && !cancel_token.is_cancelled() => {
+                gpu_pool.report_failure(lease.devices(), &e.to_string())
+            }
+            Err(_) => {}
+        }
+    }
+    let info = info?;
+
+    Ok(TaskProof {
+        proof: info.proof,
+        pv_digest: info.pv_digest.to_be_bytes::<32>().to_vec(),
+        total_cycles: info.total_cycles,
+        total_chunks: info.total_chunks,
+        gpu_seconds: info.gpu_seconds,
+        proving_seconds: info.proving_seconds,
+        timings: Some(info.timings),
+    })
+}
@@ -1,8 +1,12 @@
 use crate::{
     error::PicoError,
+    proving::ProvedInfo,
+    storage::SharedStorage,
     types::{SC, Val},
+    utils::time::unix_timestamp,
 };
 use alloy_primitives::U256;
+use clap::Args;
 use pico_vm::{
     compiler::riscv::program::Program,
     emulator::{
@@ -18,7 +22,9 @@ use pico_vm::{
     proverchain::emulate_snapshot_pipeline,
 };
 use sha2::{Digest, Sha256};
+use sqlx::FromRow;
 use std::{panic, sync::Arc};
+use tracing::error;
 
 pub struct EstimatedInfo {
     pub cost: u64,
@@ -70,9 +76,7 @@ pub fn estimate_cost(
             total_cycles
         };
 
-        let mut pv_digest = U256::from_be_bytes(sha256(&pv_stream));
-        let mask = (U256::ONE << 253) - U256::ONE;
-        pv_digest &= mask;
+        let pv_digest = pv_digest(&pv_stream);
 
         Ok(EstimatedInfo {
             cost,
@@ -90,8 +94,167 @@ pub fn estimate_cost(
     }
 }
 
+// public values digest which is the masked sha256 of the public values stream
+pub fn pv_digest(pv_stream: &[u8]) -> U256 {
+    let pv_digest = U256::from_be_bytes(sha256(pv_stream));
+    let mask = (U256::ONE << 253) - U256::ONE;
+
+    pv_digest & mask
+}
+
 fn sha256(bytes: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(bytes);
     hasher.finalize().into()
 }
+
+// backend of proving the tasks, they're calibrated separately
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProvingBackend {
+    Cpu,
+    Gpu,
+}
+
+impl ProvingBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            ProvingBackend::Cpu => "cpu",
+            ProvingBackend::Gpu => "gpu",
+        }
+    }
+}
+
+// proving work of a backend accumulated by the completed tasks
+#[derive(Debug, FromRow)]
+pub struct CalibrationRow {
+    pub backend: String,
+    pub tasks: i64,
+    pub cycles: i64,
+    pub chunks: i64,
+    // wall time of emulating and proving
+    pub proving_seconds: f64,
+    // wall time multiplied by the assigned devices, the same as the wall time for CPU
+    pub device_seconds: f64,
+}
+
+impl CalibrationRow {
+    fn per_cycle(&self, total: f64) -> Option<f64> {
+        (self.cycles > 0).then(|| total / self.cycles as f64)
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct RateCard {
+    #[clap(
+        long,
+        env = "PRICE_PER_CPU_HOUR",
+        help = "Price of an hour proving by CPU for the estimates, no CPU price if not set"
+    )]
+    pub price_per_cpu_hour: Option<f64>,
+
+    #[clap(
+        long,
+        env = "PRICE_PER_GPU_HOUR",
+        help = "Price of a GPU device hour for the estimates, no GPU price if not set"
+    )]
+    pub price_per_gpu_hour: Option<f64>,
+}
+
+// proving work of the emulated cycles estimated by the calibrations, the fields of a backend are
+// none if it's not calibrated yet
+#[derive(Debug, Default)]
+pub struct ProvingEstimate {
+    pub total_chunks: u64,
+    pub cpu_proving_secs: Option<f64>,
+    pub gpu_proving_secs: Option<f64>,
+    pub cpu_price: Option<f64>,
+    pub gpu_price: Option<f64>,
+}
+
+impl ProvingEstimate {
+    pub fn new(total_cycles: u64, calibrations: &[CalibrationRow], rate_card: &RateCard) -> Self {
+        let cycles = total_cycles as f64;
+        // the chunks are split by cycles the same for both backends
+        let (calibrated_cycles, calibrated_chunks) =
+            calibrations.iter().fold((0, 0), |(cycles, chunks), row| {
+                (cycles + row.cycles, chunks + row.chunks)
+            });
+        let total_chunks = if calibrated_cycles > 0 {
+            (cycles * calibrated_chunks as f64 / calibrated_cycles as f64).ceil() as u64
+        } else {
+            0
+        };
+
+        let mut estimate = Self {
+            total_chunks,
+            ..Self::default()
+        };
+        for row in calibrations {
+            let proving_secs = row.per_cycle(row.proving_seconds).map(|secs| secs * cycles);
+            let device_hours = row
+                .per_cycle(row.device_seconds)
+                .map(|secs| secs * cycles / 3600.0);
+            match row.backend.as_str() {
+                "cpu" => {
+                    estimate.cpu_proving_secs = proving_secs;
+                    estimate.cpu_price = device_hours
+                        .zip(rate_card.price_per_cpu_hour)
+                        .map(|(h, p)| h * p);
+                }
+                "gpu" => {
+                    estimate.gpu_proving_secs = proving_secs;
+                    estimate.gpu_price = device_hours
+                        .zip(rate_card.price_per_gpu_hour)
+                        .map(|(h, p)| h * p);
+                }
+                _ => (),
+            }
+        }
+
+        estimate
+    }
+}
+
+// proving work of a completed task to calibrate its backend
+#[derive(Debug)]
+pub struct CalibrationSample {
+    pub backend: ProvingBackend,
+    pub cycles: u64,
+    pub chunks: u64,
+    pub proving_seconds: f64,
+    pub device_seconds: f64,
+}
+
+impl CalibrationSample {
+    // none if no cycles are emulated, e.g. proved by a remote worker of the old version
+    pub fn new(info: &ProvedInfo) -> Option<Self> {
+        if info.total_cycles == 0 {
+            return None;
+        }
+
+        let (backend, device_seconds) = if info.gpu_seconds > 0.0 {
+            (ProvingBackend::Gpu, info.gpu_seconds)
+        } else {
+            (ProvingBackend::Cpu, info.proving_seconds)
+        };
+
+        Some(Self {
+            backend,
+            cycles: info.total_cycles,
+            chunks: info.total_chunks,
+            proving_seconds: info.proving_seconds,
+            device_seconds,
+        })
+    }
+}
+
+// accumulate the proving work of the completed task to its backend, the failure is only logged to
+// not fail the task
+pub async fn calibrate(storage: &SharedStorage, sample: &CalibrationSample) {
+    if let Err(e) = storage.add_calibration(sample, unix_timestamp()).await {
+        error!(
+            "[cost-estimation] failed to calibrate {} proving: {e}",
+            sample.backend.name()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    TaskState,
+    artifact_store::{self, SharedArtifactStore},
+    proving_queue::{ProvingKey, ProvingOutputs, ProvingTask, TaskStatuses, fail_task},
+    storage::SharedStorage,
+    types::SC,
+    utils::time::unix_timestamp,
+};
+use anyhow::{Result, anyhow, bail};
+use crossbeam::channel::Sender;
+use pico_vm::{
+    compiler::riscv::program::Program,
+    emulator::stdin::{EmulatorStdin, EmulatorStdinBuilder},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::time::sleep;
+use tracing::{Instrument, error, info};
+
+// interval to check the states of the dependencies
+const DEPENDENCY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+// proof and public values digest of a completed dependency
+struct DependencyProof {
+    proof: Vec<u8>,
+    pv_digest: Vec<u8>,
+}
+
+// holder of the tasks depending on the proofs of the other tasks of the same app or scheduled
+// later. a task is sent to the proving queue once its `not_before` arrives and all its dependencies
+// are completed, and their proofs and public values digests are written to its stdin in the
+// declared order
+#[derive(Clone)]
+pub struct Dependencies {
+    storage: SharedStorage,
+    artifacts: Option<SharedArtifactStore>,
+    outputs: Arc<ProvingOutputs>,
+    statuses: Arc<TaskStatuses>,
+    sender: Arc<Sender<ProvingTask>>,
+}
+
+impl Dependencies {
+    pub fn new(
+        storage: SharedStorage,
+        artifacts: Option<SharedArtifactStore>,
+        outputs: Arc<ProvingOutputs>,
+        statuses: Arc<TaskStatuses>,
+        sender: Arc<Sender<ProvingTask>>,
+    ) -> Self {
+        Self {
+            storage,
+            artifacts,
+            outputs,
+            statuses,
+            sender,
+        }
+    }
+
+    // wait for the dependencies in the background, the task fails if any of them fails
+    pub fn spawn(&self, mut task: ProvingTask, depends_on: Vec<String>) {
+        let this = self.clone();
+        let span = task.span.clone();
+        tokio::spawn(
+            async move {
+                let key = task.key.clone();
+                let proofs = match this.wait(&task, &depends_on).await {
+                    Ok(Some(proofs)) => proofs,
+                    // cancelled while waiting
+                    Ok(None) => return,
+                    Err(e) => {
+                        this.fail(&key, format!("dependency failed: {e}")).await;
+                        return;
+                    }
+                };
+                task.inputs = match write_proofs(task.inputs.take(), &proofs) {
+                    Ok(inputs) => Some(inputs),
+                    Err(e) => {
+                        let reason = format!("failed to write proofs of dependencies: {e}");
+                        this.fail(&key, reason).await;
+                        return;
+                    }
+                };
+                info!(
+                    "[dependencies] {} dependencies of task {key:?} completed, queueing it",
+                    depends_on.len()
+                );
+                if let Err(e) = this.sender.send(task) {
+                    this.fail(&key, format!("failed to send a proving task: {e}"))
+                        .await;
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    // hold the task until the unix timestamp, then wait for its dependencies if any. it's dropped
+    // if cancelled meanwhile
+    pub fn spawn_at(&self, task: ProvingTask, depends_on: Vec<String>, not_before: u64) {
+        let this = self.clone();
+        let span = task.span.clone();
+        tokio::spawn(
+            async move {
+                let delay = Duration::from_secs(not_before.saturating_sub(unix_timestamp()));
+                tokio::select! {
+                    _ = sleep(delay) => {}
+                    _ = task.cancel_token.cancelled() => return,
+                }
+                let key = task.key.clone();
+                info!("[dependencies] task {key:?} reached not_before {not_before}");
+                if !depends_on.is_empty() {
+                    this.spawn(task, depends_on);
+                    return;
+                }
+                if let Err(e) = this.sender.send(task) {
+                    this.fail(&key, format!("failed to send a proving task: {e}"))
+                        .await;
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    async fn wait(
+        &self,
+        task: &ProvingTask,
+        depends_on: &[String],
+    ) -> Result<Option<Vec<DependencyProof>>> {
+        let mut proofs = Vec::with_capacity(depends_on.len());
+        for task_id in depends_on {
+            let key = ProvingKey::new(
+                task.key.namespace().to_string(),
+                task.key.app_id().to_string(),
+                task_id.clone(),
+            );
+            loop {
+                if task.cancel_token.is_cancelled() {
+                    return Ok(None);
+                }
+                // the dependencies finished before a restart are not tracked
+                let state = self.statuses.get(&key).map(|status| status.state);
+                match state {
+                    Some(state @ (TaskState::Failed | TaskState::Cancelled)) => {
+                        bail!("task {task_id} is {state:?}")
+                    }
+                    Some(TaskState::Completed) | None => {
+                        let proof = self.load_proof(&key).await?;
+                        proofs.push(
+                            proof.ok_or_else(|| anyhow!("proof of task {task_id} is missing"))?,
+                        );
+                        break;
+                    }
+                    Some(_) => sleep(DEPENDENCY_CHECK_INTERVAL).await,
+                }
+            }
+        }
+
+        Ok(Some(proofs))
+    }
+
+    async fn load_proof(&self, key: &ProvingKey) -> Result<Option<DependencyProof>> {
+        if let Some(output) = self.outputs.get(key) {
+            return Ok(Some(DependencyProof {
+                proof: output.proof.to_vec(),
+                pv_digest: output.pv_digest.to_be_bytes_vec(),
+            }));
+        }
+        let Some(row) = self.storage.get_proof(key).await? else {
+            return Ok(None);
+        };
+        let proof = match (row.proof.is_empty(), &self.artifacts) {
+            (true, Some(store)) => store.get(&artifact_store::proof_key(key)).await?,
+            _ => Some(row.proof),
+        };
+
+        Ok(proof.map(|proof| DependencyProof {
+            proof,
+            pv_digest: row.pv_digest.unwrap_or_default(),
+        }))
+    }
+
+    async fn fail(&self, key: &ProvingKey, reason: String) {
+        error!("[dependencies] task {key:?} failed: {reason}");
+        fail_task(&self.statuses, key, reason.clone());
+        if let Err(e) = self
+            .storage
+            .update_task_state(key, TaskState::Failed, Some(&reason))
+            .await
+        {
+            error!("[dependencies] failed to persist failed task {key:?}: {e}");
+        }
+    }
+}
+
+// append the proof and the public values digest of each dependency to the stdin
+fn write_proofs(inputs: Option<Vec<u8>>, proofs: &[DependencyProof]) -> Result<Vec<u8>> {
+    let mut stdin_builder: EmulatorStdinBuilder<Vec<u8>, SC> = match inputs {
+        Some(inputs) => bincode::deserialize(&inputs)?,
+        None => EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>(),
+    };
+    for proof in proofs {
+        stdin_builder.write_slice(&proof.proof);
+        stdin_builder.write_slice(&proof.pv_digest);
+    }
+
+    Ok(bincode::serialize(&stdin_builder)?)
+}
@@ -1,7 +1,12 @@
-use crate::{ErrCode, ErrMsg, EstimateCostResponse};
+use crate::{
+    ErrCode, ErrMsg, EstimateCostResponse,
+    client::{ERR_CODE_METADATA_KEY, ESTIMATED_WAIT_METADATA_KEY, QUEUE_DEPTH_METADATA_KEY},
+};
 use pico_vm::emulator::riscv::emulator::EmulationError;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
+use tonic::{Code, Status};
 
 #[derive(Error, Debug, Serialize, Deserialize)]
 pub enum PicoError {
@@ -12,42 +17,214 @@ pub enum PicoError {
     // common internal error
     #[error("internal error: {0}")]
     InternalError(String),
+
+    // task exceeded a resource budget like the memory, it's not retried
+    #[error("{0}")]
+    ResourceExhausted(String),
+
+    // the guest program failed in emulation, e.g. panicked or hit an invalid instruction
+    #[error("execution failed: {0}")]
+    ExecutionFailed(String),
+
+    // task cannot complete before its deadline, it's not retried
+    #[error("deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
+    // the public values digest of the emulation mismatches the expected one, it's not retried
+    #[error("{0}")]
+    PvDigestMismatch(String),
+}
+
+impl PicoError {
+    pub fn code(&self) -> ErrCode {
+        match self {
+            Self::ExceededCycleLimit(_) => ErrCode::InputExceeded,
+            Self::InternalError(_) => ErrCode::Internal,
+            Self::ResourceExhausted(_) => ErrCode::ResourceExhausted,
+            Self::ExecutionFailed(_) => ErrCode::ExecutionFailed,
+            Self::DeadlineExceeded(_) => ErrCode::DeadlineExceeded,
+            Self::PvDigestMismatch(_) => ErrCode::PvDigestMismatch,
+        }
+    }
+}
+
+// code of the structured proving errors, none for the others
+pub fn error_code(e: &anyhow::Error) -> Option<ErrCode> {
+    e.downcast_ref::<PicoError>().map(PicoError::code)
+}
+
+// the failure may be caused by the device rather than the task, e.g. a CUDA error, the structured
+// failures of the task itself are excluded
+pub fn is_device_fault(e: &anyhow::Error) -> bool {
+    !matches!(
+        e.downcast_ref::<PicoError>(),
+        Some(
+            PicoError::ExceededCycleLimit(_)
+                | PicoError::ResourceExhausted(_)
+                | PicoError::ExecutionFailed(_)
+                | PicoError::DeadlineExceeded(_)
+                | PicoError::PvDigestMismatch(_)
+        )
+    )
 }
 
 impl From<EmulationError> for PicoError {
     fn from(e: EmulationError) -> Self {
         match e {
             EmulationError::ExceededCycleLimit(cycles) => Self::ExceededCycleLimit(cycles),
-            _ => Self::InternalError(e.to_string()),
+            _ => Self::ExecutionFailed(e.to_string()),
+        }
+    }
+}
+
+// code of the typed error attached to the status by the service
+pub fn err_code_of(status: &Status) -> Option<ErrCode> {
+    let value = status
+        .metadata()
+        .get(ERR_CODE_METADATA_KEY)?
+        .to_str()
+        .ok()?;
+
+    ErrCode::from_str_name(value)
+}
+
+fn metadata_u64(status: &Status, key: &str) -> Option<u64> {
+    status.metadata().get(key)?.to_str().ok()?.parse().ok()
+}
+
+// typed error of the service for the clients, parsed from the common result of a response or the
+// status of a failed RPC
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ServiceError {
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("unknown app: {0}")]
+    UnknownApp(String),
+
+    #[error("input exceeded cycle limit: {0}")]
+    InputExceeded(String),
+
+    #[error("resource exhausted: {0}")]
+    ResourceExhausted(String),
+
+    #[error("{0}")]
+    ExecutionFailed(String),
+
+    #[error("proving failed: {0}")]
+    ProvingFailed(String),
+
+    #[error("deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
+    #[error("{0}")]
+    PvDigestMismatch(String),
+
+    // the queue depth and the estimated wait are only known from the status of a failed RPC
+    #[error("service overloaded: {message}")]
+    Overloaded {
+        message: String,
+        queue_depth: Option<u64>,
+        estimated_wait: Option<Duration>,
+    },
+
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("prover internal error: {0}")]
+    Internal(String),
+
+    // the other errors of the RPC, e.g. unauthenticated or unavailable
+    #[error("rpc failed with {code:?}: {message}")]
+    Rpc { code: Code, message: String },
+}
+
+impl ServiceError {
+    // none if the code is OK
+    pub fn from_code(code: ErrCode, message: String) -> Option<Self> {
+        let e = match code {
+            ErrCode::Ok | ErrCode::ProvingPending => return None,
+            ErrCode::Inval => Self::InvalidInput(message),
+            ErrCode::AppNotFound => Self::UnknownApp(message),
+            ErrCode::InputExceeded => Self::InputExceeded(message),
+            ErrCode::ResourceExhausted => Self::ResourceExhausted(message),
+            ErrCode::ExecutionFailed => Self::ExecutionFailed(message),
+            ErrCode::ProvingFailed => Self::ProvingFailed(message),
+            ErrCode::DeadlineExceeded => Self::DeadlineExceeded(message),
+            ErrCode::PvDigestMismatch => Self::PvDigestMismatch(message),
+            ErrCode::Overloaded => Self::Overloaded {
+                message,
+                queue_depth: None,
+                estimated_wait: None,
+            },
+            ErrCode::QuotaExceeded => Self::QuotaExceeded(message),
+            ErrCode::Internal => Self::Internal(message),
+        };
+
+        Some(e)
+    }
+
+    // the common result of a response, none if it's unset or OK
+    pub fn from_err_msg(err: Option<&ErrMsg>) -> Option<Self> {
+        let err = err?;
+        let code = ErrCode::try_from(err.code).unwrap_or(ErrCode::Internal);
+
+        Self::from_code(code, err.msg.clone().unwrap_or_default())
+    }
+
+    pub fn code(&self) -> ErrCode {
+        match self {
+            Self::InvalidInput(_) => ErrCode::Inval,
+            Self::UnknownApp(_) => ErrCode::AppNotFound,
+            Self::InputExceeded(_) => ErrCode::InputExceeded,
+            Self::ResourceExhausted(_) => ErrCode::ResourceExhausted,
+            Self::ExecutionFailed(_) => ErrCode::ExecutionFailed,
+            Self::ProvingFailed(_) => ErrCode::ProvingFailed,
+            Self::DeadlineExceeded(_) => ErrCode::DeadlineExceeded,
+            Self::PvDigestMismatch(_) => ErrCode::PvDigestMismatch,
+            Self::Overloaded { .. } => ErrCode::Overloaded,
+            Self::QuotaExceeded(_) => ErrCode::QuotaExceeded,
+            Self::Internal(_) | Self::Rpc { .. } => ErrCode::Internal,
+        }
+    }
+}
+
+// the code attached by the service takes precedence over the gRPC code
+impl From<Status> for ServiceError {
+    fn from(status: Status) -> Self {
+        let message = status.message().to_string();
+        if err_code_of(&status) == Some(ErrCode::Overloaded) {
+            return Self::Overloaded {
+                message,
+                queue_depth: metadata_u64(&status, QUEUE_DEPTH_METADATA_KEY),
+                estimated_wait: metadata_u64(&status, ESTIMATED_WAIT_METADATA_KEY)
+                    .map(Duration::from_secs),
+            };
+        }
+        if let Some(e) =
+            err_code_of(&status).and_then(|code| Self::from_code(code, message.clone()))
+        {
+            return e;
+        }
+
+        match status.code() {
+            Code::InvalidArgument => Self::InvalidInput(message),
+            Code::ResourceExhausted => Self::ResourceExhausted(message),
+            Code::Internal => Self::Internal(message),
+            code => Self::Rpc { code, message },
         }
     }
 }
 
 impl From<PicoError> for EstimateCostResponse {
     fn from(e: PicoError) -> Self {
-        match e {
-            PicoError::ExceededCycleLimit(_) => {
-                let err = Some(ErrMsg {
-                    code: ErrCode::InputExceeded.into(),
-                    msg: Some(e.to_string()),
-                });
-                Self {
-                    err,
-                    cost: 0,
-                    pv_digest: vec![],
-                }
-            }
-            PicoError::InternalError(_) => {
-                let err = Some(ErrMsg {
-                    code: ErrCode::Internal.into(),
-                    msg: Some(e.to_string()),
-                });
-                Self {
-                    err,
-                    cost: 0,
-                    pv_digest: vec![],
-                }
-            }
+        let err = Some(ErrMsg {
+            code: e.code().into(),
+            msg: Some(e.to_string()),
+        });
+        Self {
+            err,
+            ..Self::default()
         }
     }
 }
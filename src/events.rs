@@ -0,0 +1,96 @@
+use crate::{
+    EventKind, ServiceEvent, SubscribeEventsRequest, TaskCollectedEvent, TaskKey, TaskState,
+    TaskStateEvent, WorkerHealthEvent, proving_queue::ProvingKey, service_event::Event,
+    utils::time::unix_timestamp,
+};
+use std::sync::LazyLock;
+use tokio::sync::broadcast;
+
+// capacity of the event channel, a lagged subscriber misses the oldest events
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+static EVENTS: LazyLock<broadcast::Sender<ServiceEvent>> =
+    LazyLock::new(|| broadcast::channel(EVENT_CHANNEL_CAPACITY).0);
+
+// subscribe to the events of the process published after subscribing
+pub fn subscribe() -> broadcast::Receiver<ServiceEvent> {
+    EVENTS.subscribe()
+}
+
+// no error if there's no subscriber
+fn publish(event: Event) {
+    let _ = EVENTS.send(ServiceEvent {
+        timestamp: unix_timestamp(),
+        event: Some(event),
+    });
+}
+
+fn task_key(key: &ProvingKey) -> TaskKey {
+    TaskKey {
+        app_id: key.app_id().to_string(),
+        task_id: key.task_id().to_string(),
+        namespace: key.namespace().to_string(),
+    }
+}
+
+pub fn task_state(key: &ProvingKey, state: TaskState, failure_reason: Option<&str>) {
+    publish(Event::TaskState(TaskStateEvent {
+        task: Some(task_key(key)),
+        state: state.into(),
+        failure_reason: failure_reason.map(str::to_string),
+    }));
+}
+
+pub fn worker_health(worker_id: &str, alive: bool) {
+    publish(Event::WorkerHealth(WorkerHealthEvent {
+        worker_id: worker_id.to_string(),
+        alive,
+    }));
+}
+
+pub fn task_collected(key: &ProvingKey) {
+    publish(Event::TaskCollected(TaskCollectedEvent {
+        task: Some(task_key(key)),
+    }));
+}
+
+// filter of a subscription, the task events are of the namespace and the app if set
+pub struct EventFilter {
+    namespace: String,
+    app_id: Option<String>,
+    kinds: Vec<EventKind>,
+}
+
+impl EventFilter {
+    pub fn new(namespace: String, req: &SubscribeEventsRequest) -> Self {
+        Self {
+            namespace,
+            app_id: req.app_id.clone(),
+            kinds: req.kinds().collect(),
+        }
+    }
+
+    pub fn matches(&self, event: &ServiceEvent) -> bool {
+        let (kind, task) = match &event.event {
+            Some(Event::TaskState(event)) => (EventKind::TaskState, event.task.as_ref()),
+            Some(Event::WorkerHealth(_)) => (EventKind::WorkerHealth, None),
+            Some(Event::TaskCollected(event)) => (EventKind::TaskCollected, event.task.as_ref()),
+            None => return false,
+        };
+        if !self.kinds.is_empty() && !self.kinds.contains(&kind) {
+            return false;
+        }
+
+        match (kind, task) {
+            (EventKind::WorkerHealth, _) => true,
+            (_, Some(task)) => {
+                task.namespace == self.namespace
+                    && self
+                        .app_id
+                        .as_ref()
+                        .is_none_or(|app_id| *app_id == task.app_id)
+            }
+            (_, None) => false,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::{FetchError, SchemeFetcher, check_size};
+use crate::utils::net::check_public_host;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use url::Url;
+
+// fetcher of the http:// and https:// URIs, it's also used by the HTTP gateways of the other
+// schemes
+#[derive(Clone)]
+pub struct HttpFetcher {
+    client: reqwest::Client,
+    // hosts allowed to resolve to the non-public addresses
+    allowed_hosts: Arc<[String]>,
+}
+
+impl HttpFetcher {
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        // the redirects are not followed, since the redirected URL is not checked
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("the fetcher client is built without TLS customization");
+
+        Self {
+            client,
+            allowed_hosts: allowed_hosts.into(),
+        }
+    }
+
+    pub async fn get(&self, url: &Url, max_size: usize) -> Result<Vec<u8>> {
+        check_public_host(url, &self.allowed_hosts)
+            .await
+            .map_err(FetchError::Forbidden)?;
+        let mut resp = self
+            .client
+            .get(url.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+        if resp.status().is_redirection() {
+            return Err(FetchError::Failed(format!(
+                "redirected by status {}, the redirects are not followed",
+                resp.status()
+            ))
+            .into());
+        }
+        if let Some(len) = resp.content_length() {
+            check_size(len as usize, max_size)?;
+        }
+
+        let mut data = vec![];
+        while let Some(chunk) = resp.chunk().await? {
+            check_size(data.len() + chunk.len(), max_size)?;
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(data)
+    }
+}
+
+#[async_trait]
+impl SchemeFetcher for HttpFetcher {
+    async fn fetch(&self, uri: &Url, max_size: usize) -> Result<Vec<u8>> {
+        self.get(uri, max_size).await
+    }
+}
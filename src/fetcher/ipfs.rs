@@ -0,0 +1,29 @@
+use super::{FetchError, HttpFetcher, SchemeFetcher};
+use anyhow::Result;
+use async_trait::async_trait;
+use url::Url;
+
+// fetcher of the ipfs://CID/PATH URIs by GATEWAY/ipfs/CID/PATH, the content is still verified by
+// its sha256 since the gateway isn't trusted
+pub struct IpfsFetcher {
+    http: HttpFetcher,
+    gateway: Url,
+}
+
+impl IpfsFetcher {
+    pub fn new(http: HttpFetcher, gateway: Url) -> Self {
+        Self { http, gateway }
+    }
+}
+
+#[async_trait]
+impl SchemeFetcher for IpfsFetcher {
+    async fn fetch(&self, uri: &Url, max_size: usize) -> Result<Vec<u8>> {
+        let cid = uri
+            .host_str()
+            .ok_or_else(|| FetchError::InvalidUri(format!("missing CID of {uri}")))?;
+        let url = self.gateway.join(&format!("ipfs/{cid}{}", uri.path()))?;
+
+        self.http.get(&url, max_size).await
+    }
+}
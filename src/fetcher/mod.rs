@@ -0,0 +1,208 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use thiserror::Error;
+use tokio::time::timeout;
+use url::Url;
+
+mod http;
+mod ipfs;
+mod s3;
+
+pub use http::HttpFetcher;
+pub use ipfs::IpfsFetcher;
+pub use s3::S3Fetcher;
+
+const MIB: usize = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FetchScheme {
+    Http,
+    Https,
+    // objects of the buckets readable by the standard AWS environment
+    S3,
+    // contents fetched from the configured HTTP gateway
+    Ipfs,
+}
+
+impl FetchScheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Https => "https",
+            Self::S3 => "s3",
+            Self::Ipfs => "ipfs",
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct FetchConfig {
+    #[clap(
+        long,
+        env = "FETCH_SCHEMES",
+        value_enum,
+        value_delimiter = ',',
+        help = "URI schemes of the inputs and ELFs fetched by the service (http, https, s3, ipfs), nothing is fetched if not set"
+    )]
+    pub fetch_schemes: Vec<FetchScheme>,
+
+    #[clap(
+        long,
+        env = "FETCH_MAX_SIZE_MB",
+        default_value = "512",
+        help = "Maximum size (MiB) of a fetched input or ELF"
+    )]
+    pub fetch_max_size_mb: usize,
+
+    #[clap(
+        long,
+        env = "FETCH_TIMEOUT_SECS",
+        default_value = "60",
+        help = "Timeout (seconds) to fetch an input or ELF"
+    )]
+    pub fetch_timeout_secs: u64,
+
+    #[clap(
+        long,
+        env = "IPFS_GATEWAY",
+        default_value = "https://ipfs.io/",
+        help = "HTTP gateway to fetch the ipfs:// URIs"
+    )]
+    pub ipfs_gateway: Url,
+
+    #[clap(
+        long,
+        env = "FETCH_ALLOWED_HOSTS",
+        value_delimiter = ',',
+        help = "Hosts of the http(s) URIs allowed to resolve to the loopback, link-local or private addresses, separated by comma. the IPFS gateway is always allowed"
+    )]
+    pub fetch_allowed_hosts: Vec<String>,
+
+    #[clap(
+        long,
+        env = "FETCH_S3_BUCKETS",
+        value_delimiter = ',',
+        help = "Buckets of the s3:// URIs read by the service credentials, separated by comma. the other buckets are rejected"
+    )]
+    pub fetch_s3_buckets: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("invalid URI: {0}")]
+    InvalidUri(String),
+
+    #[error("scheme {0} is not enabled")]
+    UnsupportedScheme(String),
+
+    #[error("expected sha256 of 32 bytes, got {0} bytes")]
+    InvalidChecksum(usize),
+
+    #[error("content exceeded the limit of {0} bytes")]
+    TooLarge(usize),
+
+    // the URI reaches the network or the bucket not open to the clients
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("checksum mismatch, expected 0x{expected}, but fetched 0x{actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    // the remote failed or timed out
+    #[error("{0}")]
+    Failed(String),
+}
+
+// fetcher of the URIs of a scheme
+#[async_trait]
+pub trait SchemeFetcher: Send + Sync {
+    // fetch the whole content, it fails by `check_size` once it exceeds `max_size`
+    async fn fetch(&self, uri: &Url, max_size: usize) -> Result<Vec<u8>>;
+}
+
+// fetcher of the inputs and ELFs by the URIs of the enabled schemes, a content is accepted only if
+// it's within the size limit and its sha256 matches
+#[derive(Clone)]
+pub struct Fetcher {
+    schemes: HashMap<String, Arc<dyn SchemeFetcher>>,
+    max_size: usize,
+    timeout: Duration,
+}
+
+impl Fetcher {
+    pub fn new(cfg: &FetchConfig) -> Self {
+        let mut fetcher = Self {
+            schemes: HashMap::new(),
+            max_size: cfg.fetch_max_size_mb.saturating_mul(MIB),
+            timeout: Duration::from_secs(cfg.fetch_timeout_secs),
+        };
+        // the gateway is configured by the operator, it may be an internal node
+        let mut allowed_hosts = cfg.fetch_allowed_hosts.clone();
+        allowed_hosts.extend(cfg.ipfs_gateway.host_str().map(str::to_string));
+        let http = HttpFetcher::new(allowed_hosts);
+        for scheme in &cfg.fetch_schemes {
+            let scheme_fetcher: Arc<dyn SchemeFetcher> = match scheme {
+                FetchScheme::Http | FetchScheme::Https => Arc::new(http.clone()),
+                FetchScheme::S3 => Arc::new(S3Fetcher::new(cfg.fetch_s3_buckets.clone())),
+                FetchScheme::Ipfs => {
+                    Arc::new(IpfsFetcher::new(http.clone(), cfg.ipfs_gateway.clone()))
+                }
+            };
+            fetcher.register(scheme.name(), scheme_fetcher);
+        }
+
+        fetcher
+    }
+
+    // plug in the fetcher of a scheme, it replaces the existing one of the same scheme
+    pub fn register(&mut self, scheme: &str, fetcher: Arc<dyn SchemeFetcher>) {
+        self.schemes.insert(scheme.to_string(), fetcher);
+    }
+
+    pub async fn fetch(&self, uri: &str, sha256: &[u8]) -> Result<Vec<u8>, FetchError> {
+        if sha256.len() != 32 {
+            return Err(FetchError::InvalidChecksum(sha256.len()));
+        }
+        let url = Url::parse(uri).map_err(|e| FetchError::InvalidUri(e.to_string()))?;
+        let fetcher = self
+            .schemes
+            .get(url.scheme())
+            .ok_or_else(|| FetchError::UnsupportedScheme(url.scheme().to_string()))?;
+
+        let data = match timeout(self.timeout, fetcher.fetch(&url, self.max_size)).await {
+            Ok(Ok(data)) => data,
+            Ok(Err(e)) => {
+                return Err(e
+                    .downcast::<FetchError>()
+                    .unwrap_or_else(|e| FetchError::Failed(e.to_string())));
+            }
+            Err(_) => {
+                return Err(FetchError::Failed(format!(
+                    "timed out after {:?}",
+                    self.timeout
+                )));
+            }
+        };
+        let actual = Sha256::digest(&data);
+        if actual.as_slice() != sha256 {
+            return Err(FetchError::ChecksumMismatch {
+                expected: hex::encode(sha256),
+                actual: hex::encode(actual),
+            });
+        }
+
+        Ok(data)
+    }
+}
+
+// fail once the content read so far or its declared length exceeds the limit
+pub fn check_size(size: usize, max_size: usize) -> Result<()> {
+    if size > max_size {
+        return Err(FetchError::TooLarge(max_size).into());
+    }
+
+    Ok(())
+}
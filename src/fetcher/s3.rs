@@ -0,0 +1,56 @@
+use super::{FetchError, SchemeFetcher, check_size};
+use anyhow::Result;
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::Client;
+use tokio::sync::OnceCell;
+use url::Url;
+
+// fetcher of the s3://BUCKET/KEY URIs, the client is created on the first fetch with the
+// credentials and region of the standard AWS environment
+pub struct S3Fetcher {
+    client: OnceCell<Client>,
+    // the buckets open to the clients, the service credentials may read the artifacts of the other
+    // tenants in the rest
+    buckets: Vec<String>,
+}
+
+impl S3Fetcher {
+    pub fn new(buckets: Vec<String>) -> Self {
+        Self {
+            client: OnceCell::new(),
+            buckets,
+        }
+    }
+}
+
+#[async_trait]
+impl SchemeFetcher for S3Fetcher {
+    async fn fetch(&self, uri: &Url, max_size: usize) -> Result<Vec<u8>> {
+        let bucket = uri
+            .host_str()
+            .ok_or_else(|| FetchError::InvalidUri(format!("missing bucket of {uri}")))?;
+        if !self.buckets.iter().any(|allowed| allowed == bucket) {
+            return Err(FetchError::Forbidden(format!("bucket {bucket} is not allowed")).into());
+        }
+        let key = uri.path().trim_start_matches('/');
+        let client = self
+            .client
+            .get_or_init(|| async {
+                Client::new(&aws_config::load_defaults(BehaviorVersion::latest()).await)
+            })
+            .await;
+
+        let mut output = client.get_object().bucket(bucket).key(key).send().await?;
+        if let Some(len) = output.content_length() {
+            check_size(len.max(0) as usize, max_size)?;
+        }
+        let mut data = vec![];
+        while let Some(chunk) = output.body.try_next().await? {
+            check_size(data.len() + chunk.len(), max_size)?;
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,756 @@
+use crate::{
+    AppInfo, AppMetadata, ErrCode, ErrMsg, EstimateCostRequest, GetTaskStatusRequest,
+    InputsCompression, ListAppsRequest, ListTasksRequest, ProofType, ProveTaskRequest,
+    RegisterAppRequest, TaskPriority, TaskState, TaskSummary, WarmUpOptions,
+    client::RETRY_AFTER_METADATA_KEY, error::err_code_of, grpc::GrpcService,
+    prover_network_server::ProverNetwork, shutdown::Shutdown,
+};
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header::RETRY_AFTER},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tonic::{Code, Extensions, Request, Status, metadata::MetadataMap};
+use tracing::{info, warn};
+
+// interceptor of the gRPC service authenticating the gateway requests the same, it's shared by
+// the concurrent requests
+pub type GatewayInterceptor =
+    Arc<dyn Fn(Request<()>) -> Result<Request<()>, Status> + Send + Sync + 'static>;
+
+// JSON gateway of the proving service for the clients not speaking gRPC, the requests are
+// converted to the gRPC messages and handled by the same service. the binary fields are base64
+// and the enums are their proto names
+#[derive(Clone)]
+struct Gateway {
+    service: Arc<GrpcService>,
+    interceptor: GatewayInterceptor,
+}
+
+impl Gateway {
+    // the headers are the metadata of the gRPC request, e.g. the API key, the bearer token and
+    // the namespace
+    fn request<T>(&self, headers: HeaderMap, message: T) -> Result<Request<T>, ApiError> {
+        let req = Request::from_parts(
+            MetadataMap::from_headers(headers),
+            Extensions::default(),
+            (),
+        );
+        let req = (self.interceptor)(req)?;
+        let (metadata, extensions, ()) = req.into_parts();
+
+        Ok(Request::from_parts(metadata, extensions, message))
+    }
+}
+
+// serve the gateway on the address, it's bound before returning to fail fast. the body limit
+// covers the base64 of the binary fields
+pub async fn serve(
+    addr: SocketAddr,
+    service: Arc<GrpcService>,
+    interceptor: GatewayInterceptor,
+    body_limit: usize,
+    shutdown: Shutdown,
+) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("[gateway] serving on {addr}");
+
+    let gateway = Gateway {
+        service,
+        interceptor,
+    };
+    let app = Router::new()
+        .route("/apps", post(register_app).get(list_apps))
+        .route("/tasks", post(prove_task).get(list_tasks))
+        .route("/tasks/{task_id}", get(get_task_status))
+        .route("/estimate", post(estimate_cost))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .with_state(gateway);
+    Ok(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown.stopped())
+            .await
+        {
+            warn!("[gateway] server exits: {e}");
+        }
+    }))
+}
+
+// the failed status of the service, it's returned as the HTTP status with the gRPC code, the
+// typed error code and the message
+struct ApiError(Status);
+
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    code: String,
+    err_code: Option<&'static str>,
+    message: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.0;
+        let http_status = match status.code() {
+            Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+                StatusCode::BAD_REQUEST
+            }
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+            Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+            Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = ErrorBody {
+            code: format!("{:?}", status.code()),
+            err_code: err_code_of(&status).map(|code| code.as_str_name()),
+            message: status.message().to_string(),
+        };
+        let mut res = (http_status, Json(body)).into_response();
+        if let Some(value) = status
+            .metadata()
+            .get(RETRY_AFTER_METADATA_KEY)
+            .and_then(|value| HeaderValue::from_bytes(value.as_bytes()).ok())
+        {
+            res.headers_mut().insert(RETRY_AFTER, value);
+        }
+
+        res
+    }
+}
+
+// the proto name of an enum field, case-insensitive
+fn parse_enum<E: Into<i32>>(
+    field: &str,
+    value: Option<&str>,
+    from_str_name: fn(&str) -> Option<E>,
+) -> Result<Option<i32>, ApiError> {
+    value
+        .map(|value| {
+            from_str_name(&value.to_uppercase())
+                .map(Into::into)
+                .ok_or_else(|| {
+                    ApiError(Status::invalid_argument(format!("invalid {field} {value}")))
+                })
+        })
+        .transpose()
+}
+
+mod base64_bytes {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(D::Error::custom)
+    }
+}
+
+mod base64_opt {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::base64_bytes")] Vec<u8>);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|wrapper| wrapper.0))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct AppInfoJson {
+    name: String,
+    description: String,
+    owner: String,
+    tags: Vec<String>,
+    toolchain_version: String,
+}
+
+impl From<AppInfoJson> for AppInfo {
+    fn from(info: AppInfoJson) -> Self {
+        Self {
+            name: info.name,
+            description: info.description,
+            owner: info.owner,
+            tags: info.tags,
+            toolchain_version: info.toolchain_version,
+        }
+    }
+}
+
+impl From<AppInfo> for AppInfoJson {
+    fn from(info: AppInfo) -> Self {
+        Self {
+            name: info.name,
+            description: info.description,
+            owner: info.owner,
+            tags: info.tags,
+            toolchain_version: info.toolchain_version,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrMsgJson {
+    code: &'static str,
+    msg: Option<String>,
+}
+
+// the common result of a response, none if OK
+fn err_msg(err: Option<ErrMsg>) -> Option<ErrMsgJson> {
+    let err = err?;
+    let code = err.code();
+    (code != ErrCode::Ok).then(|| ErrMsgJson {
+        code: code.as_str_name(),
+        msg: err.msg,
+    })
+}
+
+#[derive(Deserialize)]
+struct RegisterAppBody {
+    #[serde(default, with = "base64_bytes")]
+    elf: Vec<u8>,
+    info: Option<String>,
+    name: Option<String>,
+    app_info: Option<AppInfoJson>,
+    elf_uri: Option<String>,
+    #[serde(default, with = "base64_bytes")]
+    elf_sha256: Vec<u8>,
+    warm_up: Option<WarmUpJson>,
+}
+
+#[derive(Deserialize)]
+struct WarmUpJson {
+    #[serde(default, deserialize_with = "base64_opt::deserialize")]
+    smoke_inputs: Option<Vec<u8>>,
+}
+
+#[derive(Serialize)]
+struct RegisterAppReply {
+    app_id: String,
+    version: Option<u32>,
+}
+
+async fn register_app(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    Json(body): Json<RegisterAppBody>,
+) -> Result<impl IntoResponse, ApiError> {
+    let req = RegisterAppRequest {
+        elf: body.elf,
+        info: body.info,
+        name: body.name,
+        app_info: body.app_info.map(Into::into),
+        elf_uri: body.elf_uri,
+        elf_sha256: body.elf_sha256,
+        warm_up: body.warm_up.map(|warm_up| WarmUpOptions {
+            smoke_inputs: warm_up.smoke_inputs,
+        }),
+    };
+    let res = gateway
+        .service
+        .register_app(gateway.request(headers, req)?)
+        .await?
+        .into_inner();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RegisterAppReply {
+            app_id: res.app_id,
+            version: res.version,
+        }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct ListAppsQuery {
+    #[serde(default)]
+    page_size: u32,
+    #[serde(default)]
+    page_token: String,
+    owner: Option<String>,
+    name: Option<String>,
+    tag: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AppJson {
+    app_id: String,
+    elf_hash: String,
+    created_at: u64,
+    info: Option<String>,
+    app_info: Option<AppInfoJson>,
+}
+
+impl From<AppMetadata> for AppJson {
+    fn from(app: AppMetadata) -> Self {
+        Self {
+            app_id: app.app_id,
+            elf_hash: app.elf_hash,
+            created_at: app.created_at,
+            info: app.info,
+            app_info: app.app_info.map(Into::into),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ListAppsReply {
+    apps: Vec<AppJson>,
+    next_page_token: String,
+}
+
+async fn list_apps(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    Query(query): Query<ListAppsQuery>,
+) -> Result<Json<ListAppsReply>, ApiError> {
+    let req = ListAppsRequest {
+        page_size: query.page_size,
+        page_token: query.page_token,
+        owner: query.owner,
+        name: query.name,
+        tag: query.tag,
+    };
+    let res = gateway
+        .service
+        .list_apps(gateway.request(headers, req)?)
+        .await?
+        .into_inner();
+
+    Ok(Json(ListAppsReply {
+        apps: res.apps.into_iter().map(Into::into).collect(),
+        next_page_token: res.next_page_token,
+    }))
+}
+
+#[derive(Deserialize)]
+struct ProveTaskBody {
+    #[serde(default)]
+    app_id: String,
+    task_id: String,
+    #[serde(default, deserialize_with = "base64_opt::deserialize")]
+    inputs: Option<Vec<u8>>,
+    use_gpu: Option<bool>,
+    priority: Option<String>,
+    app_name: Option<String>,
+    app_version: Option<u32>,
+    force: Option<bool>,
+    callback_url: Option<String>,
+    verify_proof: Option<bool>,
+    proof_type: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    not_before: Option<u64>,
+    deadline: Option<u64>,
+    inputs_hash: Option<String>,
+    inputs_uri: Option<String>,
+    #[serde(default, with = "base64_bytes")]
+    inputs_sha256: Vec<u8>,
+    inputs_compression: Option<String>,
+    allow_cpu_fallback: Option<bool>,
+    #[serde(default, deserialize_with = "base64_opt::deserialize")]
+    expected_pv_digest: Option<Vec<u8>>,
+}
+
+impl TryFrom<ProveTaskBody> for ProveTaskRequest {
+    type Error = ApiError;
+
+    fn try_from(body: ProveTaskBody) -> Result<Self, Self::Error> {
+        Ok(Self {
+            app_id: body.app_id,
+            task_id: body.task_id,
+            inputs: body.inputs,
+            use_gpu: body.use_gpu,
+            priority: parse_enum(
+                "priority",
+                body.priority.as_deref(),
+                TaskPriority::from_str_name,
+            )?,
+            app_name: body.app_name,
+            app_version: body.app_version,
+            force: body.force,
+            callback_url: body.callback_url,
+            verify_proof: body.verify_proof,
+            proof_type: parse_enum(
+                "proof_type",
+                body.proof_type.as_deref(),
+                ProofType::from_str_name,
+            )?,
+            depends_on: body.depends_on,
+            not_before: body.not_before,
+            deadline: body.deadline,
+            inputs_hash: body.inputs_hash,
+            inputs_uri: body.inputs_uri,
+            inputs_sha256: body.inputs_sha256,
+            inputs_compression: parse_enum(
+                "inputs_compression",
+                body.inputs_compression.as_deref(),
+                InputsCompression::from_str_name,
+            )?,
+            allow_cpu_fallback: body.allow_cpu_fallback,
+            expected_pv_digest: body.expected_pv_digest,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ProveTaskReply {
+    app_id: String,
+    task_id: String,
+    deduplicated_by: Option<String>,
+}
+
+// the task is accepted to be proved asynchronously, its status is polled by GET /tasks/{task_id}
+async fn prove_task(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    Json(body): Json<ProveTaskBody>,
+) -> Result<impl IntoResponse, ApiError> {
+    let task_id = body.task_id.clone();
+    let req = ProveTaskRequest::try_from(body)?;
+    let res = gateway
+        .service
+        .prove_task(gateway.request(headers, req)?)
+        .await?
+        .into_inner();
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ProveTaskReply {
+            app_id: res.app_id,
+            task_id,
+            deduplicated_by: res.deduplicated_by,
+        }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct ListTasksQuery {
+    app_id: Option<String>,
+    state: Option<String>,
+    created_after: Option<u64>,
+    created_before: Option<u64>,
+    #[serde(default)]
+    page_size: u32,
+    #[serde(default)]
+    page_token: String,
+}
+
+#[derive(Serialize)]
+struct TaskJson {
+    app_id: String,
+    task_id: String,
+    state: &'static str,
+    created_at: u64,
+    started_at: Option<u64>,
+    finished_at: Option<u64>,
+    failure_reason: Option<String>,
+}
+
+impl From<TaskSummary> for TaskJson {
+    fn from(task: TaskSummary) -> Self {
+        Self {
+            state: task.state().as_str_name(),
+            app_id: task.app_id,
+            task_id: task.task_id,
+            created_at: task.created_at,
+            started_at: task.started_at,
+            finished_at: task.finished_at,
+            failure_reason: task.failure_reason,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ListTasksReply {
+    tasks: Vec<TaskJson>,
+    next_page_token: String,
+    total_count: u64,
+}
+
+async fn list_tasks(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    Query(query): Query<ListTasksQuery>,
+) -> Result<Json<ListTasksReply>, ApiError> {
+    let req = ListTasksRequest {
+        app_id: query.app_id,
+        state: parse_enum("state", query.state.as_deref(), TaskState::from_str_name)?,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        page_size: query.page_size,
+        page_token: query.page_token,
+    };
+    let res = gateway
+        .service
+        .list_tasks(gateway.request(headers, req)?)
+        .await?
+        .into_inner();
+
+    Ok(Json(ListTasksReply {
+        tasks: res.tasks.into_iter().map(Into::into).collect(),
+        next_page_token: res.next_page_token,
+        total_count: res.total_count,
+    }))
+}
+
+// the task IDs are unique within an app, so the app is required
+#[derive(Deserialize)]
+struct TaskQuery {
+    app_id: String,
+}
+
+#[derive(Serialize)]
+struct TimingsJson {
+    emulation_secs: f64,
+    chunk_proving_secs: f64,
+    recursion_secs: f64,
+    wrapping_secs: f64,
+    total_secs: f64,
+}
+
+#[derive(Serialize)]
+struct TaskStatusReply {
+    app_id: String,
+    task_id: String,
+    state: &'static str,
+    created_at: u64,
+    started_at: Option<u64>,
+    finished_at: Option<u64>,
+    failure_reason: Option<String>,
+    failure_code: Option<&'static str>,
+    attempts: u32,
+    timings: Option<TimingsJson>,
+    not_before: Option<u64>,
+    deadline: Option<u64>,
+}
+
+async fn get_task_status(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+    Query(query): Query<TaskQuery>,
+) -> Result<Json<TaskStatusReply>, ApiError> {
+    let req = GetTaskStatusRequest {
+        app_id: query.app_id.clone(),
+        task_id: task_id.clone(),
+    };
+    let res = gateway
+        .service
+        .get_task_status(gateway.request(headers, req)?)
+        .await?
+        .into_inner();
+
+    Ok(Json(TaskStatusReply {
+        app_id: query.app_id,
+        task_id,
+        state: res.state().as_str_name(),
+        created_at: res.created_at,
+        started_at: res.started_at,
+        finished_at: res.finished_at,
+        failure_code: res
+            .failure_code
+            .is_some()
+            .then(|| res.failure_code().as_str_name()),
+        failure_reason: res.failure_reason,
+        attempts: res.attempts,
+        timings: res.timings.map(|timings| TimingsJson {
+            emulation_secs: timings.emulation_secs,
+            chunk_proving_secs: timings.chunk_proving_secs,
+            recursion_secs: timings.recursion_secs,
+            wrapping_secs: timings.wrapping_secs,
+            total_secs: timings.total_secs,
+        }),
+        not_before: res.not_before,
+        deadline: res.deadline,
+    }))
+}
+
+#[derive(Deserialize)]
+struct EstimateCostBody {
+    app_id: String,
+    #[serde(default, deserialize_with = "base64_opt::deserialize")]
+    inputs: Option<Vec<u8>>,
+    bypass_cache: Option<bool>,
+    inputs_hash: Option<String>,
+    inputs_compression: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EstimateCostReply {
+    // set if the emulation failed, e.g. `EXECUTION_FAILED` or `INPUT_EXCEEDED`
+    err: Option<ErrMsgJson>,
+    cost: u64,
+    #[serde(with = "base64_bytes")]
+    pv_digest: Vec<u8>,
+    cached: bool,
+    total_cycles: u64,
+    total_chunks: u64,
+    cpu_proving_secs: Option<f64>,
+    gpu_proving_secs: Option<f64>,
+    cpu_price: Option<f64>,
+    gpu_price: Option<f64>,
+}
+
+async fn estimate_cost(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    Json(body): Json<EstimateCostBody>,
+) -> Result<Json<EstimateCostReply>, ApiError> {
+    let req = EstimateCostRequest {
+        app_id: body.app_id,
+        inputs: body.inputs,
+        bypass_cache: body.bypass_cache,
+        inputs_hash: body.inputs_hash,
+        inputs_compression: parse_enum(
+            "inputs_compression",
+            body.inputs_compression.as_deref(),
+            InputsCompression::from_str_name,
+        )?,
+    };
+    let res = gateway
+        .service
+        .estimate_cost(gateway.request(headers, req)?)
+        .await?
+        .into_inner();
+
+    Ok(Json(EstimateCostReply {
+        err: err_msg(res.err),
+        cost: res.cost,
+        pv_digest: res.pv_digest,
+        cached: res.cached,
+        total_cycles: res.total_cycles,
+        total_chunks: res.total_chunks,
+        cpu_proving_secs: res.cpu_proving_secs,
+        gpu_proving_secs: res.gpu_proving_secs,
+        cpu_price: res.cpu_price,
+        gpu_price: res.gpu_price,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prove_task_request(body: serde_json::Value) -> Result<ProveTaskRequest, Status> {
+        let body: ProveTaskBody = serde_json::from_value(body).unwrap();
+        ProveTaskRequest::try_from(body).map_err(|ApiError(status)| status)
+    }
+
+    #[test]
+    fn test_prove_task_request() {
+        let req = prove_task_request(json!({
+            "app_id": "0xabc",
+            "task_id": "task",
+            "inputs": "aW5wdXRz",
+            "priority": "high",
+            "proof_type": "compressed",
+            "inputs_compression": "ZSTD",
+            "depends_on": ["parent"],
+            "deadline": 200,
+        }))
+        .unwrap();
+        assert_eq!(req.app_id, "0xabc");
+        assert_eq!(req.task_id, "task");
+        assert_eq!(req.inputs.as_deref(), Some(b"inputs".as_slice()));
+        assert_eq!(req.priority(), TaskPriority::High);
+        assert_eq!(req.proof_type(), ProofType::Compressed);
+        assert_eq!(req.inputs_compression(), InputsCompression::Zstd);
+        assert_eq!(req.depends_on, vec!["parent"]);
+        assert_eq!(req.deadline, Some(200));
+        assert!(req.inputs_sha256.is_empty());
+        assert!(req.expected_pv_digest.is_none());
+
+        // the unset enums are left to the defaults of the service
+        let req = prove_task_request(json!({ "task_id": "task" })).unwrap();
+        assert!(req.priority.is_none());
+        assert!(req.proof_type.is_none());
+    }
+
+    #[test]
+    fn test_prove_task_request_invalid() {
+        let status = prove_task_request(json!({
+            "task_id": "task",
+            "proof_type": "plonk",
+        }))
+        .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let body = json!({ "task_id": "task", "inputs": "not base64!" });
+        assert!(serde_json::from_value::<ProveTaskBody>(body).is_err());
+    }
+
+    #[test]
+    fn test_task_json() {
+        let task = TaskSummary {
+            app_id: "0xabc".to_string(),
+            task_id: "task".to_string(),
+            state: TaskState::Failed.into(),
+            created_at: 100,
+            started_at: None,
+            finished_at: Some(200),
+            failure_reason: Some("failed".to_string()),
+        };
+        let value = serde_json::to_value(TaskJson::from(task)).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "app_id": "0xabc",
+                "task_id": "task",
+                "state": "FAILED",
+                "created_at": 100,
+                "started_at": null,
+                "finished_at": 200,
+                "failure_reason": "failed",
+            })
+        );
+    }
+
+    #[test]
+    fn test_err_msg() {
+        assert!(err_msg(None).is_none());
+        let ok = ErrMsg {
+            code: ErrCode::Ok.into(),
+            msg: None,
+        };
+        assert!(err_msg(Some(ok)).is_none());
+    }
+
+    #[test]
+    fn test_api_error() {
+        let res = ApiError(Status::not_found("cannot find task")).into_response();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let mut status = Status::resource_exhausted("rate limited");
+        status
+            .metadata_mut()
+            .insert(RETRY_AFTER_METADATA_KEY, "3".parse().unwrap());
+        let res = ApiError(status).into_response();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[RETRY_AFTER], "3");
+    }
+}
@@ -0,0 +1,408 @@
+use crate::{GpuDeviceStats, metrics::metrics, utils::time::unix_timestamp};
+use anyhow::{Result, anyhow, bail};
+use clap::Args;
+use dashmap::DashMap;
+use std::{process::Command, sync::Arc, time::Duration};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore, watch},
+    task::JoinHandle,
+    time::sleep,
+};
+use tracing::{info, warn};
+
+#[derive(Debug, Args, Clone)]
+pub struct GpuHealthConfig {
+    #[clap(
+        long,
+        env = "GPU_FAILURE_THRESHOLD",
+        default_value = "3",
+        help = "Proving attempts failed in a row on a CUDA device before it's quarantined, never quarantined if 0"
+    )]
+    pub gpu_failure_threshold: u32,
+
+    #[clap(
+        long,
+        env = "GPU_QUARANTINE_SECS",
+        default_value = "300",
+        help = "Seconds a quarantined CUDA device is kept out before it's assigned the tasks again"
+    )]
+    pub gpu_quarantine_secs: u64,
+}
+
+// device properties reported by nvidia-smi
+#[derive(Clone, Debug)]
+struct DeviceInfo {
+    index: u32,
+    name: String,
+    memory_total: u64,
+    memory_used: u64,
+    utilization: u32,
+}
+
+struct Device {
+    info: DeviceInfo,
+    // the device is unhealthy if it's missing from the last query
+    healthy: bool,
+    // permits of the tasks proving on the device at the same time
+    permits: Arc<Semaphore>,
+    // tasks waiting for the device
+    queued: usize,
+    // tasks proving on the device
+    running: usize,
+    // proving attempts failed in a row on the device
+    failures: u32,
+    // unix timestamp (seconds) until which the device is kept out by its failures
+    quarantined_until: Option<u64>,
+}
+
+impl Device {
+    fn is_usable(&self, now: u64) -> bool {
+        self.healthy && self.quarantined_until.is_none_or(|until| until <= now)
+    }
+}
+
+// pool of the CUDA devices, a GPU task is assigned to the healthy devices with the shortest queues
+// and then waits in the queues of the devices
+pub struct GpuPool {
+    devices: DashMap<u32, Device>,
+    health: GpuHealthConfig,
+    // notified once a device turns unhealthy, the tasks waiting for it are assigned again
+    unhealthy: watch::Sender<()>,
+}
+
+impl GpuPool {
+    // enumerate the CUDA devices, only the visible devices are used if not empty
+    pub fn detect(
+        visible_devices: &[u32],
+        tasks_per_device: usize,
+        health: GpuHealthConfig,
+    ) -> Self {
+        let devices = DashMap::new();
+        match query_devices() {
+            Ok(infos) => {
+                for info in infos {
+                    if !visible_devices.is_empty() && !visible_devices.contains(&info.index) {
+                        continue;
+                    }
+                    info!(
+                        "[gpu-pool] found device {}: {}, memory {} bytes",
+                        info.index, info.name, info.memory_total
+                    );
+                    metrics().set_gpu_utilization(info.index, info.utilization);
+                    devices.insert(
+                        info.index,
+                        Device {
+                            info,
+                            healthy: true,
+                            permits: Arc::new(Semaphore::new(tasks_per_device.max(1))),
+                            queued: 0,
+                            running: 0,
+                            failures: 0,
+                            quarantined_until: None,
+                        },
+                    );
+                }
+            }
+            Err(e) => warn!("[gpu-pool] no CUDA device detected: {e}"),
+        }
+
+        Self {
+            devices,
+            health,
+            unhealthy: watch::channel(()).0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    // start a background monitor refreshing the memory, utilization and health of the devices
+    pub fn start_monitor(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                match tokio::task::spawn_blocking(query_devices).await {
+                    Ok(Ok(infos)) => pool.refresh(infos),
+                    Ok(Err(e)) => {
+                        warn!("[gpu-pool] failed to query devices: {e}");
+                        pool.refresh(vec![]);
+                    }
+                    Err(e) => warn!("[gpu-pool] device query panicked: {e}"),
+                }
+            }
+        })
+    }
+
+    // assign at most the count of devices to a task and wait until all of them are available, the
+    // task is assigned again if any of them turns unhealthy while waiting
+    pub async fn acquire(self: &Arc<Self>, count: usize) -> Result<GpuLease> {
+        loop {
+            let indexes = self.assign(count)?;
+            let mut unhealthy = self.unhealthy.subscribe();
+            let lease = self.lease(indexes.clone());
+            tokio::pin!(lease);
+            loop {
+                tokio::select! {
+                    lease = &mut lease => return lease,
+                    _ = unhealthy.changed() => {}
+                }
+                if !self.is_usable(&indexes) {
+                    break;
+                }
+            }
+            warn!("[gpu-pool] GPU devices {indexes:?} turned unhealthy, assigning the task again");
+        }
+    }
+
+    // the healthy devices with the shortest queues in the order of indexes
+    fn assign(&self, count: usize) -> Result<Vec<u32>> {
+        let now = unix_timestamp();
+        let mut candidates: Vec<_> = self
+            .devices
+            .iter()
+            .filter(|device| device.is_usable(now))
+            .map(|device| {
+                (
+                    device.queued + device.running,
+                    device.info.memory_used,
+                    device.info.index,
+                )
+            })
+            .collect();
+        if candidates.is_empty() {
+            bail!("no healthy GPU device");
+        }
+        candidates.sort_unstable();
+        // acquire in the order of indexes to avoid the deadlock between tasks
+        let mut indexes: Vec<_> = candidates
+            .into_iter()
+            .take(count.max(1))
+            .map(|(_, _, index)| index)
+            .collect();
+        indexes.sort_unstable();
+
+        Ok(indexes)
+    }
+
+    fn is_usable(&self, indexes: &[u32]) -> bool {
+        let now = unix_timestamp();
+        indexes.iter().all(|index| {
+            self.devices
+                .get(index)
+                .is_some_and(|device| device.is_usable(now))
+        })
+    }
+
+    // wait until the device is available, e.g. to benchmark each device separately
+    pub async fn acquire_device(self: &Arc<Self>, index: u32) -> Result<GpuLease> {
+        match self.devices.get(&index) {
+            Some(device) if device.is_usable(unix_timestamp()) => {}
+            Some(_) => bail!("GPU device {index} is unhealthy"),
+            None => bail!("GPU device {index} not found"),
+        }
+
+        self.lease(vec![index]).await
+    }
+
+    // indexes of the healthy devices in order
+    pub fn healthy_devices(&self) -> Vec<u32> {
+        let now = unix_timestamp();
+        let mut indexes: Vec<_> = self
+            .devices
+            .iter()
+            .filter(|device| device.is_usable(now))
+            .map(|device| device.info.index)
+            .collect();
+        indexes.sort_unstable();
+
+        indexes
+    }
+
+    // queue in the devices of the sorted indexes and wait until all of them are available
+    async fn lease(self: &Arc<Self>, indexes: Vec<u32>) -> Result<GpuLease> {
+        let mut permits = Vec::with_capacity(indexes.len());
+        for index in &indexes {
+            let mut device = self
+                .devices
+                .get_mut(index)
+                .ok_or_else(|| anyhow!("GPU device {index} not found"))?;
+            device.queued += 1;
+            permits.push(device.permits.clone());
+        }
+
+        // the queued counters are released by the lease if the waiting is aborted
+        let mut lease = GpuLease {
+            pool: self.clone(),
+            devices: indexes,
+            permits: Vec::with_capacity(permits.len()),
+        };
+        for (index, permits) in lease.devices.clone().into_iter().zip(permits) {
+            let permit = permits.acquire_owned().await?;
+            if let Some(mut device) = self.devices.get_mut(&index) {
+                device.queued -= 1;
+                device.running += 1;
+            }
+            lease.permits.push(permit);
+        }
+
+        Ok(lease)
+    }
+
+    // count a failed proving attempt on the devices, a device failing the threshold times in a row
+    // is quarantined. it's quarantined again by the next failure after the quarantine unless an
+    // attempt succeeds in between
+    pub fn report_failure(&self, indexes: &[u32], reason: &str) {
+        let threshold = self.health.gpu_failure_threshold;
+        let now = unix_timestamp();
+        let mut quarantined = false;
+        for index in indexes {
+            let Some(mut device) = self.devices.get_mut(index) else {
+                continue;
+            };
+            device.failures += 1;
+            metrics().gpu_device_failures.inc();
+            if threshold > 0 && device.failures >= threshold && device.is_usable(now) {
+                let until = now + self.health.gpu_quarantine_secs;
+                warn!(
+                    "[gpu-pool] device {index} is quarantined until {until} after {} failures in a row: {reason}",
+                    device.failures
+                );
+                device.quarantined_until = Some(until);
+                quarantined = true;
+            }
+        }
+        if quarantined {
+            self.unhealthy.send_replace(());
+        }
+    }
+
+    // a successful proving attempt resets the failures of the devices
+    pub fn report_success(&self, indexes: &[u32]) {
+        for index in indexes {
+            if let Some(mut device) = self.devices.get_mut(index) {
+                device.failures = 0;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Vec<GpuDeviceStats> {
+        let now = unix_timestamp();
+        let mut stats: Vec<_> = self
+            .devices
+            .iter()
+            .map(|device| GpuDeviceStats {
+                index: device.info.index,
+                name: device.info.name.clone(),
+                memory_total: device.info.memory_total,
+                memory_used: device.info.memory_used,
+                utilization: device.info.utilization,
+                healthy: device.is_usable(now),
+                running_tasks: device.running as u32,
+                queued_tasks: device.queued as u32,
+                consecutive_failures: device.failures,
+                quarantined_until: device.quarantined_until.filter(|until| *until > now),
+            })
+            .collect();
+        stats.sort_by_key(|device| device.index);
+
+        stats
+    }
+
+    fn refresh(&self, infos: Vec<DeviceInfo>) {
+        let mut lost = false;
+        for mut device in self.devices.iter_mut() {
+            let index = device.info.index;
+            match infos.iter().find(|info| info.index == index) {
+                Some(info) => {
+                    if !device.healthy {
+                        info!("[gpu-pool] device {index} recovered");
+                    }
+                    device.info = info.clone();
+                    device.healthy = true;
+                    metrics().set_gpu_utilization(index, info.utilization);
+                }
+                None => {
+                    if device.healthy {
+                        warn!("[gpu-pool] device {index} is unhealthy");
+                        lost = true;
+                    }
+                    device.healthy = false;
+                    // the unhealthy device is not utilized by the tasks
+                    metrics().set_gpu_utilization(index, 0);
+                }
+            }
+        }
+        if lost {
+            self.unhealthy.send_replace(());
+        }
+    }
+}
+
+// assignment of the devices to a task, the devices are released on drop
+pub struct GpuLease {
+    pool: Arc<GpuPool>,
+    devices: Vec<u32>,
+    // permits acquired so far in the order of the devices
+    permits: Vec<OwnedSemaphorePermit>,
+}
+
+impl GpuLease {
+    pub fn devices(&self) -> &[u32] {
+        &self.devices
+    }
+}
+
+impl Drop for GpuLease {
+    fn drop(&mut self) {
+        for (i, index) in self.devices.iter().enumerate() {
+            if let Some(mut device) = self.pool.devices.get_mut(index) {
+                if i < self.permits.len() {
+                    device.running -= 1;
+                } else {
+                    device.queued -= 1;
+                }
+            }
+        }
+    }
+}
+
+// query the devices by nvidia-smi, the memory is reported in MiB
+fn query_devices() -> Result<Vec<DeviceInfo>> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=index,name,memory.total,memory.used,utilization.gpu",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "nvidia-smi failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_device)
+        .collect()
+}
+
+fn parse_device(line: &str) -> Result<DeviceInfo> {
+    let fields: Vec<_> = line.split(',').map(str::trim).collect();
+    let [index, name, memory_total, memory_used, utilization] = fields[..] else {
+        bail!("invalid nvidia-smi output: {line}");
+    };
+    // the unsupported values are reported as [N/A]
+    let number = |value: &str| value.parse::<u64>().unwrap_or(0);
+
+    Ok(DeviceInfo {
+        index: index.parse()?,
+        name: name.to_string(),
+        memory_total: number(memory_total) << 20,
+        memory_used: number(memory_used) << 20,
+        utilization: number(utilization) as u32,
+    })
+}
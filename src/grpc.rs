@@ -1,70 +1,334 @@
 use super::config::ServiceConfig;
 use crate::{
-    EstimateCostRequest, EstimateCostResponse, GetProvingResultRequest, GetProvingResultResponse,
-    ProveTaskRequest, ProveTaskResponse, RegisterAppRequest, RegisterAppResponse,
-    app_manager::AppManager,
-    cost_estimation::estimate_cost,
+    AppMetadata, AppUsage, AppVersion, BatchProveTaskRequest, BatchProveTaskResponse,
+    CancelTaskRequest, CancelTaskResponse, DeadLetter, DeleteProofRequest, DeleteProofResponse,
+    DeregisterAppRequest, DeregisterAppResponse, DownloadProofChunk, DownloadProofFinish,
+    DownloadProofRequest, DrainQueueRequest, DrainQueueResponse, ErrCode, ErrMsg,
+    EstimateCostRequest, EstimateCostResponse, FILE_DESCRIPTOR_SET, GetAppAnalysisRequest,
+    GetAppAnalysisResponse, GetAppRequest, GetAppResponse, GetGpuStatsRequest, GetGpuStatsResponse,
+    GetOnchainVerifierRequest, GetOnchainVerifierResponse, GetProofRequest, GetProofResponse,
+    GetProvingResultRequest, GetProvingResultResponse, GetQuotaRequest, GetQuotaResponse,
+    GetServiceKeyRequest, GetServiceKeyResponse, GetTaskStatusRequest, GetTaskStatusResponse,
+    GetUsageRequest, GetUsageResponse, InputsCompression, IssueTokenRequest, IssueTokenResponse,
+    ListAppVersionsRequest, ListAppVersionsResponse, ListAppsRequest, ListAppsResponse,
+    ListDeadLettersRequest, ListDeadLettersResponse, ListTasksRequest, ListTasksResponse,
+    ListWorkersRequest, ListWorkersResponse, ProgressEvent, ProofType, ProveTaskChunk,
+    ProveTaskProgress, ProveTaskRequest, ProveTaskResponse, ProveTaskResult, RegisterAppChunk,
+    RegisterAppRequest, RegisterAppResponse, RequeueDeadLetterRequest, RequeueDeadLetterResponse,
+    RevokeTokenRequest, RevokeTokenResponse, RunBenchmarkRequest, RunBenchmarkResponse,
+    ServiceEvent, SignedProveTaskRequest, SignedProveTaskResponse, SubscribeEventsRequest,
+    TaskPriority, TaskState, TaskSummary, VerifyProofRequest, VerifyProofResponse,
+    accounting::{self, AppUsageRow},
+    app_manager::{AppFilter, AppManager, AppMetaRow, RegisterAppError},
+    artifact_store::{self, SharedArtifactStore},
+    benchmark::Benchmark,
+    client::{
+        APP_ID_METADATA_KEY, ERR_CODE_METADATA_KEY, ESTIMATED_WAIT_METADATA_KEY,
+        NAMESPACE_METADATA_KEY, QUEUE_DEPTH_METADATA_KEY, RETRY_AFTER_METADATA_KEY,
+    },
+    coordinator::CoordinatorService,
+    coordinator_server::CoordinatorServer,
+    cost_estimation::{ProvingEstimate, estimate_cost, pv_digest},
+    dependencies::Dependencies,
+    download_proof_chunk,
+    error::err_code_of,
+    events::{self, EventFilter},
+    fetcher::{FetchError, Fetcher},
+    gateway,
+    get_onchain_verifier_request::{Claim as OnchainClaim, Proof as OnchainProof},
+    gpu_pool::GpuPool,
+    health::{HealthService, proto::health_server::HealthServer},
+    key_cache::{AppKeys, KeyCache},
+    metrics::metrics,
+    proof_metadata::ProofProvenance,
+    prove_task_chunk,
     prover_network_server::{ProverNetwork, ProverNetworkServer},
-    proving_queue::{ProvingKey, ProvingOutputs, ProvingTask},
-    types::DbPool,
-    utils::auth::AuthConfig,
+    proving::{
+        ProvingProgress,
+        backend::SharedProverBackend,
+        onchain_verifier::{self, VERIFIER_SOURCE, VERIFY_PROOF_SIGNATURE, onchain_calldata},
+    },
+    proving_queue::{
+        DEFAULT_NAMESPACE, ProvingKey, ProvingOutputs, ProvingTask, TaskStatus, TaskStatuses,
+        update_task_state,
+    },
+    quota::{self, QuotaUsed},
+    rate_limit::{RateLimited, RateLimiter, Tenant},
+    register_app_chunk::Frame,
+    retention::Retention,
+    shutdown::Shutdown,
+    storage::{AppDeletion, CostEstimateRow, DeadLetterRow, ProofRow, SharedStorage, TokenRow},
+    types::SC,
+    utils::{
+        auth::AuthConfig,
+        compression,
+        jwt::{Action, Claims, JwtAuthority},
+        signing, telemetry,
+        time::unix_timestamp,
+    },
+    verify_proof_request::{Claim, Verifier},
+    warm_up::WarmUps,
+    webhook::check_callback_url,
 };
+use alloy_primitives::U256;
 use anyhow::Result;
 use crossbeam::channel::Sender;
-use std::sync::Arc;
-use tokio::{signal::ctrl_c, task::JoinHandle};
+use dashmap::DashMap;
+use pico_vm::machine::keys::{BaseVerifyingKey, HashableKey};
+use prost::Message;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    sync::{Arc, atomic::AtomicBool},
+    time::Duration,
+};
+use tokio::{
+    sync::{RwLock, broadcast, broadcast::error::RecvError, mpsc},
+    task::JoinHandle,
+};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{
-    Request, Response, Status, async_trait,
+    Code, Request, Response, Status, Streaming, async_trait,
     codec::CompressionEncoding,
     service::{LayerExt, interceptor::InterceptedService},
     transport::Server,
 };
 use tonic_web::GrpcWebLayer;
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tower_http::{
+    cors::{Any, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::{info, info_span, warn};
+
+// buffered progress messages of a ProveTaskStream call
+const PROGRESS_STREAM_BUFFER: usize = 16;
+
+// buffered chunks of a DownloadProof call
+const DOWNLOAD_STREAM_BUFFER: usize = 4;
+
+// default and maximum chunk sizes of DownloadProof, the maximum is below the default 4 MiB message
+// limit of the clients
+const DEFAULT_DOWNLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+const MAX_DOWNLOAD_CHUNK_SIZE: usize = 3 * 1024 * 1024;
+
+// default and maximum page sizes of ListTasks and ListApps
+const DEFAULT_LIST_PAGE_SIZE: usize = 100;
+const MAX_LIST_PAGE_SIZE: usize = 1000;
+
+// maximum length of a namespace
+const MAX_NAMESPACE_LEN: usize = 64;
+
+// queued tasks of the service, with the mean proving time of the completed tasks tracked in memory
+struct Backlog {
+    depth: usize,
+    inputs_size: u64,
+    mean_proving_secs: Option<f64>,
+}
 
 pub struct GrpcService {
     cfg: ServiceConfig,
     app_manager: AppManager,
-    db_pool: Arc<DbPool>,
+    // program and keys of the apps loaded for their proving tasks
+    key_cache: Arc<KeyCache>,
+    // warm-ups of the apps requested on registration
+    warm_ups: Arc<WarmUps>,
+    storage: SharedStorage,
     outputs: Arc<ProvingOutputs>,
+    statuses: Arc<TaskStatuses>,
     sender: Arc<Sender<ProvingTask>>,
+    workers_ready: Arc<AtomicBool>,
+    gpu_pool: Arc<GpuPool>,
+    // backend verifying the raw proofs, the same one proving the tasks
+    prover_backend: SharedProverBackend,
+    artifacts: Option<SharedArtifactStore>,
+    retention: Retention,
+    // holder of the tasks waiting for their dependencies
+    dependencies: Dependencies,
+    // verifier of the JWTs if authenticated by JWT
+    jwt: Option<JwtAuthority>,
+    // fetcher of the inputs and elfs by URI
+    fetcher: Fetcher,
+    // per-tenant budgets of the requests and uploaded bytes
+    rate_limiter: RateLimiter,
+    benchmark: Benchmark,
+    // coordinator of the remote workers in coordinator mode, set once served
+    coordinator: Option<CoordinatorService>,
+    shutdown: Shutdown,
+    // locks of the apps by the namespace, the tasks are admitted by the read lock and the app is
+    // deregistered by the write lock, so no task is admitted for an app being deleted
+    app_locks: DashMap<(String, String), Arc<RwLock<()>>>,
 }
 
 impl GrpcService {
     pub fn new(
         cfg: ServiceConfig,
-        db_pool: Arc<DbPool>,
+        storage: SharedStorage,
         outputs: Arc<ProvingOutputs>,
+        statuses: Arc<TaskStatuses>,
         sender: Arc<Sender<ProvingTask>>,
+        workers_ready: Arc<AtomicBool>,
+        gpu_pool: Arc<GpuPool>,
+        artifacts: Option<SharedArtifactStore>,
+        shutdown: Shutdown,
     ) -> Self {
-        let app_manager = AppManager::new(db_pool.clone());
+        let app_manager = AppManager::new(storage.clone());
+        let key_cache = Arc::new(KeyCache::new(
+            &cfg.key_cache,
+            storage.clone(),
+            artifacts.clone(),
+        ));
+        let retention = Retention::new(
+            storage.clone(),
+            artifacts.clone(),
+            outputs.clone(),
+            statuses.clone(),
+        );
+        let dependencies = Dependencies::new(
+            storage.clone(),
+            artifacts.clone(),
+            outputs.clone(),
+            statuses.clone(),
+            sender.clone(),
+        );
+        let jwt = cfg.jwt_authority();
+        let fetcher = Fetcher::new(&cfg.fetch);
+        let rate_limiter = RateLimiter::new(&cfg.rate_limit);
+        let prover_backend = cfg.prover.backend();
+        let warm_ups = Arc::new(WarmUps::new(
+            key_cache.clone(),
+            prover_backend.clone(),
+            cfg.prover_count,
+            cfg.memory.budget(),
+        ));
+        let benchmark = Benchmark::new(
+            storage.clone(),
+            gpu_pool.clone(),
+            prover_backend.clone(),
+            cfg.prover_count,
+            cfg.memory.budget(),
+        );
 
         Self {
             cfg,
             app_manager,
-            db_pool,
+            key_cache,
+            warm_ups,
+            storage,
             outputs,
+            statuses,
             sender,
+            workers_ready,
+            gpu_pool,
+            prover_backend,
+            artifacts,
+            retention,
+            dependencies,
+            jwt,
+            fetcher,
+            rate_limiter,
+            benchmark,
+            coordinator: None,
+            shutdown,
+            app_locks: DashMap::new(),
+        }
+    }
+
+    // cleaner of the proofs and finished tasks served by this service
+    pub fn retention(&self) -> Retention {
+        self.retention.clone()
+    }
+
+    // warm the key cache by the apps with the latest tasks in background
+    pub fn start_key_cache_warming(&self) -> Option<JoinHandle<()>> {
+        self.key_cache
+            .start_warming(self.cfg.key_cache.key_cache_warm_apps)
+    }
+
+    // load the revoked tokens and sync them in background if authenticated by JWT
+    pub async fn start_revocation_sync(&self) -> Result<Option<JoinHandle<()>>> {
+        match &self.jwt {
+            Some(jwt) => Ok(Some(jwt.start_revocation_sync(self.storage.clone()).await?)),
+            None => Ok(None),
         }
     }
 
-    pub fn run(self) -> JoinHandle<()> {
+    fn jwt_authority(&self) -> Result<&JwtAuthority, Status> {
+        self.jwt
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("JWT authentication is not enabled"))
+    }
+
+    // run the gRPC server, the coordinator service is served for the remote workers if provided and
+    // the REST gateway if its address is configured
+    pub fn run(mut self, coordinator: Option<CoordinatorService>) -> JoinHandle<()> {
         info!("[proving-network] grpc server init");
+        self.coordinator = coordinator.clone();
         let handle = tokio::spawn(async move {
             let cfg = &self.cfg;
             let addr = cfg.grpc_addr;
+            let gateway_addr = cfg.gateway_addr;
             let max_grpc_msg_size = cfg.max_grpc_msg_size;
+            let shutdown = self.shutdown.clone();
             let auth_interceptor = cfg.server_auth_interceptor();
+            let tls = cfg
+                .tls
+                .server_tls_config()
+                .expect("failed to load TLS config");
+            // the health service is not authenticated for the probes of orchestrators
+            let health = HealthServer::new(HealthService::new(
+                self.storage.clone(),
+                self.workers_ready.clone(),
+                self.shutdown.clone(),
+            ));
+            let reflection = cfg.enable_reflection.then(|| {
+                info!("[proving-network] grpc server reflection enabled");
+                tonic_reflection::server::Builder::configure()
+                    .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                    .build_v1()
+                    .expect("failed to build reflection service")
+            });
+
+            let coordinator = coordinator.map(|coordinator| {
+                InterceptedService::new(
+                    CoordinatorServer::new(coordinator)
+                        .max_encoding_message_size(max_grpc_msg_size)
+                        .max_decoding_message_size(max_grpc_msg_size),
+                    with_jwt(
+                        auth_interceptor.clone(),
+                        self.jwt.as_ref(),
+                        Some(Action::Work),
+                    ),
+                )
+            });
+            // the workers are not rate limited
+            let interceptor = with_rate_limit(
+                with_jwt(auth_interceptor, self.jwt.as_ref(), None),
+                self.rate_limiter.clone(),
+            );
+
+            let service = Arc::new(self);
+            if let Some(gateway_addr) = gateway_addr {
+                // the base64 of the binary fields is 4/3 of their size
+                gateway::serve(
+                    gateway_addr,
+                    service.clone(),
+                    Arc::new(interceptor.clone()),
+                    max_grpc_msg_size * 2,
+                    shutdown.clone(),
+                )
+                .await
+                .expect("failed to serve the gateway");
+            }
 
             let base = InterceptedService::new(
-                ProverNetworkServer::new(self)
+                ProverNetworkServer::from_arc(service)
                     .max_encoding_message_size(max_grpc_msg_size)
                     .max_decoding_message_size(max_grpc_msg_size)
                     .accept_compressed(CompressionEncoding::Zstd)
                     .send_compressed(CompressionEncoding::Zstd),
-                auth_interceptor,
+                interceptor,
             );
 
             let svc = ServiceBuilder::new()
@@ -78,12 +342,25 @@ impl GrpcService {
                 .into_inner()
                 .named_layer(base);
 
-            Server::builder()
+            // the request ID is generated before creating the request span, and the request spans
+            // are the children of the trace contexts of the clients
+            let request_layer = ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(PropagateRequestIdLayer::x_request_id())
+                .layer(TraceLayer::new_for_grpc().make_span_with(telemetry::request_span))
+                .into_inner();
+            let mut builder = Server::builder().layer(request_layer);
+            if let Some(tls) = tls {
+                info!("[proving-network] grpc server TLS enabled");
+                builder = builder.tls_config(tls).expect("invalid TLS config");
+            }
+            builder
                 .accept_http1(true)
                 .add_service(svc)
-                .serve_with_shutdown(addr, async {
-                    ctrl_c().await.expect("failed to wait for shutdown");
-                })
+                .add_service(health)
+                .add_optional_service(reflection)
+                .add_optional_service(coordinator)
+                .serve_with_shutdown(addr, shutdown.stopped())
                 .await
                 .expect("failed");
         });
@@ -92,155 +369,2948 @@ impl GrpcService {
 
         handle
     }
-}
 
-#[async_trait]
-impl ProverNetwork for GrpcService {
-    // register a new application with elf
-    async fn register_app(
+    // queue a proving task and return the subscription of its progress, the scope is checked for
+    // the resolved app
+    async fn submit_task(
         &self,
-        req: Request<RegisterAppRequest>,
-    ) -> Result<Response<RegisterAppResponse>, Status> {
-        info!("receive RegisterAppRequest");
+        namespace: &str,
+        req: ProveTaskRequest,
+        claims: Option<&Claims>,
+    ) -> Result<(ProvingKey, broadcast::Receiver<ProvingProgress>), Status> {
+        self.check_unsigned()?;
+        let req = self.resolve_app(namespace, req).await?;
+        check_scope(claims, Action::Prove, Some(&req.app_id))?;
+        let app = self.load_app_keys(namespace, &req.app_id).await?;
 
-        let req = req.into_inner();
-        let app = self
-            .app_manager
-            .set_app(&req.elf, req.info)
-            .await
-            .map_err(|e| Status::internal(format!("failed to register app: {e}")))?;
-        let app_id = app.app_id;
+        self.queue_task(&app, namespace, req).await
+    }
 
-        info!("return RegisterAppResponse");
+    // the tasks are only accepted by the signed requests if required
+    fn check_unsigned(&self) -> Result<(), Status> {
+        if self.cfg.require_signed_tasks {
+            return Err(Status::permission_denied(
+                "the tasks must be submitted by the signed requests",
+            ));
+        }
 
-        Ok(Response::new(RegisterAppResponse { err: None, app_id }))
+        Ok(())
     }
 
-    // estimate gas cost
-    async fn estimate_cost(
+    // resolve the app ID of a task targeting a versioned app by name in the namespace
+    async fn resolve_app(
         &self,
-        req: Request<EstimateCostRequest>,
-    ) -> Result<Response<EstimateCostResponse>, Status> {
-        info!("receive EstimateCostRequest");
+        namespace: &str,
+        mut req: ProveTaskRequest,
+    ) -> Result<ProveTaskRequest, Status> {
+        let Some(name) = req.app_name.as_deref() else {
+            if req.app_version.is_some() {
+                return Err(Status::invalid_argument(
+                    "app_name is required for app_version",
+                ));
+            }
+            return Ok(req);
+        };
 
-        let req = req.into_inner();
-        let app_id = req.app_id;
-        let app = self
+        let row = self
             .app_manager
-            .get_app(&app_id)
+            .resolve_version(namespace, name, req.app_version)
             .await
-            .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
-            .ok_or_else(|| Status::not_found(format!("cannot find app {app_id}")))?;
+            .map_err(|e| Status::internal(format!("failed to get app version: {e}")))?
+            .ok_or_else(|| match req.app_version {
+                Some(version) => {
+                    app_not_found(format!("cannot find version {version} of app {name}"))
+                }
+                None => app_not_found(format!("cannot find app {name}")),
+            })?;
+        info!(
+            "[grpc] resolved version {} of app {name} to {}",
+            row.version, row.app_id
+        );
+        req.app_id = row.app_id;
 
-        let res = match estimate_cost(
-            app.program,
-            app.pk,
-            app.vk,
-            req.inputs.as_deref(),
-            self.cfg.max_emulation_cycles,
-            true,
-        ) {
-            Ok(info) => EstimateCostResponse {
-                err: None,
-                cost: info.cost,
-                pv_digest: info.pv_digest.to_be_bytes_vec(),
-            },
-            Err(e) => e.into(),
-        };
+        Ok(req)
+    }
 
-        info!("return EstimateCostResponse");
+    // replay the persisted unfinished tasks after restart, the interrupted tasks are re-queued
+    // from the beginning, and the ones with a stored proof are marked as completed
+    pub async fn replay_tasks(&self) -> Result<usize> {
+        let rows = self.storage.unfinished_tasks().await?;
+        info!("[grpc] replaying {} unfinished proving tasks", rows.len());
 
-        Ok(Response::new(res))
+        let mut apps = HashMap::new();
+        let mut replayed = 0;
+        for row in rows {
+            let key = row.key();
+            if self.storage.has_proof(&key).await? {
+                info!("[grpc] proof of task {key:?} is already stored");
+                self.storage
+                    .update_task_state(&key, TaskState::Completed, None)
+                    .await?;
+                continue;
+            }
+
+            let app = match apps.entry((row.namespace.clone(), row.app_id.clone())) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(self.load_app_keys(&row.namespace, &row.app_id).await)
+                }
+            };
+            let app = match app {
+                Ok(app) => app,
+                Err(status) => {
+                    warn!("[grpc] cannot replay task {key:?}: {}", status.message());
+                    self.storage
+                        .update_task_state(&key, TaskState::Failed, Some(status.message()))
+                        .await?;
+                    continue;
+                }
+            };
+
+            info!("[grpc] replaying task {key:?} in state {:?}", row.state());
+            self.storage
+                .update_task_state(&key, TaskState::Queued, None)
+                .await?;
+            let priority = row.priority();
+            let depends_on = row.depends_on();
+            let mut status = TaskStatus::queued();
+            status.created_at = row.created_at as u64;
+            status.verify_proof = row.verify_proof;
+            status.proof_type = row.proof_type();
+            status.allow_cpu_fallback = row.allow_cpu_fallback;
+            status.expected_pv_digest = row
+                .expected_pv_digest
+                .as_deref()
+                .and_then(U256::try_from_be_slice);
+            status.not_before = row.not_before.map(|not_before| not_before as u64);
+            status.deadline = row.deadline.map(|deadline| deadline as u64);
+            status.callback_url = row.callback_url;
+            let inputs = match row.inputs {
+                Some(inputs) => Some(inputs),
+                None => self.load_inputs(&key).await?,
+            };
+            self.enqueue_task(app, key, inputs, row.use_gpu, priority, status, depends_on)?;
+            replayed += 1;
+        }
+
+        Ok(replayed)
     }
 
-    // add a proving task
-    async fn prove_task(
+    // load the inputs of a replayed task from the artifact store
+    async fn load_inputs(&self, key: &ProvingKey) -> Result<Option<Vec<u8>>> {
+        match &self.artifacts {
+            Some(store) => store.get(&artifact_store::inputs_key(key)).await,
+            None => Ok(None),
+        }
+    }
+
+    // load the inputs of a dead letter from the database or the artifact store
+    async fn load_dead_letter_inputs(&self, key: &ProvingKey) -> Result<Option<Vec<u8>>> {
+        if let Some(inputs) = self.storage.dead_letter_inputs(key).await? {
+            return Ok(Some(inputs));
+        }
+        match &self.artifacts {
+            Some(store) => {
+                store
+                    .get(&artifact_store::dead_letter_inputs_key(key))
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    // fill the proving time and price of the emulated cycles by the calibrations, they're left
+    // unset if failed to load the calibrations
+    async fn estimate_response(
         &self,
-        req: Request<ProveTaskRequest>,
-    ) -> Result<Response<ProveTaskResponse>, Status> {
-        info!("receive ProveTaskRequest");
+        cost: u64,
+        total_cycles: u64,
+        pv_digest: Vec<u8>,
+        cached: bool,
+    ) -> EstimateCostResponse {
+        let calibrations = self.storage.calibrations().await.unwrap_or_else(|e| {
+            warn!("[grpc] failed to load calibrations: {e}");
+            vec![]
+        });
+        let estimate = ProvingEstimate::new(total_cycles, &calibrations, &self.cfg.rate_card);
 
-        let req = req.into_inner();
-        let app_id = req.app_id;
-        let app = self
-            .app_manager
-            .get_app(&app_id)
+        EstimateCostResponse {
+            err: None,
+            cost,
+            pv_digest,
+            cached,
+            total_cycles,
+            total_chunks: estimate.total_chunks,
+            cpu_proving_secs: estimate.cpu_proving_secs,
+            gpu_proving_secs: estimate.gpu_proving_secs,
+            cpu_price: estimate.cpu_price,
+            gpu_price: estimate.gpu_price,
+        }
+    }
+
+    async fn drop_dead_letter(&self, key: &ProvingKey) -> Result<()> {
+        self.storage.delete_dead_letter(key).await?;
+        if let Some(store) = &self.artifacts {
+            store
+                .delete(&artifact_store::dead_letter_inputs_key(key))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // load the stored proof, the proof offloaded to the artifact store is filled in
+    // proof, public values digest, proof type and provenance of a finished task. the proof is kept
+    // in memory for GetProvingResult, so it's not removed here
+    async fn find_proof(
+        &self,
+        key: &ProvingKey,
+    ) -> Result<(Vec<u8>, Vec<u8>, ProofType, ProofProvenance), Status> {
+        if let Some(output) = self.outputs.get(key) {
+            return Ok((
+                output.proof.to_vec(),
+                output.pv_digest.to_be_bytes_vec(),
+                output.proof_type,
+                output.provenance.clone(),
+            ));
+        }
+
+        self.load_proof(key)
+            .await?
+            .map(|row| {
+                let proof_type = row.proof_type();
+                let provenance = ProofProvenance::from(&row);
+                (
+                    row.proof,
+                    row.pv_digest.unwrap_or_default(),
+                    proof_type,
+                    provenance,
+                )
+            })
+            .ok_or_else(|| Status::not_found(format!("cannot find proof of task {key:?}")))
+    }
+
+    async fn load_proof(&self, key: &ProvingKey) -> Result<Option<ProofRow>, Status> {
+        let row = self
+            .storage
+            .get_proof(key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get proof from database: {e}")))?;
+        match (row, &self.artifacts) {
+            (Some(mut row), Some(store)) if row.proof.is_empty() => {
+                row.proof = store
+                    .get(&artifact_store::proof_key(key))
+                    .await
+                    .map_err(|e| {
+                        Status::internal(format!("failed to get proof from artifact store: {e}"))
+                    })?
+                    .ok_or_else(|| {
+                        Status::data_loss(format!("proof of task {key:?} is missing"))
+                    })?;
+                Ok(Some(row))
+            }
+            (row, _) => Ok(row),
+        }
+    }
+
+    // the status of a proof stored without a task record, e.g. proved before the tasks are
+    // persisted
+    async fn proved_task_status(&self, key: &ProvingKey) -> Result<TaskStatus, Status> {
+        let row = self
+            .storage
+            .get_proof(key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get task from database: {e}")))?
+            .ok_or_else(|| Status::not_found(format!("cannot find task {key:?}")))?;
+
+        let finished_at = row.created_at as u64;
+        Ok(TaskStatus {
+            state: TaskState::Completed,
+            created_at: finished_at,
+            started_at: None,
+            finished_at: Some(finished_at),
+            failure_reason: None,
+            ..TaskStatus::queued()
+        })
+    }
+
+    // load the program and keys of an app in the namespace for queueing its tasks
+    async fn load_app_keys(&self, namespace: &str, app_id: &str) -> Result<AppKeys, Status> {
+        self.key_cache
+            .get(namespace, app_id)
             .await
             .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
-            .ok_or_else(|| Status::not_found(format!("cannot find app {app_id}")))?;
+            .ok_or_else(|| app_not_found(format!("cannot find app {app_id}")))
+    }
+
+    // persist and queue a proving task of the loaded app
+    async fn queue_task(
+        &self,
+        app: &AppKeys,
+        namespace: &str,
+        mut req: ProveTaskRequest,
+    ) -> Result<(ProvingKey, broadcast::Receiver<ProvingProgress>), Status> {
+        if self.shutdown.is_draining() {
+            return Err(Status::unavailable("service is shutting down"));
+        }
+        if self.shutdown.is_admission_closed() {
+            return Err(Status::unavailable("service is draining the queue"));
+        }
+        let key = ProvingKey::new(namespace.to_string(), req.app_id, req.task_id);
+        req.inputs = match req.inputs_uri.as_deref() {
+            Some(uri) => {
+                if req.inputs.is_some() || req.inputs_hash.is_some() {
+                    return Err(Status::invalid_argument(
+                        "inputs_uri must not be set with inputs or inputs_hash",
+                    ));
+                }
+                Some(self.fetch("inputs", uri, &req.inputs_sha256).await?)
+            }
+            None => {
+                self.resolve_inputs(namespace, req.inputs, req.inputs_hash.as_deref())
+                    .await?
+            }
+        };
+        req.inputs = self
+            .decompress_inputs(
+                req.inputs_compression(),
+                req.inputs_hash.as_deref(),
+                req.inputs,
+            )
+            .await?;
+        // counted once by the resolved inputs, whether inline, uploaded, fetched or compressed
+        metrics()
+            .inputs_received_bytes
+            .inc_by(req.inputs.as_ref().map_or(0, |inputs| inputs.len() as u64));
+        // the task submitted before by the same ID is returned instead of proving it again
+        let inputs_hash = inputs_hash(req.inputs.as_deref());
+        if let Some(submitted) = self
+            .find_submitted(&key, &inputs_hash, req.proof_type())
+            .await?
+        {
+            info!("[grpc] task {key:?} is already submitted with the same payload");
+            return Ok(submitted);
+        }
+
+        self.check_dependencies(&key, &req.depends_on).await?;
+        let expected_pv_digest = req
+            .expected_pv_digest
+            .as_deref()
+            .map(|digest| {
+                U256::try_from_be_slice(digest).ok_or_else(|| {
+                    Status::invalid_argument("expected_pv_digest must be at most 32 bytes")
+                })
+            })
+            .transpose()?;
+
+        // the same inputs of the app are proved only once unless forced, the inputs of the tasks
+        // with dependencies are completed by the proofs of the dependencies, and the task with an
+        // expected digest is checked by its own emulation
+        if !req.force.unwrap_or(false) && req.depends_on.is_empty() && expected_pv_digest.is_none()
+        {
+            if let Some(duplicate) = self
+                .find_duplicate(&key, &inputs_hash, req.proof_type())
+                .await?
+            {
+                info!(
+                    "[grpc] task {key:?} is deduplicated by task {:?}",
+                    duplicate.0
+                );
+                return Ok(duplicate);
+            }
+        }
 
-        let key = ProvingKey::new(app_id, req.task_id);
         // Default to cpu if not specified
         let use_gpu = req.use_gpu.unwrap_or(false);
-        let task = ProvingTask::new(
-            key,
-            app.program,
-            Arc::new(app.pk),
-            Arc::new(app.vk),
+        let allow_cpu_fallback = req.allow_cpu_fallback.unwrap_or(false);
+        // the GPU tasks are proved by the remote workers in coordinator mode
+        if use_gpu && !allow_cpu_fallback && !self.cfg.coordinator_mode && self.gpu_pool.is_empty()
+        {
+            return Err(Status::failed_precondition("no GPU device available"));
+        }
+        let priority = req.priority();
+        if let Some(url) = &req.callback_url {
+            check_callback_url(url, &self.cfg.webhook.webhook_allowed_hosts)
+                .await
+                .map_err(Status::invalid_argument)?;
+        }
+        if let Some(deadline) = req.deadline {
+            self.check_deadline(&key, &inputs_hash, use_gpu, req.not_before, deadline)
+                .await?;
+        }
+        self.check_quota(&key).await?;
+        self.check_backlog(req.inputs.as_ref().map_or(0, Vec::len))?;
+        // the app may be deregistered while the task is prepared, it's checked again once admitted
+        let app_lock = self.app_lock(namespace, key.app_id());
+        let _admission = app_lock.read().await;
+        self.app_manager
+            .get_app_meta(namespace, key.app_id())
+            .await
+            .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
+            .ok_or_else(|| app_not_found(format!("cannot find app {}", key.app_id())))?;
+        let mut status = TaskStatus::queued();
+        status.callback_url = req.callback_url.clone();
+        status.verify_proof = req.verify_proof.unwrap_or(false);
+        status.proof_type = req.proof_type();
+        status.allow_cpu_fallback = allow_cpu_fallback;
+        status.expected_pv_digest = expected_pv_digest;
+        status.not_before = req.not_before;
+        status.deadline = req.deadline;
+        // the inputs are offloaded to the artifact store if configured
+        let db_inputs = match (&self.artifacts, &req.inputs) {
+            (Some(store), Some(inputs)) => {
+                store
+                    .put(&artifact_store::inputs_key(&key), inputs)
+                    .await
+                    .map_err(|e| Status::internal(format!("failed to store inputs: {e}")))?;
+                None
+            }
+            _ => req.inputs.as_deref(),
+        };
+        // persist before queueing to replay it after restart
+        self.storage
+            .insert_task(
+                &key,
+                db_inputs,
+                &inputs_hash,
+                use_gpu,
+                priority,
+                status.created_at,
+                req.callback_url.as_deref(),
+                status.verify_proof,
+                status.proof_type,
+                &req.depends_on,
+                req.not_before,
+                req.deadline,
+                allow_cpu_fallback,
+                req.expected_pv_digest.as_deref(),
+            )
+            .await
+            .map_err(|e| Status::internal(format!("failed to persist proving task: {e}")))?;
+        let progress = self.enqueue_task(
+            app,
+            key.clone(),
             req.inputs,
             use_gpu,
-        );
-        self.sender
-            .send(task)
-            .map_err(|e| Status::internal(format!("failed to send a proving task: {e}")))?;
+            priority,
+            status,
+            req.depends_on,
+        )?;
 
-        info!("return ProveTaskResponse");
+        Ok((key, progress))
+    }
 
-        Ok(Response::new(ProveTaskResponse { err: None }))
+    fn app_lock(&self, namespace: &str, app_id: &str) -> Arc<RwLock<()>> {
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+        self.app_locks
+            .entry((namespace.to_string(), app_id.to_string()))
+            .or_default()
+            .clone()
     }
 
-    // try to fetch the proving result if complete
-    async fn get_proving_result(
+    // charge the uploaded bytes to the budget of the tenant
+    fn admit_bytes(&self, tenant: Option<&Tenant>, bytes: usize) -> Result<(), Status> {
+        match tenant {
+            Some(tenant) => self
+                .rate_limiter
+                .check_bytes(tenant, bytes)
+                .map_err(rate_limited),
+            None => Ok(()),
+        }
+    }
+
+    // fetch the inputs or elf by the URI, it's accepted only if the sha256 matches
+    async fn fetch(&self, name: &str, uri: &str, sha256: &[u8]) -> Result<Vec<u8>, Status> {
+        let data = self.fetcher.fetch(uri, sha256).await.map_err(|e| {
+            let msg = format!("failed to fetch {name} from {uri}: {e}");
+            match e {
+                FetchError::Failed(_) => Status::failed_precondition(msg),
+                _ => Status::invalid_argument(msg),
+            }
+        })?;
+        info!("[grpc] fetched {name} of {} bytes by URI", data.len());
+
+        Ok(data)
+    }
+
+    // decompress the inputs sent or fetched compressed, the uploaded inputs referenced by hash are
+    // stored as is
+    async fn decompress_inputs(
         &self,
-        req: Request<GetProvingResultRequest>,
-    ) -> Result<Response<GetProvingResultResponse>, Status> {
-        info!("receive GetProvingResultRequest");
+        inputs_compression: InputsCompression,
+        inputs_hash: Option<&str>,
+        inputs: Option<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Status> {
+        if inputs_compression == InputsCompression::Uncompressed {
+            return Ok(inputs);
+        }
+        if inputs_hash.is_some() {
+            return Err(Status::invalid_argument(
+                "inputs_compression must not be set with inputs_hash",
+            ));
+        }
+        let Some(inputs) = inputs else {
+            return Ok(None);
+        };
 
-        let req = req.into_inner();
-        let key = ProvingKey::new(req.app_id, req.task_id);
+        let compressed_size = inputs.len();
+        let max_size = self.cfg.max_decompressed_inputs_size;
+        let inputs = tokio::task::spawn_blocking(move || {
+            compression::decompress_inputs(inputs_compression, inputs, max_size)
+        })
+        .await
+        .map_err(|e| Status::internal(format!("failed to decompress inputs: {e}")))?
+        .map_err(|e| Status::invalid_argument(format!("failed to decompress inputs: {e}")))?;
+        info!(
+            "[grpc] decompressed {:?} inputs from {compressed_size} to {} bytes",
+            inputs_compression,
+            inputs.len()
+        );
 
-        info!("[grpc] looking for proof with key: {:?}", key);
+        Ok(Some(inputs))
+    }
 
-        // First try to get from memory (for recently completed proofs)
-        info!("[grpc] checking memory for key: {:?}", key);
-        info!("[grpc] current memory entries: {}", self.outputs.len());
+    // load the uploaded inputs of the namespace if referenced by the hash instead of inline
+    async fn resolve_inputs(
+        &self,
+        namespace: &str,
+        inputs: Option<Vec<u8>>,
+        inputs_hash: Option<&str>,
+    ) -> Result<Option<Vec<u8>>, Status> {
+        let Some(inputs_hash) = inputs_hash else {
+            return Ok(inputs);
+        };
+        if inputs.is_some() {
+            return Err(Status::invalid_argument(
+                "inputs and inputs_hash must not be both set",
+            ));
+        }
 
-        let proof = if let Some((_, output)) = self.outputs.remove(&key) {
-            info!(
-                "[grpc] found proof in memory, size: {} bytes",
-                output.proof.len()
-            );
-            Some(output.proof)
+        let row = self
+            .storage
+            .get_uploaded_inputs(namespace, inputs_hash)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get uploaded inputs: {e}")))?
+            .ok_or_else(|| {
+                Status::not_found(format!("cannot find uploaded inputs {inputs_hash}"))
+            })?;
+        let inputs = match (row.inputs, &self.artifacts) {
+            (Some(inputs), _) => Some(inputs),
+            (None, Some(store)) => store
+                .get(&artifact_store::uploaded_inputs_key(namespace, inputs_hash))
+                .await
+                .map_err(|e| Status::internal(format!("failed to load uploaded inputs: {e}")))?,
+            (None, None) => None,
+        };
+
+        inputs
+            .map(Some)
+            .ok_or_else(|| Status::not_found(format!("uploaded inputs {inputs_hash} are missing")))
+    }
+
+    // reject the task which cannot complete before its deadline, the proving time is estimated by
+    // the cached cost estimate of the same inputs if any
+    async fn check_deadline(
+        &self,
+        key: &ProvingKey,
+        inputs_hash: &str,
+        use_gpu: bool,
+        not_before: Option<u64>,
+        deadline: u64,
+    ) -> Result<(), Status> {
+        let start = not_before.unwrap_or_default().max(unix_timestamp());
+        if deadline <= start {
+            return Err(deadline_exceeded(format!(
+                "deadline {deadline} is not after the start {start} of the task"
+            )));
+        }
+
+        let cached = match self
+            .storage
+            .get_cost_estimate(key.app_id(), inputs_hash)
+            .await
+        {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("[grpc] failed to get cached estimate for deadline of task {key:?}: {e}");
+                None
+            }
+        };
+        let Some(row) = cached else {
+            return Ok(());
+        };
+        let calibrations = self.storage.calibrations().await.unwrap_or_else(|e| {
+            warn!("[grpc] failed to load calibrations: {e}");
+            vec![]
+        });
+        let estimate =
+            ProvingEstimate::new(row.total_cycles as u64, &calibrations, &self.cfg.rate_card);
+        let proving_secs = if use_gpu {
+            estimate.gpu_proving_secs
         } else {
-            info!("[grpc] proof not in memory, checking database");
-            // If not in memory, try to get from database
-            let db_proof = sqlx::query_as::<_, (Option<Vec<u8>>,)>(
-                "SELECT proof FROM proofs WHERE app_id = ? AND task_id = ?",
+            estimate.cpu_proving_secs
+        };
+        if let Some(secs) = proving_secs.filter(|secs| start as f64 + secs > deadline as f64) {
+            return Err(deadline_exceeded(format!(
+                "estimated proving time of {secs:.0}s cannot meet the deadline {deadline}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    // the tasks held by `not_before` are not counted until released
+    fn backlog(&self) -> Backlog {
+        let now = unix_timestamp();
+        let mut backlog = Backlog {
+            depth: 0,
+            inputs_size: 0,
+            mean_proving_secs: None,
+        };
+        let (mut completed, mut proving_secs) = (0, 0.0);
+        for status in self.statuses.iter() {
+            match (status.state, &status.timings) {
+                (TaskState::Queued, _) if status.not_before.is_none_or(|at| at <= now) => {
+                    backlog.depth += 1;
+                    backlog.inputs_size += status.inputs_size as u64;
+                }
+                (TaskState::Completed, Some(timings)) => {
+                    completed += 1;
+                    proving_secs += timings.total_secs;
+                }
+                _ => {}
+            }
+        }
+        if completed > 0 {
+            backlog.mean_proving_secs = Some(proving_secs / completed as f64);
+        }
+
+        backlog
+    }
+
+    // reject a new task if the queue is over its limits, the first task of an empty queue is
+    // accepted whatever its inputs size. the wait is estimated by the queued tasks over the
+    // concurrent slots at the mean proving time
+    fn check_backlog(&self, inputs_size: usize) -> Result<(), Status> {
+        let max_depth = self.cfg.max_queue_depth;
+        let max_inputs_size = self.cfg.max_queued_inputs_size;
+        if max_depth.is_none() && max_inputs_size.is_none() {
+            return Ok(());
+        }
+
+        let backlog = self.backlog();
+        let reason = if max_depth.is_some_and(|max| backlog.depth >= max) {
+            format!("{} queued tasks reached the limit", backlog.depth)
+        } else if max_inputs_size.is_some_and(|max| {
+            backlog.inputs_size > 0 && backlog.inputs_size + inputs_size as u64 > max
+        }) {
+            format!(
+                "{} bytes of queued inputs exceeded the limit",
+                backlog.inputs_size + inputs_size as u64
             )
-            .bind(&key.app_id())
-            .bind(&key.task_id())
-            .fetch_optional(&*self.db_pool)
+        } else {
+            return Ok(());
+        };
+        let slots = self.cfg.max_concurrent_tasks.max(1);
+        let estimated_wait_secs = backlog
+            .mean_proving_secs
+            .map(|secs| (backlog.depth.div_ceil(slots) as f64 * secs).ceil() as u64);
+        metrics().tasks_rejected_overloaded.inc();
+        warn!("[grpc] rejected a new task as overloaded: {reason}");
+
+        Err(overloaded(reason, backlog.depth, estimated_wait_secs))
+    }
+
+    // usage of the app in the namespace counted against its quotas
+    async fn quota_usage(&self, namespace: &str, app_id: &str) -> Result<QuotaUsed, Status> {
+        let rows = accounting::app_usage(
+            &self.storage,
+            namespace,
+            Some(app_id),
+            None,
+            quota::day_start(),
+            quota::day_end(),
+        )
+        .await
+        .map_err(|e| Status::internal(format!("failed to get usage: {e}")))?;
+        let stored_bytes = self
+            .storage
+            .stored_bytes(namespace, app_id)
             .await
-            .map_err(|e| Status::internal(format!("failed to get proof from database: {e}")))?
-            .and_then(|row| row.0);
+            .map_err(|e| Status::internal(format!("failed to get stored bytes: {e}")))?;
+        let concurrent_tasks = self
+            .statuses
+            .iter()
+            .filter(|status| {
+                status.key().namespace() == namespace
+                    && status.key().app_id() == app_id
+                    && !status.is_finished()
+            })
+            .count() as u64;
 
-            if let Some(ref proof_data) = db_proof {
-                info!(
-                    "[grpc] found proof in database, size: {} bytes",
-                    proof_data.len()
-                );
-            } else {
-                info!("[grpc] proof not found in database");
+        Ok(QuotaUsed {
+            daily_cycles: rows.iter().map(|row| row.cycles.max(0) as u64).sum(),
+            stored_bytes,
+            concurrent_tasks,
+        })
+    }
+
+    // reject a new task if a quota of its app is exhausted, the cycles of the tasks in progress are
+    // not known yet, so the last tasks of a day may overrun the daily cycles
+    async fn check_quota(&self, key: &ProvingKey) -> Result<(), Status> {
+        let limits = self.cfg.quota.limits(key.namespace(), key.app_id());
+        if limits.is_unlimited() {
+            return Ok(());
+        }
+
+        let usage = self.quota_usage(key.namespace(), key.app_id()).await?;
+        let Some((kind, limit, used)) = limits.exceeded(&usage) else {
+            return Ok(());
+        };
+        metrics().tasks_rejected_quota.inc();
+        warn!(
+            "[grpc] rejected task {key:?} by quota of {}: {used} of {limit}",
+            kind.name()
+        );
+
+        Err(with_err_code(
+            Status::resource_exhausted(format!(
+                "quota of {} exhausted: {used} of {limit}",
+                kind.name()
+            )),
+            ErrCode::QuotaExceeded,
+        ))
+    }
+
+    // the dependencies must be the distinct tasks of the same app, which are tracked and not failed
+    // or have a stored proof
+    async fn check_dependencies(
+        &self,
+        key: &ProvingKey,
+        depends_on: &[String],
+    ) -> Result<(), Status> {
+        for (i, task_id) in depends_on.iter().enumerate() {
+            if task_id == key.task_id() {
+                return Err(Status::invalid_argument(format!(
+                    "task {task_id} cannot depend on itself"
+                )));
+            }
+            // the dependencies are persisted separated by comma
+            if task_id.contains(',') {
+                return Err(Status::invalid_argument(format!(
+                    "task ID {task_id} of dependency must not contain comma"
+                )));
+            }
+            if depends_on[..i].contains(task_id) {
+                return Err(Status::invalid_argument(format!(
+                    "duplicate dependency {task_id}"
+                )));
             }
 
-            db_proof.map(Arc::from)
+            let dependency = ProvingKey::new(
+                key.namespace().to_string(),
+                key.app_id().to_string(),
+                task_id.clone(),
+            );
+            let tracked = match self.statuses.get(&dependency) {
+                Some(status)
+                    if matches!(status.state, TaskState::Failed | TaskState::Cancelled) =>
+                {
+                    return Err(Status::failed_precondition(format!(
+                        "dependency {task_id} is {:?}",
+                        status.state
+                    )));
+                }
+                Some(_) => true,
+                None => false,
+            };
+            if !tracked
+                && !self.storage.has_proof(&dependency).await.map_err(|e| {
+                    Status::internal(format!("failed to check proof of dependency: {e}"))
+                })?
+            {
+                return Err(Status::not_found(format!(
+                    "cannot find dependency task {dependency:?}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    // find the task proving or proved the same inputs of the app in the namespace of the new task,
+    // the progress is subscribed from it
+    async fn find_duplicate(
+        &self,
+        new_key: &ProvingKey,
+        inputs_hash: &str,
+        proof_type: ProofType,
+    ) -> Result<Option<(ProvingKey, broadcast::Receiver<ProvingProgress>)>, Status> {
+        let Some((task_id, state)) = self
+            .storage
+            .find_task_by_inputs(
+                new_key.namespace(),
+                new_key.app_id(),
+                inputs_hash,
+                proof_type,
+            )
+            .await
+            .map_err(|e| Status::internal(format!("failed to find task by inputs: {e}")))?
+        else {
+            return Ok(None);
         };
+        let key = ProvingKey::new(
+            new_key.namespace().to_string(),
+            new_key.app_id().to_string(),
+            task_id,
+        );
+        if state == TaskState::Completed && !self.track_completed(&key).await? {
+            return Ok(None);
+        }
 
-        info!("return GetProvingResultResponse");
+        // the in-memory state is the latest one, the task may fail since queried
+        let progress = match self.statuses.get(&key) {
+            Some(status) if !matches!(status.state, TaskState::Failed | TaskState::Cancelled) => {
+                status.progress.subscribe()
+            }
+            _ => return Ok(None),
+        };
 
-        Ok(Response::new(GetProvingResultResponse {
-            err: None,
-            proof: proof.map(|arc_proof| arc_proof.to_vec()),
-        }))
+        Ok(Some((key, progress)))
+    }
+
+    // find the task submitted before by the same ID, it's returned if the payload matches and
+    // rejected otherwise. the failed and cancelled tasks are queued again by a resubmission
+    async fn find_submitted(
+        &self,
+        key: &ProvingKey,
+        inputs_hash: &str,
+        proof_type: ProofType,
+    ) -> Result<Option<(ProvingKey, broadcast::Receiver<ProvingProgress>)>, Status> {
+        let Some((submitted_hash, submitted_type, state)) = self
+            .storage
+            .task_fingerprint(key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get proving task: {e}")))?
+        else {
+            return Ok(None);
+        };
+        // the in-memory state is the latest one
+        let state = self.statuses.get(key).map_or(state, |status| status.state);
+        if matches!(state, TaskState::Failed | TaskState::Cancelled) {
+            return Ok(None);
+        }
+        if submitted_type != proof_type
+            || submitted_hash.is_some_and(|submitted| submitted != inputs_hash)
+        {
+            return Err(Status::already_exists(format!(
+                "proving task {key:?} is already submitted with a different payload"
+            )));
+        }
+        if state == TaskState::Completed && !self.track_completed(key).await? {
+            return Ok(None);
+        }
+
+        Ok(self
+            .statuses
+            .get(key)
+            .map(|status| (key.clone(), status.progress.subscribe())))
+    }
+
+    // track the task proved before a restart, return false if the proof is deleted by the
+    // retention
+    async fn track_completed(&self, key: &ProvingKey) -> Result<bool, Status> {
+        if self.statuses.contains_key(key) {
+            return Ok(true);
+        }
+        let Some(row) = self
+            .storage
+            .get_proof(key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get proof from database: {e}")))?
+        else {
+            return Ok(false);
+        };
+        let finished_at = row.created_at as u64;
+        self.statuses
+            .entry(key.clone())
+            .or_insert_with(|| TaskStatus {
+                state: TaskState::Completed,
+                created_at: finished_at,
+                started_at: None,
+                finished_at: Some(finished_at),
+                failure_reason: None,
+                proof_type: row.proof_type(),
+                ..TaskStatus::queued()
+            });
+
+        Ok(true)
+    }
+
+    // track and send a proving task to the proving queue, it's held until `not_before` and waits
+    // for the dependencies if any
+    #[allow(clippy::too_many_arguments)]
+    fn enqueue_task(
+        &self,
+        app: &AppKeys,
+        key: ProvingKey,
+        inputs: Option<Vec<u8>>,
+        use_gpu: bool,
+        priority: TaskPriority,
+        mut status: TaskStatus,
+        depends_on: Vec<String>,
+    ) -> Result<broadcast::Receiver<ProvingProgress>, Status> {
+        // the task span is the child of the request span, or a root span if replayed
+        status.span = info_span!(
+            "proving_task",
+            namespace = key.namespace(),
+            app_id = key.app_id(),
+            task_id = key.task_id(),
+        );
+        status.queue_span = info_span!(parent: &status.span, "queue");
+        status.inputs_size = inputs.as_ref().map_or(0, Vec::len);
+        // subscribe before queueing to not miss any progress
+        let progress = status.progress.subscribe();
+        let task = ProvingTask::new(
+            key.clone(),
+            app.program.clone(),
+            app.pk.clone(),
+            app.vk.clone(),
+            inputs,
+            use_gpu,
+            status.allow_cpu_fallback,
+            priority,
+            status.deadline,
+            status.proof_type,
+            status.cancel_token.clone(),
+            status.progress.clone(),
+            status.span.clone(),
+        );
+        let not_before = status
+            .not_before
+            .filter(|not_before| *not_before > unix_timestamp());
+        self.statuses.insert(key, status);
+        metrics().tasks_queued.inc();
+        events::task_state(&task.key, TaskState::Queued, None);
+        if let Some(not_before) = not_before {
+            info!("[grpc] task {:?} is held until {not_before}", task.key);
+            self.dependencies.spawn_at(task, depends_on, not_before);
+            return Ok(progress);
+        }
+        if !depends_on.is_empty() {
+            info!(
+                "[grpc] task {:?} waits for {} dependencies",
+                task.key,
+                depends_on.len()
+            );
+            self.dependencies.spawn(task, depends_on);
+            return Ok(progress);
+        }
+        self.sender.send(task).map_err(|e| {
+            self.statuses.remove(&e.0.key);
+            Status::internal(format!("failed to send a proving task: {e}"))
+        })?;
+
+        Ok(progress)
+    }
+}
+
+#[async_trait]
+impl ProverNetwork for GrpcService {
+    type ProveTaskStreamStream = ReceiverStream<Result<ProveTaskProgress, Status>>;
+    type DownloadProofStream = ReceiverStream<Result<DownloadProofChunk, Status>>;
+    type SubscribeEventsStream = ReceiverStream<Result<ServiceEvent, Status>>;
+
+    // register a new application with elf
+    async fn register_app(
+        &self,
+        req: Request<RegisterAppRequest>,
+    ) -> Result<Response<RegisterAppResponse>, Status> {
+        info!("receive RegisterAppRequest");
+
+        authorize(&req, Action::Register, None)?;
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let req = req.into_inner();
+        let smoke_size = req
+            .warm_up
+            .as_ref()
+            .and_then(|warm_up| warm_up.smoke_inputs.as_ref())
+            .map_or(0, Vec::len);
+        self.admit_bytes(tenant.as_ref(), req.elf.len() + smoke_size)?;
+        check_app_name(req.name.as_deref())?;
+        let elf = match req.elf_uri.as_deref() {
+            Some(_) if !req.elf.is_empty() => {
+                return Err(Status::invalid_argument(
+                    "elf and elf_uri must not be both set",
+                ));
+            }
+            Some(uri) => self.fetch("elf", uri, &req.elf_sha256).await?,
+            None => req.elf,
+        };
+        let registration = self
+            .app_manager
+            .set_app(
+                &namespace,
+                &elf,
+                req.info,
+                req.app_info,
+                req.name.as_deref(),
+            )
+            .await
+            .map_err(register_app_status)?;
+        if let Some(warm_up) = req.warm_up {
+            self.warm_ups
+                .start(&namespace, &registration.app_id, warm_up.smoke_inputs);
+        }
+
+        info!("return RegisterAppResponse");
+
+        Ok(Response::new(RegisterAppResponse {
+            err: None,
+            app_id: registration.app_id,
+            version: registration.version,
+        }))
+    }
+
+    // register a new application by uploading the elf in chunks
+    async fn register_app_stream(
+        &self,
+        req: Request<Streaming<RegisterAppChunk>>,
+    ) -> Result<Response<RegisterAppResponse>, Status> {
+        info!("receive RegisterAppStreamRequest");
+
+        authorize(&req, Action::Register, None)?;
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let mut stream = req.into_inner();
+        let mut elf = vec![];
+        let finish = loop {
+            let chunk = stream
+                .message()
+                .await?
+                .ok_or_else(|| Status::invalid_argument("elf upload ends without finish frame"))?;
+            match chunk.frame {
+                Some(register_app_chunk::Frame::ElfChunk(data)) => {
+                    check_upload_size("elf", elf.len(), data.len(), self.cfg.max_elf_size)?;
+                    self.admit_bytes(tenant.as_ref(), data.len())?;
+                    elf.extend_from_slice(&data);
+                }
+                Some(register_app_chunk::Frame::Finish(finish)) => break finish,
+                None => return Err(Status::invalid_argument("empty elf upload frame")),
+            }
+        };
+        if stream.message().await?.is_some() {
+            return Err(Status::invalid_argument(
+                "unexpected frame after finish frame",
+            ));
+        }
+
+        if let Some(smoke_inputs) = finish
+            .warm_up
+            .as_ref()
+            .and_then(|warm_up| warm_up.smoke_inputs.as_ref())
+        {
+            self.admit_bytes(tenant.as_ref(), smoke_inputs.len())?;
+        }
+        check_sha256("elf", &elf, &finish.elf_sha256)?;
+        check_app_name(finish.name.as_deref())?;
+        info!("[grpc] received elf of {} bytes by streaming", elf.len());
+
+        let registration = self
+            .app_manager
+            .set_app(
+                &namespace,
+                &elf,
+                finish.info,
+                finish.app_info,
+                finish.name.as_deref(),
+            )
+            .await
+            .map_err(register_app_status)?;
+        if let Some(warm_up) = finish.warm_up {
+            self.warm_ups
+                .start(&namespace, &registration.app_id, warm_up.smoke_inputs);
+        }
+
+        info!("return RegisterAppStreamResponse");
+
+        Ok(Response::new(RegisterAppResponse {
+            err: None,
+            app_id: registration.app_id,
+            version: registration.version,
+        }))
+    }
+
+    // estimate gas cost
+    async fn estimate_cost(
+        &self,
+        req: Request<EstimateCostRequest>,
+    ) -> Result<Response<EstimateCostResponse>, Status> {
+        info!("receive EstimateCostRequest");
+
+        authorize(&req, Action::Prove, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let req = req.into_inner();
+        self.admit_bytes(tenant.as_ref(), req.inputs.as_ref().map_or(0, Vec::len))?;
+        let app_id = req.app_id;
+        let app = self
+            .app_manager
+            .get_app(&namespace, &app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
+            .ok_or_else(|| app_not_found(format!("cannot find app {app_id}")))?;
+        let inputs = self
+            .resolve_inputs(&namespace, req.inputs, req.inputs_hash.as_deref())
+            .await?;
+        let inputs = self
+            .decompress_inputs(req.inputs_compression(), req.inputs_hash.as_deref(), inputs)
+            .await?;
+
+        // the estimate is the same for the same elf and inputs
+        let inputs_hash = inputs_hash(inputs.as_deref());
+        if !req.bypass_cache.unwrap_or(false) {
+            let cached = self
+                .storage
+                .get_cost_estimate(&app_id, &inputs_hash)
+                .await
+                .map_err(|e| Status::internal(format!("failed to get cached estimate: {e}")))?;
+            if let Some(row) = cached {
+                info!("[grpc] return cached estimate of app {app_id} for inputs {inputs_hash}");
+                let res = self
+                    .estimate_response(
+                        row.cost as u64,
+                        row.total_cycles as u64,
+                        row.pv_digest,
+                        true,
+                    )
+                    .await;
+                return Ok(Response::new(res));
+            }
+        }
+
+        let res = match estimate_cost(
+            app.program,
+            app.pk,
+            app.vk,
+            inputs.as_deref(),
+            self.cfg.max_emulation_cycles,
+            true,
+        ) {
+            Ok(info) => {
+                let row = CostEstimateRow {
+                    cost: info.cost as i64,
+                    total_cycles: info.total_cycles as i64,
+                    pv_digest: info.pv_digest.to_be_bytes_vec(),
+                };
+                // the estimate is still returned if failed to cache
+                if let Err(e) = self
+                    .storage
+                    .store_cost_estimate(&app_id, &inputs_hash, &row)
+                    .await
+                {
+                    warn!("[grpc] failed to cache estimate of app {app_id}: {e}");
+                }
+                self.estimate_response(info.cost, info.total_cycles, row.pv_digest, false)
+                    .await
+            }
+            Err(e) => e.into(),
+        };
+
+        info!("return EstimateCostResponse");
+
+        Ok(Response::new(res))
+    }
+
+    // add a proving task
+    async fn prove_task(
+        &self,
+        req: Request<ProveTaskRequest>,
+    ) -> Result<Response<ProveTaskResponse>, Status> {
+        info!("receive ProveTaskRequest");
+
+        let claims = req.extensions().get::<Claims>().cloned();
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let req = req.into_inner();
+        self.admit_bytes(tenant.as_ref(), req.inputs.as_ref().map_or(0, Vec::len))?;
+        let task_id = req.task_id.clone();
+        let (key, _) = self.submit_task(&namespace, req, claims.as_ref()).await?;
+
+        info!("return ProveTaskResponse");
+
+        Ok(Response::new(ProveTaskResponse {
+            err: None,
+            app_id: key.app_id().to_string(),
+            deduplicated_by: deduplicated_by(&key, &task_id),
+        }))
+    }
+
+    // add a proving task by a signed request, the signer is verified before the task is resolved,
+    // and the nonce is used up even if the task is rejected then
+    async fn prove_task_signed(
+        &self,
+        req: Request<SignedProveTaskRequest>,
+    ) -> Result<Response<SignedProveTaskResponse>, Status> {
+        info!("receive SignedProveTaskRequest");
+
+        let claims = req.extensions().get::<Claims>().cloned();
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let signed = req.into_inner();
+        let signer = signing::verify_signer(&signed, unix_timestamp())
+            .map_err(|e| Status::unauthenticated(format!("invalid signed request: {e}")))?;
+        let req = ProveTaskRequest::decode(signed.payload.as_slice())
+            .map_err(|e| Status::invalid_argument(format!("invalid payload: {e}")))?;
+        self.admit_bytes(tenant.as_ref(), req.inputs.as_ref().map_or(0, Vec::len))?;
+        let task_id = req.task_id.clone();
+        let req = self.resolve_app(&namespace, req).await?;
+        check_scope(claims.as_ref(), Action::Prove, Some(&req.app_id))?;
+        let app = self.load_app_keys(&namespace, &req.app_id).await?;
+
+        let key = ProvingKey::new(namespace.clone(), req.app_id.clone(), task_id.clone());
+        let inserted = self
+            .storage
+            .insert_signed_task(&signer, signed.nonce, &key, unix_timestamp())
+            .await
+            .map_err(|e| Status::internal(format!("failed to record signed task: {e}")))?;
+        if !inserted {
+            return Err(Status::already_exists(format!(
+                "nonce {} of signer {signer} is already used",
+                signed.nonce
+            )));
+        }
+        info!("[grpc] task {key:?} is signed by {signer}");
+        let (key, _) = self.queue_task(&app, &namespace, req).await?;
+
+        info!("return SignedProveTaskResponse");
+
+        Ok(Response::new(SignedProveTaskResponse {
+            err: None,
+            app_id: key.app_id().to_string(),
+            deduplicated_by: deduplicated_by(&key, &task_id),
+            signer,
+        }))
+    }
+
+    // add a batch of proving tasks, the rejected tasks don't fail the whole batch
+    async fn batch_prove_task(
+        &self,
+        req: Request<BatchProveTaskRequest>,
+    ) -> Result<Response<BatchProveTaskResponse>, Status> {
+        info!("receive BatchProveTaskRequest");
+
+        let claims = req.extensions().get::<Claims>().cloned();
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let tasks = req.into_inner().tasks;
+        if tasks.is_empty() {
+            return Err(Status::invalid_argument("no proving tasks in the batch"));
+        }
+        self.check_unsigned()?;
+        // the whole batch is charged at once
+        let bytes = tasks
+            .iter()
+            .map(|task| task.inputs.as_ref().map_or(0, Vec::len))
+            .sum();
+        self.admit_bytes(tenant.as_ref(), bytes)?;
+
+        // load each app only once for the batch
+        let mut apps = HashMap::new();
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let task_id = task.task_id.clone();
+            let requested_app_id = task.app_id.clone();
+            let task = match self.resolve_app(&namespace, task).await.and_then(|task| {
+                check_scope(claims.as_ref(), Action::Prove, Some(&task.app_id))?;
+                Ok(task)
+            }) {
+                Ok(task) => task,
+                Err(status) => {
+                    results.push(ProveTaskResult {
+                        app_id: requested_app_id,
+                        task_id,
+                        err: Some(task_err_msg(&status)),
+                        deduplicated_by: None,
+                    });
+                    continue;
+                }
+            };
+            let app_id = task.app_id.clone();
+            let app = match apps.entry(app_id.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.load_app_keys(&namespace, &app_id).await),
+            };
+            let (err, deduplicated) = match app {
+                Ok(app) => match self.queue_task(app, &namespace, task).await {
+                    Ok((key, _)) => (None, deduplicated_by(&key, &task_id)),
+                    Err(status) => (Some(task_err_msg(&status)), None),
+                },
+                Err(status) => (Some(task_err_msg(status)), None),
+            };
+
+            results.push(ProveTaskResult {
+                app_id,
+                task_id,
+                err,
+                deduplicated_by: deduplicated,
+            });
+        }
+
+        let accepted = results.iter().filter(|res| res.err.is_none()).count();
+        info!(
+            "[grpc] accepted {accepted} of {} batch proving tasks",
+            results.len()
+        );
+
+        info!("return BatchProveTaskResponse");
+
+        Ok(Response::new(BatchProveTaskResponse { err: None, results }))
+    }
+
+    // add a proving task by uploading the inputs in chunks
+    async fn prove_task_upload(
+        &self,
+        req: Request<Streaming<ProveTaskChunk>>,
+    ) -> Result<Response<ProveTaskResponse>, Status> {
+        info!("receive ProveTaskUploadRequest");
+
+        let claims = req.extensions().get::<Claims>().cloned();
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let mut stream = req.into_inner();
+        let mut inputs = vec![];
+        let mut has_inputs = false;
+        let finish = loop {
+            let chunk = stream.message().await?.ok_or_else(|| {
+                Status::invalid_argument("inputs upload ends without finish frame")
+            })?;
+            match chunk.frame {
+                Some(prove_task_chunk::Frame::InputsChunk(data)) => {
+                    check_upload_size(
+                        "inputs",
+                        inputs.len(),
+                        data.len(),
+                        self.cfg.max_inputs_size,
+                    )?;
+                    self.admit_bytes(tenant.as_ref(), data.len())?;
+                    has_inputs = true;
+                    inputs.extend_from_slice(&data);
+                }
+                Some(prove_task_chunk::Frame::Finish(finish)) => break finish,
+                None => return Err(Status::invalid_argument("empty inputs upload frame")),
+            }
+        };
+        if stream.message().await?.is_some() {
+            return Err(Status::invalid_argument(
+                "unexpected frame after finish frame",
+            ));
+        }
+
+        if has_inputs {
+            check_sha256("inputs", &inputs, &finish.inputs_sha256)?;
+            info!(
+                "[grpc] received inputs of {} bytes by streaming",
+                inputs.len()
+            );
+        }
+
+        let req = ProveTaskRequest {
+            app_id: finish.app_id,
+            task_id: finish.task_id,
+            inputs: has_inputs.then_some(inputs),
+            use_gpu: finish.use_gpu,
+            priority: finish.priority,
+            app_name: finish.app_name,
+            app_version: finish.app_version,
+            force: finish.force,
+            callback_url: finish.callback_url,
+            verify_proof: finish.verify_proof,
+            proof_type: finish.proof_type,
+            depends_on: finish.depends_on,
+            not_before: finish.not_before,
+            deadline: finish.deadline,
+            inputs_hash: finish.inputs_hash,
+            inputs_uri: finish.inputs_uri,
+            inputs_sha256: finish.inputs_sha256,
+            inputs_compression: finish.inputs_compression,
+            allow_cpu_fallback: finish.allow_cpu_fallback,
+            expected_pv_digest: finish.expected_pv_digest,
+        };
+        let task_id = req.task_id.clone();
+        let (key, _) = self.submit_task(&namespace, req, claims.as_ref()).await?;
+
+        info!("return ProveTaskUploadResponse");
+
+        Ok(Response::new(ProveTaskResponse {
+            err: None,
+            app_id: key.app_id().to_string(),
+            deduplicated_by: deduplicated_by(&key, &task_id),
+        }))
+    }
+
+    // upload the inputs in chunks once to be referenced by their hash in the later tasks of the
+    // namespace
+    async fn upload_inputs(
+        &self,
+        req: Request<Streaming<UploadInputsChunk>>,
+    ) -> Result<Response<UploadInputsResponse>, Status> {
+        info!("receive UploadInputsRequest");
+
+        authorize(&req, Action::Prove, None)?;
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let mut stream = req.into_inner();
+        let mut inputs = vec![];
+        while let Some(chunk) = stream.message().await? {
+            let size = chunk.inputs_chunk.len();
+            check_upload_size("inputs", inputs.len(), size, self.cfg.max_inputs_size)?;
+            self.admit_bytes(tenant.as_ref(), size)?;
+            inputs.extend_from_slice(&chunk.inputs_chunk);
+        }
+        if inputs.is_empty() {
+            return Err(Status::invalid_argument("no inputs uploaded"));
+        }
+
+        let inputs_hash = inputs_hash(Some(&inputs));
+        // the inputs are offloaded to the artifact store if configured
+        let db_inputs = match &self.artifacts {
+            Some(store) => {
+                store
+                    .put(
+                        &artifact_store::uploaded_inputs_key(&namespace, &inputs_hash),
+                        &inputs,
+                    )
+                    .await
+                    .map_err(|e| Status::internal(format!("failed to store inputs: {e}")))?;
+                None
+            }
+            None => Some(&inputs[..]),
+        };
+        self.storage
+            .store_uploaded_inputs(
+                &namespace,
+                &inputs_hash,
+                db_inputs,
+                inputs.len(),
+                unix_timestamp(),
+            )
+            .await
+            .map_err(|e| Status::internal(format!("failed to persist uploaded inputs: {e}")))?;
+        info!(
+            "[grpc] uploaded inputs {inputs_hash} of {} bytes to namespace {namespace}",
+            inputs.len()
+        );
+
+        info!("return UploadInputsResponse");
+
+        Ok(Response::new(UploadInputsResponse {
+            err: None,
+            inputs_hash,
+            size: inputs.len() as u64,
+        }))
+    }
+
+    // add a proving task and stream its progress until finished
+    async fn prove_task_stream(
+        &self,
+        req: Request<ProveTaskRequest>,
+    ) -> Result<Response<Self::ProveTaskStreamStream>, Status> {
+        info!("receive ProveTaskStreamRequest");
+
+        let claims = req.extensions().get::<Claims>().cloned();
+        let namespace = request_namespace(&req)?;
+        let tenant = req.extensions().get::<Tenant>().cloned();
+        let req = req.into_inner();
+        self.admit_bytes(tenant.as_ref(), req.inputs.as_ref().map_or(0, Vec::len))?;
+        let (key, mut progress) = self.submit_task(&namespace, req, claims.as_ref()).await?;
+        let statuses = self.statuses.clone();
+        let (sender, receiver) = mpsc::channel(PROGRESS_STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            let mut tracker = ProgressTracker::default();
+            // the deduplicated task may be already finished
+            let mut event = if statuses
+                .get(&key)
+                .is_some_and(|status| status.is_finished())
+            {
+                ProgressEvent::TaskFinished
+            } else {
+                ProgressEvent::TaskQueued
+            };
+            loop {
+                let msg = match statuses.get(&key) {
+                    Some(status) => tracker.message(event, &status),
+                    None => break,
+                };
+                let finished = event == ProgressEvent::TaskFinished;
+                if sender.send(Ok(msg)).await.is_err() {
+                    info!("[grpc] progress stream of task {key:?} closed by client");
+                    break;
+                }
+                if finished {
+                    break;
+                }
+
+                event = loop {
+                    match progress.recv().await {
+                        Ok(p) => break tracker.update(p),
+                        // the counters in the next event cover the lagged ones, but the finish
+                        // event mustn't be missed
+                        Err(RecvError::Lagged(_)) => {
+                            if statuses
+                                .get(&key)
+                                .is_some_and(|status| status.is_finished())
+                            {
+                                break ProgressEvent::TaskFinished;
+                            }
+                        }
+                        Err(RecvError::Closed) => break ProgressEvent::TaskFinished,
+                    }
+                };
+            }
+        });
+
+        info!("return ProveTaskStreamResponse");
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    // try to fetch the proving result if complete
+    async fn get_proving_result(
+        &self,
+        req: Request<GetProvingResultRequest>,
+    ) -> Result<Response<GetProvingResultResponse>, Status> {
+        info!("receive GetProvingResultRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let key = ProvingKey::new(namespace, req.app_id, req.task_id);
+
+        info!("[grpc] looking for proof with key: {:?}", key);
+
+        // First try to get from memory (for recently completed proofs)
+        info!("[grpc] checking memory for key: {:?}", key);
+        info!("[grpc] current memory entries: {}", self.outputs.len());
+
+        let proof = if let Some((_, output)) = self.outputs.remove(&key) {
+            info!(
+                "[grpc] found proof in memory, size: {} bytes",
+                output.proof.len()
+            );
+            Some(output.proof)
+        } else {
+            info!("[grpc] proof not in memory, checking database");
+            // If not in memory, try to get from database
+            let db_proof = self.load_proof(&key).await?.map(|row| row.proof);
+
+            if let Some(ref proof_data) = db_proof {
+                info!(
+                    "[grpc] found proof in database, size: {} bytes",
+                    proof_data.len()
+                );
+            } else {
+                info!("[grpc] proof not found in database");
+            }
+
+            db_proof.map(Arc::from)
+        };
+
+        let timings = self
+            .statuses
+            .get(&key)
+            .and_then(|status| status.timings.clone());
+
+        info!("return GetProvingResultResponse");
+
+        Ok(Response::new(GetProvingResultResponse {
+            err: None,
+            proof: proof.map(|arc_proof| arc_proof.to_vec()),
+            timings,
+        }))
+    }
+
+    // query the lifecycle state of a proving task
+    async fn get_task_status(
+        &self,
+        req: Request<GetTaskStatusRequest>,
+    ) -> Result<Response<GetTaskStatusResponse>, Status> {
+        info!("receive GetTaskStatusRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let key = ProvingKey::new(namespace, req.app_id, req.task_id);
+
+        let status = match self.statuses.get(&key) {
+            Some(status) => status.clone(),
+            None => {
+                // the task may have been finished before a restart, check the database
+                let row = self.storage.get_task(&key).await.map_err(|e| {
+                    Status::internal(format!("failed to get task from database: {e}"))
+                })?;
+                match row {
+                    Some(row) => TaskStatus::restored(&row),
+                    None => self.proved_task_status(&key).await?,
+                }
+            }
+        };
+
+        info!("return GetTaskStatusResponse");
+
+        Ok(Response::new(GetTaskStatusResponse {
+            err: None,
+            state: status.state.into(),
+            created_at: status.created_at,
+            started_at: status.started_at,
+            finished_at: status.finished_at,
+            failure_reason: status.failure_reason,
+            attempts: status.attempts,
+            timings: status.timings,
+            failure_code: status.failure_code.map(Into::into),
+            not_before: status.not_before,
+            deadline: status.deadline,
+        }))
+    }
+
+    // cancel a queued or in-progress proving task
+    async fn cancel_task(
+        &self,
+        req: Request<CancelTaskRequest>,
+    ) -> Result<Response<CancelTaskResponse>, Status> {
+        info!("receive CancelTaskRequest");
+
+        authorize(&req, Action::Prove, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let key = ProvingKey::new(namespace, req.app_id, req.task_id);
+
+        let cancel_token = match self.statuses.get(&key) {
+            Some(status) if status.is_finished() => {
+                return Err(Status::failed_precondition(format!(
+                    "proving task {key:?} is already finished"
+                )));
+            }
+            Some(status) => status.cancel_token.clone(),
+            None => return Err(Status::not_found(format!("cannot find task {key:?}"))),
+        };
+
+        // queued tasks are skipped by the proving queue, in-progress proving is aborted by the
+        // emulator, gateway and provers
+        cancel_token.cancel();
+        update_task_state(&self.statuses, &key, TaskState::Cancelled);
+        self.storage
+            .update_task_state(&key, TaskState::Cancelled, None)
+            .await
+            .map_err(|e| Status::internal(format!("failed to persist cancelled task: {e}")))?;
+        info!("[grpc] cancelled proving task {key:?}");
+
+        info!("return CancelTaskResponse");
+
+        Ok(Response::new(CancelTaskResponse { err: None }))
+    }
+
+    // download a completed proof with its public values digest and metadata
+    async fn get_proof(
+        &self,
+        req: Request<GetProofRequest>,
+    ) -> Result<Response<GetProofResponse>, Status> {
+        info!("receive GetProofRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let key = ProvingKey::new(namespace, req.app_id, req.task_id);
+
+        let (proof, pv_digest, proof_type, provenance) = self.find_proof(&key).await?;
+        let calldata = match proof_type {
+            ProofType::Evm => evm_calldata(&key, &proof, &pv_digest),
+            ProofType::Core | ProofType::Compressed => vec![],
+        };
+
+        info!("return GetProofResponse");
+
+        Ok(Response::new(GetProofResponse {
+            err: None,
+            proof,
+            pv_digest,
+            signature: provenance.signature.clone(),
+            metadata: Some(provenance.into()),
+            proof_type: proof_type.into(),
+            calldata,
+        }))
+    }
+
+    // download a completed proof in chunks, the last frame has the checksum of the whole proof
+    async fn download_proof(
+        &self,
+        req: Request<DownloadProofRequest>,
+    ) -> Result<Response<Self::DownloadProofStream>, Status> {
+        info!("receive DownloadProofRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let chunk_size = match req.chunk_size {
+            None | Some(0) => DEFAULT_DOWNLOAD_CHUNK_SIZE,
+            Some(size) => (size as usize).min(MAX_DOWNLOAD_CHUNK_SIZE),
+        };
+        let key = ProvingKey::new(namespace, req.app_id, req.task_id);
+
+        let (proof, pv_digest, proof_type, provenance) = self.find_proof(&key).await?;
+        let finish = DownloadProofFinish {
+            proof_sha256: Sha256::digest(&proof).to_vec(),
+            proof_size: proof.len() as u64,
+            pv_digest,
+            proof_type: proof_type.into(),
+            signature: provenance.signature.clone(),
+            metadata: Some(provenance.into()),
+        };
+        let (sender, receiver) = mpsc::channel(DOWNLOAD_STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            let frames = proof
+                .chunks(chunk_size)
+                .map(|chunk| download_proof_chunk::Frame::ProofChunk(chunk.to_vec()))
+                .chain([download_proof_chunk::Frame::Finish(finish)]);
+            for frame in frames {
+                let chunk = DownloadProofChunk { frame: Some(frame) };
+                if sender.send(Ok(chunk)).await.is_err() {
+                    info!("[grpc] proof download of task {key:?} closed by client");
+                    return;
+                }
+            }
+            info!("[grpc] downloaded proof of task {key:?} in chunks of {chunk_size} bytes");
+        });
+
+        info!("return DownloadProofResponse");
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    // delete the proof and the record of a finished task
+    async fn delete_proof(
+        &self,
+        req: Request<DeleteProofRequest>,
+    ) -> Result<Response<DeleteProofResponse>, Status> {
+        info!("receive DeleteProofRequest");
+
+        authorize(&req, Action::Delete, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let key = ProvingKey::new(namespace, req.app_id, req.task_id);
+
+        if self.retention.is_running(&key) {
+            return Err(Status::failed_precondition(format!(
+                "proving task {key:?} is not finished"
+            )));
+        }
+        let deleted = self
+            .retention
+            .delete_task(&key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to delete proof: {e}")))?;
+        if !deleted {
+            return Err(Status::not_found(format!(
+                "cannot find proof of task {key:?}"
+            )));
+        }
+        info!("[grpc] deleted proof of task {key:?}");
+
+        info!("return DeleteProofResponse");
+
+        Ok(Response::new(DeleteProofResponse { err: None }))
+    }
+
+    // list the tracked proving tasks with filters and pagination
+    async fn list_tasks(
+        &self,
+        req: Request<ListTasksRequest>,
+    ) -> Result<Response<ListTasksResponse>, Status> {
+        info!("receive ListTasksRequest");
+
+        // all the apps are listed if not filtered
+        authorize(&req, Action::Read, req.get_ref().app_id.as_deref())?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let state = req
+            .state
+            .map(|state| {
+                TaskState::try_from(state)
+                    .map_err(|_| Status::invalid_argument(format!("invalid task state {state}")))
+            })
+            .transpose()?;
+        let (offset, page_size) = parse_page(&req.page_token, req.page_size)?;
+
+        let mut tasks: Vec<_> = self
+            .statuses
+            .iter()
+            .filter(|entry| {
+                let (key, status) = entry.pair();
+                key.namespace() == namespace
+                    && req
+                        .app_id
+                        .as_ref()
+                        .is_none_or(|app_id| app_id == key.app_id())
+                    && state.is_none_or(|state| state == status.state)
+                    && req.created_after.is_none_or(|t| status.created_at >= t)
+                    && req.created_before.is_none_or(|t| status.created_at < t)
+            })
+            .map(|entry| task_summary(entry.key(), entry.value()))
+            .collect();
+        tasks.sort_by(|a, b| {
+            (a.created_at, &a.app_id, &a.task_id).cmp(&(b.created_at, &b.app_id, &b.task_id))
+        });
+
+        let total_count = tasks.len();
+        let tasks: Vec<_> = tasks.into_iter().skip(offset).take(page_size).collect();
+        let next_offset = offset + tasks.len();
+        let next_page_token = if next_offset < total_count {
+            next_offset.to_string()
+        } else {
+            String::new()
+        };
+
+        info!("return ListTasksResponse");
+
+        Ok(Response::new(ListTasksResponse {
+            err: None,
+            tasks,
+            next_page_token,
+            total_count: total_count as u64,
+        }))
+    }
+
+    // get the metadata of a registered application
+    async fn get_app(
+        &self,
+        req: Request<GetAppRequest>,
+    ) -> Result<Response<GetAppResponse>, Status> {
+        info!("receive GetAppRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let app_id = req.into_inner().app_id;
+        let app = self
+            .app_manager
+            .get_app_meta(&namespace, &app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
+            .ok_or_else(|| app_not_found(format!("cannot find app {app_id}")))?;
+
+        let warm_up = self.warm_ups.status(&namespace, &app.app_id);
+
+        info!("return GetAppResponse");
+
+        Ok(Response::new(GetAppResponse {
+            err: None,
+            app: Some(app_metadata(app)),
+            warm_up: Some(warm_up),
+        }))
+    }
+
+    // get the static analysis of the elf of an application
+    async fn get_app_analysis(
+        &self,
+        req: Request<GetAppAnalysisRequest>,
+    ) -> Result<Response<GetAppAnalysisResponse>, Status> {
+        info!("receive GetAppAnalysisRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let app_id = req.into_inner().app_id;
+        let analysis = self
+            .app_manager
+            .get_app_analysis(&namespace, &app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get app analysis: {e}")))?;
+        let Some(analysis) = analysis else {
+            // the apps registered before analyzing have no analysis
+            let registered = self
+                .app_manager
+                .get_app_meta(&namespace, &app_id)
+                .await
+                .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
+                .is_some();
+            if registered {
+                return Err(Status::not_found(format!(
+                    "app {app_id} is registered without analysis"
+                )));
+            }
+            return Err(app_not_found(format!("cannot find app {app_id}")));
+        };
+
+        info!("return GetAppAnalysisResponse");
+
+        Ok(Response::new(GetAppAnalysisResponse {
+            err: None,
+            analysis: Some(analysis.into()),
+        }))
+    }
+
+    // list the registered applications with pagination
+    async fn list_apps(
+        &self,
+        req: Request<ListAppsRequest>,
+    ) -> Result<Response<ListAppsResponse>, Status> {
+        info!("receive ListAppsRequest");
+
+        authorize(&req, Action::Read, None)?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let (offset, page_size) = parse_page(&req.page_token, req.page_size)?;
+        let filter = AppFilter {
+            namespace: Some(namespace),
+            owner: req.owner,
+            name: req.name,
+            tag: req.tag,
+        };
+
+        // fetch one more app to check if there's a next page
+        let mut apps = self
+            .app_manager
+            .list_app_metas(&filter, offset, page_size + 1)
+            .await
+            .map_err(|e| Status::internal(format!("failed to list apps: {e}")))?;
+        let next_page_token = if apps.len() > page_size {
+            apps.truncate(page_size);
+            (offset + page_size).to_string()
+        } else {
+            String::new()
+        };
+
+        info!("return ListAppsResponse");
+
+        Ok(Response::new(ListAppsResponse {
+            err: None,
+            apps: apps.into_iter().map(app_metadata).collect(),
+            next_page_token,
+        }))
+    }
+
+    // list the elf versions of a named application
+    async fn list_app_versions(
+        &self,
+        req: Request<ListAppVersionsRequest>,
+    ) -> Result<Response<ListAppVersionsResponse>, Status> {
+        info!("receive ListAppVersionsRequest");
+
+        authorize(&req, Action::Read, None)?;
+        let namespace = request_namespace(&req)?;
+        let name = req.into_inner().name;
+        check_app_name(Some(&name))?;
+        let versions = self
+            .app_manager
+            .list_versions(&namespace, &name)
+            .await
+            .map_err(|e| Status::internal(format!("failed to list app versions: {e}")))?
+            .into_iter()
+            .map(|row| AppVersion {
+                version: row.version as u32,
+                app_id: row.app_id,
+                created_at: row.created_at as u64,
+            })
+            .collect();
+
+        info!("return ListAppVersionsResponse");
+
+        Ok(Response::new(ListAppVersionsResponse {
+            err: None,
+            versions,
+        }))
+    }
+
+    // remove a registered application and its proofs
+    async fn deregister_app(
+        &self,
+        req: Request<DeregisterAppRequest>,
+    ) -> Result<Response<DeregisterAppResponse>, Status> {
+        info!("receive DeregisterAppRequest");
+
+        authorize(&req, Action::Delete, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let app_id = req.into_inner().app_id;
+        let normalized_app_id = app_id.strip_prefix("0x").unwrap_or(&app_id).to_string();
+        // the tasks of the app in the other namespaces are kept
+        let same_app = |key: &ProvingKey| {
+            key.namespace() == namespace
+                && key.app_id().strip_prefix("0x").unwrap_or(key.app_id()) == normalized_app_id
+        };
+
+        // the tasks are not admitted until the app is removed
+        let app_lock = self.app_lock(&namespace, &app_id);
+        let _deregistration = app_lock.write().await;
+
+        // the app cannot be removed if any of its tasks is queued or proving
+        if self
+            .statuses
+            .iter()
+            .any(|entry| same_app(entry.key()) && !entry.value().is_finished())
+        {
+            return Err(Status::failed_precondition(format!(
+                "app {app_id} has tasks in progress"
+            )));
+        }
+
+        // the artifacts are found by the task records, so they're deleted before the records. a
+        // failed deletion leaves the app registered to be deregistered again
+        let artifacts = self
+            .retention
+            .delete_app_artifacts(&namespace, &app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to delete app artifacts: {e}")))?;
+        if artifacts > 0 {
+            info!("[grpc] deleted the artifacts of {artifacts} tasks of app {app_id}");
+        }
+
+        let deletion = self
+            .app_manager
+            .delete_app(&namespace, &app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to deregister app: {e}")))?;
+        match deletion {
+            AppDeletion::NotFound => {
+                return Err(app_not_found(format!("cannot find app {app_id}")));
+            }
+            AppDeletion::Kept => (),
+            AppDeletion::Removed { elf_hash } => {
+                self.key_cache.evict(&app_id, elf_hash.as_deref()).await
+            }
+        }
+
+        // new proving tasks are rejected since the app is removed, clean up the cached proofs
+        self.outputs.retain(|key, _| !same_app(key));
+        self.warm_ups.remove(&namespace, &app_id);
+        // the waiting admissions hold the removed lock, and find the app removed
+        self.app_locks
+            .remove(&(namespace.clone(), normalized_app_id.clone()));
+        info!("[grpc] deregistered app {app_id} of namespace {namespace}");
+
+        info!("return DeregisterAppResponse");
+
+        Ok(Response::new(DeregisterAppResponse { err: None }))
+    }
+
+    async fn verify_proof(
+        &self,
+        req: Request<VerifyProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        info!("receive VerifyProofRequest");
+
+        // the app of a raw vk is its vk hash
+        let (app_id, vk) = match &req.get_ref().verifier {
+            Some(Verifier::AppId(app_id)) => (app_id.clone(), None),
+            Some(Verifier::Vk(vk)) => {
+                let vk: BaseVerifyingKey<SC> = bincode::deserialize(vk)
+                    .map_err(|e| Status::invalid_argument(format!("invalid vk: {e}")))?;
+                (vk.hash_str_via_bn254(), Some(Arc::new(vk)))
+            }
+            None => return Err(Status::invalid_argument("app_id or vk is required")),
+        };
+        authorize(&req, Action::Read, Some(&app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let proof_type = req.proof_type();
+        let vk_hash = parse_vk_hash(&app_id)?;
+
+        // the STARK proofs are verified against the riscv vk of the registered app
+        let vk = match (proof_type, vk) {
+            (ProofType::Evm, _) => None,
+            (_, Some(vk)) => Some(vk),
+            (_, None) => Some(self.load_app_keys(&namespace, &app_id).await?.vk),
+        };
+        let pv_digest = match req.claim {
+            Some(Claim::PublicValues(public_values)) => pv_digest(&public_values),
+            Some(Claim::PvDigest(digest)) => U256::try_from_be_slice(&digest)
+                .ok_or_else(|| Status::invalid_argument("pv_digest must be at most 32 bytes"))?,
+            None => {
+                return Err(Status::invalid_argument(
+                    "public_values or pv_digest is required",
+                ));
+            }
+        };
+
+        let reason = self
+            .prover_backend
+            .verify(&req.proof, proof_type, vk_hash, vk.as_deref(), pv_digest)
+            .err()
+            .map(|e| e.to_string());
+        info!(
+            "[grpc] verified {proof_type:?} proof of vk hash {vk_hash:#x}: valid={}",
+            reason.is_none()
+        );
+
+        info!("return VerifyProofResponse");
+
+        Ok(Response::new(VerifyProofResponse {
+            err: None,
+            valid: reason.is_none(),
+            reason,
+        }))
+    }
+
+    // the key of the signatures of the completed proofs, for the downstream to attribute them
+    async fn get_service_key(
+        &self,
+        _req: Request<GetServiceKeyRequest>,
+    ) -> Result<Response<GetServiceKeyResponse>, Status> {
+        info!("receive GetServiceKeyRequest");
+
+        let signer = self
+            .cfg
+            .proof_signer()
+            .map_err(|e| Status::internal(format!("invalid proof signing key: {e}")))?
+            .ok_or_else(|| Status::failed_precondition("proof signing is not enabled"))?;
+
+        info!("return GetServiceKeyResponse");
+
+        Ok(Response::new(GetServiceKeyResponse {
+            err: None,
+            scheme: signer.scheme().into(),
+            public_key: signer.public_key(),
+            signer: signer.signer(),
+        }))
+    }
+
+    // get the verifier contract of an app, and the calldata of a task proof or a raw proof for it
+    async fn get_onchain_verifier(
+        &self,
+        req: Request<GetOnchainVerifierRequest>,
+    ) -> Result<Response<GetOnchainVerifierResponse>, Status> {
+        info!("receive GetOnchainVerifierRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let vk_hash = parse_vk_hash(&req.app_id)?;
+        self.app_manager
+            .get_app_meta(&namespace, &req.app_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get app: {e}")))?
+            .ok_or_else(|| app_not_found(format!("cannot find app {}", req.app_id)))?;
+
+        let calldata = match req.proof {
+            Some(OnchainProof::TaskId(task_id)) => {
+                let key = ProvingKey::new(namespace, req.app_id, task_id);
+                let (proof, pv_digest, proof_type, _) = self.find_proof(&key).await?;
+                if proof_type != ProofType::Evm {
+                    return Err(Status::failed_precondition(format!(
+                        "proof of task {key:?} is {proof_type:?}, expected EVM"
+                    )));
+                }
+                let pv_digest = U256::try_from_be_slice(&pv_digest).unwrap_or_default();
+                onchain_calldata(&proof, vk_hash, pv_digest).map_err(|e| {
+                    Status::internal(format!("failed to encode calldata of task {key:?}: {e}"))
+                })?
+            }
+            Some(OnchainProof::OnchainProof(proof)) => {
+                let pv_digest = match req.claim {
+                    Some(OnchainClaim::PublicValues(public_values)) => pv_digest(&public_values),
+                    Some(OnchainClaim::PvDigest(digest)) => U256::try_from_be_slice(&digest)
+                        .ok_or_else(|| {
+                            Status::invalid_argument("pv_digest must be at most 32 bytes")
+                        })?,
+                    None => {
+                        return Err(Status::invalid_argument(
+                            "public_values or pv_digest is required for onchain_proof",
+                        ));
+                    }
+                };
+                onchain_calldata(&proof, vk_hash, pv_digest)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?
+            }
+            None => vec![],
+        };
+
+        info!("return GetOnchainVerifierResponse");
+
+        Ok(Response::new(GetOnchainVerifierResponse {
+            err: None,
+            verifier_source: VERIFIER_SOURCE.to_string(),
+            verify_function: VERIFY_PROOF_SIGNATURE.to_string(),
+            vk_hash: vk_hash.to_be_bytes_vec(),
+            calldata,
+        }))
+    }
+
+    async fn get_gpu_stats(
+        &self,
+        req: Request<GetGpuStatsRequest>,
+    ) -> Result<Response<GetGpuStatsResponse>, Status> {
+        info!("receive GetGpuStatsRequest");
+
+        authorize(&req, Action::Read, None)?;
+        let devices = self.gpu_pool.stats();
+
+        info!("return GetGpuStatsResponse");
+
+        Ok(Response::new(GetGpuStatsResponse {
+            err: None,
+            devices,
+            cpu_fallbacks: metrics().gpu_cpu_fallbacks.get(),
+        }))
+    }
+
+    // aggregate the usage of the apps completed in the time window
+    async fn get_usage(
+        &self,
+        req: Request<GetUsageRequest>,
+    ) -> Result<Response<GetUsageResponse>, Status> {
+        info!("receive GetUsageRequest");
+
+        // all the apps are aggregated if not filtered
+        authorize(&req, Action::Read, req.get_ref().app_id.as_deref())?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let end_time = req.end_time.unwrap_or_else(unix_timestamp);
+        if req.start_time > end_time {
+            return Err(Status::invalid_argument(
+                "start_time must not be later than end_time",
+            ));
+        }
+
+        let rows = accounting::app_usage(
+            &self.storage,
+            &namespace,
+            req.app_id.as_deref(),
+            req.signer.as_deref(),
+            req.start_time,
+            end_time,
+        )
+        .await
+        .map_err(|e| Status::internal(format!("failed to get usage: {e}")))?;
+        let apps: Vec<_> = rows.into_iter().map(app_usage).collect();
+        let total = apps.iter().fold(AppUsage::default(), |mut total, app| {
+            total.tasks += app.tasks;
+            total.cycles += app.cycles;
+            total.chunks += app.chunks;
+            total.gpu_seconds += app.gpu_seconds;
+            total.stored_bytes += app.stored_bytes;
+            total
+        });
+
+        info!("return GetUsageResponse");
+
+        Ok(Response::new(GetUsageResponse {
+            err: None,
+            apps,
+            total: Some(total),
+        }))
+    }
+
+    // issue a JWT with the scopes, the token is recorded for the revocation
+    async fn issue_token(
+        &self,
+        req: Request<IssueTokenRequest>,
+    ) -> Result<Response<IssueTokenResponse>, Status> {
+        info!("receive IssueTokenRequest");
+
+        authorize(&req, Action::Admin, None)?;
+        let jwt = self.jwt_authority()?;
+        // the admin bound to a namespace only issues the tokens of the namespace
+        let bound = req
+            .extensions()
+            .get::<Claims>()
+            .and_then(|claims| claims.namespace.clone());
+        let req = req.into_inner();
+        if req.subject.is_empty() {
+            return Err(Status::invalid_argument("subject is required"));
+        }
+        let namespace = match (req.namespace, bound) {
+            (Some(namespace), Some(bound)) if namespace != bound => {
+                return Err(Status::permission_denied(format!(
+                    "token of namespace {bound} is not allowed to issue tokens of {namespace}"
+                )));
+            }
+            (namespace, bound) => namespace.or(bound),
+        };
+        if let Some(namespace) = &namespace {
+            check_namespace(namespace)?;
+        }
+
+        let ttl = Duration::from_secs(req.ttl_secs.unwrap_or(self.cfg.jwt_token_ttl_secs));
+        let (token, claims) = jwt
+            .issue(&req.subject, namespace, req.scopes, ttl)
+            .map_err(|e| Status::invalid_argument(format!("failed to issue token: {e}")))?;
+        self.storage
+            .insert_token(&TokenRow {
+                token_id: claims.jti.clone(),
+                subject: claims.sub.clone(),
+                namespace: claims.namespace.clone(),
+                scopes: claims.scopes.join(","),
+                created_at: claims.iat as i64,
+                expires_at: claims.exp as i64,
+            })
+            .await
+            .map_err(|e| Status::internal(format!("failed to persist token: {e}")))?;
+        info!(
+            "[grpc] issued token {} of {} with scopes {:?}",
+            claims.jti, claims.sub, claims.scopes
+        );
+
+        info!("return IssueTokenResponse");
+
+        Ok(Response::new(IssueTokenResponse {
+            err: None,
+            token,
+            token_id: claims.jti,
+            expires_at: claims.exp,
+        }))
+    }
+
+    // revoke an issued JWT, the other replicas reject it after syncing the revoked tokens
+    async fn revoke_token(
+        &self,
+        req: Request<RevokeTokenRequest>,
+    ) -> Result<Response<RevokeTokenResponse>, Status> {
+        info!("receive RevokeTokenRequest");
+
+        authorize_global(&req, Action::Admin)?;
+        let jwt = self.jwt_authority()?;
+        let token_id = req.into_inner().token_id;
+
+        let revoked = self
+            .storage
+            .revoke_token(&token_id)
+            .await
+            .map_err(|e| Status::internal(format!("failed to revoke token: {e}")))?;
+        if !revoked {
+            return Err(Status::not_found(format!("cannot find token {token_id}")));
+        }
+        jwt.revoke(&token_id);
+        info!("[grpc] revoked token {token_id}");
+
+        info!("return RevokeTokenResponse");
+
+        Ok(Response::new(RevokeTokenResponse { err: None }))
+    }
+
+    // list the permanently failed tasks with pagination
+    async fn list_dead_letters(
+        &self,
+        req: Request<ListDeadLettersRequest>,
+    ) -> Result<Response<ListDeadLettersResponse>, Status> {
+        info!("receive ListDeadLettersRequest");
+
+        // all the apps are listed if not filtered
+        authorize(&req, Action::Read, req.get_ref().app_id.as_deref())?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let (offset, page_size) = parse_page(&req.page_token, req.page_size)?;
+
+        // fetch one more dead letter to check if there's a next page
+        let mut rows = self
+            .storage
+            .list_dead_letters(&namespace, req.app_id.as_deref(), offset, page_size + 1)
+            .await
+            .map_err(|e| Status::internal(format!("failed to list dead letters: {e}")))?;
+        let next_page_token = if rows.len() > page_size {
+            rows.truncate(page_size);
+            (offset + page_size).to_string()
+        } else {
+            String::new()
+        };
+
+        info!("return ListDeadLettersResponse");
+
+        Ok(Response::new(ListDeadLettersResponse {
+            err: None,
+            dead_letters: rows.into_iter().map(dead_letter).collect(),
+            next_page_token,
+        }))
+    }
+
+    // queue a dead letter again, it's dropped once queued and added again if failed again
+    async fn requeue_dead_letter(
+        &self,
+        req: Request<RequeueDeadLetterRequest>,
+    ) -> Result<Response<RequeueDeadLetterResponse>, Status> {
+        info!("receive RequeueDeadLetterRequest");
+
+        authorize(&req, Action::Admin, None)?;
+        let namespace = request_namespace(&req)?;
+        let req = req.into_inner();
+        let key = ProvingKey::new(namespace.clone(), req.app_id, req.task_id);
+
+        let row = self
+            .storage
+            .get_dead_letter(&key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to get dead letter: {e}")))?
+            .ok_or_else(|| Status::not_found(format!("cannot find dead letter of task {key:?}")))?;
+        let inputs = self
+            .load_dead_letter_inputs(&key)
+            .await
+            .map_err(|e| Status::internal(format!("failed to load dead letter inputs: {e}")))?;
+        let app = self.load_app_keys(&namespace, key.app_id()).await?;
+        let prove_req = ProveTaskRequest {
+            app_id: key.app_id().to_string(),
+            task_id: key.task_id().to_string(),
+            inputs,
+            use_gpu: Some(row.use_gpu),
+            priority: Some(row.priority().into()),
+            app_name: None,
+            app_version: None,
+            // the same inputs are proved again even if another task is proving them
+            force: Some(true),
+            verify_proof: Some(row.verify_proof),
+            proof_type: Some(row.proof_type().into()),
+            depends_on: row.depends_on(),
+            // the requeued dead letter is proved at once, its deadline has likely passed
+            not_before: None,
+            deadline: None,
+            callback_url: row.callback_url,
+            inputs_hash: None,
+            inputs_uri: None,
+            inputs_sha256: vec![],
+            // the dead letter inputs are stored decompressed
+            inputs_compression: None,
+            allow_cpu_fallback: Some(row.allow_cpu_fallback),
+            expected_pv_digest: row.expected_pv_digest,
+        };
+        self.queue_task(&app, &namespace, prove_req).await?;
+
+        if let Err(e) = self.drop_dead_letter(&key).await {
+            warn!("[grpc] failed to drop requeued dead letter of task {key:?}: {e}");
+        }
+        info!("[grpc] requeued dead letter of task {key:?}");
+
+        info!("return RequeueDeadLetterResponse");
+
+        Ok(Response::new(RequeueDeadLetterResponse { err: None }))
+    }
+
+    // prove a bundled workload on the local provers, the results are the calibration baseline of
+    // the cost estimation
+    async fn run_benchmark(
+        &self,
+        req: Request<RunBenchmarkRequest>,
+    ) -> Result<Response<RunBenchmarkResponse>, Status> {
+        info!("receive RunBenchmarkRequest");
+
+        authorize_global(&req, Action::Admin)?;
+        let req = req.into_inner();
+        // the tasks are proved by the remote workers in coordinator mode
+        if self.cfg.coordinator_mode {
+            return Err(Status::failed_precondition(
+                "benchmark is not supported in coordinator mode",
+            ));
+        }
+        if self.cfg.prover_count == 0 {
+            return Err(Status::failed_precondition("no local prover"));
+        }
+
+        let results = self
+            .benchmark
+            .run(req.workload(), req.proof_type())
+            .await
+            .map_err(|e| Status::internal(format!("failed to run benchmark: {e}")))?
+            .ok_or_else(|| Status::aborted("another benchmark is running"))?;
+
+        info!("return RunBenchmarkResponse");
+
+        Ok(Response::new(RunBenchmarkResponse { err: None, results }))
+    }
+
+    // list the remote workers alive, none if not in coordinator mode
+    async fn list_workers(
+        &self,
+        req: Request<ListWorkersRequest>,
+    ) -> Result<Response<ListWorkersResponse>, Status> {
+        info!("receive ListWorkersRequest");
+
+        authorize(&req, Action::Read, None)?;
+        let workers = self
+            .coordinator
+            .as_ref()
+            .map(CoordinatorService::worker_statuses)
+            .unwrap_or_default();
+
+        info!("return ListWorkersResponse");
+
+        Ok(Response::new(ListWorkersResponse { err: None, workers }))
+    }
+
+    // close or reopen the admission of the new tasks, the queue is drained once the queued and
+    // active tasks of the response reach 0
+    async fn drain_queue(
+        &self,
+        req: Request<DrainQueueRequest>,
+    ) -> Result<Response<DrainQueueResponse>, Status> {
+        info!("receive DrainQueueRequest");
+
+        authorize_global(&req, Action::Admin)?;
+        let draining = !req.into_inner().resume;
+        if draining != self.shutdown.is_admission_closed() {
+            info!("[grpc] admission of new tasks closed: {draining}");
+        }
+        self.shutdown.set_admission_closed(draining);
+        let (mut queued_tasks, mut active_tasks) = (0, 0);
+        for status in self.statuses.iter() {
+            match status.state {
+                TaskState::Queued => queued_tasks += 1,
+                TaskState::Executing | TaskState::Proving => active_tasks += 1,
+                _ => {}
+            }
+        }
+
+        info!("return DrainQueueResponse");
+
+        Ok(Response::new(DrainQueueResponse {
+            err: None,
+            draining,
+            queued_tasks,
+            active_tasks,
+        }))
+    }
+
+    async fn get_quota(
+        &self,
+        req: Request<GetQuotaRequest>,
+    ) -> Result<Response<GetQuotaResponse>, Status> {
+        info!("receive GetQuotaRequest");
+
+        authorize(&req, Action::Read, Some(&req.get_ref().app_id))?;
+        let namespace = request_namespace(&req)?;
+        let app_id = req.into_inner().app_id;
+        let limits = self.cfg.quota.limits(&namespace, &app_id);
+        let usage = self.quota_usage(&namespace, &app_id).await?;
+
+        info!("return GetQuotaResponse");
+
+        Ok(Response::new(GetQuotaResponse {
+            err: None,
+            daily_cycles: Some(quota::quota_usage(limits.daily_cycles, usage.daily_cycles)),
+            stored_bytes: Some(quota::quota_usage(limits.stored_bytes, usage.stored_bytes)),
+            concurrent_tasks: Some(quota::quota_usage(
+                limits.concurrent_tasks,
+                usage.concurrent_tasks,
+            )),
+            daily_resets_at: quota::day_end(),
+        }))
+    }
+
+    // stream the events until the client closes the stream or the service stops, a lagged
+    // subscriber skips the missed events
+    async fn subscribe_events(
+        &self,
+        req: Request<SubscribeEventsRequest>,
+    ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
+        info!("receive SubscribeEventsRequest");
+
+        authorize(&req, Action::Read, req.get_ref().app_id.as_deref())?;
+        let namespace = request_namespace(&req)?;
+        let filter = EventFilter::new(namespace, req.get_ref());
+        // subscribe before returning to not miss the events of the following calls
+        let mut events = events::subscribe();
+        let stopped = self.shutdown.clone().stopped();
+        let (sender, receiver) = mpsc::channel(PROGRESS_STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            tokio::pin!(stopped);
+            loop {
+                let event = tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = sender.closed() => break,
+                    event = events.recv() => event,
+                };
+                match event {
+                    Ok(event) if filter.matches(&event) => {
+                        if sender.send(Ok(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("[grpc] event subscriber lagged, {missed} events missed");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            info!("[grpc] event stream closed");
+        });
+
+        info!("return SubscribeEventsResponse");
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+// compose the JWT verification after the authentication, the claims are attached to the requests
+fn with_jwt(
+    auth: impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static,
+    jwt: Option<&JwtAuthority>,
+    required: Option<Action>,
+) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static {
+    let jwt = jwt.map(|jwt| jwt.interceptor(required));
+
+    move |req: Request<()>| {
+        let req = auth(req)?;
+        match &jwt {
+            Some(jwt) => jwt(req),
+            None => Ok(req),
+        }
+    }
+}
+
+// admit the request by the request budget of its tenant after the authentication, the tenant is
+// attached to the request to charge the uploaded bytes in the handlers
+fn with_rate_limit(
+    auth: impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static,
+    rate_limiter: RateLimiter,
+) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static {
+    move |req: Request<()>| {
+        let mut req = auth(req)?;
+        let tenant = Tenant::of(&req);
+        rate_limiter.check_request(&tenant).map_err(rate_limited)?;
+        req.extensions_mut().insert(tenant);
+
+        Ok(req)
+    }
+}
+
+// check the scope of the JWT attached to the request, it's allowed if not authenticated by JWT
+fn authorize<T>(req: &Request<T>, action: Action, app_id: Option<&str>) -> Result<(), Status> {
+    check_scope(req.extensions().get::<Claims>(), action, app_id)
+}
+
+// check the global action of the JWT, it's rejected if the token is bound to a namespace
+fn authorize_global<T>(req: &Request<T>, action: Action) -> Result<(), Status> {
+    match req.extensions().get::<Claims>() {
+        Some(claims) if !claims.allows_global(action) => Err(Status::permission_denied(format!(
+            "token of {} is not allowed to {action} across the namespaces",
+            claims.sub
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn check_scope(
+    claims: Option<&Claims>,
+    action: Action,
+    app_id: Option<&str>,
+) -> Result<(), Status> {
+    match claims {
+        Some(claims) if !claims.allows(action, app_id) => Err(Status::permission_denied(format!(
+            "token of {} is not allowed to {action} app {}",
+            claims.sub,
+            app_id.unwrap_or("*")
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// reject the upload by chunks once it exceeds the max size, before buffering the chunk
+fn check_upload_size(name: &str, buffered: usize, chunk: usize, max: usize) -> Result<(), Status> {
+    if buffered.saturating_add(chunk) > max {
+        return Err(Status::resource_exhausted(format!(
+            "{name} exceeds the max size of {max} bytes"
+        )));
+    }
+
+    Ok(())
+}
+
+// check the sha256 of the uploaded data
+fn check_sha256(name: &str, data: &[u8], expected: &[u8]) -> Result<(), Status> {
+    let actual = Sha256::digest(data);
+    if actual.as_slice() != expected {
+        return Err(Status::invalid_argument(format!(
+            "{name} checksum mismatch, expected 0x{}, but uploaded 0x{}",
+            hex::encode(expected),
+            hex::encode(actual),
+        )));
+    }
+
+    Ok(())
+}
+
+// attach the code of the typed error to the status, it's parsed by `ServiceError` of the clients
+fn with_err_code(mut status: Status, code: ErrCode) -> Status {
+    if let Ok(value) = code.as_str_name().parse() {
+        status.metadata_mut().insert(ERR_CODE_METADATA_KEY, value);
+    }
+
+    status
+}
+
+// the clients back off by the retry-after metadata in seconds
+fn rate_limited(e: RateLimited) -> Status {
+    let secs = e.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut status = with_err_code(
+        Status::resource_exhausted(format!(
+            "{} rate limit exceeded, retry after {secs}s",
+            e.budget
+        )),
+        ErrCode::ResourceExhausted,
+    );
+    if let Ok(value) = secs.to_string().parse() {
+        status
+            .metadata_mut()
+            .insert(RETRY_AFTER_METADATA_KEY, value);
+    }
+
+    status
+}
+
+// the queue depth and the estimated wait if known are attached for the clients to back off
+fn overloaded(reason: String, queue_depth: usize, estimated_wait_secs: Option<u64>) -> Status {
+    let message = match estimated_wait_secs {
+        Some(secs) => format!("service overloaded, {reason}, estimated wait {secs}s"),
+        None => format!("service overloaded, {reason}"),
+    };
+    let mut status = with_err_code(Status::resource_exhausted(message), ErrCode::Overloaded);
+    let metadata = status.metadata_mut();
+    if let Ok(value) = queue_depth.to_string().parse() {
+        metadata.insert(QUEUE_DEPTH_METADATA_KEY, value);
+    }
+    if let Some(Ok(value)) = estimated_wait_secs.map(|secs| secs.to_string().parse()) {
+        metadata.insert(ESTIMATED_WAIT_METADATA_KEY, value);
+    }
+
+    status
+}
+
+fn app_not_found(message: String) -> Status {
+    with_err_code(Status::not_found(message), ErrCode::AppNotFound)
+}
+
+// not the gRPC deadline code, which is retried by the clients as transient
+fn deadline_exceeded(message: String) -> Status {
+    with_err_code(
+        Status::failed_precondition(message),
+        ErrCode::DeadlineExceeded,
+    )
+}
+
+// convert the rejection of a batch proving task to the common result
+fn task_err_msg(status: &Status) -> ErrMsg {
+    let code = err_code_of(status).unwrap_or(match status.code() {
+        Code::Internal | Code::Unavailable => ErrCode::Internal,
+        _ => ErrCode::Inval,
+    });
+
+    ErrMsg {
+        code: code.into(),
+        msg: Some(status.message().to_string()),
+    }
+}
+
+// parse the app ID (riscv vk hash) as an uint256
+fn parse_vk_hash(app_id: &str) -> Result<U256, Status> {
+    onchain_verifier::parse_vk_hash(app_id).map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+// namespace of the request from the metadata, or the one bound to the JWT if not set. the token
+// bound to a namespace cannot access the others
+fn request_namespace<T>(req: &Request<T>) -> Result<String, Status> {
+    let bound = req
+        .extensions()
+        .get::<Claims>()
+        .and_then(|claims| claims.namespace.as_deref());
+    let namespace = match req.metadata().get(NAMESPACE_METADATA_KEY) {
+        Some(value) => value
+            .to_str()
+            .map_err(|_| Status::invalid_argument("namespace must be ASCII"))?,
+        None => bound.unwrap_or(DEFAULT_NAMESPACE),
+    };
+    check_namespace(namespace)?;
+    if bound.is_some_and(|bound| bound != namespace) {
+        return Err(Status::permission_denied(format!(
+            "token is not allowed to access namespace {namespace}"
+        )));
+    }
+
+    Ok(namespace.to_string())
+}
+
+// the namespace is a component of the artifact keys, so only the safe characters are allowed
+fn check_namespace(namespace: &str) -> Result<(), Status> {
+    let valid = !namespace.is_empty()
+        && namespace.len() <= MAX_NAMESPACE_LEN
+        && namespace
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if !valid {
+        return Err(Status::invalid_argument(format!(
+            "invalid namespace {namespace:?}, expected at most {MAX_NAMESPACE_LEN} letters, \
+             digits, '-' or '_'"
+        )));
+    }
+
+    Ok(())
+}
+
+// hex of the sha256 of the inputs, the empty inputs are hashed as empty bytes
+fn inputs_hash(inputs: Option<&[u8]>) -> String {
+    hex::encode(Sha256::digest(inputs.unwrap_or_default()))
+}
+
+// calldata of the EVM proof to be verified on-chain, it's empty if failed to encode
+fn evm_calldata(key: &ProvingKey, proof: &[u8], pv_digest: &[u8]) -> Vec<u8> {
+    let pv_digest = U256::try_from_be_slice(pv_digest).unwrap_or_default();
+    onchain_verifier::parse_vk_hash(key.app_id())
+        .and_then(|vk_hash| onchain_calldata(proof, vk_hash, pv_digest))
+        .unwrap_or_else(|e| {
+            warn!("[grpc] failed to encode calldata of task {key:?}: {e}");
+            vec![]
+        })
+}
+
+// the name of a versioned app must not be empty if provided
+fn check_app_name(name: Option<&str>) -> Result<(), Status> {
+    if name.is_some_and(|name| name.trim().is_empty()) {
+        return Err(Status::invalid_argument("app name must not be empty"));
+    }
+
+    Ok(())
+}
+
+// parse the page token as the offset and the page size with the default and maximum
+fn parse_page(page_token: &str, page_size: u32) -> Result<(usize, usize), Status> {
+    let offset = if page_token.is_empty() {
+        0
+    } else {
+        page_token
+            .parse::<usize>()
+            .map_err(|_| Status::invalid_argument("invalid page token"))?
+    };
+    let page_size = match page_size as usize {
+        0 => DEFAULT_LIST_PAGE_SIZE,
+        size => size.min(MAX_LIST_PAGE_SIZE),
+    };
+
+    Ok((offset, page_size))
+}
+
+fn app_metadata(row: AppMetaRow) -> AppMetadata {
+    AppMetadata {
+        app_id: row.app_id,
+        elf_hash: row.elf_hash.unwrap_or_default(),
+        created_at: row.created_at.unwrap_or_default() as u64,
+        info: row.info,
+        app_info: Some(row.app_info.into()),
+    }
+}
+
+fn app_usage(row: AppUsageRow) -> AppUsage {
+    AppUsage {
+        app_id: row.app_id,
+        tasks: row.tasks as u64,
+        cycles: row.cycles as u64,
+        chunks: row.chunks as u64,
+        gpu_seconds: row.gpu_seconds,
+        stored_bytes: row.stored_bytes as u64,
+    }
+}
+
+fn dead_letter(row: DeadLetterRow) -> DeadLetter {
+    DeadLetter {
+        priority: row.priority().into(),
+        proof_type: row.proof_type().into(),
+        depends_on: row.depends_on(),
+        app_id: row.app_id,
+        task_id: row.task_id,
+        failure_reason: row.failure_reason,
+        attempts: row.attempts as u32,
+        failed_at: row.failed_at as u64,
+        use_gpu: row.use_gpu,
+        inputs_size: row.inputs_size as u64,
+        callback_url: row.callback_url,
+        verify_proof: row.verify_proof,
+        allow_cpu_fallback: row.allow_cpu_fallback,
+        expected_pv_digest: row.expected_pv_digest,
+    }
+}
+
+fn task_summary(key: &ProvingKey, status: &TaskStatus) -> TaskSummary {
+    TaskSummary {
+        app_id: key.app_id().to_string(),
+        task_id: key.task_id().to_string(),
+        state: status.state.into(),
+        created_at: status.created_at,
+        started_at: status.started_at,
+        finished_at: status.finished_at,
+        failure_reason: status.failure_reason.clone(),
+    }
+}
+
+// accumulate the proving progress for the streaming messages
+#[derive(Default)]
+struct ProgressTracker {
+    total_chunks: usize,
+    proved_chunks: usize,
+    combined_proofs: usize,
+}
+
+impl ProgressTracker {
+    fn update(&mut self, progress: ProvingProgress) -> ProgressEvent {
+        match progress {
+            ProvingProgress::Started => ProgressEvent::TaskStarted,
+            ProvingProgress::EmulationComplete { total_chunks } => {
+                self.total_chunks = total_chunks;
+                ProgressEvent::EmulationComplete
+            }
+            ProvingProgress::ChunkProved { proved_chunks } => {
+                self.proved_chunks = proved_chunks;
+                ProgressEvent::ChunkProved
+            }
+            ProvingProgress::CombineProved { combined_proofs } => {
+                self.combined_proofs = combined_proofs;
+                ProgressEvent::CombineProved
+            }
+            ProvingProgress::EmbedProved => ProgressEvent::EmbedProved,
+            ProvingProgress::Finished => ProgressEvent::TaskFinished,
+        }
+    }
+
+    fn message(&self, event: ProgressEvent, status: &TaskStatus) -> ProveTaskProgress {
+        let elapsed_secs = status
+            .started_at
+            .map(|started_at| {
+                status
+                    .finished_at
+                    .unwrap_or_else(unix_timestamp)
+                    .saturating_sub(started_at)
+            })
+            .unwrap_or_default();
+
+        ProveTaskProgress {
+            err: None,
+            event: event.into(),
+            state: status.state.into(),
+            total_chunks: self.total_chunks as u64,
+            proved_chunks: self.proved_chunks as u64,
+            // combining n chunk proofs requires n - 1 combine proofs
+            total_combines: self.total_chunks.saturating_sub(1) as u64,
+            combined_proofs: self.combined_proofs as u64,
+            elapsed_secs,
+            failure_reason: status.failure_reason.clone(),
+        }
+    }
+}
+
+// the earlier task serving the submitted one if deduplicated
+fn deduplicated_by(key: &ProvingKey, task_id: &str) -> Option<String> {
+    (key.task_id() != task_id).then(|| key.task_id().to_string())
+}
+
+// the duplicate registration returns the existing app ID in the metadata
+fn register_app_status(e: RegisterAppError) -> Status {
+    match e {
+        RegisterAppError::AlreadyExists(app_id) => {
+            let mut status = Status::already_exists(format!("app already exists {app_id}"));
+            if let Ok(value) = app_id.parse() {
+                status.metadata_mut().insert(APP_ID_METADATA_KEY, value);
+            }
+            status
+        }
+        RegisterAppError::InvalidElf(e) => Status::invalid_argument(format!("invalid elf: {e}")),
+        RegisterAppError::InvalidInfo(e) => {
+            Status::invalid_argument(format!("invalid app info: {e}"))
+        }
+        RegisterAppError::Internal(e) => Status::internal(format!("failed to register app: {e}")),
     }
 }
@@ -0,0 +1,111 @@
+use crate::{prover_network_server::SERVICE_NAME, shutdown::Shutdown, storage::SharedStorage};
+use derive_more::Constructor;
+use proto::{
+    HealthCheckRequest, HealthCheckResponse, health_check_response::ServingStatus,
+    health_server::Health,
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::sleep};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, async_trait};
+use tracing::{info, warn};
+
+pub mod proto {
+    tonic::include_proto!("grpc.health.v1");
+}
+
+// interval of re-checking the serving status for Watch
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Constructor, Clone)]
+pub struct HealthService {
+    storage: SharedStorage,
+    // set by the proving queue once its workers are ready for the proving tasks
+    workers_ready: Arc<AtomicBool>,
+    shutdown: Shutdown,
+}
+
+impl HealthService {
+    // serving only if the workers are ready and the database is reachable
+    async fn serving_status(&self, service: &str) -> ServingStatus {
+        if !service.is_empty() && service != SERVICE_NAME {
+            return ServingStatus::ServiceUnknown;
+        }
+
+        // not serving while draining, so the load balancers stop routing the new tasks here
+        if self.shutdown.is_draining()
+            || self.shutdown.is_admission_closed()
+            || !self.workers_ready.load(Ordering::Acquire)
+        {
+            return ServingStatus::NotServing;
+        }
+
+        match self.storage.ping().await {
+            Ok(_) => ServingStatus::Serving,
+            Err(e) => {
+                warn!("[health] database is unreachable: {e}");
+                ServingStatus::NotServing
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Health for HealthService {
+    type WatchStream = ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+    async fn check(
+        &self,
+        req: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service = req.into_inner().service;
+        let status = self.serving_status(&service).await;
+        if status == ServingStatus::ServiceUnknown {
+            return Err(Status::not_found(format!("unknown service {service}")));
+        }
+
+        Ok(Response::new(HealthCheckResponse {
+            status: status.into(),
+        }))
+    }
+
+    async fn watch(
+        &self,
+        req: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = req.into_inner().service;
+        info!("[health] start watching service {service:?}");
+
+        let (tx, rx) = mpsc::channel(1);
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut last_status = None;
+            loop {
+                let status = this.serving_status(&service).await;
+                if last_status != Some(status) {
+                    let res = HealthCheckResponse {
+                        status: status.into(),
+                    };
+                    // the client has stopped watching
+                    if tx.send(Ok(res)).await.is_err() {
+                        break;
+                    }
+                    last_status = Some(status);
+                }
+
+                tokio::select! {
+                    _ = sleep(WATCH_INTERVAL) => {}
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{
+    app_manager::App,
+    artifact_store::{self, SharedArtifactStore},
+    storage::SharedStorage,
+    types::SC,
+};
+use anyhow::{Result, anyhow};
+use clap::Args;
+use dashmap::DashMap;
+use pico_vm::{
+    compiler::riscv::program::Program,
+    machine::keys::{BaseProvingKey, BaseVerifyingKey, HashableKey},
+};
+use std::{sync::Arc, time::Instant};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+#[derive(Debug, Args, Clone)]
+pub struct KeyCacheConfig {
+    #[clap(
+        long,
+        env = "KEY_CACHE_CAPACITY",
+        default_value = "32",
+        help = "Apps with the program and keys kept in memory, the least recently used is evicted"
+    )]
+    pub key_cache_capacity: usize,
+
+    #[clap(
+        long,
+        env = "KEY_CACHE_WARM_APPS",
+        default_value = "8",
+        help = "Apps with the latest tasks loaded into the key cache on boot, 0 to disable"
+    )]
+    pub key_cache_warm_apps: usize,
+}
+
+// program and keys of an app shared by its proving tasks
+#[derive(Clone)]
+pub struct AppKeys {
+    pub program: Arc<Program>,
+    pub pk: Arc<BaseProvingKey<SC>>,
+    pub vk: Arc<BaseVerifyingKey<SC>>,
+}
+
+impl From<App> for AppKeys {
+    fn from(app: App) -> Self {
+        Self {
+            program: app.program,
+            pk: Arc::new(app.pk),
+            vk: Arc::new(app.vk),
+        }
+    }
+}
+
+struct CachedKeys {
+    keys: AppKeys,
+    last_used: Instant,
+}
+
+// cache of the app keys in memory, backed by the artifact store keyed by the elf hash and the
+// prover version, so a restarted service or another replica loads them without reading the app
+// rows from the DB
+pub struct KeyCache {
+    storage: SharedStorage,
+    artifacts: Option<SharedArtifactStore>,
+    capacity: usize,
+    entries: DashMap<String, CachedKeys>,
+}
+
+impl KeyCache {
+    pub fn new(
+        cfg: &KeyCacheConfig,
+        storage: SharedStorage,
+        artifacts: Option<SharedArtifactStore>,
+    ) -> Self {
+        Self {
+            storage,
+            artifacts,
+            capacity: cfg.key_cache_capacity,
+            entries: DashMap::new(),
+        }
+    }
+
+    // get the keys of the app registered in the namespace, none if not registered
+    pub async fn get(&self, namespace: &str, app_id: &str) -> Result<Option<AppKeys>> {
+        // remove the prefix `0x`
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+
+        // the keys are shared by the namespaces, the registration is checked on every get
+        let Some(meta) = self.storage.get_app_meta(namespace, app_id).await? else {
+            return Ok(None);
+        };
+
+        self.load(app_id, meta.elf_hash.as_deref()).await.map(Some)
+    }
+
+    // drop the keys of an app removed from all the namespaces from memory and the artifact store,
+    // the keys are kept while another namespace registers the app
+    pub async fn evict(&self, app_id: &str, elf_hash: Option<&str>) {
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+        self.entries.remove(app_id);
+
+        let elf_hash = elf_hash.filter(|elf_hash| !elf_hash.is_empty());
+        let Some((store, elf_hash)) = self.artifacts.as_ref().zip(elf_hash) else {
+            return;
+        };
+        let key = artifact_store::app_keys_key(elf_hash);
+        match store.delete(&key).await {
+            Ok(()) => info!("[key-cache] deleted the keys of app {app_id} from {key}"),
+            Err(e) => warn!("[key-cache] failed to delete the keys of app {app_id}: {e}"),
+        }
+    }
+
+    // load the keys of the apps with the latest tasks in background, so their first tasks after
+    // the restart don't wait for loading
+    pub fn start_warming(self: &Arc<Self>, apps: usize) -> Option<JoinHandle<()>> {
+        if apps == 0 || self.capacity == 0 {
+            return None;
+        }
+
+        let cache = self.clone();
+        Some(tokio::spawn(async move {
+            let recent = match cache.storage.recent_apps(apps.min(cache.capacity)).await {
+                Ok(recent) => recent,
+                Err(e) => {
+                    warn!("[key-cache] failed to get the recent apps: {e}");
+                    return;
+                }
+            };
+            let mut warmed = 0;
+            for (app_id, elf_hash) in recent {
+                match cache.load(&app_id, elf_hash.as_deref()).await {
+                    Ok(_) => warmed += 1,
+                    Err(e) => warn!("[key-cache] failed to warm the keys of app {app_id}: {e}"),
+                }
+            }
+            info!("[key-cache] warmed the keys of {warmed} apps");
+        }))
+    }
+
+    // load the keys from memory, then the artifact store, then the app row of the DB
+    async fn load(&self, app_id: &str, elf_hash: Option<&str>) -> Result<AppKeys> {
+        if let Some(mut cached) = self.entries.get_mut(app_id) {
+            cached.last_used = Instant::now();
+            return Ok(cached.keys.clone());
+        }
+
+        // the apps registered without the elf hash are not kept in the artifact store
+        let elf_hash = elf_hash.filter(|elf_hash| !elf_hash.is_empty());
+        let keys = match self.load_artifact(app_id, elf_hash).await {
+            Some(keys) => keys,
+            None => {
+                let row = self
+                    .storage
+                    .get_app(None, app_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("app {app_id} is removed"))?;
+                let keys = AppKeys::from(App::from(row));
+                self.store_artifact(app_id, elf_hash, &keys).await;
+                keys
+            }
+        };
+        self.insert(app_id, keys.clone());
+
+        Ok(keys)
+    }
+
+    // none if not stored, the corrupted artifact or the keys of another app are ignored and
+    // replaced by the ones from the DB
+    async fn load_artifact(&self, app_id: &str, elf_hash: Option<&str>) -> Option<AppKeys> {
+        let (store, elf_hash) = self.artifacts.as_ref().zip(elf_hash)?;
+        let key = artifact_store::app_keys_key(elf_hash);
+        let data = match store.get(&key).await {
+            Ok(data) => data?,
+            Err(e) => {
+                warn!("[key-cache] failed to get the keys of app {app_id}: {e}");
+                return None;
+            }
+        };
+        let (program, pk, vk): (Program, BaseProvingKey<SC>, BaseVerifyingKey<SC>) =
+            match bincode::deserialize(&data) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    warn!("[key-cache] invalid keys {key} of app {app_id}: {e}");
+                    return None;
+                }
+            };
+        if vk.hash_str_via_bn254().strip_prefix("0x") != Some(app_id) {
+            warn!("[key-cache] keys {key} don't belong to app {app_id}");
+            return None;
+        }
+        info!("[key-cache] loaded the keys of app {app_id} from {key}");
+
+        Some(AppKeys {
+            program: Arc::new(program),
+            pk: Arc::new(pk),
+            vk: Arc::new(vk),
+        })
+    }
+
+    // the keys are still served from the DB if failed to store
+    async fn store_artifact(&self, app_id: &str, elf_hash: Option<&str>, keys: &AppKeys) {
+        let Some((store, elf_hash)) = self.artifacts.as_ref().zip(elf_hash) else {
+            return;
+        };
+        let key = artifact_store::app_keys_key(elf_hash);
+        let data = match bincode::serialize(&(&*keys.program, &*keys.pk, &*keys.vk)) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("[key-cache] failed to serialize the keys of app {app_id}: {e}");
+                return;
+            }
+        };
+        match store.put(&key, &data).await {
+            Ok(()) => info!("[key-cache] stored the keys of app {app_id} to {key}"),
+            Err(e) => warn!("[key-cache] failed to store the keys of app {app_id}: {e}"),
+        }
+    }
+
+    fn insert(&self, app_id: &str, keys: AppKeys) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(app_id) && self.entries.len() >= self.capacity {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|cached| cached.last_used)
+                .map(|cached| cached.key().clone());
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+        self.entries.insert(
+            app_id.to_string(),
+            CachedKeys {
+                keys,
+                last_used: Instant::now(),
+            },
+        );
+    }
+}
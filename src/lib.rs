@@ -1,12 +1,40 @@
+pub mod accounting;
+pub mod app_analysis;
 pub mod app_manager;
+pub mod artifact_store;
+pub mod benchmark;
+pub mod client;
 pub mod config;
+pub mod coordinator;
 pub mod cost_estimation;
+pub mod dependencies;
 pub mod error;
+pub mod events;
+pub mod fetcher;
+pub mod gateway;
+pub mod gpu_pool;
 pub mod grpc;
+pub mod health;
+pub mod key_cache;
+pub mod metrics;
+pub mod proof_metadata;
 pub mod proving;
 pub mod proving_queue;
+pub mod quota;
+pub mod rate_limit;
+pub mod retention;
+pub mod retry;
+pub mod scheduler;
+pub mod shutdown;
+pub mod storage;
 pub mod types;
 pub mod utils;
+pub mod warm_up;
+pub mod webhook;
 
 tonic::include_proto!("prover_network");
 tonic::include_proto!("proving");
+
+// encoded descriptors of the served protos for the gRPC reflection
+pub const FILE_DESCRIPTOR_SET: &[u8] =
+    tonic::include_file_descriptor_set!("prover_network_descriptor");
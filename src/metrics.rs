@@ -0,0 +1,191 @@
+use anyhow::Result;
+use axum::{
+    Router,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::IntoResponse,
+    routing::get,
+};
+use prometheus::{
+    Histogram, HistogramOpts, HistogramVec, IntCounter, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder, exponential_buckets,
+};
+use std::{net::SocketAddr, sync::LazyLock, time::Duration};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tracing::{info, warn};
+
+static METRICS: LazyLock<Metrics> =
+    LazyLock::new(|| Metrics::new().expect("failed to register metrics"));
+
+// metrics of the process, they're exported by the /metrics endpoint if served
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+// phases of proving a task, the proving phase overlaps the emulation since the chunks are proved
+// once emulated
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    // emulation of the program with the inputs
+    Emulation,
+    // riscv, combine and embed proofs until the embed proof is generated
+    Proving,
+    // on-chain proof generation from the embed proof
+    Onchain,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Emulation => "emulation",
+            Phase::Proving => "proving",
+            Phase::Onchain => "onchain",
+        }
+    }
+}
+
+pub struct Metrics {
+    registry: Registry,
+    // tasks queued by the submissions and the replays after restart
+    pub tasks_queued: IntCounter,
+    // tasks executing or proving now
+    pub tasks_active: IntGauge,
+    pub tasks_completed: IntCounter,
+    pub tasks_failed: IntCounter,
+    pub tasks_cancelled: IntCounter,
+    // new tasks rejected by the backlog limits of the queue
+    pub tasks_rejected_overloaded: IntCounter,
+    // new tasks rejected by the quotas of their apps
+    pub tasks_rejected_quota: IntCounter,
+    // bytes of the inputs of the prove requests, resolved from the uploads, URIs and compression
+    pub inputs_received_bytes: IntCounter,
+    // time from queueing to executing a task, including the GPU waiting
+    pub queue_wait_seconds: Histogram,
+    proving_duration_seconds: HistogramVec,
+    // utilization (percent) of the GPU devices by the last query
+    gpu_utilization: IntGaugeVec,
+    // proving attempts failed on the GPU devices, counted per device
+    pub gpu_device_failures: IntCounter,
+    // GPU tasks proved on CPU as no GPU device was healthy
+    pub gpu_cpu_fallbacks: IntCounter,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        // from 0.1 second to about 7 hours
+        let buckets = exponential_buckets(0.1, 2.0, 18)?;
+        let metrics = Self {
+            registry: Registry::new(),
+            tasks_queued: IntCounter::new("pico_tasks_queued_total", "Proving tasks queued")?,
+            tasks_active: IntGauge::new("pico_tasks_active", "Proving tasks executing or proving")?,
+            tasks_completed: IntCounter::new(
+                "pico_tasks_completed_total",
+                "Proving tasks completed",
+            )?,
+            tasks_failed: IntCounter::new("pico_tasks_failed_total", "Proving tasks failed")?,
+            tasks_cancelled: IntCounter::new(
+                "pico_tasks_cancelled_total",
+                "Proving tasks cancelled",
+            )?,
+            tasks_rejected_overloaded: IntCounter::new(
+                "pico_tasks_rejected_overloaded_total",
+                "Proving tasks rejected by the backlog limits of the queue",
+            )?,
+            tasks_rejected_quota: IntCounter::new(
+                "pico_tasks_rejected_quota_total",
+                "Proving tasks rejected by the quotas of their apps",
+            )?,
+            inputs_received_bytes: IntCounter::new(
+                "pico_inputs_received_bytes_total",
+                "Bytes of the resolved inputs of the prove requests",
+            )?,
+            queue_wait_seconds: Histogram::with_opts(
+                HistogramOpts::new(
+                    "pico_queue_wait_seconds",
+                    "Time from queueing to executing a proving task",
+                )
+                .buckets(buckets.clone()),
+            )?,
+            proving_duration_seconds: HistogramVec::new(
+                HistogramOpts::new(
+                    "pico_proving_duration_seconds",
+                    "Duration of the proving phases of a task",
+                )
+                .buckets(buckets),
+                &["phase"],
+            )?,
+            gpu_utilization: IntGaugeVec::new(
+                Opts::new(
+                    "pico_gpu_utilization_percent",
+                    "Utilization of the GPU devices",
+                ),
+                &["device"],
+            )?,
+            gpu_device_failures: IntCounter::new(
+                "pico_gpu_device_failures_total",
+                "Proving attempts failed on the GPU devices",
+            )?,
+            gpu_cpu_fallbacks: IntCounter::new(
+                "pico_gpu_cpu_fallbacks_total",
+                "GPU tasks proved on CPU as no GPU device was healthy",
+            )?,
+        };
+
+        let registry = &metrics.registry;
+        registry.register(Box::new(metrics.tasks_queued.clone()))?;
+        registry.register(Box::new(metrics.tasks_active.clone()))?;
+        registry.register(Box::new(metrics.tasks_completed.clone()))?;
+        registry.register(Box::new(metrics.tasks_failed.clone()))?;
+        registry.register(Box::new(metrics.tasks_cancelled.clone()))?;
+        registry.register(Box::new(metrics.tasks_rejected_overloaded.clone()))?;
+        registry.register(Box::new(metrics.tasks_rejected_quota.clone()))?;
+        registry.register(Box::new(metrics.inputs_received_bytes.clone()))?;
+        registry.register(Box::new(metrics.queue_wait_seconds.clone()))?;
+        registry.register(Box::new(metrics.proving_duration_seconds.clone()))?;
+        registry.register(Box::new(metrics.gpu_utilization.clone()))?;
+        registry.register(Box::new(metrics.gpu_device_failures.clone()))?;
+        registry.register(Box::new(metrics.gpu_cpu_fallbacks.clone()))?;
+
+        Ok(metrics)
+    }
+
+    pub fn observe_phase(&self, phase: Phase, duration: Duration) {
+        self.proving_duration_seconds
+            .with_label_values(&[phase.name()])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn set_gpu_utilization(&self, device: u32, utilization: u32) {
+        self.gpu_utilization
+            .with_label_values(&[&device.to_string()])
+            .set(utilization.into());
+    }
+}
+
+// serve the metrics by HTTP GET /metrics on the address, it's bound before returning to fail fast
+pub async fn serve(addr: SocketAddr) -> Result<JoinHandle<()>> {
+    serve_registry(addr, metrics().registry.clone()).await
+}
+
+// serve the metrics of the registry, e.g. the ones of the tools not running the service
+pub async fn serve_registry(addr: SocketAddr, registry: Registry) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("[metrics] serving on {addr}/metrics");
+
+    let app = Router::new().route("/metrics", get(move || export(registry.clone())));
+    Ok(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            warn!("[metrics] server exits: {e}");
+        }
+    }))
+}
+
+// metrics in the Prometheus text format
+async fn export(registry: Registry) -> impl IntoResponse {
+    match TextEncoder::new().encode_to_string(&registry.gather()) {
+        Ok(body) => ([(CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(e) => {
+            warn!("[metrics] failed to encode metrics: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
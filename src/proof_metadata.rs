@@ -0,0 +1,104 @@
+use crate::{
+    ProofEnvelope, ProofMetadata, ProofSignature, ProofType,
+    proving_queue::ProvingKey,
+    storage::ProofRow,
+    types::{EMBED_SC_NAME, ONCHAIN_PROOF_SYSTEM, PROVER_VERSION, SC_NAME},
+    utils::time::unix_timestamp,
+};
+use pico_vm::instances::compiler::vk_merkle::vk_verification_enabled;
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+// version of the service storing the proofs
+pub const SERVICE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// provenance of a proof stored next to it, so the consumers check the compatibility before using
+// it. the fields are none for the proofs stored before it's recorded
+#[derive(Clone, Debug, Default)]
+pub struct ProofProvenance {
+    pub prover_version: Option<String>,
+    pub service_version: Option<String>,
+    // sha256 (hex) of the elf, none if the app is registered without it
+    pub elf_hash: Option<String>,
+    // sha256 (hex) of the serialized inputs
+    pub inputs_hash: Option<String>,
+    // unix timestamp (seconds) of storing the proof
+    pub created_at: u64,
+    // wall time of emulating and proving
+    pub proving_seconds: Option<f64>,
+    // signature of the service over the envelope of the proof, none if not signed
+    pub signature: Option<ProofSignature>,
+}
+
+impl ProofProvenance {
+    // provenance of a proof generated by this service now
+    pub fn new(
+        elf_hash: Option<String>,
+        inputs_hash: Option<String>,
+        proving_seconds: f64,
+    ) -> Self {
+        Self {
+            prover_version: Some(PROVER_VERSION.to_string()),
+            service_version: Some(SERVICE_VERSION.to_string()),
+            elf_hash,
+            inputs_hash,
+            created_at: unix_timestamp(),
+            proving_seconds: Some(proving_seconds),
+            signature: None,
+        }
+    }
+}
+
+// the envelope signed by the service, the metadata doesn't include the signature itself
+pub fn proof_envelope(
+    key: &ProvingKey,
+    proof: &[u8],
+    pv_digest: Vec<u8>,
+    proof_type: ProofType,
+    provenance: &ProofProvenance,
+) -> ProofEnvelope {
+    let app_id = key.app_id();
+    ProofEnvelope {
+        app_id: app_id.strip_prefix("0x").unwrap_or(app_id).to_string(),
+        task_id: key.task_id().to_string(),
+        proof_sha256: Sha256::digest(proof).to_vec(),
+        pv_digest,
+        proof_type: proof_type.into(),
+        metadata: Some(provenance.clone().into()),
+    }
+}
+
+impl From<&ProofRow> for ProofProvenance {
+    fn from(row: &ProofRow) -> Self {
+        Self {
+            prover_version: row.prover_version.clone(),
+            service_version: row.service_version.clone(),
+            elf_hash: row.elf_hash.clone(),
+            inputs_hash: row.inputs_hash.clone(),
+            created_at: row.created_at as u64,
+            proving_seconds: row.proving_seconds,
+            signature: row
+                .signature
+                .as_deref()
+                .and_then(|signature| ProofSignature::decode(signature).ok()),
+        }
+    }
+}
+
+// the configs of this service are reported with the provenance of the proof
+impl From<ProofProvenance> for ProofMetadata {
+    fn from(provenance: ProofProvenance) -> Self {
+        Self {
+            stark_config: SC_NAME.to_string(),
+            embed_stark_config: EMBED_SC_NAME.to_string(),
+            proof_system: ONCHAIN_PROOF_SYSTEM.to_string(),
+            vk_verification: vk_verification_enabled(),
+            prover_version: provenance.prover_version,
+            service_version: provenance.service_version,
+            elf_hash: provenance.elf_hash,
+            inputs_hash: provenance.inputs_hash,
+            created_at: provenance.created_at,
+            proving_seconds: provenance.proving_seconds,
+        }
+    }
+}
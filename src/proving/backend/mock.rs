@@ -0,0 +1,129 @@
+use crate::{
+    ProofType,
+    cost_estimation::estimate_cost,
+    proving::{
+        ProvedInfo, ProvingProgress,
+        backend::{OnEmulated, ProveOptions, ProverBackend, ProverBackendKind},
+        onchain_verifier::parse_vk_hash,
+        timing::{PhaseTimer, ProvingPhase},
+    },
+    proving_queue::ProvingTask,
+    types::SC,
+};
+use alloy_primitives::{U256, keccak256};
+use anyhow::{Result, bail, ensure};
+use async_trait::async_trait;
+use pico_vm::machine::keys::BaseVerifyingKey;
+use tokio::time::Instant;
+use tracing::info;
+
+const MOCK_PROOF_DOMAIN: &[u8] = b"pico-proving-service/MockProof/v1";
+
+// the size of the groth16 proof, so the calldata of the mock EVM proofs is encoded as the real ones
+const MOCK_PROOF_SIZE: usize = 256;
+
+// the instant backend for testing. the tasks are emulated for the real public values digest and
+// cycles, and the proof is derived from the claim instead of proved, so it's not sound
+pub struct MockBackend;
+
+impl MockBackend {
+    async fn prove_to(
+        &self,
+        task: ProvingTask,
+        on_emulated: OnEmulated<'_>,
+        proof_type: ProofType,
+    ) -> Result<ProvedInfo> {
+        info!("[mock-prover] proving {:?} as {proof_type:?}", task.key);
+        let start = Instant::now();
+        let vk_hash = parse_vk_hash(task.key.app_id())?;
+        let (program, pk, vk) = (task.program.clone(), (*task.pk).clone(), (*task.vk).clone());
+        let inputs = task.inputs;
+        let info = tokio::task::spawn_blocking(move || {
+            estimate_cost(program, pk, vk, inputs.as_deref(), None, false)
+        })
+        .await??;
+        let timer = PhaseTimer::default();
+        timer.add(ProvingPhase::Emulation, start.elapsed());
+        if task.cancel_token.is_cancelled() {
+            bail!("proving task cancelled");
+        }
+        on_emulated();
+        // no subscriber is not an error
+        let _ = task
+            .progress
+            .send(ProvingProgress::EmulationComplete { total_chunks: 0 });
+
+        let elapsed = start.elapsed();
+        Ok(ProvedInfo {
+            proof: mock_proof(proof_type, vk_hash, info.pv_digest),
+            pv_digest: info.pv_digest,
+            total_cycles: info.total_cycles,
+            total_chunks: 0,
+            gpu_seconds: 0.0,
+            proving_seconds: elapsed.as_secs_f64(),
+            timings: timer.timings(elapsed),
+        })
+    }
+}
+
+#[async_trait]
+impl ProverBackend for MockBackend {
+    fn kind(&self) -> ProverBackendKind {
+        ProverBackendKind::Mock
+    }
+
+    async fn prove_core(
+        &self,
+        task: ProvingTask,
+        _opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        self.prove_to(task, on_emulated, ProofType::Core).await
+    }
+
+    async fn compress(
+        &self,
+        task: ProvingTask,
+        _opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        self.prove_to(task, on_emulated, ProofType::Compressed)
+            .await
+    }
+
+    async fn wrap(
+        &self,
+        task: ProvingTask,
+        _opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        self.prove_to(task, on_emulated, ProofType::Evm).await
+    }
+
+    fn verify(
+        &self,
+        proof: &[u8],
+        proof_type: ProofType,
+        vk_hash: U256,
+        _riscv_vk: Option<&BaseVerifyingKey<SC>>,
+        pv_digest: U256,
+    ) -> Result<()> {
+        ensure!(
+            proof == mock_proof(proof_type, vk_hash, pv_digest).as_slice(),
+            "mock proof mismatches the claimed {proof_type:?} proof"
+        );
+
+        Ok(())
+    }
+}
+
+// the keccak256 of the claim repeated to the size of the groth16 proof
+fn mock_proof(proof_type: ProofType, vk_hash: U256, pv_digest: U256) -> Vec<u8> {
+    let mut claim = MOCK_PROOF_DOMAIN.to_vec();
+    claim.extend_from_slice(&(proof_type as i32).to_be_bytes());
+    claim.extend_from_slice(&vk_hash.to_be_bytes::<32>());
+    claim.extend_from_slice(&pv_digest.to_be_bytes::<32>());
+    let word = keccak256(&claim);
+
+    word.repeat(MOCK_PROOF_SIZE / word.len())
+}
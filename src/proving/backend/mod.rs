@@ -0,0 +1,125 @@
+use crate::{
+    ProofType,
+    artifact_store::SharedArtifactStore,
+    proving::{ProvedInfo, memory::MemoryBudget},
+    proving_queue::ProvingTask,
+    types::SC,
+};
+use alloy_primitives::U256;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+use pico_vm::machine::keys::BaseVerifyingKey;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+mod mock;
+mod pico;
+
+pub use mock::MockBackend;
+pub use pico::PicoBackend;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ProverBackendKind {
+    // the pico_vm pipeline of the emulator, gateway and provers
+    Pico,
+    // emulate the tasks and return the instant unsound proofs, for testing only
+    Mock,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct ProverBackendConfig {
+    #[clap(
+        long,
+        env = "PROVER_BACKEND",
+        default_value = "pico",
+        value_enum,
+        help = "Backend proving the tasks (pico, mock), the mock proofs are not sound and for testing only"
+    )]
+    pub prover_backend: ProverBackendKind,
+}
+
+impl ProverBackendConfig {
+    pub fn backend(&self) -> SharedProverBackend {
+        match self.prover_backend {
+            ProverBackendKind::Pico => Arc::new(PicoBackend),
+            ProverBackendKind::Mock => Arc::new(MockBackend),
+        }
+    }
+}
+
+// options of a proving attempt, they're ignored by the backends not supporting them
+pub struct ProveOptions<'a> {
+    pub prover_count: usize,
+    // the assigned CUDA devices to prove by GPU, or prove by CPU if empty
+    pub gpu_devices: &'a [u32],
+    // store to checkpoint the proofs for resuming, no checkpoint if none
+    pub checkpoint_store: Option<&'a SharedArtifactStore>,
+    // memory budget of the task, unlimited if none
+    pub memory_budget: Option<MemoryBudget>,
+    // cancelled to preempt the task, the checkpoint is kept to resume it. it should be a child of
+    // the cancel token of the task
+    pub preempt_token: Option<CancellationToken>,
+}
+
+// called once the emulation is complete and only proving remains
+pub type OnEmulated<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+// the proving pipeline of the tasks. each stage proves the task from the inputs up to its proof
+// type, since pico_vm pipelines the recursion with the chunk proving instead of resuming from the
+// proof of the previous stage
+#[async_trait]
+pub trait ProverBackend: Send + Sync {
+    fn kind(&self) -> ProverBackendKind;
+
+    // the STARK proof combining the riscv chunks
+    async fn prove_core(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo>;
+
+    // the constant-size STARK proof compressing the core proof
+    async fn compress(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo>;
+
+    // the groth16 proof wrapping the embed proof to be verified on-chain
+    async fn wrap(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo>;
+
+    // verify the proof of the app (vk hash) and the public values digest, the STARK proofs are
+    // verified against the riscv vk, or trusted as verified by the provers in proving without it
+    fn verify(
+        &self,
+        proof: &[u8],
+        proof_type: ProofType,
+        vk_hash: U256,
+        riscv_vk: Option<&BaseVerifyingKey<SC>>,
+        pv_digest: U256,
+    ) -> Result<()>;
+
+    // prove the task to the stage of its proof type
+    async fn prove(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        match task.proof_type {
+            ProofType::Core => self.prove_core(task, opts, on_emulated).await,
+            ProofType::Compressed => self.compress(task, opts, on_emulated).await,
+            ProofType::Evm => self.wrap(task, opts, on_emulated).await,
+        }
+    }
+}
+
+pub type SharedProverBackend = Arc<dyn ProverBackend>;
@@ -0,0 +1,128 @@
+use crate::{
+    ProofType,
+    cost_estimation::pv_digest,
+    proving::{
+        self, ProvedInfo,
+        backend::{OnEmulated, ProveOptions, ProverBackend, ProverBackendKind},
+        onchain_verifier::verify_onchain_proof,
+        worker::prover::{
+            combine::{CombineHandler, CombineProver},
+            compress::{CompressHandler, CompressProver},
+        },
+    },
+    proving_queue::ProvingTask,
+    types::SC,
+};
+use alloy_primitives::U256;
+use anyhow::{Context, Result, ensure};
+use async_trait::async_trait;
+use pico_vm::machine::{keys::BaseVerifyingKey, proof::MetaProof};
+
+const VERIFIER_ID: &str = "verifier";
+
+// the default backend running the pico_vm pipeline, the pipeline stops at the proof type of the
+// task
+pub struct PicoBackend;
+
+impl PicoBackend {
+    async fn prove_to(
+        &self,
+        mut task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+        proof_type: ProofType,
+    ) -> Result<ProvedInfo> {
+        task.proof_type = proof_type;
+        proving::prove_task(
+            task,
+            opts.prover_count,
+            opts.gpu_devices,
+            opts.checkpoint_store,
+            opts.memory_budget,
+            opts.preempt_token,
+            on_emulated,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl ProverBackend for PicoBackend {
+    fn kind(&self) -> ProverBackendKind {
+        ProverBackendKind::Pico
+    }
+
+    async fn prove_core(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        self.prove_to(task, opts, on_emulated, ProofType::Core)
+            .await
+    }
+
+    async fn compress(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        self.prove_to(task, opts, on_emulated, ProofType::Compressed)
+            .await
+    }
+
+    async fn wrap(
+        &self,
+        task: ProvingTask,
+        opts: ProveOptions<'_>,
+        on_emulated: OnEmulated<'_>,
+    ) -> Result<ProvedInfo> {
+        self.prove_to(task, opts, on_emulated, ProofType::Evm).await
+    }
+
+    // the STARK proofs of the tasks are verified by the provers in proving
+    fn verify(
+        &self,
+        proof: &[u8],
+        proof_type: ProofType,
+        vk_hash: U256,
+        riscv_vk: Option<&BaseVerifyingKey<SC>>,
+        pv_digest: U256,
+    ) -> Result<()> {
+        match (proof_type, riscv_vk) {
+            (ProofType::Evm, _) => verify_onchain_proof(proof, vk_hash, pv_digest),
+            (ProofType::Core | ProofType::Compressed, None) => Ok(()),
+            (ProofType::Core | ProofType::Compressed, Some(riscv_vk)) => {
+                verify_stark_proof(proof, proof_type, riscv_vk, pv_digest)
+            }
+        }
+    }
+}
+
+// verify the serialized STARK proof by the machine of its stage, the public values stream carried
+// by the proof must match the claimed digest
+fn verify_stark_proof(
+    proof: &[u8],
+    proof_type: ProofType,
+    riscv_vk: &BaseVerifyingKey<SC>,
+    claimed_digest: U256,
+) -> Result<()> {
+    let proof: MetaProof<SC> = bincode::deserialize(proof).context("invalid STARK proof")?;
+    if proof_type == ProofType::Core {
+        CombineProver::new(VERIFIER_ID.to_string()).verify(&proof, riscv_vk)?;
+    } else {
+        CompressProver::new(VERIFIER_ID.to_string()).verify(&proof, riscv_vk)?;
+    }
+
+    let pv_stream = proof
+        .pv_stream
+        .as_deref()
+        .context("STARK proof carries no public values")?;
+    ensure!(
+        pv_digest(pv_stream) == claimed_digest,
+        "public values digest mismatches the claim"
+    );
+
+    Ok(())
+}
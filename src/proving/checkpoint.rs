@@ -0,0 +1,214 @@
+use crate::{
+    artifact_store::{self, SharedArtifactStore},
+    proving::gateway::handler::proof_tree::IndexedProof,
+    proving_queue::ProvingKey,
+    types::{EmbedSC, SC},
+};
+use anyhow::Result;
+use pico_vm::machine::proof::MetaProof;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tokio::runtime::Handle;
+use tracing::{info, warn};
+
+type ChunkProof = IndexedProof<MetaProof<SC>>;
+
+// artifact of the digest of the inputs, the checkpoint is dropped if it doesn't match
+const DIGEST_FILE: &str = "digest";
+const EMBED_FILE: &str = "embed.bin";
+const CHUNK_PREFIX: &str = "chunk-";
+
+// checkpoint of a proving task in the artifact store, the chunk and combine proofs are persisted
+// by the chunk ranges, and the embed proof is persisted before the on-chain proving. a resumed
+// task continues from the persisted proofs since the emulation is deterministic
+pub struct Checkpoint {
+    store: SharedArtifactStore,
+    prefix: String,
+}
+
+impl Checkpoint {
+    pub fn new(store: SharedArtifactStore, key: &ProvingKey) -> Self {
+        let prefix = artifact_store::checkpoint_prefix(key);
+
+        Self { store, prefix }
+    }
+
+    // load the persisted proofs of the same inputs, the stale checkpoint is dropped
+    pub async fn load(&self, inputs: Option<&[u8]>) -> Result<Resumed> {
+        let digest = hex::encode(Sha256::digest(inputs.unwrap_or_default()));
+        match self.store.get(&self.key(DIGEST_FILE)).await? {
+            Some(saved) if saved == digest.as_bytes() => (),
+            saved => {
+                if saved.is_some() {
+                    warn!("[checkpoint] inputs changed, dropping {}", self.prefix);
+                    self.clear().await?;
+                }
+                self.store
+                    .put(&self.key(DIGEST_FILE), digest.as_bytes())
+                    .await?;
+                return Ok(Resumed::default());
+            }
+        }
+
+        let embed = self.read::<MetaProof<EmbedSC>>(EMBED_FILE).await;
+        if embed.is_some() {
+            info!(
+                "[checkpoint] resuming from the embed proof in {}",
+                self.prefix
+            );
+            return Ok(Resumed {
+                embed,
+                ..Default::default()
+            });
+        }
+
+        // keep the widest proofs not covered by the others
+        let mut ranges = self.chunk_ranges().await?;
+        ranges.sort_by_key(|(start, end)| (*start, usize::MAX - end));
+        let mut proofs = BTreeMap::new();
+        let mut covered_end = None;
+        for (start, end) in ranges {
+            if covered_end.is_some_and(|covered_end| end <= covered_end) {
+                continue;
+            }
+            if let Some(proof) = self.read::<ChunkProof>(&chunk_file(start, end)).await {
+                proofs.insert(start, proof);
+                covered_end = Some(end);
+            }
+        }
+        info!(
+            "[checkpoint] resuming with {} proofs in {}",
+            proofs.len(),
+            self.prefix
+        );
+
+        Ok(Resumed {
+            proofs,
+            embed: None,
+            skip_until: None,
+        })
+    }
+
+    // persist a chunk or combine proof, the proofs covered by it are removed. it's called from the
+    // blocking gateway thread
+    pub fn save_proof(&self, proof: &ChunkProof) -> Result<()> {
+        let (start, end) = (proof.start_chunk, proof.end_chunk);
+        let data = bincode::serialize(proof)?;
+
+        Handle::current().block_on(async {
+            self.store
+                .put(&self.key(&chunk_file(start, end)), &data)
+                .await?;
+            for (sub_start, sub_end) in self.chunk_ranges().await? {
+                if start <= sub_start && sub_end <= end && (sub_start, sub_end) != (start, end) {
+                    self.store
+                        .delete(&self.key(&chunk_file(sub_start, sub_end)))
+                        .await?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    // persist the embed proof, the chunk and combine proofs are no longer required
+    pub fn save_embed(&self, proof: &MetaProof<EmbedSC>) -> Result<()> {
+        let data = bincode::serialize(proof)?;
+
+        Handle::current().block_on(async {
+            self.store.put(&self.key(EMBED_FILE), &data).await?;
+            for (start, end) in self.chunk_ranges().await? {
+                self.store
+                    .delete(&self.key(&chunk_file(start, end)))
+                    .await?;
+            }
+
+            Ok(())
+        })
+    }
+
+    // remove the checkpoint of a finished task
+    pub async fn clear(&self) -> Result<()> {
+        self.store.delete_prefix(&self.prefix).await
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}{name}", self.prefix)
+    }
+
+    async fn chunk_ranges(&self) -> Result<Vec<(usize, usize)>> {
+        let keys = self.store.list(&self.prefix).await?;
+
+        Ok(keys
+            .iter()
+            .filter_map(|key| parse_chunk_range(key.strip_prefix(&self.prefix)?))
+            .collect())
+    }
+
+    async fn read<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let key = self.key(name);
+        let data = self
+            .store
+            .get(&key)
+            .await
+            .inspect_err(|e| warn!("[checkpoint] failed to read {key}: {e}"))
+            .ok()??;
+        bincode::deserialize(&data)
+            .inspect_err(|e| warn!("[checkpoint] ignoring corrupted {key}: {e}"))
+            .ok()
+    }
+}
+
+// how to handle a chunk of the resumed task
+pub enum Restore {
+    // prove the chunk as usual
+    Prove,
+    // the chunk is covered by a restored proof
+    Skip,
+    // restore the proof starting from the chunk
+    Proof(ChunkProof),
+}
+
+// persisted proofs of a resumed task
+#[derive(Default)]
+pub struct Resumed {
+    // proofs by the start chunks
+    proofs: BTreeMap<usize, ChunkProof>,
+    embed: Option<MetaProof<EmbedSC>>,
+    // the last chunk covered by the restored proofs
+    skip_until: Option<usize>,
+}
+
+impl Resumed {
+    // the chunks are emulated in order
+    pub fn restore(&mut self, chunk_index: usize) -> Restore {
+        if self.embed.is_some() || self.skip_until.is_some_and(|end| chunk_index <= end) {
+            return Restore::Skip;
+        }
+        match self.proofs.remove(&chunk_index) {
+            Some(proof) => {
+                self.skip_until = Some(proof.end_chunk);
+                Restore::Proof(proof)
+            }
+            None => Restore::Prove,
+        }
+    }
+
+    pub fn take_embed(&mut self) -> Option<MetaProof<EmbedSC>> {
+        self.embed.take()
+    }
+}
+
+fn chunk_file(start: usize, end: usize) -> String {
+    format!("{CHUNK_PREFIX}{start}-{end}.bin")
+}
+
+fn parse_chunk_range(name: &str) -> Option<(usize, usize)> {
+    let (start, end) = name
+        .strip_prefix(CHUNK_PREFIX)?
+        .strip_suffix(".bin")?
+        .split_once('-')?;
+
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
@@ -1,12 +1,18 @@
 use crate::{
-    proving::messages::{
-        gateway::GatewayMsg,
-        riscv::{RiscvMsg, RiscvRequest},
+    cost_estimation::pv_digest,
+    proving::{
+        ProvingProgress,
+        memory::MemoryBudget,
+        messages::{
+            gateway::GatewayMsg,
+            riscv::{RiscvMsg, RiscvRequest},
+        },
     },
     proving_queue::ProvingTask,
     types::SC,
 };
-use anyhow::Result;
+use alloy_primitives::U256;
+use anyhow::{Result, bail};
 use crossbeam::channel::{Receiver, Sender, bounded};
 use log::{debug, info};
 use p3_koala_bear::KoalaBear;
@@ -24,12 +30,34 @@ use pico_vm::{
 };
 use std::{sync::Arc, thread, time::Instant};
 
+pub struct EmulatedInfo {
+    pub total_cycles: u64,
+    pub total_chunks: u64,
+    pub pv_digest: U256,
+}
+
 pub trait EmulatorRunner: StarkGenericConfig {
-    fn run(task: ProvingTask, gateway_endpoint: Arc<Sender<GatewayMsg>>) -> Result<()>;
+    fn run(
+        task: ProvingTask,
+        gateway_endpoint: Arc<Sender<GatewayMsg>>,
+        memory_budget: Option<MemoryBudget>,
+    ) -> Result<EmulatedInfo>;
 }
 
 impl EmulatorRunner for KoalaBearPoseidon2 {
-    fn run(task: ProvingTask, gateway_endpoint: Arc<Sender<GatewayMsg>>) -> Result<()> {
+    fn run(
+        task: ProvingTask,
+        gateway_endpoint: Arc<Sender<GatewayMsg>>,
+        memory_budget: Option<MemoryBudget>,
+    ) -> Result<EmulatedInfo> {
+        let cancel_token = task.cancel_token.clone();
+
+        // reject the inputs too large for the budget before emulating
+        let inputs_size = task.inputs.as_ref().map_or(0, Vec::len);
+        if let Some(budget) = &memory_budget {
+            budget.check(inputs_size, 0)?;
+        }
+
         // Setups
         let _vk_manager = <KoalaBearPoseidon2 as HasStaticVkManager>::static_vk_manager();
 
@@ -71,9 +99,16 @@ impl EmulatorRunner for KoalaBearPoseidon2 {
 
         // Start the emulator thread.
         log_section("RISCV EMULATE PHASE");
+        let emulator_cancel_token = cancel_token.clone();
         let emulator_handle = thread::spawn(move || {
             let mut batch_num = 1;
+            let mut exhausted = None;
             loop {
+                if emulator_cancel_token.is_cancelled() {
+                    tracing::debug!("--- Stop emulation at batch-{batch_num} for cancelled task");
+                    break;
+                }
+
                 let start_local = Instant::now();
 
                 let report = emulator.next_record_batch(&mut |record| {
@@ -92,11 +127,22 @@ impl EmulatorRunner for KoalaBearPoseidon2 {
                     break;
                 }
 
+                // stop the emulation and the proving of the task once it exceeds the budget
+                if let Some(Err(e)) = memory_budget
+                    .as_ref()
+                    .map(|budget| budget.check(inputs_size, emulator.cycles()))
+                {
+                    tracing::warn!("--- Stop emulation at batch-{batch_num}: {e}");
+                    emulator_cancel_token.cancel();
+                    exhausted = Some(e);
+                    break;
+                }
+
                 batch_num += 1;
             }
 
             // Move and return the emulator for further usage.
-            emulator
+            (emulator, exhausted)
 
             // `record_sender` will be dropped when the emulator thread completes.
         });
@@ -106,6 +152,11 @@ impl EmulatorRunner for KoalaBearPoseidon2 {
         let mut chunk_index = 0;
 
         while let Ok(record) = record_receiver.recv() {
+            // drain the remaining records without proving if the task is cancelled
+            if cancel_token.is_cancelled() {
+                continue;
+            }
+
             let req = RiscvRequest {
                 chunk_index,
                 record,
@@ -122,18 +173,42 @@ impl EmulatorRunner for KoalaBearPoseidon2 {
             chunk_index += 1;
         }
 
+        let (mut emulator, exhausted) = emulator_handle.join().unwrap();
+        let total_cycles = emulator.cycles();
+        info!("Total Cycles: {}", total_cycles);
+
+        if let Some(e) = exhausted {
+            return Err(e.into());
+        }
+
+        if cancel_token.is_cancelled() {
+            bail!("proving task cancelled during emulation");
+        }
+
         // send the emulator complete message
         gateway_endpoint.send(GatewayMsg::EmulatorComplete)?;
+        let _ = task.progress.send(ProvingProgress::EmulationComplete {
+            total_chunks: chunk_index,
+        });
 
-        let emulator = emulator_handle.join().unwrap();
-        info!("Total Cycles: {}", emulator.cycles());
+        let pv_digest = pv_digest(&emulator.get_pv_stream());
 
-        Ok(())
+        Ok(EmulatedInfo {
+            total_cycles,
+            total_chunks: chunk_index as u64,
+            pv_digest,
+        })
     }
 }
 
-pub fn run(task: ProvingTask, gateway_endpoint: Arc<Sender<GatewayMsg>>) {
+pub fn run(
+    task: ProvingTask,
+    gateway_endpoint: Arc<Sender<GatewayMsg>>,
+    memory_budget: Option<MemoryBudget>,
+) -> Result<EmulatedInfo> {
     debug!("[coordinator] emulator init");
-    SC::run(task.clone(), gateway_endpoint.clone()).unwrap();
+    let info = SC::run(task, gateway_endpoint, memory_budget)?;
     debug!("[coordinator] emulator run completed");
+
+    Ok(info)
 }
@@ -20,6 +20,8 @@ pub struct GatewayHandler {
     proof_tree: ProofTree<MetaProof<SC>>,
     // store the embed proof result
     embed_proof: Option<MetaProof<EmbedSC>>,
+    // final STARK proof of the core or compressed proof types
+    stark_proof: Option<MetaProof<SC>>,
 }
 
 impl GatewayHandler {
@@ -28,11 +30,16 @@ impl GatewayHandler {
             emulator_complete: false,
             proof_tree: ProofTree::default(),
             embed_proof: None,
+            stark_proof: None,
         }
     }
 
     pub fn complete(&self) -> bool {
-        self.embed_proof.is_some()
+        self.embed_proof.is_some() || self.stark_proof.is_some()
+    }
+
+    pub fn get_stark_proof(&self) -> Option<&MetaProof<SC>> {
+        self.stark_proof.as_ref()
     }
 
     pub fn get_embed_proof(&self) -> Option<MetaProof<EmbedSC>> {
@@ -95,6 +102,10 @@ impl GatewayHandler {
                 self.set_embed_proof(proof);
                 info!("[gateway] received embed proof from worker prover");
             }
+            GatewayMsg::Stark(proof) => {
+                self.stark_proof = Some(proof);
+                info!("[gateway] received final STARK proof from worker prover");
+            }
             // Compress and embed phases are now handled directly in worker provers
             // No message handling needed here
             _ => panic!("unsupported"),
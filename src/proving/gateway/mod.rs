@@ -1,32 +1,74 @@
-use crate::proving::{
-    messages::{combine::CombineMsg, gateway::GatewayMsg, riscv::RiscvMsg},
-    onchain::prove_embed_onchain,
+use crate::{
+    metrics::{Phase, metrics},
+    proving::{
+        ProgressSender, ProvingProgress,
+        checkpoint::{Checkpoint, Restore, Resumed},
+        messages::{
+            combine::CombineMsg,
+            gateway::GatewayMsg,
+            riscv::{RiscvMsg, RiscvResponse},
+        },
+        onchain::prove_embed_onchain,
+        timing::{PhaseTimer, ProvingPhase},
+    },
 };
 use crossbeam::channel::{Receiver, select_biased};
-use handler::GatewayHandler;
+use handler::{GatewayHandler, proof_tree::IndexedProof};
 use log::debug;
 use pico_vm::thread::channel::DuplexUnboundedEndpoint;
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{Span, error, info, info_span, warn};
 
 pub(crate) mod handler;
 
 pub type GatewayEndpoint = DuplexUnboundedEndpoint<GatewayMsg, GatewayMsg>;
 
+// interval to check the cancellation if no messages are received
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 pub fn run(
     // exit the whole app directly if proving complete
     emulator_receiver: Arc<Receiver<GatewayMsg>>,
     grpc_endpoint: Arc<GatewayEndpoint>,
     completion_sender: tokio::sync::oneshot::Sender<Vec<u8>>,
+    cancel_token: CancellationToken,
+    progress: ProgressSender,
+    // persist the proofs to resume the task if any
+    checkpoint: Option<(Checkpoint, Resumed)>,
+    // the on-chain proving is timed as wrapping
+    timer: Arc<PhaseTimer>,
 ) -> JoinHandle<()> {
     debug!("[coordinator] gateway init with proof callback");
 
+    // the blocking thread doesn't inherit the span of the proving workflow
+    let span = Span::current();
     let thread_handle = tokio::task::spawn_blocking(move || {
+        let _entered = span.enter();
         let mut gateway_handler: GatewayHandler = GatewayHandler::new();
+        let proving_start = Instant::now();
         let mut completion_sender = Some(completion_sender);
+        let mut proved_chunks = 0;
+        let mut combined_proofs = 0;
+        let (checkpoint, mut resumed) = checkpoint.unzip();
+        // the checkpoint failure is only logged since the proving could go on
+        let save_proof = |proof: &IndexedProof<_>| {
+            if let Some(Err(e)) = checkpoint.as_ref().map(|c| c.save_proof(proof)) {
+                warn!("[gateway] failed to checkpoint proof: {e}");
+            }
+        };
 
         loop {
+            if cancel_token.is_cancelled() {
+                // dropping the completion sender notifies the cancellation to the proving workflow
+                info!("[gateway] proving task cancelled, exit");
+                break;
+            }
+
             select_biased! {
                 recv(emulator_receiver) -> msg => {
                     let msg = match msg {
@@ -34,17 +76,45 @@ pub fn run(
                         Err(_) => break, // Channel closed, exit gracefully
                     };
                     match msg {
-                        GatewayMsg::Riscv(RiscvMsg::Request(..), _, _) => {
-                            let no_task = gateway_handler.process_riscv_req(&msg).unwrap();
-                            assert!(no_task.is_none());
-                            // send the task to grpc
-                            grpc_endpoint.send(msg).unwrap();
+                        GatewayMsg::Riscv(RiscvMsg::Request(ref req), _, _) => {
+                            let chunk_index = req.chunk_index;
+                            let restore = resumed
+                                .as_mut()
+                                .map_or(Restore::Prove, |resumed| resumed.restore(chunk_index));
+                            match restore {
+                                Restore::Prove => {
+                                    let no_task = gateway_handler.process_riscv_req(&msg).unwrap();
+                                    assert!(no_task.is_none());
+                                    // send the task to grpc
+                                    grpc_endpoint.send(msg).unwrap();
+                                }
+                                Restore::Skip => (),
+                                Restore::Proof(proof) => {
+                                    info!(
+                                        "[gateway] restored proof of chunks {}-{}",
+                                        proof.start_chunk, proof.end_chunk
+                                    );
+                                    proved_chunks += proof.end_chunk - proof.start_chunk + 1;
+                                    let _ = progress.send(ProvingProgress::ChunkProved { proved_chunks });
+                                    gateway_handler.process_riscv_req(&msg).unwrap();
+                                    let res = RiscvResponse::new(chunk_index, proof);
+                                    let res = GatewayMsg::Riscv(RiscvMsg::Response(res), chunk_index.to_string(), "".to_string());
+                                    // send the combine task to grpc if any
+                                    if let Some(msg) = gateway_handler.process(res).unwrap() {
+                                        grpc_endpoint.send(msg).unwrap();
+                                    }
+                                }
+                            }
                         }
                         GatewayMsg::EmulatorComplete => {
-                            if let Some(exit_msg) = gateway_handler.process(msg.clone()).unwrap() {
-                                if matches!(exit_msg, GatewayMsg::Exit) {
-                                    break; // Exit the gateway loop
-                                }
+                            gateway_handler.process(msg.clone()).unwrap();
+                            // all the chunks are skipped if resumed from the embed proof
+                            if let Some(embed_proof) = resumed.as_mut().and_then(Resumed::take_embed) {
+                                info!("[gateway] restored embed proof");
+                                let _ = progress.send(ProvingProgress::EmbedProved);
+                                gateway_handler.process(GatewayMsg::Embed(embed_proof)).unwrap();
+                                send_onchain_proof(&gateway_handler, &mut completion_sender, &timer);
+                                break; // Exit the gateway loop
                             }
                         }
                         _ => panic!("unsupported"),
@@ -55,40 +125,43 @@ pub fn run(
                         Ok(msg) => msg,
                         Err(_) => break, // Channel closed, exit gracefully
                     };
+                    // report the proving progress, no error if there's no subscriber
+                    let _ = match &msg {
+                        GatewayMsg::Riscv(RiscvMsg::Response(res), _, _) => {
+                            save_proof(&res.proof);
+                            proved_chunks += 1;
+                            progress.send(ProvingProgress::ChunkProved { proved_chunks })
+                        }
+                        GatewayMsg::Combine(CombineMsg::Response(res), _, _) => {
+                            save_proof(&res.proof);
+                            combined_proofs += 1;
+                            progress.send(ProvingProgress::CombineProved { combined_proofs })
+                        }
+                        GatewayMsg::Embed(proof) => {
+                            metrics().observe_phase(Phase::Proving, proving_start.elapsed());
+                            if let Some(Err(e)) = checkpoint.as_ref().map(|c| c.save_embed(proof)) {
+                                warn!("[gateway] failed to checkpoint embed proof: {e}");
+                            }
+                            progress.send(ProvingProgress::EmbedProved)
+                        }
+                        GatewayMsg::Stark(_) => {
+                            metrics().observe_phase(Phase::Proving, proving_start.elapsed());
+                            Ok(0)
+                        }
+                        _ => Ok(0),
+                    };
                     match msg {
                         GatewayMsg::Riscv(RiscvMsg::Response(..), _, _)
                         | GatewayMsg::Combine(CombineMsg::Response(..), _, _)
                         | GatewayMsg::Embed(..)
+                        | GatewayMsg::Stark(..)
                         | GatewayMsg::Exit => {
                             // save the generated proof to the chunk_index slot in proof tree
                             if let Some(msg) = gateway_handler.process(msg.clone()).unwrap() {
                                 match msg {
                                     GatewayMsg::Exit => {
                                         info!("[gateway] received Exit message, proving complete");
-                                        // Proving is complete. Generate on-chain proof and send via callback
-                                        if let Some(embed_proof) = gateway_handler.get_embed_proof() {
-                                            // Run on-chain dockerized phase to obtain final proof bytes
-                                            let proof_bytes = match prove_embed_onchain(embed_proof) {
-                                                Ok(bytes) => bytes,
-                                                Err(e) => {
-                                                    error!("[gateway] on-chain proof generation failed: {}", e);
-                                                    vec![]
-                                                }
-                                            };
-                                            let proof_size = proof_bytes.len();
-                                            info!("[gateway] sending final on-chain proof via callback, size: {} bytes", proof_size);
-
-                                            // Send proof via completion signal
-                                            if let Some(sender) = completion_sender.take() {
-                                                let _ = sender.send(proof_bytes);
-                                            }
-                                        } else {
-                                            error!("[gateway] Exit received but no embed proof available");
-                                            // Send empty proof to avoid hanging
-                                            if let Some(sender) = completion_sender.take() {
-                                                let _ = sender.send(vec![]);
-                                            }
-                                        }
+                                        send_final_proof(&gateway_handler, &mut completion_sender, &timer);
                                         break; // Exit the gateway loop
                                     }
                                     _ => {
@@ -103,6 +176,8 @@ pub fn run(
                         _ => panic!("unsupported"),
                     }
                 }
+                // wake up periodically to check the cancellation
+                default(CANCEL_CHECK_INTERVAL) => (),
             }
         }
     });
@@ -112,4 +187,65 @@ pub fn run(
     thread_handle
 }
 
-// on-chain proof generation handled in Exit branch above
+// Proving is complete. Send the STARK proof of the core or compressed proof types, or generate the
+// on-chain proof of the embed proof
+fn send_final_proof(
+    gateway_handler: &GatewayHandler,
+    completion_sender: &mut Option<tokio::sync::oneshot::Sender<Vec<u8>>>,
+    timer: &PhaseTimer,
+) {
+    let Some(stark_proof) = gateway_handler.get_stark_proof() else {
+        send_onchain_proof(gateway_handler, completion_sender, timer);
+        return;
+    };
+
+    let proof_bytes = bincode::serialize(stark_proof).unwrap_or_else(|e| {
+        error!("[gateway] failed to serialize final STARK proof: {}", e);
+        vec![]
+    });
+    info!(
+        "[gateway] sending final STARK proof via callback, size: {} bytes",
+        proof_bytes.len()
+    );
+    if let Some(sender) = completion_sender.take() {
+        let _ = sender.send(proof_bytes);
+    }
+}
+
+// Generate on-chain proof and send via callback
+fn send_onchain_proof(
+    gateway_handler: &GatewayHandler,
+    completion_sender: &mut Option<tokio::sync::oneshot::Sender<Vec<u8>>>,
+    timer: &PhaseTimer,
+) {
+    if let Some(embed_proof) = gateway_handler.get_embed_proof() {
+        // Run on-chain dockerized phase to obtain final proof bytes
+        let start = Instant::now();
+        let proof_bytes = info_span!("onchain").in_scope(|| prove_embed_onchain(embed_proof));
+        metrics().observe_phase(Phase::Onchain, start.elapsed());
+        timer.add(ProvingPhase::Wrapping, start.elapsed());
+        let proof_bytes = match proof_bytes {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("[gateway] on-chain proof generation failed: {}", e);
+                vec![]
+            }
+        };
+        let proof_size = proof_bytes.len();
+        info!(
+            "[gateway] sending final on-chain proof via callback, size: {} bytes",
+            proof_size
+        );
+
+        // Send proof via completion signal
+        if let Some(sender) = completion_sender.take() {
+            let _ = sender.send(proof_bytes);
+        }
+    } else {
+        error!("[gateway] Exit received but no embed proof available");
+        // Send empty proof to avoid hanging
+        if let Some(sender) = completion_sender.take() {
+            let _ = sender.send(vec![]);
+        }
+    }
+}
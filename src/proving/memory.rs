@@ -0,0 +1,70 @@
+use crate::error::PicoError;
+use clap::Args;
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Debug, Args, Clone)]
+pub struct MemoryBudgetConfig {
+    #[clap(
+        long,
+        env = "MAX_TASK_MEMORY_MB",
+        help = "Memory budget (MiB) of a proving task estimated from the input size and cycles, unlimited if not set"
+    )]
+    pub max_task_memory_mb: Option<u64>,
+
+    #[clap(
+        long,
+        env = "MEMORY_BYTES_PER_CYCLE",
+        default_value = "32",
+        help = "Estimated memory (bytes) of a task per emulated cycle"
+    )]
+    pub memory_bytes_per_cycle: u64,
+
+    #[clap(
+        long,
+        env = "MEMORY_BYTES_PER_INPUT_BYTE",
+        default_value = "16",
+        help = "Estimated memory (bytes) of a task per byte of the inputs"
+    )]
+    pub memory_bytes_per_input_byte: u64,
+}
+
+impl MemoryBudgetConfig {
+    pub fn budget(&self) -> Option<MemoryBudget> {
+        self.max_task_memory_mb.map(|mb| MemoryBudget {
+            max_bytes: mb.saturating_mul(MIB),
+            bytes_per_cycle: self.memory_bytes_per_cycle,
+            bytes_per_input_byte: self.memory_bytes_per_input_byte,
+        })
+    }
+}
+
+// memory budget of a task, the usage is estimated by the input size and the emulated cycles, and
+// the task is failed once the estimate exceeds the budget instead of crashing the whole process
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryBudget {
+    max_bytes: u64,
+    bytes_per_cycle: u64,
+    bytes_per_input_byte: u64,
+}
+
+impl MemoryBudget {
+    pub fn estimate(&self, inputs_size: usize, cycles: u64) -> u64 {
+        (inputs_size as u64)
+            .saturating_mul(self.bytes_per_input_byte)
+            .saturating_add(cycles.saturating_mul(self.bytes_per_cycle))
+    }
+
+    pub fn check(&self, inputs_size: usize, cycles: u64) -> Result<(), PicoError> {
+        let estimate = self.estimate(inputs_size, cycles);
+        if estimate > self.max_bytes {
+            return Err(PicoError::ResourceExhausted(format!(
+                "estimated task memory of {} MiB at {cycles} cycles exceeded the budget of {} MiB",
+                estimate.div_ceil(MIB),
+                self.max_bytes / MIB
+            )));
+        }
+
+        Ok(())
+    }
+}
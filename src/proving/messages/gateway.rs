@@ -1,6 +1,6 @@
 use crate::{
     proving::messages::{combine::CombineMsg, riscv::RiscvMsg},
-    types::EmbedSC,
+    types::{EmbedSC, SC},
 };
 use pico_vm::machine::proof::MetaProof;
 
@@ -21,6 +21,8 @@ pub enum GatewayMsg {
     Combine(CombineMsg, TaskId, IpAddr),
     // embed proof from direct execution
     Embed(MetaProof<EmbedSC>),
+    // final STARK proof of the core or compressed proof types, it's not wrapped on-chain
+    Stark(MetaProof<SC>),
     // close a client by ip
     Close(IpAddr),
     // exit
@@ -30,7 +32,11 @@ pub enum GatewayMsg {
 impl GatewayMsg {
     pub fn ip_addr(&self) -> IpAddr {
         match self {
-            Self::EmulatorComplete | Self::RequestTask | Self::Exit | Self::Embed(_) => "",
+            Self::EmulatorComplete
+            | Self::RequestTask
+            | Self::Exit
+            | Self::Embed(_)
+            | Self::Stark(_) => "",
             Self::Riscv(_, _, ip_addr) => ip_addr,
             Self::Combine(_, _, ip_addr) => ip_addr,
             Self::Close(ip_addr) => ip_addr,
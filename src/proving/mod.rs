@@ -1,23 +1,116 @@
 use crate::{
-    proving::worker::prover::{Prover, ProverRunner},
+    PhaseTimings,
+    artifact_store::SharedArtifactStore,
+    cost_estimation::estimate_cost,
+    error::PicoError,
+    metrics::{Phase, metrics},
+    proving::{
+        checkpoint::{Checkpoint, Resumed},
+        memory::MemoryBudget,
+        timing::{PhaseTimer, ProvingPhase},
+        worker::prover::{Prover, ProverRunner},
+    },
     proving_queue::ProvingTask,
 };
-use anyhow::Result;
+use alloy_primitives::U256;
+use anyhow::{Result, bail};
 use futures::future::join_all;
 use pico_vm::thread::channel::{DuplexUnboundedChannel, SingleUnboundedChannel};
-use tracing::info;
+use std::sync::Arc;
+use tokio::{sync::broadcast, time::Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, info_span, warn};
 
+pub mod backend;
+pub(crate) mod checkpoint;
 mod emulator;
 pub mod gateway;
+pub mod memory;
 pub mod messages;
 pub mod onchain;
+pub mod onchain_verifier;
+pub mod timing;
 pub mod worker;
 
-pub async fn prove_task(task: ProvingTask, prover_count: usize) -> Result<Vec<u8>> {
+#[derive(Clone, Debug)]
+pub enum ProvingProgress {
+    // the task is popped from the queue and emulation starts
+    Started,
+    // emulation is complete and the total number of chunks is known
+    EmulationComplete { total_chunks: usize },
+    // the riscv-convert proofs generated so far
+    ChunkProved { proved_chunks: usize },
+    // the combine proofs generated so far
+    CombineProved { combined_proofs: usize },
+    // the final embed proof is generated, on-chain proving remains
+    EmbedProved,
+    // the task is completed, failed or cancelled
+    Finished,
+}
+
+pub type ProgressSender = broadcast::Sender<ProvingProgress>;
+
+pub struct ProvedInfo {
+    pub proof: Vec<u8>,
+    pub pv_digest: U256,
+    pub total_cycles: u64,
+    // chunks emulated and proved, including the ones resumed from the checkpoint
+    pub total_chunks: u64,
+    // wall time of proving multiplied by the assigned GPU devices, 0 if proved by CPU
+    pub gpu_seconds: f64,
+    // wall time of emulating and proving
+    pub proving_seconds: f64,
+    pub timings: PhaseTimings,
+}
+
+pub async fn prove_task(
+    mut task: ProvingTask,
+    prover_count: usize,
+    // the assigned CUDA devices to prove by GPU, or prove by CPU if empty
+    gpu_devices: &[u32],
+    // store to checkpoint the proofs for resuming, no checkpoint if none
+    checkpoint_store: Option<&SharedArtifactStore>,
+    // memory budget of the task, unlimited if none
+    memory_budget: Option<MemoryBudget>,
+    // cancelled to preempt the task, the checkpoint is kept to resume it. it should be a child of
+    // the cancel token of the task
+    preempt_token: Option<CancellationToken>,
+    // called once the emulation is complete and only proving remains
+    on_emulated: impl FnOnce(),
+) -> Result<ProvedInfo> {
     info!("[proving] starting prove_task for: {:?}", task.key);
+    let start = Instant::now();
 
     // Create a completion signal with proof result
     let (completion_sender, completion_receiver) = tokio::sync::oneshot::channel();
+    // the pipeline is stopped by a child token if the task exceeds the memory budget or is
+    // preempted, so the task is failed or re-queued instead of cancelled
+    let task_token = task.cancel_token.clone();
+    let preempt_token = preempt_token.unwrap_or_else(|| task_token.child_token());
+    task.cancel_token = preempt_token.child_token();
+    let cancel_token = task.cancel_token.clone();
+    let timer = Arc::new(PhaseTimer::default());
+
+    // resume from the persisted proofs of the last attempt
+    let checkpoint = match checkpoint_store {
+        Some(store) => {
+            let checkpoint = Checkpoint::new(store.clone(), &task.key);
+            let resumed = checkpoint
+                .load(task.inputs.as_deref())
+                .await
+                .unwrap_or_else(|e| {
+                    warn!(
+                        "[proving] failed to load checkpoint of {:?}: {}",
+                        task.key, e
+                    );
+                    Resumed::default()
+                });
+            Some((checkpoint, resumed))
+        }
+        None => None,
+    };
+    let finished_checkpoint =
+        checkpoint_store.map(|store| Checkpoint::new(store.clone(), &task.key));
 
     let emulator_gateway_channel = SingleUnboundedChannel::default();
     let gateway_worker_channel = DuplexUnboundedChannel::default();
@@ -27,22 +120,37 @@ pub async fn prove_task(task: ProvingTask, prover_count: usize) -> Result<Vec<u8
         emulator_gateway_channel.receiver(),
         gateway_worker_channel.endpoint1(),
         completion_sender,
+        cancel_token.clone(),
+        task.progress.clone(),
+        checkpoint,
+        timer.clone(),
     );
 
-    // start provers
+    // start provers, the GPU provers are spread over the devices to prove the chunks in parallel,
+    // and the proofs are merged by the recursion provers in the same way
     let provers: Vec<_> = (0..prover_count)
         .enumerate()
         .map(|(i, _)| {
             let prover_id = format!("prover-{i}");
             let worker_endpoint = gateway_worker_channel.endpoint2().clone_inner();
 
-            if task.use_gpu {
-                info!("[proving] creating CUDA prover: {}", prover_id);
-                let prover = Prover::new_cuda(prover_id, worker_endpoint, task.clone());
+            if !gpu_devices.is_empty() {
+                let device = gpu_devices[i % gpu_devices.len()];
+                info!(
+                    "[proving] creating CUDA prover: {} on device {}",
+                    prover_id, device
+                );
+                let prover = Prover::new_cuda(
+                    prover_id,
+                    worker_endpoint,
+                    task.clone(),
+                    timer.clone(),
+                    device,
+                );
                 prover.run_cuda()
             } else {
                 info!("[proving] creating CPU prover: {}", prover_id);
-                let prover = Prover::new(prover_id, worker_endpoint, task.clone());
+                let prover = Prover::new(prover_id, worker_endpoint, task.clone(), timer.clone());
                 prover.run()
             }
         })
@@ -50,13 +158,31 @@ pub async fn prove_task(task: ProvingTask, prover_count: usize) -> Result<Vec<u8
 
     // start emulator
     // We no longer need an emulator channel and sending start message
-    emulator::run(task, emulator_gateway_channel.sender());
+    let emulation_start = Instant::now();
+    let emulated_info = info_span!("emulation")
+        .in_scope(|| emulator::run(task, emulator_gateway_channel.sender(), memory_budget))?;
+    metrics().observe_phase(Phase::Emulation, emulation_start.elapsed());
+    timer.add(ProvingPhase::Emulation, emulation_start.elapsed());
+    on_emulated();
 
     // Wait for proving to complete
     info!("[proving] waiting for proving to complete");
 
     // Wait for completion signal from gateway and get the proof
-    let proof_bytes = completion_receiver.await?;
+    let proof_bytes = match completion_receiver.await {
+        Ok(proof_bytes) => proof_bytes,
+        // the gateway drops the completion sender if the task is cancelled
+        Err(_) if task_token.is_cancelled() => {
+            clear_checkpoint(finished_checkpoint).await;
+            bail!("proving task cancelled")
+        }
+        Err(_) if preempt_token.is_cancelled() => bail!("proving task preempted"),
+        Err(e) => return Err(e.into()),
+    };
+    // keep the checkpoint to retry the on-chain proving if it failed
+    if !proof_bytes.is_empty() {
+        clear_checkpoint(finished_checkpoint).await;
+    }
     info!("[proving] received completion signal from gateway with proof");
 
     // Wait for all handles to complete (with timeout to avoid hanging)
@@ -74,5 +200,45 @@ pub async fn prove_task(task: ProvingTask, prover_count: usize) -> Result<Vec<u8
     }
 
     info!("[proving] proving workflow completed successfully");
-    Ok(proof_bytes)
+    let elapsed = start.elapsed();
+    let proving_seconds = elapsed.as_secs_f64();
+    Ok(ProvedInfo {
+        proof: proof_bytes,
+        pv_digest: emulated_info.pv_digest,
+        total_cycles: emulated_info.total_cycles,
+        total_chunks: emulated_info.total_chunks,
+        gpu_seconds: proving_seconds * gpu_devices.len() as f64,
+        proving_seconds,
+        timings: timer.timings(elapsed),
+    })
+}
+
+// emulate the task and compare its public values digest before proving, so the task with wrong
+// inputs is failed without spending the proving time
+pub async fn check_pv_digest(task: &ProvingTask, expected: U256) -> Result<()> {
+    let program = task.program.clone();
+    let (pk, vk) = ((*task.pk).clone(), (*task.vk).clone());
+    let inputs = task.inputs.clone();
+    let info = tokio::task::spawn_blocking(move || {
+        estimate_cost(program, pk, vk, inputs.as_deref(), None, false)
+    })
+    .await??;
+    if info.pv_digest != expected {
+        return Err(PicoError::PvDigestMismatch(format!(
+            "public values digest mismatch: expected {expected:#x}, emulated {:#x}",
+            info.pv_digest
+        ))
+        .into());
+    }
+    info!("[proving] public values digest of {:?} matched", task.key);
+
+    Ok(())
+}
+
+async fn clear_checkpoint(checkpoint: Option<Checkpoint>) {
+    if let Some(checkpoint) = checkpoint {
+        if let Err(e) = checkpoint.clear().await {
+            warn!("[proving] failed to clear checkpoint: {}", e);
+        }
+    }
 }
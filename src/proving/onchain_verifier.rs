@@ -0,0 +1,166 @@
+use alloy_primitives::{U256, keccak256};
+use anyhow::{Result, anyhow, ensure};
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, G1, G2, Group, Gt, pairing_batch};
+
+// verifying key of the gnark groth16 circuit, it's the same as `fixtures/Groth16Verifier.sol`
+const ALPHA: [&str; 2] = [
+    "2387709920592184083440184305498248923596928447823501187834217907152206095815",
+    "16686835283464898692711987473057532756821822450477229260603483205883234808568",
+];
+// G2 points are in order of X_0, X_1, Y_0, Y_1
+const BETA_NEG: [&str; 4] = [
+    "7494559153497325892045627170966046336166672325896621750350558282396932644674",
+    "19460410880324300906068073396466272299182785508436074329672385121652777787174",
+    "14577117304217101835514219113496688541393521412607681298002371304801323513546",
+    "15387827228489212131083561577128866348537587305481193946043600818376163115486",
+];
+const GAMMA_NEG: [&str; 4] = [
+    "14512406860132349939823521203747839065265857227818496771070772556055588527931",
+    "4966308327417805485464086277584946103432506040208007145149616060952408691251",
+    "3241757543565136230626773806796689085518185448342070109220598213528121292162",
+    "5962817364414905811318381373037653103667126665806354554331187139689300328134",
+];
+const DELTA_NEG: [&str; 4] = [
+    "21217336168531776567005224004724604559195743901519586247848595992077568080799",
+    "15879362912550509366627867945434114595552423953830847398364481836128969335054",
+    "11540609877356913424862790636753914054989469465975963075543318386208461042820",
+    "12459331055386951964144762907628279845298091869204047684751637003338077748385",
+];
+const CONSTANT: [&str; 2] = [
+    "10372641589178417033734188891882358682388878447230138700714530082853510756831",
+    "766489151683426586479684031461755325218003430206782683325502978842018132736",
+];
+const PUB_0: [&str; 2] = [
+    "1557248352606326426810769133632832035056769273981423293422574201084031233063",
+    "19250353664327455202179678437083287022061320007408601850916845369348511692598",
+];
+const PUB_1: [&str; 2] = [
+    "3866107190902558060538340110776331123449029945227315439679708502368968517238",
+    "5609415587610033580100911761576564983021031792697121293864770546937755355575",
+];
+
+// groth16 proof points (A, B, C) in EIP-197 format
+const PROOF_SIZE: usize = 256;
+// proof points followed by the public inputs, as generated by this service
+const PROOF_WITH_INPUTS_SIZE: usize = 320;
+/// Function of `fixtures/Groth16Verifier.sol` to verify the uncompressed proof.
+pub const VERIFY_PROOF_SIGNATURE: &str = "verifyProof(uint256[8],uint256[2])";
+
+/// Solidity source of the groth16 verifier contract shared by all the applications.
+pub const VERIFIER_SOURCE: &str = include_str!("../../fixtures/Groth16Verifier.sol");
+
+/// Parse the riscv vk hash of the app ID in hex.
+pub fn parse_vk_hash(app_id: &str) -> Result<U256> {
+    U256::from_str_radix(app_id.strip_prefix("0x").unwrap_or(app_id), 16)
+        .map_err(|e| anyhow!("invalid app id {app_id}: {e}"))
+}
+
+/// Encode the calldata of `verifyProof` of `fixtures/Groth16Verifier.sol`, i.e. the selector
+/// followed by the proof points and the public inputs (vk hash, public values digest).
+pub fn onchain_calldata(proof: &[u8], vk_hash: U256, pv_digest: U256) -> Result<Vec<u8>> {
+    ensure!(
+        proof.len() == PROOF_SIZE || proof.len() == PROOF_WITH_INPUTS_SIZE,
+        "proof must be {PROOF_SIZE} or {PROOF_WITH_INPUTS_SIZE} bytes, but got {}",
+        proof.len(),
+    );
+    let mut calldata = Vec::with_capacity(4 + PROOF_WITH_INPUTS_SIZE);
+    calldata.extend_from_slice(&keccak256(VERIFY_PROOF_SIGNATURE)[..4]);
+    calldata.extend_from_slice(&proof[..PROOF_SIZE]);
+    calldata.extend_from_slice(&vk_hash.to_be_bytes::<32>());
+    calldata.extend_from_slice(&pv_digest.to_be_bytes::<32>());
+
+    Ok(calldata)
+}
+
+/// Verify the on-chain groth16 proof against the riscv vk hash (app ID) and public values digest,
+/// it follows `verifyProof` of `fixtures/Groth16Verifier.sol`.
+pub fn verify_onchain_proof(proof: &[u8], vk_hash: U256, pv_digest: U256) -> Result<()> {
+    ensure!(
+        proof.len() == PROOF_SIZE || proof.len() == PROOF_WITH_INPUTS_SIZE,
+        "proof must be {PROOF_SIZE} or {PROOF_WITH_INPUTS_SIZE} bytes, but got {}",
+        proof.len(),
+    );
+    let words: Vec<_> = proof.chunks(32).map(U256::from_be_slice).collect();
+
+    // the public inputs attached to the proof must be the claimed ones
+    if words.len() == PROOF_WITH_INPUTS_SIZE / 32 {
+        ensure!(
+            words[8] == vk_hash,
+            "vk hash in proof mismatches the claimed one"
+        );
+        ensure!(
+            words[9] == pv_digest,
+            "public values digest in proof mismatches the claimed one"
+        );
+    }
+
+    let a = g1(words[0], words[1])?;
+    // EIP-197 encodes the F2 coefficients in big-endian order
+    let b = g2(words[3], words[2], words[5], words[4])?;
+    let c = g1(words[6], words[7])?;
+
+    // public input linear combination
+    let l = constant_g1(&CONSTANT)?
+        + constant_g1(&PUB_0)? * fr(vk_hash)?
+        + constant_g1(&PUB_1)? * fr(pv_digest)?;
+
+    let pairs = [
+        (a, b),
+        (c, constant_g2(&DELTA_NEG)?),
+        (constant_g1(&ALPHA)?, constant_g2(&BETA_NEG)?),
+        (l, constant_g2(&GAMMA_NEG)?),
+    ];
+    ensure!(pairing_batch(&pairs) == Gt::one(), "pairing check failed");
+
+    Ok(())
+}
+
+fn fq(value: U256) -> Result<Fq> {
+    Fq::from_slice(&value.to_be_bytes::<32>())
+        .map_err(|e| anyhow!("invalid base field element {value}: {e:?}"))
+}
+
+fn fr(value: U256) -> Result<Fr> {
+    Fr::from_slice(&value.to_be_bytes::<32>())
+        .map_err(|e| anyhow!("public input {value} is not in scalar field: {e:?}"))
+}
+
+fn g1(x: U256, y: U256) -> Result<G1> {
+    // (0, 0) is the point at infinity
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+
+    AffineG1::new(fq(x)?, fq(y)?)
+        .map(Into::into)
+        .map_err(|e| anyhow!("invalid G1 point: {e:?}"))
+}
+
+fn g2(x0: U256, x1: U256, y0: U256, y1: U256) -> Result<G2> {
+    let x = Fq2::new(fq(x0)?, fq(x1)?);
+    let y = Fq2::new(fq(y0)?, fq(y1)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+
+    AffineG2::new(x, y)
+        .map(Into::into)
+        .map_err(|e| anyhow!("invalid G2 point: {e:?}"))
+}
+
+fn constant(value: &str) -> Result<U256> {
+    U256::from_str_radix(value, 10).map_err(|e| anyhow!("invalid verifying key constant: {e}"))
+}
+
+fn constant_g1(point: &[&str; 2]) -> Result<G1> {
+    g1(constant(point[0])?, constant(point[1])?)
+}
+
+fn constant_g2(point: &[&str; 4]) -> Result<G2> {
+    g2(
+        constant(point[0])?,
+        constant(point[1])?,
+        constant(point[2])?,
+        constant(point[3])?,
+    )
+}
@@ -0,0 +1,55 @@
+use crate::PhaseTimings;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+// phases of the timing breakdown of a task, the trace generation of a chunk is done by pico-vm in
+// proving the chunk, so it's counted as chunk proving
+#[derive(Clone, Copy, Debug)]
+pub enum ProvingPhase {
+    // emulation of the program with the inputs
+    Emulation,
+    // riscv proofs of the chunks including the trace generation
+    ChunkProving,
+    // convert, combine and compress proofs
+    Recursion,
+    // embed and on-chain proofs
+    Wrapping,
+}
+
+// time spent in the phases of a task, the phases run by the provers in parallel are summed over
+// the provers
+#[derive(Debug, Default)]
+pub struct PhaseTimer {
+    micros: [AtomicU64; 4],
+}
+
+impl PhaseTimer {
+    pub fn add(&self, phase: ProvingPhase, duration: Duration) {
+        self.micros[phase as usize].fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    // run the closure and count its time in the phase
+    pub fn time<T>(&self, phase: ProvingPhase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let res = f();
+        self.add(phase, start.elapsed());
+
+        res
+    }
+
+    pub fn timings(&self, total: Duration) -> PhaseTimings {
+        let secs = |phase: ProvingPhase| {
+            self.micros[phase as usize].load(Ordering::Relaxed) as f64 / 1_000_000.0
+        };
+
+        PhaseTimings {
+            emulation_secs: secs(ProvingPhase::Emulation),
+            chunk_proving_secs: secs(ProvingPhase::ChunkProving),
+            recursion_secs: secs(ProvingPhase::Recursion),
+            wrapping_secs: secs(ProvingPhase::Wrapping),
+            total_secs: total.as_secs_f64(),
+        }
+    }
+}
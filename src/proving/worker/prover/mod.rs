@@ -5,12 +5,20 @@ pub mod riscv_convert;
 
 use super::WorkerEndpoint;
 use crate::{
-    proving::messages::{
-        combine::CombineMsg, embed::EmbedRequest, gateway::GatewayMsg, riscv::RiscvMsg,
+    ProofType,
+    proving::{
+        messages::{
+            combine::{CombineMsg, CombineResponse},
+            embed::EmbedRequest,
+            gateway::GatewayMsg,
+            riscv::RiscvMsg,
+        },
+        timing::{PhaseTimer, ProvingPhase},
     },
     proving_queue::ProvingTask,
     types::{SC, Val},
 };
+use anyhow::Result;
 use combine::{CombineHandler, CombineProver};
 use compress::{CompressHandler, CompressProver};
 use embed::{EmbedHandler, EmbedProver};
@@ -22,7 +30,8 @@ use pico_vm::{
 use riscv_convert::{RiscvConvertHandler, RiscvConvertProver};
 use std::sync::Arc;
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{Span, error, info, info_span};
 
 type VkRoot = [Val; DIGEST_SIZE];
 
@@ -34,10 +43,24 @@ pub struct Prover {
     compress: CompressProver,
     embed: EmbedProver,
     vk_root: VkRoot,
+    // the final combine proof is compressed and embedded up to the proof type
+    proof_type: ProofType,
+    // time of the phases proved by this prover
+    timer: Arc<PhaseTimer>,
+    cancel_token: CancellationToken,
+    // span of the proving workflow creating the prover, the parent of the proof spans
+    span: Span,
 }
 
 impl Prover {
-    pub fn new(prover_id: String, endpoint: Arc<WorkerEndpoint>, task: ProvingTask) -> Self {
+    pub fn new(
+        prover_id: String,
+        endpoint: Arc<WorkerEndpoint>,
+        task: ProvingTask,
+        timer: Arc<PhaseTimer>,
+    ) -> Self {
+        let cancel_token = task.cancel_token.clone();
+        let proof_type = task.proof_type;
         let riscv_convert = RiscvConvertProver::new(prover_id.clone(), task);
         let combine = CombineProver::new(prover_id.clone());
         let compress = CompressProver::new(prover_id.clone());
@@ -54,6 +77,85 @@ impl Prover {
             compress,
             embed,
             vk_root,
+            proof_type,
+            timer,
+            cancel_token,
+            span: Span::current(),
+        }
+    }
+
+    // the final proof of the proof type, none if it fails the verification
+    fn final_proof(&self, res: &CombineResponse) -> Option<GatewayMsg> {
+        let riscv_vk = self.riscv_convert.riscv_vk();
+        if self.proof_type == ProofType::Core {
+            let verified = self.combine.verify(&res.proof.inner, riscv_vk);
+            return self.verified("core", verified, || {
+                GatewayMsg::Stark(res.proof.inner.as_ref().clone())
+            });
+        }
+
+        // Direct execution of compress and embed phases
+        info!(
+            "[{}] final combine complete, executing compress phase directly",
+            self.prover_id
+        );
+        let compress_res = info_span!("compress").in_scope(|| {
+            self.timer.time(ProvingPhase::Recursion, || {
+                self.compress.process(compress::CompressRequest {
+                    chunk_index: res.chunk_index,
+                    proof: res.proof.clone(),
+                })
+            })
+        });
+        if self.proof_type == ProofType::Compressed {
+            let verified = self.compress.verify(&compress_res.proof.inner, riscv_vk);
+            return self.verified("compressed", verified, || {
+                GatewayMsg::Stark(compress_res.proof.inner.as_ref().clone())
+            });
+        }
+
+        info!(
+            "[{}] compress complete, executing embed phase directly",
+            self.prover_id
+        );
+        let embed_res = info_span!("embed").in_scope(|| {
+            self.timer.time(ProvingPhase::Wrapping, || {
+                self.embed.process(EmbedRequest {
+                    chunk_index: compress_res.chunk_index,
+                    proof: compress_res.proof,
+                })
+            })
+        });
+
+        // Verify the final embed proof before sending
+        let verified = self.embed.verify(&embed_res.proof.inner, riscv_vk);
+        self.verified("embed", verified, || {
+            GatewayMsg::Embed(embed_res.proof.inner.as_ref().clone())
+        })
+    }
+
+    // the final proof is sent to the gateway only if verified
+    fn verified(
+        &self,
+        name: &str,
+        verified: Result<()>,
+        msg: impl FnOnce() -> GatewayMsg,
+    ) -> Option<GatewayMsg> {
+        match verified {
+            Ok(()) => {
+                info!(
+                    "[{}] succeeded to verify final {name} proof, sending it to gateway",
+                    self.prover_id
+                );
+                Some(msg())
+            }
+            Err(e) => {
+                error!(
+                    "[{}] failed to verify final {name} proof: {e}",
+                    self.prover_id
+                );
+                None
+            }
         }
     }
 
@@ -62,6 +164,8 @@ impl Prover {
         _prover_id: String,
         _endpoint: Arc<WorkerEndpoint>,
         _task: ProvingTask,
+        _timer: Arc<PhaseTimer>,
+        _device: u32,
     ) -> Self {
         unimplemented!()
     }
@@ -78,24 +182,36 @@ impl ProverRunner for Prover {
         info!("[{}] : start", self.prover_id);
 
         tokio::task::spawn_blocking(move || {
+            let _entered = self.span.clone().entered();
             // request for task first
             let msg = GatewayMsg::RequestTask;
             self.endpoint.send(msg).unwrap();
 
             while let Ok(msg) = self.endpoint.recv() {
+                if self.cancel_token.is_cancelled() {
+                    info!("[{}] proving task cancelled, exit", self.prover_id);
+                    break;
+                }
+
                 match msg {
                     GatewayMsg::Riscv(RiscvMsg::Request(req), task_id, ip_addr) => {
                         info!(
                             "[{}] receive riscv request of chunk-{}",
                             self.prover_id, &req.chunk_index,
                         );
-                        let res = self.riscv_convert.process(req, &self.vk_root);
+                        let res =
+                            info_span!("riscv_chunk", chunk = req.chunk_index).in_scope(|| {
+                                self.riscv_convert.process(req, &self.vk_root, &self.timer)
+                            });
                         info!(
                             "[{}] send riscv response of chunk-{}",
                             self.prover_id, &res.chunk_index,
                         );
                         let msg = GatewayMsg::Riscv(RiscvMsg::Response(res), task_id, ip_addr);
-                        self.endpoint.send(msg).unwrap();
+                        if self.endpoint.send(msg).is_err() {
+                            // gateway has exited, the task may have been cancelled
+                            break;
+                        }
                     }
                     GatewayMsg::Combine(CombineMsg::Request(req), task_id, ip_addr) => {
                         info!(
@@ -103,49 +219,17 @@ impl ProverRunner for Prover {
                             self.prover_id, &req.chunk_index,
                         );
                         let flag_complete = req.flag_complete;
-                        let res = self.combine.process(req);
+                        let res = info_span!("combine", chunk = req.chunk_index).in_scope(|| {
+                            self.timer
+                                .time(ProvingPhase::Recursion, || self.combine.process(req))
+                        });
                         if flag_complete {
-                            // Direct execution of compress and embed phases
-                            info!(
-                                "[{}] final combine complete, executing compress phase directly",
-                                self.prover_id
-                            );
-                            let compress_res = self.compress.process(compress::CompressRequest {
-                                chunk_index: res.chunk_index,
-                                proof: res.proof.clone(),
-                            });
-
-                            info!(
-                                "[{}] compress complete, executing embed phase directly",
-                                self.prover_id
-                            );
-                            let embed_res = self.embed.process(EmbedRequest {
-                                chunk_index: compress_res.chunk_index,
-                                proof: compress_res.proof,
-                            });
-
-                            // Verify the final embed proof before sending
-                            if self
-                                .embed
-                                .verify(&embed_res.proof.inner, self.riscv_convert.riscv_vk())
-                                .is_ok()
-                            {
-                                info!("[{}] succeeded to verify final embed proof", self.prover_id,);
-
-                                // Send the embed proof directly to gateway
-                                info!(
-                                    "[{}] embed complete, sending embed proof to gateway",
-                                    self.prover_id
-                                );
-                                let embed_proof_msg =
-                                    GatewayMsg::Embed(embed_res.proof.inner.as_ref().clone());
-                                self.endpoint.send(embed_proof_msg).unwrap();
+                            if let Some(final_proof_msg) = self.final_proof(&res) {
+                                self.endpoint.send(final_proof_msg).unwrap();
 
                                 // Send Exit message to complete the workflow
                                 self.endpoint.send(GatewayMsg::Exit).unwrap();
                                 break; // Exit the worker loop
-                            } else {
-                                error!("[{}] failed to verify final embed proof", self.prover_id);
                             }
                         }
                         info!(
@@ -153,7 +237,10 @@ impl ProverRunner for Prover {
                             self.prover_id, &res.chunk_index,
                         );
                         let msg = GatewayMsg::Combine(CombineMsg::Response(res), task_id, ip_addr);
-                        self.endpoint.send(msg).unwrap();
+                        if self.endpoint.send(msg).is_err() {
+                            // gateway has exited, the task may have been cancelled
+                            break;
+                        }
                     }
                     // Compress and embed phases are now handled directly in the combine phase
                     // No separate message handling needed
@@ -163,7 +250,9 @@ impl ProverRunner for Prover {
 
                 // request for the next task
                 let msg = GatewayMsg::RequestTask;
-                self.endpoint.send(msg).unwrap();
+                if self.endpoint.send(msg).is_err() {
+                    break;
+                }
             }
         })
     }
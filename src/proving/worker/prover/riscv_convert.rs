@@ -3,6 +3,7 @@ use crate::{
     proving::{
         gateway::handler::proof_tree::IndexedProof,
         messages::riscv::{RiscvRequest, RiscvResponse},
+        timing::{PhaseTimer, ProvingPhase},
     },
     proving_queue::ProvingTask,
     types::{SC, Val},
@@ -97,11 +98,11 @@ impl RiscvConvertProver {
 
 /// specialization for running prover on either babybear or koalabear
 pub trait RiscvConvertHandler {
-    fn process(&self, req: RiscvRequest, vk_root: &VkRoot) -> RiscvResponse;
+    fn process(&self, req: RiscvRequest, vk_root: &VkRoot, timer: &PhaseTimer) -> RiscvResponse;
 }
 
 impl RiscvConvertHandler for RiscvConvertProver {
-    fn process(&self, req: RiscvRequest, vk_root: &VkRoot) -> RiscvResponse {
+    fn process(&self, req: RiscvRequest, vk_root: &VkRoot, timer: &PhaseTimer) -> RiscvResponse {
         log_section("RISCV PHASE");
 
         let mut challenger = self.riscv_machine.config().challenger().clone();
@@ -117,13 +118,15 @@ impl RiscvConvertHandler for RiscvConvertProver {
 
         let start = Instant::now();
 
-        let proof = self.riscv_machine.prove_record(
-            chunk_index,
-            &self.pk,
-            &challenger,
-            self.riscv_shape_config.as_ref(),
-            req.record,
-        );
+        let proof = timer.time(ProvingPhase::ChunkProving, || {
+            self.riscv_machine.prove_record(
+                chunk_index,
+                &self.pk,
+                &challenger,
+                self.riscv_shape_config.as_ref(),
+                req.record,
+            )
+        });
 
         info!("RISCV Phase complete! chunk_index: {}", chunk_index);
 
@@ -151,9 +154,10 @@ impl RiscvConvertHandler for RiscvConvertProver {
             recursion_opts,
         );
 
-        let proof = self
-            .convert_machine
-            .prove_with_index(chunk_index as u32, &convert_witness);
+        let proof = timer.time(ProvingPhase::Recursion, || {
+            self.convert_machine
+                .prove_with_index(chunk_index as u32, &convert_witness)
+        });
         let proof = IndexedProof::new(proof, chunk_index, chunk_index);
 
         info!(
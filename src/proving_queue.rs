@@ -1,26 +1,73 @@
 use crate::{
+    ErrCode, PhaseTimings, ProofType, TaskPriority, TaskState,
+    accounting::{self, UsageRecord},
+    artifact_store::{self, SharedArtifactStore},
     config::ServiceConfig,
-    proving,
-    types::{DbPool, SC},
+    coordinator::CoordinatorService,
+    cost_estimation::{self, CalibrationSample},
+    error::{PicoError, error_code, is_device_fault},
+    events,
+    gpu_pool::GpuPool,
+    metrics::metrics,
+    proof_metadata::{ProofProvenance, proof_envelope},
+    proving::{
+        self, ProgressSender, ProvedInfo, ProvingProgress,
+        backend::{ProveOptions, SharedProverBackend},
+        checkpoint::Checkpoint,
+        onchain_verifier::{onchain_calldata, parse_vk_hash},
+    },
+    retry::RetryPolicy,
+    scheduler::{Scheduler, lane_of},
+    shutdown::Shutdown,
+    storage::{SharedStorage, TaskStatusRow},
+    types::SC,
+    utils::{signing::RequestSigner, time::unix_timestamp},
+    webhook::{TaskCallback, WebhookNotifier},
 };
-use crossbeam::channel::Receiver;
+use alloy_primitives::U256;
+use anyhow::{Result, anyhow};
+use crossbeam::channel::{Receiver, Sender, select, unbounded};
 use dashmap::DashMap;
 use derive_more::Constructor;
 use pico_vm::{
     compiler::riscv::program::Program,
     machine::keys::{BaseProvingKey, BaseVerifyingKey},
 };
-use std::sync::Arc;
-use tokio::{task::JoinHandle, time::Instant};
-use tracing::{error, info};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::broadcast,
+    task::JoinHandle,
+    time::{Instant, sleep},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, Span, error, info, info_span, warn};
+
+// namespace of the requests without one, the data before the namespaces belongs to it
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+// reason of the tasks re-queued by the preemption
+pub(crate) const PREEMPTED_REASON: &str = "preempted by a higher-priority task";
 
 #[derive(Constructor, Debug, Eq, Hash, PartialEq, Clone)]
 pub struct ProvingKey {
+    // the tasks of the tenants are isolated by the namespaces
+    namespace: String,
     app_id: String,
     task_id: String,
 }
 
 impl ProvingKey {
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
     pub fn app_id(&self) -> &str {
         &self.app_id
     }
@@ -38,21 +85,219 @@ pub struct ProvingTask {
     pub vk: Arc<BaseVerifyingKey<SC>>,
     pub inputs: Option<Vec<u8>>,
     pub use_gpu: bool,
+    // the GPU task is proved on CPU if no GPU device is healthy
+    pub allow_cpu_fallback: bool,
+    pub priority: TaskPriority,
+    // unix timestamp (seconds) by which the task must complete, it's failed if not started by then
+    pub deadline: Option<u64>,
+    pub proof_type: ProofType,
+    // cancelled if the client cancels this task, it's checked by emulator, gateway and provers
+    pub cancel_token: CancellationToken,
+    // progress events of emulation and proving
+    pub progress: ProgressSender,
+    // span of the whole life of the task, the parent of the proving spans
+    pub span: Span,
 }
 
 #[derive(Constructor)]
 pub struct ProvingOutput {
     pub proof: Arc<[u8]>,
+    pub pv_digest: U256,
+    pub proof_type: ProofType,
+    pub provenance: ProofProvenance,
 }
 
 pub type ProvingOutputs = DashMap<ProvingKey, ProvingOutput>;
 
+#[derive(Clone, Debug)]
+pub struct TaskStatus {
+    pub state: TaskState,
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    // queued or re-queued for retrying at, for the queue wait time
+    pub queued_at: Instant,
+    pub finished_at: Option<u64>,
+    // failure reason if failed, or the last failure if retrying
+    pub failure_reason: Option<String>,
+    // proving attempts started so far
+    pub attempts: u32,
+    pub cancel_token: CancellationToken,
+    pub progress: ProgressSender,
+    // spans of the task and its waiting in the queue, they're closed once the task is finished
+    pub span: Span,
+    pub queue_span: Span,
+    // URL to post the result once completed or failed
+    pub callback_url: Option<String>,
+    // verify the proof before completing the task
+    pub verify_proof: bool,
+    pub proof_type: ProofType,
+    // the GPU task is proved on CPU if no GPU device is healthy
+    pub allow_cpu_fallback: bool,
+    // public values digest the task must produce, it's checked by emulation before proving
+    pub expected_pv_digest: Option<U256>,
+    // time spent in the proving phases once completed
+    pub timings: Option<PhaseTimings>,
+    // code of the structured failure if failed, e.g. exceeded the memory budget
+    pub failure_code: Option<ErrCode>,
+    // unix timestamp (seconds) before which the task is held in the queue
+    pub not_before: Option<u64>,
+    // unix timestamp (seconds) by which the task must complete
+    pub deadline: Option<u64>,
+    // size of the inputs, counted in the queued bytes while queued
+    pub inputs_size: usize,
+}
+
+// capacity of the progress channel, lagged subscribers only miss the intermediate events
+const PROGRESS_CHANNEL_CAPACITY: usize = 64;
+
+impl TaskStatus {
+    // create a status for a newly queued task
+    pub fn queued() -> Self {
+        Self {
+            state: TaskState::Queued,
+            created_at: unix_timestamp(),
+            started_at: None,
+            queued_at: Instant::now(),
+            finished_at: None,
+            failure_reason: None,
+            attempts: 0,
+            cancel_token: CancellationToken::new(),
+            progress: broadcast::channel(PROGRESS_CHANNEL_CAPACITY).0,
+            span: Span::none(),
+            queue_span: Span::none(),
+            callback_url: None,
+            verify_proof: false,
+            proof_type: ProofType::Evm,
+            allow_cpu_fallback: false,
+            expected_pv_digest: None,
+            timings: None,
+            failure_code: None,
+            not_before: None,
+            deadline: None,
+            inputs_size: 0,
+        }
+    }
+
+    // restore the status of a task untracked since a restart from the persisted one
+    pub fn restored(row: &TaskStatusRow) -> Self {
+        let mut status = Self {
+            state: row.state(),
+            created_at: row.created_at as u64,
+            started_at: row.started_at.map(|started_at| started_at as u64),
+            failure_reason: row.failure_reason.clone(),
+            attempts: row.attempts as u32,
+            timings: row.timings(),
+            not_before: row.not_before.map(|not_before| not_before as u64),
+            deadline: row.deadline.map(|deadline| deadline as u64),
+            ..Self::queued()
+        };
+        if status.is_finished() {
+            status.finished_at = Some(row.updated_at as u64);
+        }
+
+        status
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            TaskState::Completed | TaskState::Failed | TaskState::Cancelled
+        )
+    }
+}
+
+pub type TaskStatuses = DashMap<ProvingKey, TaskStatus>;
+
+// update the state of a tracked task, it's ignored if the task is not tracked or already finished
+pub fn update_task_state(statuses: &TaskStatuses, key: &ProvingKey, state: TaskState) {
+    set_task_state(statuses, key, state, None);
+}
+
+// mark a tracked task as failed with the reason
+pub fn fail_task(statuses: &TaskStatuses, key: &ProvingKey, reason: String) {
+    set_task_state(statuses, key, TaskState::Failed, Some(reason));
+}
+
+// mark a tracked task as queued again for retrying with the last failure reason
+pub fn requeue_task(statuses: &TaskStatuses, key: &ProvingKey, reason: String) {
+    set_task_state(statuses, key, TaskState::Queued, Some(reason));
+}
+
+fn set_task_state(
+    statuses: &TaskStatuses,
+    key: &ProvingKey,
+    state: TaskState,
+    failure_reason: Option<String>,
+) {
+    if let Some(mut status) = statuses.get_mut(key) {
+        if status.is_finished() {
+            return;
+        }
+        let was_active = is_active(status.state);
+        status.state = state;
+        status.failure_reason = failure_reason;
+
+        let metrics = metrics();
+        match (was_active, is_active(state)) {
+            (false, true) => metrics.tasks_active.inc(),
+            (true, false) => metrics.tasks_active.dec(),
+            _ => (),
+        }
+        let progress = match state {
+            TaskState::Executing => {
+                status.started_at = Some(unix_timestamp());
+                status.attempts += 1;
+                status.queue_span = Span::none();
+                metrics
+                    .queue_wait_seconds
+                    .observe(status.queued_at.elapsed().as_secs_f64());
+                Some(ProvingProgress::Started)
+            }
+            TaskState::Completed | TaskState::Failed | TaskState::Cancelled => {
+                status.finished_at = Some(unix_timestamp());
+                status.queue_span = Span::none();
+                status.span = Span::none();
+                match state {
+                    TaskState::Completed => metrics.tasks_completed.inc(),
+                    TaskState::Failed => metrics.tasks_failed.inc(),
+                    _ => metrics.tasks_cancelled.inc(),
+                }
+                Some(ProvingProgress::Finished)
+            }
+            TaskState::Queued => {
+                status.queued_at = Instant::now();
+                status.queue_span = info_span!(parent: &status.span, "queue");
+                None
+            }
+            TaskState::Proving => None,
+        };
+        if let Some(progress) = progress {
+            // no error if there's no subscriber
+            let _ = status.progress.send(progress);
+        }
+        events::task_state(key, state, status.failure_reason.as_deref());
+    }
+}
+
+fn is_active(state: TaskState) -> bool {
+    matches!(state, TaskState::Executing | TaskState::Proving)
+}
+
 #[derive(Constructor)]
 pub struct ProvingQueue {
     cfg: ServiceConfig,
     outputs: Arc<ProvingOutputs>,
+    statuses: Arc<TaskStatuses>,
     receiver: Arc<Receiver<ProvingTask>>,
-    db_pool: Arc<DbPool>,
+    // sender of the same queue for re-queueing the retried tasks
+    sender: Arc<Sender<ProvingTask>>,
+    storage: SharedStorage,
+    // true while the queue loop is running to dispatch tasks to the workers
+    workers_ready: Arc<AtomicBool>,
+    gpu_pool: Arc<GpuPool>,
+    artifacts: Option<SharedArtifactStore>,
+    // no queued task is started while draining, they're replayed after restart
+    shutdown: Shutdown,
 }
 
 impl ProvingQueue {
@@ -65,72 +310,88 @@ impl ProvingQueue {
 
         let cfg = self.cfg.clone();
         let receiver = self.receiver.clone();
-        let outputs = self.outputs.clone();
-        let db_pool = self.db_pool.clone();
+        let max_concurrent_tasks = cfg.max_concurrent_tasks.max(1);
+        let preemption = cfg.enable_preemption;
+        let mut scheduler = new_scheduler(&cfg);
+        let runner = self.task_runner();
+        let workers_ready = self.workers_ready.clone();
+        let shutdown = self.shutdown.clone();
 
         let handle = tokio::spawn(async move {
+            // no workers could be started without any prover
+            workers_ready.store(runner.cfg.prover_count > 0, Ordering::Release);
+            // keys of the finished tasks to release the running slots
+            let (done_sender, done_receiver) = unbounded::<ProvingKey>();
+            let mut running = HashMap::new();
             loop {
-                let task = match tokio::task::block_in_place(|| receiver.recv()) {
-                    Ok(task) => task,
-                    Err(_) => {
-                        info!("[proving-network] channel closed, exiting queue loop");
-                        break;
-                    }
-                };
-                let task_key = task.key.clone();
-                info!("[proving-network] starting proving task: {:?}", task_key);
-
-                // Run the real proving workflow with database pool
-                info!("[proving-network] calling prove_task for: {:?}", task_key);
-                let start = Instant::now();
-                let result = proving::prove_task(task, cfg.prover_count).await;
-                info!(
-                    "[proving-network] prove_task returned for {:?}, proving time : {}",
-                    task_key,
-                    start.elapsed().as_secs_f32(),
-                );
+                // move the newly submitted tasks to the scheduler
+                while let Ok(task) = receiver.try_recv() {
+                    scheduler.push(task);
+                }
+                while let Ok(key) = done_receiver.try_recv() {
+                    scheduler.finish(&key);
+                    running.remove(&key);
+                }
 
-                match result {
-                    Ok(proof_bytes) => {
-                        info!(
-                            "[proving-network] proving completed successfully for task: {:?}, proof size: {} bytes",
-                            task_key,
-                            proof_bytes.len()
-                        );
+                if preemption && scheduler.running() >= max_concurrent_tasks {
+                    preempt_lowest(&scheduler, &running);
+                }
 
-                        // Store proof in memory for quick access
-                        let proof_arc: Arc<[u8]> = Arc::from(proof_bytes);
-                        let output = ProvingOutput::new(proof_arc.clone());
-                        let _ = outputs.insert(task_key.clone(), output);
+                if !shutdown.is_draining() && scheduler.running() < max_concurrent_tasks {
+                    if let Some(task) = scheduler.pop() {
                         info!(
-                            "[proving-network] proof stored in memory for task: {:?}, total memory entries: {}",
-                            task_key,
-                            outputs.len()
+                            "[proving-network] scheduled {:?} task, {} tasks running, {} tasks remain queued",
+                            task.priority,
+                            scheduler.running(),
+                            scheduler.len()
+                        );
+                        let preempt_token = task.cancel_token.child_token();
+                        running.insert(
+                            task.key.clone(),
+                            RunningTask {
+                                priority: task.priority,
+                                started_at: Instant::now(),
+                                preempt_token: preempt_token.clone(),
+                            },
                         );
+                        let runner = runner.clone();
+                        let done_sender = done_sender.clone();
+                        tokio::spawn(async move {
+                            let key = task.key.clone();
+                            // the logs of the task carry the task and request spans
+                            let span = task.span.clone();
+                            runner.run_task(task, preempt_token).instrument(span).await;
+                            let _ = done_sender.send(key);
+                        });
+                        continue;
+                    }
+                }
 
-                        // Store proof in database
-                        if let Err(e) =
-                            Self::store_proof_in_db(&db_pool, &task_key, &proof_arc).await
-                        {
-                            error!(
-                                "[proving-network] failed to store proof in database for task {:?}: {}",
-                                task_key, e
-                            );
-                        } else {
-                            info!(
-                                "[proving-network] proof stored in database for task: {:?}",
-                                task_key
-                            );
+                // wait for a new task or a finished one
+                let closed = tokio::task::block_in_place(|| {
+                    select! {
+                        recv(receiver) -> task => match task {
+                            Ok(task) => {
+                                scheduler.push(task);
+                                false
+                            }
+                            Err(_) => true,
+                        },
+                        recv(done_receiver) -> key => {
+                            if let Ok(key) = key {
+                                scheduler.finish(&key);
+                                running.remove(&key);
+                            }
+                            false
                         }
                     }
-                    Err(e) => {
-                        error!(
-                            "[proving-network] failed to prove task {:?}: {}",
-                            task_key, e
-                        );
-                    }
+                });
+                if closed {
+                    info!("[proving-network] channel closed, exiting queue loop");
+                    break;
                 }
             }
+            workers_ready.store(false, Ordering::Release);
         });
 
         info!("[proving-network] proving queue init end");
@@ -138,17 +399,668 @@ impl ProvingQueue {
         handle
     }
 
-    async fn store_proof_in_db(
-        db_pool: &Arc<DbPool>,
-        key: &ProvingKey,
-        proof: &[u8],
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query("INSERT OR REPLACE INTO proofs (app_id, task_id, proof) VALUES (?, ?, ?)")
-            .bind(key.app_id())
-            .bind(key.task_id())
-            .bind(proof)
-            .execute(&**db_pool)
-            .await?;
+    // dispatch the tasks to the remote workers by the coordinator instead of proving locally
+    pub fn coordinator(&self) -> CoordinatorService {
+        CoordinatorService::new(
+            self.task_runner(),
+            self.receiver.clone(),
+            new_scheduler(&self.cfg),
+            self.storage.clone(),
+            self.workers_ready.clone(),
+            Duration::from_secs(self.cfg.worker_heartbeat_timeout_secs),
+            self.cfg.enable_preemption,
+            self.shutdown.clone(),
+        )
+    }
+
+    fn task_runner(&self) -> Arc<TaskRunner> {
+        Arc::new(TaskRunner {
+            cfg: self.cfg.clone(),
+            retry_policy: self.cfg.retry_policy(),
+            outputs: self.outputs.clone(),
+            statuses: self.statuses.clone(),
+            storage: self.storage.clone(),
+            sender: self.sender.clone(),
+            gpu_pool: self.gpu_pool.clone(),
+            artifacts: self.artifacts.clone(),
+            webhook: WebhookNotifier::new(&self.cfg.webhook),
+            // the key is checked by the config validation
+            proof_signer: self.cfg.proof_signer().ok().flatten().map(Arc::new),
+            prover_backend: self.cfg.prover.backend(),
+        })
+    }
+}
+
+// a task running locally, it's preempted by cancelling the token
+struct RunningTask {
+    priority: TaskPriority,
+    started_at: Instant,
+    preempt_token: CancellationToken,
+}
+
+// preempt the lowest-priority running task for a queued task of a higher priority, the latest
+// started one loses the least proving. only one task is preempted until its slot is released
+fn preempt_lowest(scheduler: &Scheduler, running: &HashMap<ProvingKey, RunningTask>) {
+    if running
+        .values()
+        .any(|task| task.preempt_token.is_cancelled())
+    {
+        return;
+    }
+    let lowest = running
+        .iter()
+        .filter(|(_, task)| scheduler.has_higher_priority(task.priority))
+        .max_by_key(|(_, task)| (lane_of(task.priority), task.started_at));
+    if let Some((key, task)) = lowest {
+        info!(
+            "[proving-network] preempting {:?} task {key:?} for a higher-priority task",
+            task.priority
+        );
+        task.preempt_token.cancel();
+    }
+}
+
+fn new_scheduler(cfg: &ServiceConfig) -> Scheduler {
+    Scheduler::new(
+        Duration::from_secs(cfg.priority_starvation_secs),
+        cfg.app_weights.iter().cloned().collect(),
+        cfg.app_max_concurrent_tasks,
+    )
+}
+
+// run the scheduled tasks, multiple tasks may run concurrently
+pub(crate) struct TaskRunner {
+    cfg: ServiceConfig,
+    retry_policy: RetryPolicy,
+    outputs: Arc<ProvingOutputs>,
+    statuses: Arc<TaskStatuses>,
+    storage: SharedStorage,
+    // sender of the queue for re-queueing the retried tasks
+    sender: Arc<Sender<ProvingTask>>,
+    gpu_pool: Arc<GpuPool>,
+    // store of the proofs, inputs and checkpoints, they're kept in the database if none
+    artifacts: Option<SharedArtifactStore>,
+    webhook: WebhookNotifier,
+    // key signing the completed proofs, they're not signed if none
+    proof_signer: Option<Arc<RequestSigner>>,
+    prover_backend: SharedProverBackend,
+}
+
+impl TaskRunner {
+    async fn run_task(&self, task: ProvingTask, preempt_token: CancellationToken) {
+        let cfg = &self.cfg;
+        let statuses = &self.statuses;
+        let storage = &self.storage;
+
+        let task_key = task.key.clone();
+        if task.cancel_token.is_cancelled() {
+            info!("[proving-network] skip cancelled task: {:?}", task_key);
+            return;
+        }
+        if self.fail_expired(&task).await {
+            return;
+        }
+        info!("[proving-network] starting proving task: {:?}", task_key);
+
+        // the task with an expected digest is emulated before waiting for the GPU devices, so the
+        // wrong inputs are failed cheaply
+        let mut started = None;
+        if let Some(expected) = self.expected_pv_digest(&task_key) {
+            let attempt = self.start_task(&task_key).await;
+            let span = info_span!(parent: &task.span, "check_pv_digest", attempt);
+            if let Err(e) = proving::check_pv_digest(&task, expected)
+                .instrument(span)
+                .await
+            {
+                self.complete_task(&task_key, attempt, None, Err(e)).await;
+                return;
+            }
+            started = Some(attempt);
+        }
+
+        // wait for the GPU devices in the queues of the assigned devices
+        let gpu_lease = if task.use_gpu {
+            let lease = tokio::select! {
+                lease = self.gpu_pool.acquire(cfg.gpu_devices_per_task) => lease,
+                _ = task.cancel_token.cancelled() => {
+                    info!("[proving-network] task cancelled while waiting for GPU: {:?}", task_key);
+                    return;
+                }
+            };
+            match lease {
+                Ok(lease) => {
+                    info!(
+                        "[proving-network] assigned GPU devices {:?} to task: {:?}",
+                        lease.devices(),
+                        task_key
+                    );
+                    Some(lease)
+                }
+                Err(e) if task.allow_cpu_fallback => {
+                    warn!(
+                        "[proving-network] proving task {:?} on CPU, failed to assign GPU devices: {e}",
+                        task_key
+                    );
+                    metrics().gpu_cpu_fallbacks.inc();
+                    None
+                }
+                Err(e) => {
+                    let failure = format!("failed to assign GPU devices: {e}");
+                    error!("[proving-network] {failure} for task {:?}", task_key);
+                    self.dead_letter(&task_key, &failure).await;
+                    persist_task_state(storage, &task_key, TaskState::Failed, Some(&failure)).await;
+                    self.notify(&task_key, TaskCallback::failed(&task_key, &failure));
+                    fail_task(statuses, &task_key, failure);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
+        // Run the real proving workflow with the storage
+        info!("[proving-network] calling prove_task for: {:?}", task_key);
+        let attempt = match started {
+            Some(attempt) => attempt,
+            None => self.start_task(&task_key).await,
+        };
+        // keep a copy for retrying or resuming after preempted, it's not required for the last
+        // attempt without preemption
+        let retry = (attempt < self.retry_policy.max_attempts || cfg.enable_preemption)
+            .then(|| task.clone());
+        let start = Instant::now();
+        let gpu_devices = gpu_lease.as_ref().map_or(&[][..], |lease| lease.devices());
+        let checkpoint_store = cfg.artifacts.checkpoint_store(self.artifacts.as_ref());
+        let span = info_span!(parent: &task.span, "prove", attempt);
+        let cancel_token = task.cancel_token.clone();
+        let opts = ProveOptions {
+            prover_count: cfg.prover_count,
+            gpu_devices,
+            checkpoint_store,
+            memory_budget: cfg.memory.budget(),
+            preempt_token: Some(preempt_token.clone()),
+        };
+        let result = self
+            .prover_backend
+            .prove(
+                task,
+                opts,
+                Box::new(|| update_task_state(statuses, &task_key, TaskState::Proving)),
+            )
+            .instrument(span)
+            .await;
+        // the failures of the devices are counted to quarantine the faulty ones, not the aborted
+        // attempts
+        if let Some(lease) = &gpu_lease {
+            match &result {
+                Ok(_) => self.gpu_pool.report_success(lease.devices()),
+                Err(e)
+                    if is_device_fault(e)
+                        && !preempt_token.is_cancelled()
+                        && !cancel_token.is_cancelled() =>
+                {
+                    self.gpu_pool
+                        .report_failure(lease.devices(), &e.to_string())
+                }
+                Err(_) => {}
+            }
+        }
+        // release the device before waiting for the retry backoff
+        drop(gpu_lease);
+        info!(
+            "[proving-network] prove_task returned for {:?}, proving time : {}",
+            task_key,
+            start.elapsed().as_secs_f32(),
+        );
+
+        match retry {
+            Some(task)
+                if result.is_err()
+                    && preempt_token.is_cancelled()
+                    && !task.cancel_token.is_cancelled() =>
+            {
+                self.preempt_task(task).await
+            }
+            retry => self.complete_task(&task_key, attempt, retry, result).await,
+        }
+    }
+
+    // fail the task reaching its deadline before started, it's not worth proving anymore
+    pub(crate) async fn fail_expired(&self, task: &ProvingTask) -> bool {
+        let Some(deadline) = task
+            .deadline
+            .filter(|deadline| *deadline <= unix_timestamp())
+        else {
+            return false;
+        };
+        let attempt = self
+            .statuses
+            .get(&task.key)
+            .map_or(1, |status| status.attempts);
+        let e = PicoError::DeadlineExceeded(format!(
+            "deadline {deadline} reached before the task started"
+        ));
+        self.complete_task(&task.key, attempt, None, Err(e.into()))
+            .await;
+
+        true
+    }
+
+    // mark the task as executing and return the attempt number
+    pub(crate) async fn start_task(&self, task_key: &ProvingKey) -> u32 {
+        update_task_state(&self.statuses, task_key, TaskState::Executing);
+        // proving state is not persisted since it's replayed the same as executing
+        persist_task_state(&self.storage, task_key, TaskState::Executing, None).await;
+
+        self.statuses
+            .get(task_key)
+            .map_or(1, |status| status.attempts)
+    }
+
+    // store the proof of the task, or retry or fail the task by the proving error
+    pub(crate) async fn complete_task(
+        &self,
+        task_key: &ProvingKey,
+        attempt: u32,
+        retry: Option<ProvingTask>,
+        result: Result<ProvedInfo>,
+    ) {
+        let retry_policy = &self.retry_policy;
+        let outputs = &self.outputs;
+        let statuses = &self.statuses;
+        let storage = &self.storage;
+
+        // a corrupted proof (e.g. by a flaky GPU) fails the attempt to be retried
+        let result = result.and_then(|info| {
+            self.verify_proof(task_key, &info)?;
+            self.check_pv_digest(task_key, &info)?;
+            Ok(info)
+        });
+        let failure_code = result.as_ref().err().and_then(error_code);
+        let failure = match result {
+            Ok(info) if info.proof.is_empty() => {
+                error!(
+                    "[proving-network] empty proof generated for task {:?}",
+                    task_key
+                );
+                "on-chain proof generation failed".to_string()
+            }
+            Ok(info) => {
+                info!(
+                    "[proving-network] proving completed successfully for task: {:?}, proof size: {} bytes",
+                    task_key,
+                    info.proof.len()
+                );
+
+                let usage = UsageRecord::new(task_key, &info, unix_timestamp());
+                let calibration = CalibrationSample::new(&info);
+                let proof_type = self.proof_type(task_key);
+                let provenance = self.proof_provenance(task_key, &info, proof_type).await;
+                // Store proof in memory for quick access
+                let proof_arc: Arc<[u8]> = Arc::from(info.proof);
+                let output = ProvingOutput::new(
+                    proof_arc.clone(),
+                    info.pv_digest,
+                    proof_type,
+                    provenance.clone(),
+                );
+                let _ = outputs.insert(task_key.clone(), output);
+                info!(
+                    "[proving-network] proof stored in memory for task: {:?}, total memory entries: {}",
+                    task_key,
+                    outputs.len()
+                );
+
+                // Store proof in database, only the metadata if it's offloaded to the artifact store
+                if let Err(e) = self
+                    .store_proof(
+                        task_key,
+                        &proof_arc,
+                        info.pv_digest,
+                        proof_type,
+                        &provenance,
+                    )
+                    .await
+                {
+                    error!(
+                        "[proving-network] failed to store proof in database for task {:?}: {}",
+                        task_key, e
+                    );
+                } else {
+                    info!(
+                        "[proving-network] proof stored in database for task: {:?}",
+                        task_key
+                    );
+                }
+                if let Err(e) = storage.store_task_timings(task_key, &info.timings).await {
+                    error!(
+                        "[proving-network] failed to persist timings of task {:?}: {}",
+                        task_key, e
+                    );
+                }
+                if let Some(mut status) = statuses.get_mut(task_key) {
+                    status.timings = Some(info.timings);
+                }
+                update_task_state(statuses, task_key, TaskState::Completed);
+                persist_task_state(storage, task_key, TaskState::Completed, None).await;
+                accounting::record_usage(storage, &usage).await;
+                if let Some(calibration) = &calibration {
+                    cost_estimation::calibrate(storage, calibration).await;
+                }
+                self.drop_inputs(task_key).await;
+                let proof_uri = self
+                    .artifacts
+                    .as_ref()
+                    .map(|store| store.uri(&artifact_store::proof_key(task_key)));
+                self.notify(
+                    task_key,
+                    TaskCallback::completed(task_key, proof_uri, info.pv_digest),
+                );
+                return;
+            }
+            Err(e) => {
+                error!(
+                    "[proving-network] failed to prove task {:?}: {}",
+                    task_key, e
+                );
+                e.to_string()
+            }
+        };
+
+        match retry {
+            // exceeding the memory budget and the digest mismatch are deterministic and the
+            // deadline is never extended, they're not retried
+            Some(task)
+                if !task.cancel_token.is_cancelled()
+                    && !matches!(
+                        failure_code,
+                        Some(
+                            ErrCode::ResourceExhausted
+                                | ErrCode::DeadlineExceeded
+                                | ErrCode::PvDigestMismatch
+                        )
+                    )
+                    && retry_policy.should_retry(attempt, &failure) =>
+            {
+                let backoff = retry_policy.backoff(attempt);
+                info!(
+                    "[proving-network] retrying task {:?} after {:?}, failed attempt {}/{}",
+                    task_key, backoff, attempt, retry_policy.max_attempts,
+                );
+                requeue_task(statuses, task_key, failure.clone());
+                persist_task_state(storage, task_key, TaskState::Queued, Some(&failure)).await;
+                retry_task(task, self.sender.clone(), backoff);
+            }
+            _ => {
+                self.dead_letter(task_key, &failure).await;
+                persist_task_state(storage, task_key, TaskState::Failed, Some(&failure)).await;
+                self.notify(task_key, TaskCallback::failed(task_key, &failure));
+                fail_task(statuses, task_key, failure);
+                if let Some(mut status) = statuses.get_mut(task_key) {
+                    status.failure_code = failure_code;
+                }
+                self.drop_inputs(task_key).await;
+                // the checkpoint is only kept for retrying
+                self.clear_checkpoint(task_key).await;
+            }
+        }
+    }
+
+    pub(crate) async fn clear_checkpoint(&self, task_key: &ProvingKey) {
+        if let Some(store) = self.cfg.artifacts.checkpoint_store(self.artifacts.as_ref()) {
+            if let Err(e) = Checkpoint::new(store.clone(), task_key).clear().await {
+                warn!("[proving-network] failed to clear checkpoint of task {task_key:?}: {e}");
+            }
+        }
+    }
+
+    fn proof_type(&self, task_key: &ProvingKey) -> ProofType {
+        self.statuses
+            .get(task_key)
+            .map(|status| status.proof_type)
+            .unwrap_or_default()
+    }
+
+    // the hashes of the elf and the inputs are none if failed to load, and the signature is none if
+    // failed to sign, the proof is still stored
+    async fn proof_provenance(
+        &self,
+        task_key: &ProvingKey,
+        info: &ProvedInfo,
+        proof_type: ProofType,
+    ) -> ProofProvenance {
+        let elf_hash = match self
+            .storage
+            .get_app_meta(task_key.namespace(), task_key.app_id())
+            .await
+        {
+            Ok(meta) => meta.and_then(|meta| meta.elf_hash),
+            Err(e) => {
+                warn!("[proving-network] failed to get the elf hash of task {task_key:?}: {e}");
+                None
+            }
+        };
+        let inputs_hash = match self.storage.task_fingerprint(task_key).await {
+            Ok(fingerprint) => fingerprint.and_then(|(inputs_hash, _, _)| inputs_hash),
+            Err(e) => {
+                warn!("[proving-network] failed to get the inputs hash of task {task_key:?}: {e}");
+                None
+            }
+        };
+
+        let mut provenance = ProofProvenance::new(
+            elf_hash.filter(|elf_hash| !elf_hash.is_empty()),
+            inputs_hash,
+            info.proving_seconds,
+        );
+        if let Some(signer) = &self.proof_signer {
+            let envelope = proof_envelope(
+                task_key,
+                &info.proof,
+                info.pv_digest.to_be_bytes_vec(),
+                proof_type,
+                &provenance,
+            );
+            match signer.sign_proof(&envelope) {
+                Ok(signature) => provenance.signature = Some(signature),
+                Err(e) => warn!("[proving-network] failed to sign proof of task {task_key:?}: {e}"),
+            }
+        }
+
+        provenance
+    }
+
+    pub(crate) fn expected_pv_digest(&self, task_key: &ProvingKey) -> Option<U256> {
+        self.statuses
+            .get(task_key)
+            .and_then(|status| status.expected_pv_digest)
+    }
+
+    // the digest of the proof is checked as well, e.g. the proof reported by a remote worker
+    fn check_pv_digest(&self, task_key: &ProvingKey, info: &ProvedInfo) -> Result<()> {
+        match self.expected_pv_digest(task_key) {
+            Some(expected) if expected != info.pv_digest => {
+                Err(PicoError::PvDigestMismatch(format!(
+                    "public values digest mismatch: expected {expected:#x}, proved {:#x}",
+                    info.pv_digest
+                ))
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // verify the proof if required by the app or the task by the backend, the STARK proofs are
+    // verified by the pico_vm provers and the empty proof is failed by the caller
+    fn verify_proof(&self, task_key: &ProvingKey, info: &ProvedInfo) -> Result<()> {
+        // the configured apps and the task may have the prefix `0x` or not
+        let app_id = task_key.app_id();
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+        let required = self
+            .cfg
+            .verify_proof_apps
+            .iter()
+            .any(|id| id.strip_prefix("0x").unwrap_or(id) == app_id)
+            || self
+                .statuses
+                .get(task_key)
+                .is_some_and(|status| status.verify_proof);
+        if !required || info.proof.is_empty() {
+            return Ok(());
+        }
+
+        let proof_type = self.proof_type(task_key);
+        parse_vk_hash(app_id)
+            .and_then(|vk_hash| {
+                self.prover_backend
+                    .verify(&info.proof, proof_type, vk_hash, None, info.pv_digest)
+            })
+            .map_err(|e| anyhow!("proof verification failed: {e}"))?;
+        info!("[proving-network] verified proof of task {task_key:?}");
+
         Ok(())
     }
+
+    async fn store_proof(
+        &self,
+        task_key: &ProvingKey,
+        proof: &[u8],
+        pv_digest: U256,
+        proof_type: ProofType,
+        provenance: &ProofProvenance,
+    ) -> Result<()> {
+        match &self.artifacts {
+            Some(store) => {
+                store
+                    .put(&artifact_store::proof_key(task_key), proof)
+                    .await?;
+                // the calldata is kept next to the EVM proof to be submitted on-chain directly
+                if proof_type == ProofType::Evm {
+                    let calldata = parse_vk_hash(task_key.app_id())
+                        .and_then(|vk_hash| onchain_calldata(proof, vk_hash, pv_digest))?;
+                    store
+                        .put(&artifact_store::calldata_key(task_key), &calldata)
+                        .await?;
+                }
+                self.storage
+                    .store_proof(task_key, &[], pv_digest, proof_type, provenance)
+                    .await
+            }
+            None => {
+                self.storage
+                    .store_proof(task_key, proof, pv_digest, proof_type, provenance)
+                    .await
+            }
+        }
+    }
+
+    // the inputs in the artifact store are dropped once the task is finished, the same as the
+    // database
+    async fn drop_inputs(&self, task_key: &ProvingKey) {
+        if let Some(store) = &self.artifacts {
+            if let Err(e) = store.delete(&artifact_store::inputs_key(task_key)).await {
+                warn!("[proving-network] failed to drop inputs of task {task_key:?}: {e}");
+            }
+        }
+    }
+
+    // keep the permanently failed task with its inputs to be requeued, the cancelled tasks are
+    // skipped since they're already finished
+    async fn dead_letter(&self, task_key: &ProvingKey, failure: &str) {
+        let attempts = match self.statuses.get(task_key) {
+            Some(status) if status.is_finished() => return,
+            Some(status) => status.attempts,
+            None => 0,
+        };
+        match self.store_dead_letter(task_key, failure, attempts).await {
+            Ok(()) => info!("[proving-network] task {task_key:?} is moved to the dead letters"),
+            Err(e) => {
+                error!("[proving-network] failed to store dead letter of task {task_key:?}: {e}")
+            }
+        }
+    }
+
+    async fn store_dead_letter(
+        &self,
+        task_key: &ProvingKey,
+        failure: &str,
+        attempts: u32,
+    ) -> Result<()> {
+        if let Some(store) = &self.artifacts {
+            if let Some(inputs) = store.get(&artifact_store::inputs_key(task_key)).await? {
+                store
+                    .put(&artifact_store::dead_letter_inputs_key(task_key), &inputs)
+                    .await?;
+            }
+        }
+
+        self.storage
+            .insert_dead_letter(task_key, failure, attempts, unix_timestamp())
+            .await
+    }
+
+    // post the result to the callback URL of the task if any. the cancelled tasks are not notified,
+    // their proving may still complete or fail once cancelled
+    fn notify(&self, task_key: &ProvingKey, callback: TaskCallback) {
+        let url = self
+            .statuses
+            .get(task_key)
+            .filter(|status| status.state != TaskState::Cancelled)
+            .and_then(|status| status.callback_url.clone());
+        if let Some(url) = url {
+            self.webhook.notify(url, callback);
+        }
+    }
+
+    // re-queue the interrupted task immediately without counting it as a failure
+    pub(crate) async fn reassign_task(&self, task: ProvingTask, reason: String) {
+        requeue_task(&self.statuses, &task.key, reason.clone());
+        persist_task_state(&self.storage, &task.key, TaskState::Queued, Some(&reason)).await;
+        retry_task(task, self.sender.clone(), Duration::ZERO);
+    }
+
+    // re-queue the preempted task to resume from its checkpoint, the attempt isn't counted
+    pub(crate) async fn preempt_task(&self, task: ProvingTask) {
+        info!(
+            "[proving-network] task {:?} preempted, re-queueing it",
+            task.key
+        );
+        if let Some(mut status) = self.statuses.get_mut(&task.key) {
+            status.attempts = status.attempts.saturating_sub(1);
+        }
+        self.reassign_task(task, PREEMPTED_REASON.to_string()).await;
+    }
+}
+
+// persist the task state, the failure is only logged since the proving result is kept in memory
+async fn persist_task_state(
+    storage: &SharedStorage,
+    key: &ProvingKey,
+    state: TaskState,
+    failure_reason: Option<&str>,
+) {
+    if let Err(e) = storage.update_task_state(key, state, failure_reason).await {
+        error!(
+            "[proving-network] failed to persist state {:?} of task {:?}: {}",
+            state, key, e
+        );
+    }
+}
+
+// re-queue the task after the backoff, it's dropped if cancelled meanwhile
+fn retry_task(task: ProvingTask, sender: Arc<Sender<ProvingTask>>, backoff: Duration) {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = sleep(backoff) => {}
+            _ = task.cancel_token.cancelled() => return,
+        }
+
+        let task_key = task.key.clone();
+        if let Err(e) = sender.send(task) {
+            error!(
+                "[proving-network] failed to re-queue task {:?}: {}",
+                task_key, e
+            );
+        }
+    });
 }
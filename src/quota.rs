@@ -0,0 +1,200 @@
+use crate::{QuotaUsage, utils::time::unix_timestamp};
+use clap::Args;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Args, Clone)]
+pub struct QuotaConfig {
+    #[clap(
+        long,
+        env = "QUOTA_DAILY_CYCLES",
+        help = "Cycles proved per UTC day of an app in a namespace, unlimited if not set"
+    )]
+    pub quota_daily_cycles: Option<u64>,
+
+    #[clap(
+        long,
+        env = "QUOTA_MAX_STORED_BYTES",
+        help = "Bytes of the stored proofs of an app in a namespace, unlimited if not set"
+    )]
+    pub quota_max_stored_bytes: Option<u64>,
+
+    #[clap(
+        long,
+        env = "QUOTA_MAX_CONCURRENT_TASKS",
+        help = "Queued and in-progress tasks of an app in a namespace, unlimited if not set"
+    )]
+    pub quota_max_concurrent_tasks: Option<u64>,
+
+    #[clap(
+        long,
+        env = "APP_QUOTAS",
+        value_delimiter = ',',
+        value_parser = parse_app_quota,
+        help = "Quotas of the apps as APP_ID:LIMIT=VALUE or NAMESPACE/APP_ID:LIMIT=VALUE separated by comma, overriding the QUOTA_* defaults. LIMIT is daily_cycles, stored_bytes or concurrent_tasks, and 0 is unlimited"
+    )]
+    pub app_quotas: Vec<AppQuota>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotaKind {
+    DailyCycles,
+    StoredBytes,
+    ConcurrentTasks,
+}
+
+impl QuotaKind {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "daily_cycles" => Some(QuotaKind::DailyCycles),
+            "stored_bytes" => Some(QuotaKind::StoredBytes),
+            "concurrent_tasks" => Some(QuotaKind::ConcurrentTasks),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QuotaKind::DailyCycles => "daily cycles",
+            QuotaKind::StoredBytes => "stored bytes",
+            QuotaKind::ConcurrentTasks => "concurrent tasks",
+        }
+    }
+}
+
+// quota override of an app in all the namespaces, or in one namespace if set
+#[derive(Clone, Debug)]
+pub struct AppQuota {
+    pub namespace: Option<String>,
+    pub app_id: String,
+    pub kind: QuotaKind,
+    pub limit: Option<u64>,
+}
+
+// limits of an app in a namespace, unlimited if none
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QuotaLimits {
+    pub daily_cycles: Option<u64>,
+    pub stored_bytes: Option<u64>,
+    pub concurrent_tasks: Option<u64>,
+}
+
+impl QuotaLimits {
+    fn set(&mut self, kind: QuotaKind, limit: Option<u64>) {
+        match kind {
+            QuotaKind::DailyCycles => self.daily_cycles = limit,
+            QuotaKind::StoredBytes => self.stored_bytes = limit,
+            QuotaKind::ConcurrentTasks => self.concurrent_tasks = limit,
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.daily_cycles.is_none()
+            && self.stored_bytes.is_none()
+            && self.concurrent_tasks.is_none()
+    }
+
+    // the first exhausted quota by the usage
+    pub fn exceeded(&self, usage: &QuotaUsed) -> Option<(QuotaKind, u64, u64)> {
+        [
+            (
+                QuotaKind::DailyCycles,
+                self.daily_cycles,
+                usage.daily_cycles,
+            ),
+            (
+                QuotaKind::StoredBytes,
+                self.stored_bytes,
+                usage.stored_bytes,
+            ),
+            (
+                QuotaKind::ConcurrentTasks,
+                self.concurrent_tasks,
+                usage.concurrent_tasks,
+            ),
+        ]
+        .into_iter()
+        .find_map(|(kind, limit, used)| {
+            limit
+                .filter(|limit| used >= *limit)
+                .map(|limit| (kind, limit, used))
+        })
+    }
+}
+
+// usage of an app in a namespace counted against its quotas
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QuotaUsed {
+    pub daily_cycles: u64,
+    pub stored_bytes: u64,
+    pub concurrent_tasks: u64,
+}
+
+impl QuotaConfig {
+    // the defaults overridden by the app, then by the app in the namespace
+    pub fn limits(&self, namespace: &str, app_id: &str) -> QuotaLimits {
+        let mut limits = QuotaLimits {
+            daily_cycles: self.quota_daily_cycles,
+            stored_bytes: self.quota_max_stored_bytes,
+            concurrent_tasks: self.quota_max_concurrent_tasks,
+        };
+        let overrides = self
+            .app_quotas
+            .iter()
+            .filter(|quota| quota.app_id == app_id);
+        for quota in overrides.clone().filter(|quota| quota.namespace.is_none()) {
+            limits.set(quota.kind, quota.limit);
+        }
+        for quota in overrides.filter(|quota| quota.namespace.as_deref() == Some(namespace)) {
+            limits.set(quota.kind, quota.limit);
+        }
+
+        limits
+    }
+}
+
+// unix timestamp (seconds) of the start of the current UTC day, the daily cycles are counted from it
+pub fn day_start() -> u64 {
+    let now = unix_timestamp();
+    now - now % DAY_SECS
+}
+
+pub fn day_end() -> u64 {
+    day_start() + DAY_SECS
+}
+
+// the remaining is none if unlimited
+pub fn quota_usage(limit: Option<u64>, used: u64) -> QuotaUsage {
+    QuotaUsage {
+        limit,
+        used,
+        remaining: limit.map(|limit| limit.saturating_sub(used)),
+    }
+}
+
+// parse the app quota as [NAMESPACE/]APP_ID:LIMIT=VALUE
+fn parse_app_quota(s: &str) -> Result<AppQuota, String> {
+    let invalid = || format!("invalid app quota {s}, expected [NAMESPACE/]APP_ID:LIMIT=VALUE");
+    let (subject, quota) = s.trim().split_once(':').ok_or_else(invalid)?;
+    let (kind, limit) = quota.split_once('=').ok_or_else(invalid)?;
+    let kind = QuotaKind::parse(kind.trim())
+        .ok_or_else(|| format!("invalid quota {kind} of {subject}"))?;
+    let limit: u64 = limit
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid {} quota of {subject}: {e}", kind.name()))?;
+    let (namespace, app_id) = match subject.split_once('/') {
+        Some((namespace, app_id)) => (Some(namespace.to_string()), app_id),
+        None => (None, subject),
+    };
+    if app_id.is_empty() || namespace.as_ref().is_some_and(String::is_empty) {
+        return Err(invalid());
+    }
+
+    Ok(AppQuota {
+        namespace,
+        app_id: app_id.strip_prefix("0x").unwrap_or(app_id).to_string(),
+        kind,
+        limit: (limit > 0).then_some(limit),
+    })
+}
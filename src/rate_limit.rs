@@ -0,0 +1,235 @@
+use crate::{
+    client::NAMESPACE_METADATA_KEY,
+    proving_queue::DEFAULT_NAMESPACE,
+    utils::{auth::API_KEY_METADATA_KEY, jwt::Claims},
+};
+use clap::Args;
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tonic::Request;
+
+// tenants tracked before pruning the idle buckets, which are full the same as the new ones
+const MAX_TRACKED_TENANTS: usize = 10_000;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Args, Clone)]
+pub struct RateLimitConfig {
+    #[clap(
+        long,
+        env = "RATE_LIMIT_REQUESTS_PER_MINUTE",
+        help = "Requests per minute of a tenant, unlimited if not set"
+    )]
+    pub rate_limit_requests_per_minute: Option<u32>,
+
+    #[clap(
+        long,
+        env = "RATE_LIMIT_BYTES_PER_MINUTE",
+        help = "Bytes of the uploaded inputs and elfs per minute of a tenant, unlimited if not set"
+    )]
+    pub rate_limit_bytes_per_minute: Option<u64>,
+}
+
+// tenant of a request, it's the namespace with the API key digest, the JWT subject or anonymous
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tenant(String);
+
+impl Tenant {
+    // the namespace is not validated here, the invalid ones are rejected by the handlers
+    pub fn of(req: &Request<()>) -> Self {
+        let claims = req.extensions().get::<Claims>();
+        let namespace = req
+            .metadata()
+            .get(NAMESPACE_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| claims.and_then(|claims| claims.namespace.as_deref()))
+            .unwrap_or(DEFAULT_NAMESPACE);
+        let api_key = req
+            .metadata()
+            .get(API_KEY_METADATA_KEY)
+            .and_then(|value| value.to_str().ok());
+        // the API key is not kept in memory
+        let principal = match (claims, api_key) {
+            (Some(claims), _) => format!("jwt:{}", claims.sub),
+            (None, Some(key)) => format!("key:{}", &hex::encode(Sha256::digest(key))[..16]),
+            (None, None) => "anonymous".to_string(),
+        };
+
+        Self(format!("{namespace}/{principal}"))
+    }
+}
+
+// rejection by a budget of the tenant, the call could be retried after the duration
+#[derive(Debug)]
+pub struct RateLimited {
+    pub budget: &'static str,
+    pub retry_after: Duration,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// token buckets of a budget per minute, they're refilled continuously up to the budget
+struct Budget {
+    name: &'static str,
+    per_minute: f64,
+    buckets: DashMap<Tenant, Bucket>,
+}
+
+impl Budget {
+    fn new(name: &'static str, per_minute: u64) -> Self {
+        Self {
+            name,
+            per_minute: per_minute as f64,
+            buckets: DashMap::new(),
+        }
+    }
+
+    // a cost above the budget is admitted once the bucket is full, and its debt delays the
+    // following calls
+    fn take(&self, tenant: &Tenant, cost: f64) -> Result<(), RateLimited> {
+        if self.buckets.len() > MAX_TRACKED_TENANTS {
+            self.buckets
+                .retain(|_, bucket| bucket.updated_at.elapsed() < MINUTE);
+        }
+
+        let now = Instant::now();
+        let mut bucket = self.buckets.entry(tenant.clone()).or_insert(Bucket {
+            tokens: self.per_minute,
+            updated_at: now,
+        });
+        let refilled = now.duration_since(bucket.updated_at).as_secs_f64() / 60.0 * self.per_minute;
+        bucket.tokens = (bucket.tokens + refilled).min(self.per_minute);
+        bucket.updated_at = now;
+
+        let required = cost.min(self.per_minute);
+        if bucket.tokens < required {
+            let missing = required - bucket.tokens;
+            return Err(RateLimited {
+                budget: self.name,
+                retry_after: Duration::from_secs_f64(missing / self.per_minute * 60.0),
+            });
+        }
+        bucket.tokens -= cost;
+
+        Ok(())
+    }
+}
+
+// admission of the calls by the per-tenant budgets of requests and uploaded bytes per minute, the
+// buckets of a tenant are local to the process
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    requests: Option<Arc<Budget>>,
+    bytes: Option<Arc<Budget>>,
+}
+
+impl RateLimiter {
+    pub fn new(cfg: &RateLimitConfig) -> Self {
+        let budget = |name, per_minute: Option<u64>| {
+            per_minute
+                .filter(|per_minute| *per_minute > 0)
+                .map(|per_minute| Arc::new(Budget::new(name, per_minute)))
+        };
+
+        Self {
+            requests: budget("request", cfg.rate_limit_requests_per_minute.map(u64::from)),
+            bytes: budget("byte", cfg.rate_limit_bytes_per_minute),
+        }
+    }
+
+    pub fn check_request(&self, tenant: &Tenant) -> Result<(), RateLimited> {
+        match &self.requests {
+            Some(budget) => budget.take(tenant, 1.0),
+            None => Ok(()),
+        }
+    }
+
+    pub fn check_bytes(&self, tenant: &Tenant, bytes: usize) -> Result<(), RateLimited> {
+        match &self.bytes {
+            Some(budget) if bytes > 0 => budget.take(tenant, bytes as f64),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant() -> Tenant {
+        Tenant("default/anonymous".to_string())
+    }
+
+    // move the last update of the bucket back, as if the time passed
+    fn elapse(budget: &Budget, tenant: &Tenant, elapsed: Duration) {
+        let mut bucket = budget.buckets.get_mut(tenant).unwrap();
+        bucket.updated_at = bucket.updated_at.checked_sub(elapsed).unwrap();
+    }
+
+    #[test]
+    fn test_take_and_refill() {
+        let (budget, tenant) = (Budget::new("request", 60), tenant());
+        for _ in 0..60 {
+            budget.take(&tenant, 1.0).unwrap();
+        }
+        let limited = budget.take(&tenant, 1.0).unwrap_err();
+        assert_eq!(limited.budget, "request");
+        assert!(limited.retry_after > Duration::ZERO);
+        assert!(limited.retry_after <= Duration::from_secs(1));
+
+        // a token is refilled per second
+        elapse(&budget, &tenant, Duration::from_secs(2));
+        budget.take(&tenant, 1.0).unwrap();
+        budget.take(&tenant, 1.0).unwrap();
+        assert!(budget.take(&tenant, 1.0).is_err());
+
+        // the bucket isn't refilled beyond the budget
+        elapse(&budget, &tenant, Duration::from_secs(600));
+        for _ in 0..60 {
+            budget.take(&tenant, 1.0).unwrap();
+        }
+        assert!(budget.take(&tenant, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_debt() {
+        let (budget, tenant) = (Budget::new("byte", 100), tenant());
+        // the cost above the budget is admitted by the full bucket, then 150 tokens are owed
+        budget.take(&tenant, 250.0).unwrap();
+        let limited = budget.take(&tenant, 1.0).unwrap_err();
+        assert!(limited.retry_after > Duration::from_secs(90));
+        assert!(limited.retry_after <= Duration::from_secs(91));
+
+        // the debt is paid by the refill of 1.5 minutes
+        elapse(&budget, &tenant, Duration::from_secs(60));
+        assert!(budget.take(&tenant, 1.0).is_err());
+        elapse(&budget, &tenant, Duration::from_secs(31));
+        budget.take(&tenant, 1.0).unwrap();
+    }
+
+    #[test]
+    fn test_tenants_and_unlimited() {
+        let budget = Budget::new("request", 1);
+        budget.take(&tenant(), 1.0).unwrap();
+        assert!(budget.take(&tenant(), 1.0).is_err());
+        budget
+            .take(&Tenant("default/jwt:other".to_string()), 1.0)
+            .unwrap();
+
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            rate_limit_requests_per_minute: None,
+            rate_limit_bytes_per_minute: Some(0),
+        });
+        for _ in 0..100 {
+            limiter.check_request(&tenant()).unwrap();
+            limiter.check_bytes(&tenant(), 1 << 30).unwrap();
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{
+    app_manager::AppFilter,
+    artifact_store::{self, SharedArtifactStore},
+    events,
+    proving_queue::{ProvingKey, ProvingOutputs, TaskStatuses},
+    storage::SharedStorage,
+    utils::time::unix_timestamp,
+};
+use anyhow::Result;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+
+// apps and expired tasks fetched by a query of the GC
+const GC_BATCH_SIZE: usize = 100;
+
+// retention of the proofs and finished tasks, they're kept forever if no TTL applies to the app
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    default_ttl: Option<Duration>,
+    // TTLs of the apps overriding the default one
+    app_ttls: HashMap<String, Duration>,
+}
+
+impl RetentionPolicy {
+    pub fn new(default_ttl: Option<Duration>, app_ttls: HashMap<String, Duration>) -> Self {
+        Self {
+            default_ttl,
+            app_ttls,
+        }
+    }
+
+    pub fn ttl(&self, app_id: &str) -> Option<Duration> {
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+        self.app_ttls.get(app_id).copied().or(self.default_ttl)
+    }
+
+    // TTL of the uploaded inputs, they're not owned by any app
+    pub fn default_ttl(&self) -> Option<Duration> {
+        self.default_ttl
+    }
+
+    // nothing expires if no TTL is configured
+    pub fn is_empty(&self) -> bool {
+        self.default_ttl.is_none() && self.app_ttls.is_empty()
+    }
+}
+
+// cleaner of the proofs, inputs and finished tasks in the database, the artifact store and memory
+#[derive(Clone)]
+pub struct Retention {
+    storage: SharedStorage,
+    artifacts: Option<SharedArtifactStore>,
+    outputs: Arc<ProvingOutputs>,
+    statuses: Arc<TaskStatuses>,
+}
+
+impl Retention {
+    pub fn new(
+        storage: SharedStorage,
+        artifacts: Option<SharedArtifactStore>,
+        outputs: Arc<ProvingOutputs>,
+        statuses: Arc<TaskStatuses>,
+    ) -> Self {
+        Self {
+            storage,
+            artifacts,
+            outputs,
+            statuses,
+        }
+    }
+
+    // start a background GC deleting the expired proofs and tasks periodically
+    pub fn start_gc(self, policy: RetentionPolicy, interval: Duration) -> JoinHandle<()> {
+        info!("[retention] gc init");
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                match self.collect(&policy).await {
+                    Ok(0) => (),
+                    Ok(deleted) => info!("[retention] deleted {deleted} expired tasks"),
+                    Err(e) => warn!("[retention] gc failed: {e}"),
+                }
+            }
+        })
+    }
+
+    // the proof of a queued or proving task must not be deleted, it may be resubmitted with the
+    // same task ID
+    pub fn is_running(&self, key: &ProvingKey) -> bool {
+        self.statuses
+            .get(key)
+            .is_some_and(|status| !status.is_finished())
+    }
+
+    // delete the proof, inputs and record of a finished task, return false if none exists. the
+    // artifacts are deleted first, so a failed deletion is retried by the next GC round
+    pub async fn delete_task(&self, key: &ProvingKey) -> Result<bool> {
+        if let Some(store) = &self.artifacts {
+            delete_task_artifacts(store, key).await?;
+        }
+        let deleted = self.storage.delete_proof(key).await?;
+        let cached = self.outputs.remove(key).is_some();
+        let tracked = self
+            .statuses
+            .remove_if(key, |_, status| status.is_finished())
+            .is_some();
+
+        Ok(deleted || cached || tracked)
+    }
+
+    // delete the artifacts of all the tasks of the app in the namespace before the app is deleted,
+    // including the checkpoints and the dead letter inputs. return the number of the tasks
+    pub async fn delete_app_artifacts(&self, namespace: &str, app_id: &str) -> Result<usize> {
+        let Some(store) = &self.artifacts else {
+            return Ok(0);
+        };
+        let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+        let mut deleted = 0;
+        // the records are kept until the app is deleted, so the pages don't shift
+        loop {
+            let keys = self
+                .storage
+                .app_tasks(namespace, app_id, deleted, GC_BATCH_SIZE)
+                .await?;
+            for key in &keys {
+                delete_task_artifacts(store, key).await?;
+                store
+                    .delete(&artifact_store::dead_letter_inputs_key(key))
+                    .await?;
+                store
+                    .delete_prefix(&artifact_store::checkpoint_prefix(key))
+                    .await?;
+            }
+            deleted += keys.len();
+            if keys.len() < GC_BATCH_SIZE {
+                return Ok(deleted);
+            }
+        }
+    }
+
+    async fn collect(&self, policy: &RetentionPolicy) -> Result<usize> {
+        let now = unix_timestamp();
+        let mut deleted = 0;
+        let mut offset = 0;
+        loop {
+            let apps = self
+                .storage
+                .list_app_metas(&AppFilter::default(), offset, GC_BATCH_SIZE)
+                .await?;
+            for app in &apps {
+                if let Some(ttl) = policy.ttl(&app.app_id) {
+                    let before = now.saturating_sub(ttl.as_secs());
+                    deleted += self.collect_app(&app.app_id, before).await?;
+                }
+            }
+            if apps.len() < GC_BATCH_SIZE {
+                break;
+            }
+            offset += apps.len();
+        }
+        if let Some(ttl) = policy.default_ttl() {
+            self.collect_uploaded_inputs(now.saturating_sub(ttl.as_secs()))
+                .await?;
+        }
+
+        Ok(deleted)
+    }
+
+    // delete the inputs uploaded before the timestamp, the tasks referencing them keep their own
+    // copies
+    async fn collect_uploaded_inputs(&self, before: u64) -> Result<()> {
+        let mut deleted = 0;
+        loop {
+            let uploads = self
+                .storage
+                .expired_uploaded_inputs(before, GC_BATCH_SIZE)
+                .await?;
+            for (namespace, inputs_hash) in &uploads {
+                if let Some(store) = &self.artifacts {
+                    store
+                        .delete(&artifact_store::uploaded_inputs_key(namespace, inputs_hash))
+                        .await?;
+                }
+                self.storage
+                    .delete_uploaded_inputs(namespace, inputs_hash)
+                    .await?;
+                deleted += 1;
+            }
+            if uploads.len() < GC_BATCH_SIZE {
+                break;
+            }
+        }
+        if deleted > 0 {
+            info!("[retention] deleted {deleted} expired uploaded inputs");
+        }
+
+        Ok(())
+    }
+
+    // delete the tasks of the app in all the namespaces finished before the timestamp
+    async fn collect_app(&self, app_id: &str, before: u64) -> Result<usize> {
+        let mut deleted = 0;
+        loop {
+            let keys = self
+                .storage
+                .expired_tasks(app_id, before, GC_BATCH_SIZE)
+                .await?;
+            let mut skipped = false;
+            for key in &keys {
+                if self.is_running(key) {
+                    skipped = true;
+                    continue;
+                }
+                if self.delete_task(key).await? {
+                    events::task_collected(key);
+                }
+                deleted += 1;
+            }
+            // the skipped tasks are fetched again, leave them to the next round
+            if skipped || keys.len() < GC_BATCH_SIZE {
+                return Ok(deleted);
+            }
+        }
+    }
+}
+
+async fn delete_task_artifacts(store: &SharedArtifactStore, key: &ProvingKey) -> Result<()> {
+    store.delete(&artifact_store::proof_key(key)).await?;
+    store.delete(&artifact_store::calldata_key(key)).await?;
+    store.delete(&artifact_store::inputs_key(key)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+// retry policy of the failed proving tasks
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    // maximum attempts including the first one, no retry if it's 1
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // lowercase substrings of the retriable error messages
+    pub retriable_errors: Vec<String>,
+}
+
+impl RetryPolicy {
+    pub fn new(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        retriable_errors: &[String],
+    ) -> Self {
+        let retriable_errors = retriable_errors
+            .iter()
+            .map(|pattern| pattern.trim().to_lowercase())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+            retriable_errors,
+        }
+    }
+
+    // check if the task could be retried after the failed attempt (starting from 1)
+    pub fn should_retry(&self, attempt: u32, error: &str) -> bool {
+        attempt < self.max_attempts && self.is_retriable(error)
+    }
+
+    // transient errors like GPU OOM and RPC failures are retriable
+    pub fn is_retriable(&self, error: &str) -> bool {
+        let error = error.to_lowercase();
+        self.retriable_errors
+            .iter()
+            .any(|pattern| error.contains(pattern))
+    }
+
+    // exponential backoff after the failed attempt (starting from 1)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(
+            max_attempts,
+            Duration::from_secs(2),
+            Duration::from_secs(10),
+            &[" CUDA OUT OF MEMORY ".to_string(), " ".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_new() {
+        let policy = policy(0);
+        assert_eq!(policy.max_attempts, 1);
+        assert_eq!(policy.retriable_errors, ["cuda out of memory"]);
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = policy(3);
+        assert!(policy.is_retriable("prover: CUDA out of memory, tried to allocate"));
+        assert!(!policy.is_retriable("invalid inputs"));
+        assert!(policy.should_retry(1, "cuda out of memory"));
+        assert!(policy.should_retry(2, "cuda out of memory"));
+        assert!(!policy.should_retry(3, "cuda out of memory"));
+        assert!(!policy.should_retry(1, "invalid inputs"));
+        // nothing is retried without the patterns
+        let policy = RetryPolicy::new(3, Duration::ZERO, Duration::ZERO, &[]);
+        assert!(!policy.should_retry(1, "cuda out of memory"));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = policy(10);
+        let backoffs: Vec<_> = (0..=5)
+            .map(|attempt| policy.backoff(attempt).as_secs())
+            .collect();
+        assert_eq!(backoffs, [2, 2, 4, 8, 10, 10]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,394 @@
+use crate::{
+    TaskPriority,
+    proving_queue::{ProvingKey, ProvingTask},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+// lanes of the priorities from high to low
+const LANE_COUNT: usize = 3;
+
+// pass increment of an app with weight 1, the increment is divided by the app weight
+const STRIDE: u64 = 1 << 20;
+
+// the fields of a task the scheduler orders it by
+pub trait Schedulable {
+    fn key(&self) -> &ProvingKey;
+
+    fn priority(&self) -> TaskPriority;
+
+    // unix timestamp (seconds) by which the task must complete
+    fn deadline(&self) -> Option<u64>;
+}
+
+impl Schedulable for ProvingTask {
+    fn key(&self) -> &ProvingKey {
+        &self.key
+    }
+
+    fn priority(&self) -> TaskPriority {
+        self.priority
+    }
+
+    fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+}
+
+struct QueuedTask<T> {
+    task: T,
+    queued_at: Instant,
+}
+
+// the apps are scheduled in their namespaces, so the tenants registering the same elf don't share
+// the fair share or the running cap
+type AppKey = (String, String);
+
+// queued tasks of an app in a lane
+struct AppQueue<T> {
+    tasks: VecDeque<QueuedTask<T>>,
+    // virtual time of the app, the app with the minimum pass is served first
+    pass: u64,
+}
+
+struct Lane<T> {
+    apps: HashMap<AppKey, AppQueue<T>>,
+}
+
+impl<T> Default for Lane<T> {
+    fn default() -> Self {
+        Self {
+            apps: HashMap::new(),
+        }
+    }
+}
+
+// priority scheduler of the queued tasks, the high-priority lanes are drained first, and the task
+// waiting longer than the starvation timeout in a lower lane is served first. the tasks with
+// deadlines in a lane are served earliest deadline first, the other apps in a lane are served by
+// weighted fair queueing (stride scheduling), and the running tasks of an app are limited by the
+// per-app cap
+pub struct Scheduler<T = ProvingTask> {
+    lanes: [Lane<T>; LANE_COUNT],
+    starvation_timeout: Duration,
+    // scheduling weights of the apps, default weight is 1. they're global, the app has the same
+    // weight in each namespace registering it
+    app_weights: HashMap<String, u32>,
+    // maximum running tasks of each app in a namespace
+    app_max_running: Option<usize>,
+    // running tasks of the apps
+    running: HashMap<AppKey, usize>,
+}
+
+impl<T: Schedulable> Scheduler<T> {
+    pub fn new(
+        starvation_timeout: Duration,
+        app_weights: HashMap<String, u32>,
+        app_max_running: Option<usize>,
+    ) -> Self {
+        Self {
+            lanes: Default::default(),
+            starvation_timeout,
+            app_weights,
+            app_max_running,
+            running: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, task: T) {
+        let lane = &mut self.lanes[lane_of(task.priority())];
+        let app = app_key(task.key());
+        if !lane.apps.contains_key(&app) {
+            // a newly active app starts from the current virtual time, it can't claim the time
+            // when it was idle
+            let pass = lane.apps.values().map(|app| app.pass).min().unwrap_or(0);
+            lane.apps.insert(
+                app.clone(),
+                AppQueue {
+                    tasks: VecDeque::new(),
+                    pass,
+                },
+            );
+        }
+        let tasks = &mut lane.apps.get_mut(&app).expect("app queue must exist").tasks;
+        // the task with a deadline is queued ahead of the ones with later or no deadlines
+        let position = match task.deadline() {
+            Some(deadline) => tasks
+                .iter()
+                .position(|queued| queued.task.deadline().is_none_or(|other| other > deadline))
+                .unwrap_or(tasks.len()),
+            None => tasks.len(),
+        };
+        tasks.insert(
+            position,
+            QueuedTask {
+                task,
+                queued_at: Instant::now(),
+            },
+        );
+    }
+
+    // pop the next task to run, the app of the task is counted as running until finished
+    pub fn pop(&mut self) -> Option<T> {
+        let now = Instant::now();
+        // the oldest starving task of the lower lanes
+        let starving = (1..LANE_COUNT)
+            .filter_map(|lane| {
+                self.oldest_eligible(lane)
+                    .filter(|queued_at| now.duration_since(*queued_at) >= self.starvation_timeout)
+                    .map(|queued_at| (lane, queued_at))
+            })
+            .min_by_key(|(_, queued_at)| *queued_at)
+            .map(|(lane, _)| lane);
+
+        let lane = starving
+            .or_else(|| (0..LANE_COUNT).find(|lane| self.oldest_eligible(*lane).is_some()))?;
+
+        let app = self.lanes[lane]
+            .apps
+            .iter()
+            .filter(|(app, _)| self.is_eligible(app))
+            .filter_map(|(app, queue)| queue.tasks.front().map(|front| (app, queue.pass, front)))
+            // the earliest deadline of the apps is served first, then by fair queueing
+            .min_by_key(|(_, pass, front)| {
+                let deadline = front.task.deadline();
+                (deadline.is_none(), deadline, *pass, front.queued_at)
+            })
+            .map(|(app, _, _)| app.clone())?;
+        let weight = self.app_weights.get(&app.1).copied().unwrap_or(1).max(1);
+
+        let queue = self.lanes[lane].apps.get_mut(&app)?;
+        let queued = queue.tasks.pop_front()?;
+        queue.pass += STRIDE / u64::from(weight);
+        if queue.tasks.is_empty() {
+            self.lanes[lane].apps.remove(&app);
+        }
+        *self.running.entry(app).or_default() += 1;
+
+        Some(queued.task)
+    }
+
+    // release the running slot of a popped task
+    pub fn finish(&mut self, key: &ProvingKey) {
+        let app = app_key(key);
+        if let Some(running) = self.running.get_mut(&app) {
+            *running -= 1;
+            if *running == 0 {
+                self.running.remove(&app);
+            }
+        }
+    }
+
+    // whether an eligible task of a higher priority is queued, to preempt a running task
+    pub fn has_higher_priority(&self, priority: TaskPriority) -> bool {
+        (0..lane_of(priority)).any(|lane| self.oldest_eligible(lane).is_some())
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.values().sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes
+            .iter()
+            .flat_map(|lane| lane.apps.values())
+            .map(|app| app.tasks.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(|lane| lane.apps.is_empty())
+    }
+
+    // the oldest task in the lane of the apps not reaching the running cap
+    fn oldest_eligible(&self, lane: usize) -> Option<Instant> {
+        self.lanes[lane]
+            .apps
+            .iter()
+            .filter(|(app, _)| self.is_eligible(app))
+            .filter_map(|(_, queue)| queue.tasks.front().map(|queued| queued.queued_at))
+            .min()
+    }
+
+    fn is_eligible(&self, app: &AppKey) -> bool {
+        self.app_max_running
+            .is_none_or(|max| self.running.get(app).copied().unwrap_or(0) < max)
+    }
+}
+
+pub(crate) fn lane_of(priority: TaskPriority) -> usize {
+    match priority {
+        TaskPriority::High => 0,
+        TaskPriority::Normal => 1,
+        TaskPriority::Low => 2,
+    }
+}
+
+// the app ID may be submitted with or without the 0x prefix
+fn app_key(key: &ProvingKey) -> AppKey {
+    let app_id = key.app_id().strip_prefix("0x").unwrap_or(key.app_id());
+
+    (key.namespace().to_string(), app_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proving_queue::DEFAULT_NAMESPACE;
+
+    struct TestTask {
+        key: ProvingKey,
+        priority: TaskPriority,
+        deadline: Option<u64>,
+    }
+
+    impl Schedulable for TestTask {
+        fn key(&self) -> &ProvingKey {
+            &self.key
+        }
+
+        fn priority(&self) -> TaskPriority {
+            self.priority
+        }
+
+        fn deadline(&self) -> Option<u64> {
+            self.deadline
+        }
+    }
+
+    fn task(app_id: &str, task_id: &str, priority: TaskPriority) -> TestTask {
+        TestTask {
+            key: ProvingKey::new(
+                DEFAULT_NAMESPACE.to_string(),
+                app_id.to_string(),
+                task_id.to_string(),
+            ),
+            priority,
+            deadline: None,
+        }
+    }
+
+    fn pop_all(scheduler: &mut Scheduler<TestTask>) -> Vec<String> {
+        std::iter::from_fn(|| scheduler.pop())
+            .map(|task| task.key.task_id().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_priority_lanes() {
+        let mut scheduler = Scheduler::new(Duration::from_secs(3600), HashMap::new(), None);
+        scheduler.push(task("a", "low", TaskPriority::Low));
+        scheduler.push(task("a", "normal", TaskPriority::Normal));
+        scheduler.push(task("b", "high", TaskPriority::High));
+        scheduler.push(task("a", "normal-2", TaskPriority::Normal));
+        assert_eq!(scheduler.len(), 4);
+        assert_eq!(
+            pop_all(&mut scheduler),
+            ["high", "normal", "normal-2", "low"]
+        );
+        assert_eq!(scheduler.running(), 4);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn test_starvation_promotion() {
+        // the tasks in the lower lanes waiting beyond the timeout are served first, the oldest
+        // first
+        let mut scheduler = Scheduler::new(Duration::ZERO, HashMap::new(), None);
+        for (task_id, priority) in [
+            ("low", TaskPriority::Low),
+            ("normal", TaskPriority::Normal),
+            ("high", TaskPriority::High),
+        ] {
+            scheduler.push(task("a", task_id, priority));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pop_all(&mut scheduler), ["low", "normal", "high"]);
+    }
+
+    #[test]
+    fn test_stride_fairness() {
+        let weights = HashMap::from([("a".to_string(), 2)]);
+        let mut scheduler = Scheduler::new(Duration::from_secs(3600), weights, None);
+        for i in 0..6 {
+            scheduler.push(task("0xa", &format!("a{i}"), TaskPriority::Normal));
+            scheduler.push(task("b", &format!("b{i}"), TaskPriority::Normal));
+        }
+
+        // the app of weight 2 is served twice as often
+        let served = (0..6)
+            .filter(|_| scheduler.pop().unwrap().key.app_id() == "0xa")
+            .count();
+        assert_eq!(served, 4);
+        assert_eq!(scheduler.len(), 6);
+    }
+
+    #[test]
+    fn test_app_max_running() {
+        let mut scheduler = Scheduler::new(Duration::from_secs(3600), HashMap::new(), Some(1));
+        scheduler.push(task("a", "a0", TaskPriority::Normal));
+        scheduler.push(task("a", "a1", TaskPriority::Normal));
+        scheduler.push(task("b", "b0", TaskPriority::Normal));
+        assert_eq!(pop_all(&mut scheduler), ["a0", "b0"]);
+        // the app ID may be finished with the prefix
+        scheduler.finish(&task("0xa", "a0", TaskPriority::Normal).key);
+        assert_eq!(pop_all(&mut scheduler), ["a1"]);
+        assert_eq!(scheduler.running(), 2);
+    }
+
+    #[test]
+    fn test_deadline_ordering() {
+        let with_deadline = |app_id, task_id, deadline| TestTask {
+            deadline: Some(deadline),
+            ..task(app_id, task_id, TaskPriority::Normal)
+        };
+        let mut scheduler = Scheduler::new(Duration::from_secs(3600), HashMap::new(), None);
+        scheduler.push(task("a", "a-none", TaskPriority::Normal));
+        scheduler.push(with_deadline("a", "a-200", 200));
+        scheduler.push(with_deadline("a", "a-100", 100));
+        scheduler.push(with_deadline("b", "b-150", 150));
+        scheduler.push(task("b", "b-none", TaskPriority::Normal));
+        assert_eq!(
+            pop_all(&mut scheduler),
+            // the tasks without deadlines are served by fair queueing
+            ["a-100", "b-150", "a-200", "b-none", "a-none"]
+        );
+    }
+
+    #[test]
+    fn test_has_higher_priority() {
+        let mut scheduler = Scheduler::new(Duration::from_secs(3600), HashMap::new(), Some(1));
+        scheduler.push(task("a", "normal", TaskPriority::Normal));
+        assert!(scheduler.has_higher_priority(TaskPriority::Low));
+        assert!(!scheduler.has_higher_priority(TaskPriority::Normal));
+        assert!(!scheduler.has_higher_priority(TaskPriority::High));
+
+        // the queued task of an app at its running cap can't preempt the others
+        scheduler.push(task("a", "high", TaskPriority::High));
+        assert!(scheduler.has_higher_priority(TaskPriority::Normal));
+        assert_eq!(pop_all(&mut scheduler), ["high"]);
+        assert!(!scheduler.has_higher_priority(TaskPriority::Low));
+    }
+
+    #[test]
+    fn test_namespace_isolation() {
+        let in_namespace = |namespace: &str, task_id: &str| TestTask {
+            key: ProvingKey::new(namespace.to_string(), "a".to_string(), task_id.to_string()),
+            ..task("a", task_id, TaskPriority::Normal)
+        };
+        let mut scheduler = Scheduler::new(Duration::from_secs(3600), HashMap::new(), Some(1));
+        scheduler.push(in_namespace(DEFAULT_NAMESPACE, "a0"));
+        scheduler.push(in_namespace(DEFAULT_NAMESPACE, "a1"));
+        scheduler.push(in_namespace("team-b", "b0"));
+
+        // the same app in another namespace has its own running cap
+        assert_eq!(pop_all(&mut scheduler), ["a0", "b0"]);
+        scheduler.finish(&in_namespace("team-b", "b0").key);
+        assert!(scheduler.pop().is_none());
+        scheduler.finish(&in_namespace(DEFAULT_NAMESPACE, "a0").key);
+        assert_eq!(pop_all(&mut scheduler), ["a1"]);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    TaskState,
+    proving_queue::{ProvingKey, TaskStatuses, requeue_task},
+    storage::SharedStorage,
+};
+use anyhow::Result;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    signal::{
+        ctrl_c,
+        unix::{SignalKind, signal},
+    },
+    time::{Instant, sleep},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+// interval to check the in-flight tasks while draining
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// failure reason of the tasks interrupted by the shutdown, they're replayed after restart
+const INTERRUPTED_REASON: &str = "interrupted by shutdown";
+
+// state of the graceful shutdown shared by the gRPC server, the proving queue and the coordinator
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    // no new task is accepted or started while draining
+    draining: Arc<AtomicBool>,
+    // no new task is accepted while the admins drain the queue, but the queued ones are started
+    admission_closed: Arc<AtomicBool>,
+    // the gRPC server stops once cancelled
+    stopped: CancellationToken,
+}
+
+impl Shutdown {
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Release);
+    }
+
+    pub fn is_admission_closed(&self) -> bool {
+        self.admission_closed.load(Ordering::Acquire)
+    }
+
+    pub fn set_admission_closed(&self, closed: bool) {
+        self.admission_closed.store(closed, Ordering::Release);
+    }
+
+    pub fn stop(&self) {
+        self.stopped.cancel();
+    }
+
+    // resolved once stopped, for the graceful shutdown of the gRPC server
+    pub async fn stopped(self) {
+        self.stopped.cancelled_owned().await
+    }
+}
+
+// wait for SIGTERM or Ctrl-C
+pub async fn wait_for_signal() -> Result<()> {
+    let mut sigterm = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = sigterm.recv() => info!("[shutdown] received SIGTERM"),
+        res = ctrl_c() => {
+            res?;
+            info!("[shutdown] received Ctrl-C");
+        }
+    }
+
+    Ok(())
+}
+
+// wait for the executing and proving tasks to finish up to the timeout, the unfinished ones are
+// persisted as queued to be replayed after restart and resumed from the checkpoints if enabled.
+// return the count of the unfinished tasks
+pub async fn drain_tasks(
+    statuses: &TaskStatuses,
+    storage: &SharedStorage,
+    timeout: Duration,
+) -> usize {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let active = active_tasks(statuses);
+        if active.is_empty() {
+            info!("[shutdown] all in-flight tasks finished");
+            return 0;
+        }
+        if Instant::now() >= deadline {
+            warn!(
+                "[shutdown] {} tasks are still in-flight after {timeout:?}",
+                active.len()
+            );
+            for key in &active {
+                requeue_task(statuses, key, INTERRUPTED_REASON.to_string());
+                if let Err(e) = storage
+                    .update_task_state(key, TaskState::Queued, Some(INTERRUPTED_REASON))
+                    .await
+                {
+                    error!("[shutdown] failed to persist interrupted task {key:?}: {e}");
+                }
+            }
+            return active.len();
+        }
+        info!("[shutdown] waiting for {} in-flight tasks", active.len());
+        sleep(DRAIN_CHECK_INTERVAL).await;
+    }
+}
+
+fn active_tasks(statuses: &TaskStatuses) -> Vec<ProvingKey> {
+    statuses
+        .iter()
+        .filter(|entry| matches!(entry.state, TaskState::Executing | TaskState::Proving))
+        .map(|entry| entry.key().clone())
+        .collect()
+}
@@ -0,0 +1,534 @@
+use crate::{
+    PhaseTimings, ProofType, TaskPriority, TaskState,
+    accounting::{AppUsageRow, UsageRecord},
+    app_analysis::AppAnalysisRow,
+    app_manager::{AppFilter, AppInfoRow, AppMetaRow, AppRow, AppVersionRow},
+    cost_estimation::{CalibrationRow, CalibrationSample},
+    proof_metadata::ProofProvenance,
+    proving_queue::ProvingKey,
+};
+use alloy_primitives::U256;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use prost::Message;
+use sqlx::FromRow;
+use std::sync::Arc;
+
+mod postgres;
+mod sqlite;
+
+pub use postgres::PostgresStorage;
+pub use sqlite::SqliteStorage;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum StorageBackend {
+    // embedded database, the migrations are run by sqlx-cli
+    Sqlite,
+    // shared database for multiple replicas, the migrations are run on connecting
+    Postgres,
+}
+
+// result of deleting an app from a namespace
+#[derive(Debug, PartialEq, Eq)]
+pub enum AppDeletion {
+    // the app isn't registered in the namespace
+    NotFound,
+    // the app is still registered by another namespace
+    Kept,
+    // the app is removed from all the namespaces with the elf hash its keys are stored by
+    Removed { elf_hash: Option<String> },
+}
+
+#[derive(Debug, FromRow)]
+pub struct TaskRow {
+    pub namespace: String,
+    pub app_id: String,
+    pub task_id: String,
+    pub inputs: Option<Vec<u8>>,
+    pub use_gpu: bool,
+    pub priority: i32,
+    pub state: i32,
+    pub created_at: i64,
+    pub callback_url: Option<String>,
+    pub verify_proof: bool,
+    pub proof_type: i32,
+    // task IDs of the dependencies separated by comma
+    pub depends_on: Option<String>,
+    // unix timestamp (seconds) before which the task is held
+    pub not_before: Option<i64>,
+    // unix timestamp (seconds) by which the task must complete
+    pub deadline: Option<i64>,
+    // the GPU task is proved on CPU if no healthy device
+    pub allow_cpu_fallback: bool,
+    // public values digest (big-endian) the emulation must produce to prove the task
+    pub expected_pv_digest: Option<Vec<u8>>,
+}
+
+impl TaskRow {
+    pub fn key(&self) -> ProvingKey {
+        ProvingKey::new(
+            self.namespace.clone(),
+            self.app_id.clone(),
+            self.task_id.clone(),
+        )
+    }
+
+    pub fn priority(&self) -> TaskPriority {
+        TaskPriority::try_from(self.priority).unwrap_or_default()
+    }
+
+    pub fn state(&self) -> TaskState {
+        TaskState::try_from(self.state).unwrap_or_default()
+    }
+
+    pub fn proof_type(&self) -> ProofType {
+        ProofType::try_from(self.proof_type).unwrap_or_default()
+    }
+
+    pub fn depends_on(&self) -> Vec<String> {
+        split_depends_on(self.depends_on.as_deref())
+    }
+}
+
+// the persisted status of a task, it's reported once the task is untracked after a restart
+#[derive(Debug, FromRow)]
+pub struct TaskStatusRow {
+    pub state: i32,
+    pub failure_reason: Option<String>,
+    pub created_at: i64,
+    // unix timestamp (seconds) of the last state change, the finishing time once finished
+    pub updated_at: i64,
+    // unix timestamp (seconds) of starting the last attempt
+    pub started_at: Option<i64>,
+    // proving attempts started so far
+    pub attempts: i32,
+    // protobuf encoded PhaseTimings once completed
+    pub timings: Option<Vec<u8>>,
+    pub not_before: Option<i64>,
+    pub deadline: Option<i64>,
+}
+
+impl TaskStatusRow {
+    pub fn state(&self) -> TaskState {
+        TaskState::try_from(self.state).unwrap_or_default()
+    }
+
+    pub fn timings(&self) -> Option<PhaseTimings> {
+        self.timings
+            .as_deref()
+            .and_then(|timings| PhaseTimings::decode(timings).ok())
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct ProofRow {
+    pub proof: Vec<u8>,
+    pub pv_digest: Option<Vec<u8>>,
+    // unix timestamp (seconds) of storing the proof
+    pub created_at: i64,
+    pub proof_type: i32,
+    // provenance of the proof, none if stored before it's recorded
+    pub prover_version: Option<String>,
+    pub service_version: Option<String>,
+    pub elf_hash: Option<String>,
+    pub inputs_hash: Option<String>,
+    pub proving_seconds: Option<f64>,
+    // protobuf encoded ProofSignature of the service, none if not signed
+    pub signature: Option<Vec<u8>>,
+}
+
+impl ProofRow {
+    pub fn proof_type(&self) -> ProofType {
+        ProofType::try_from(self.proof_type).unwrap_or_default()
+    }
+}
+
+// a permanently failed task kept to be inspected and requeued, the inputs are loaded separately
+#[derive(Debug, FromRow)]
+pub struct DeadLetterRow {
+    pub namespace: String,
+    pub app_id: String,
+    pub task_id: String,
+    // size of the inputs kept in the database, 0 if none or offloaded to the artifact store
+    pub inputs_size: i64,
+    pub use_gpu: bool,
+    pub priority: i32,
+    pub callback_url: Option<String>,
+    pub verify_proof: bool,
+    pub proof_type: i32,
+    pub depends_on: Option<String>,
+    pub allow_cpu_fallback: bool,
+    pub expected_pv_digest: Option<Vec<u8>>,
+    pub failure_reason: String,
+    pub attempts: i32,
+    pub failed_at: i64,
+}
+
+impl DeadLetterRow {
+    pub fn key(&self) -> ProvingKey {
+        ProvingKey::new(
+            self.namespace.clone(),
+            self.app_id.clone(),
+            self.task_id.clone(),
+        )
+    }
+
+    pub fn priority(&self) -> TaskPriority {
+        TaskPriority::try_from(self.priority).unwrap_or_default()
+    }
+
+    pub fn proof_type(&self) -> ProofType {
+        ProofType::try_from(self.proof_type).unwrap_or_default()
+    }
+
+    pub fn depends_on(&self) -> Vec<String> {
+        split_depends_on(self.depends_on.as_deref())
+    }
+}
+
+// join the task IDs of the dependencies by comma, none if no dependency
+fn join_depends_on(depends_on: &[String]) -> Option<String> {
+    (!depends_on.is_empty()).then(|| depends_on.join(","))
+}
+
+fn split_depends_on(depends_on: Option<&str>) -> Vec<String> {
+    depends_on
+        .filter(|depends_on| !depends_on.is_empty())
+        .map(|depends_on| depends_on.split(',').map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+// cached estimate of the inputs of an app
+#[derive(Debug, FromRow)]
+pub struct CostEstimateRow {
+    pub cost: i64,
+    pub total_cycles: i64,
+    pub pv_digest: Vec<u8>,
+}
+
+// inputs uploaded to be referenced by their hash, `inputs` is none if offloaded to the artifact
+// store
+#[derive(Debug, FromRow)]
+pub struct UploadedInputsRow {
+    pub inputs: Option<Vec<u8>>,
+    pub size: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct TokenRow {
+    pub token_id: String,
+    pub subject: String,
+    // the only namespace accessible by the token if bound
+    pub namespace: Option<String>,
+    // scopes separated by comma
+    pub scopes: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+#[async_trait]
+pub trait AppRepository: Send + Sync {
+    // get the app registered in the namespace, or in any namespace if not specified
+    async fn get_app(&self, namespace: Option<&str>, app_id: &str) -> Result<Option<AppRow>>;
+
+    // the program and keys are shared by the namespaces registering the same elf
+    async fn insert_app(&self, row: &AppRow, info: &AppInfoRow, created_at: u64) -> Result<()>;
+
+    // add the app with its info to the namespace, return false if it's already registered in the
+    // namespace. the info is kept by the namespace, not shared with the others registering the elf
+    async fn insert_app_namespace(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        info: Option<&str>,
+        app_info: &AppInfoRow,
+        created_at: u64,
+    ) -> Result<bool>;
+
+    // delete the app with its proofs and tasks in the namespace, the app is removed once no
+    // namespace registers it
+    async fn delete_app(&self, namespace: &str, app_id: &str) -> Result<AppDeletion>;
+
+    async fn get_app_meta(&self, namespace: &str, app_id: &str) -> Result<Option<AppMetaRow>>;
+
+    // find the app registered with the same elf by its content hash
+    async fn find_app_by_elf_hash(&self, elf_hash: &str) -> Result<Option<String>>;
+
+    // the analysis is shared by the namespaces registering the same elf
+    async fn insert_app_analysis(&self, row: &AppAnalysisRow) -> Result<()>;
+
+    // get the analysis of the app registered in the namespace
+    async fn get_app_analysis(
+        &self,
+        namespace: &str,
+        app_id: &str,
+    ) -> Result<Option<AppAnalysisRow>>;
+
+    // the app IDs with their elf hashes ordered by their latest task, at most of the limit
+    async fn recent_apps(&self, limit: usize) -> Result<Vec<(String, Option<String>)>>;
+
+    // list the app metadata matching the filter ordered by registration time
+    async fn list_app_metas(
+        &self,
+        filter: &AppFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AppMetaRow>>;
+
+    // append the app as the next version of the name in the namespace, return the new version
+    async fn insert_app_version(
+        &self,
+        namespace: &str,
+        name: &str,
+        app_id: &str,
+        created_at: u64,
+    ) -> Result<u32>;
+
+    // get the pinned version of the name, or the latest one if not pinned
+    async fn get_app_version(
+        &self,
+        namespace: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<Option<AppVersionRow>>;
+
+    // list the versions of the name from the oldest to the latest
+    async fn list_app_versions(&self, namespace: &str, name: &str) -> Result<Vec<AppVersionRow>>;
+}
+
+#[async_trait]
+pub trait TaskRepository: Send + Sync {
+    // persist a queued task with its inputs, it replaces the finished task with the same key
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_task(
+        &self,
+        key: &ProvingKey,
+        inputs: Option<&[u8]>,
+        inputs_hash: &str,
+        use_gpu: bool,
+        priority: TaskPriority,
+        created_at: u64,
+        callback_url: Option<&str>,
+        verify_proof: bool,
+        proof_type: ProofType,
+        depends_on: &[String],
+        not_before: Option<u64>,
+        deadline: Option<u64>,
+        allow_cpu_fallback: bool,
+        expected_pv_digest: Option<&[u8]>,
+    ) -> Result<()>;
+
+    // update the state of an unfinished task, the inputs are dropped once finished since it's
+    // never replayed
+    async fn update_task_state(
+        &self,
+        key: &ProvingKey,
+        state: TaskState,
+        failure_reason: Option<&str>,
+    ) -> Result<()>;
+
+    // get the queued and in-progress tasks in the order of queueing
+    async fn unfinished_tasks(&self) -> Result<Vec<TaskRow>>;
+
+    // get the persisted status of the task
+    async fn get_task(&self, key: &ProvingKey) -> Result<Option<TaskStatusRow>>;
+
+    // record the time spent in the proving phases of the task
+    async fn store_task_timings(&self, key: &ProvingKey, timings: &PhaseTimings) -> Result<()>;
+
+    // find the latest task of the app in the namespace proving or proved the inputs to the proof
+    // type, the failed and cancelled tasks are excluded
+    async fn find_task_by_inputs(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        inputs_hash: &str,
+        proof_type: ProofType,
+    ) -> Result<Option<(String, TaskState)>>;
+
+    // get the inputs hash, the proof type and the state of the task, a resubmission of the task ID
+    // is matched against them. the hash is none for the tasks persisted before it's recorded
+    async fn task_fingerprint(
+        &self,
+        key: &ProvingKey,
+    ) -> Result<Option<(Option<String>, ProofType, TaskState)>>;
+
+    async fn store_proof(
+        &self,
+        key: &ProvingKey,
+        proof: &[u8],
+        pv_digest: U256,
+        proof_type: ProofType,
+        provenance: &ProofProvenance,
+    ) -> Result<()>;
+
+    async fn get_proof(&self, key: &ProvingKey) -> Result<Option<ProofRow>>;
+
+    // check if the proof is stored, the task may be interrupted after storing its proof
+    async fn has_proof(&self, key: &ProvingKey) -> Result<bool>;
+
+    // delete the proof and the finished task, return false if neither exists
+    async fn delete_proof(&self, key: &ProvingKey) -> Result<bool>;
+
+    // get the cached estimate of the inputs of the app
+    async fn get_cost_estimate(
+        &self,
+        app_id: &str,
+        inputs_hash: &str,
+    ) -> Result<Option<CostEstimateRow>>;
+
+    // cache the estimate of the inputs of the app, it replaces the existing one
+    async fn store_cost_estimate(
+        &self,
+        app_id: &str,
+        inputs_hash: &str,
+        row: &CostEstimateRow,
+    ) -> Result<()>;
+
+    // tasks of the app in all the namespaces whose proofs are stored or tasks are finished before
+    // the timestamp
+    async fn expired_tasks(
+        &self,
+        app_id: &str,
+        before: u64,
+        limit: usize,
+    ) -> Result<Vec<ProvingKey>>;
+
+    // tasks of the app in the namespace with a stored proof, a task record or a dead letter,
+    // ordered by the task ID for paging
+    async fn app_tasks(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ProvingKey>>;
+
+    // store the uploaded inputs, the upload of the same inputs refreshes the creation time
+    async fn store_uploaded_inputs(
+        &self,
+        namespace: &str,
+        inputs_hash: &str,
+        inputs: Option<&[u8]>,
+        size: usize,
+        created_at: u64,
+    ) -> Result<()>;
+
+    async fn get_uploaded_inputs(
+        &self,
+        namespace: &str,
+        inputs_hash: &str,
+    ) -> Result<Option<UploadedInputsRow>>;
+
+    // namespaces and hashes of the inputs uploaded before the timestamp
+    async fn expired_uploaded_inputs(
+        &self,
+        before: u64,
+        limit: usize,
+    ) -> Result<Vec<(String, String)>>;
+
+    async fn delete_uploaded_inputs(&self, namespace: &str, inputs_hash: &str) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait TokenRepository: Send + Sync {
+    async fn insert_token(&self, row: &TokenRow) -> Result<()>;
+
+    // mark the token as revoked, return false if it's not issued
+    async fn revoke_token(&self, token_id: &str) -> Result<bool>;
+
+    // IDs of the revoked tokens not expired at the timestamp
+    async fn revoked_tokens(&self, now: u64) -> Result<Vec<String>>;
+}
+
+#[async_trait]
+pub trait DeadLetterRepository: Send + Sync {
+    // move the unfinished task to the dead letters with its inputs, it should be called before the
+    // task is persisted as failed since the inputs are dropped then
+    async fn insert_dead_letter(
+        &self,
+        key: &ProvingKey,
+        failure_reason: &str,
+        attempts: u32,
+        failed_at: u64,
+    ) -> Result<()>;
+
+    // list the dead letters of the namespace ordered by failure time
+    async fn list_dead_letters(
+        &self,
+        namespace: &str,
+        app_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<DeadLetterRow>>;
+
+    async fn get_dead_letter(&self, key: &ProvingKey) -> Result<Option<DeadLetterRow>>;
+
+    async fn dead_letter_inputs(&self, key: &ProvingKey) -> Result<Option<Vec<u8>>>;
+
+    // return false if the dead letter doesn't exist
+    async fn delete_dead_letter(&self, key: &ProvingKey) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait UsageRepository: Send + Sync {
+    async fn insert_usage(&self, usage: &UsageRecord) -> Result<()>;
+
+    // aggregate the usage of the apps in the namespace recorded in [start, end) by app, only the
+    // tasks signed by the signer if set
+    async fn app_usage(
+        &self,
+        namespace: &str,
+        app_id: Option<&str>,
+        signer: Option<&str>,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<AppUsageRow>>;
+
+    // bytes of the proofs of the app stored in the namespace by their usage records, the proofs
+    // recorded by a re-proving are counted once
+    async fn stored_bytes(&self, namespace: &str, app_id: &str) -> Result<u64>;
+
+    // record the task of a signed request, return false if the nonce is already used by the signer
+    async fn insert_signed_task(
+        &self,
+        signer: &str,
+        nonce: u64,
+        key: &ProvingKey,
+        created_at: u64,
+    ) -> Result<bool>;
+
+    // accumulate the proving work of a completed task to the calibration of its backend
+    async fn add_calibration(&self, sample: &CalibrationSample, updated_at: u64) -> Result<()>;
+
+    async fn calibrations(&self) -> Result<Vec<CalibrationRow>>;
+}
+
+#[async_trait]
+pub trait Storage:
+    AppRepository + TaskRepository + TokenRepository + DeadLetterRepository + UsageRepository
+{
+    // check if the database is reachable
+    async fn ping(&self) -> Result<()>;
+}
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+// connect to the database of the configured backend
+pub async fn connect(backend: StorageBackend, db_url: &str) -> Result<SharedStorage> {
+    let storage: SharedStorage = match backend {
+        StorageBackend::Sqlite => Arc::new(SqliteStorage::connect(db_url).await?),
+        StorageBackend::Postgres => Arc::new(PostgresStorage::connect(db_url).await?),
+    };
+
+    Ok(storage)
+}
+
+// finished states are never updated
+fn is_finished(state: TaskState) -> bool {
+    matches!(
+        state,
+        TaskState::Completed | TaskState::Failed | TaskState::Cancelled
+    )
+}
@@ -0,0 +1,1080 @@
+use super::{
+    AppDeletion, AppRepository, CostEstimateRow, DeadLetterRepository, DeadLetterRow, ProofRow,
+    Storage, TaskRepository, TaskRow, TaskStatusRow, TokenRepository, TokenRow, UploadedInputsRow,
+    UsageRepository, is_finished, join_depends_on,
+};
+use crate::{
+    PhaseTimings, ProofType, TaskPriority, TaskState,
+    accounting::{AppUsageRow, UsageRecord},
+    app_analysis::AppAnalysisRow,
+    app_manager::{AppFilter, AppInfoRow, AppMetaRow, AppRow, AppVersionRow},
+    cost_estimation::{CalibrationRow, CalibrationSample},
+    proof_metadata::ProofProvenance,
+    proving_queue::ProvingKey,
+    utils::time::unix_timestamp,
+};
+use alloy_primitives::U256;
+use anyhow::Result;
+use async_trait::async_trait;
+use prost::Message;
+use sqlx::{PgPool, postgres::PgPoolOptions};
+
+pub struct PostgresStorage {
+    db_pool: PgPool,
+}
+
+impl PostgresStorage {
+    pub async fn connect(db_url: &str) -> Result<Self> {
+        let db_pool = PgPoolOptions::new().connect(db_url).await?;
+
+        // the schema is embedded and migrated in code, since multiple replicas share the database
+        sqlx::migrate!("./migrations/postgres")
+            .run(&db_pool)
+            .await?;
+
+        Ok(Self { db_pool })
+    }
+}
+
+#[async_trait]
+impl AppRepository for PostgresStorage {
+    async fn get_app(&self, namespace: Option<&str>, app_id: &str) -> Result<Option<AppRow>> {
+        let row = sqlx::query_as::<_, AppRow>(
+            "SELECT app_id, program, pk, vk, info, elf_hash FROM apps WHERE app_id = $1 \
+             AND ($2::TEXT IS NULL OR EXISTS (SELECT 1 FROM app_namespaces \
+             WHERE app_namespaces.app_id = apps.app_id AND namespace = $2))",
+        )
+        .bind(app_id)
+        .bind(namespace)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn insert_app(&self, row: &AppRow, info: &AppInfoRow, created_at: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO apps (app_id, program, pk, vk, info, elf_hash, created_at, \
+             app_name, description, owner, tags, toolchain_version) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+        )
+        .bind(&row.app_id)
+        .bind(&row.program)
+        .bind(&row.pk)
+        .bind(&row.vk)
+        .bind(&row.info)
+        .bind(&row.elf_hash)
+        .bind(created_at as i64)
+        .bind(&info.app_name)
+        .bind(&info.description)
+        .bind(&info.owner)
+        .bind(&info.tags)
+        .bind(&info.toolchain_version)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_app_namespace(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        info: Option<&str>,
+        app_info: &AppInfoRow,
+        created_at: u64,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            "INSERT INTO app_namespaces (namespace, app_id, created_at, info, app_name, \
+             description, owner, tags, toolchain_version) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(namespace)
+        .bind(app_id)
+        .bind(created_at as i64)
+        .bind(info)
+        .bind(&app_info.app_name)
+        .bind(&app_info.description)
+        .bind(&app_info.owner)
+        .bind(&app_info.tags)
+        .bind(&app_info.toolchain_version)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn delete_app(&self, namespace: &str, app_id: &str) -> Result<AppDeletion> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("DELETE FROM proofs WHERE namespace = $1 AND app_id = $2")
+            .bind(namespace)
+            .bind(app_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM tasks WHERE namespace = $1 AND app_id = $2")
+            .bind(namespace)
+            .bind(app_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM dead_letters WHERE namespace = $1 AND app_id = $2")
+            .bind(namespace)
+            .bind(app_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM app_versions WHERE namespace = $1 AND app_id = $2")
+            .bind(namespace)
+            .bind(app_id)
+            .execute(&mut *tx)
+            .await?;
+        let res = sqlx::query("DELETE FROM app_namespaces WHERE namespace = $1 AND app_id = $2")
+            .bind(namespace)
+            .bind(app_id)
+            .execute(&mut *tx)
+            .await?;
+        // the cached estimates and the analysis are shared by the namespaces
+        sqlx::query(
+            "DELETE FROM cost_estimates WHERE app_id = $1 \
+             AND NOT EXISTS (SELECT 1 FROM app_namespaces WHERE app_id = $1)",
+        )
+        .bind(app_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "DELETE FROM app_analyses WHERE app_id = $1 \
+             AND NOT EXISTS (SELECT 1 FROM app_namespaces WHERE app_id = $1)",
+        )
+        .bind(app_id)
+        .execute(&mut *tx)
+        .await?;
+        let removed: Option<(Option<String>,)> = sqlx::query_as(
+            "DELETE FROM apps WHERE app_id = $1 \
+             AND NOT EXISTS (SELECT 1 FROM app_namespaces WHERE app_id = $1) RETURNING elf_hash",
+        )
+        .bind(app_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(match removed {
+            _ if res.rows_affected() == 0 => AppDeletion::NotFound,
+            Some((elf_hash,)) => AppDeletion::Removed { elf_hash },
+            None => AppDeletion::Kept,
+        })
+    }
+
+    async fn get_app_meta(&self, namespace: &str, app_id: &str) -> Result<Option<AppMetaRow>> {
+        let row = sqlx::query_as::<_, AppMetaRow>(
+            "SELECT apps.app_id, apps.elf_hash, n.created_at, n.info, n.app_name, n.description, \
+             n.owner, n.tags, n.toolchain_version FROM apps \
+             JOIN app_namespaces n ON n.app_id = apps.app_id \
+             WHERE apps.app_id = $1 AND n.namespace = $2",
+        )
+        .bind(app_id)
+        .bind(namespace)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn find_app_by_elf_hash(&self, elf_hash: &str) -> Result<Option<String>> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT app_id FROM apps WHERE elf_hash = $1")
+            .bind(elf_hash)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        Ok(row.map(|(app_id,)| app_id))
+    }
+
+    async fn insert_app_analysis(&self, row: &AppAnalysisRow) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO app_analyses (app_id, text_size, data_size, bss_size, memory_image_size, \
+             entry, syscalls, baseline_cycles, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (app_id) DO UPDATE SET \
+             text_size = EXCLUDED.text_size, data_size = EXCLUDED.data_size, \
+             bss_size = EXCLUDED.bss_size, memory_image_size = EXCLUDED.memory_image_size, \
+             entry = EXCLUDED.entry, syscalls = EXCLUDED.syscalls, \
+             baseline_cycles = EXCLUDED.baseline_cycles, created_at = EXCLUDED.created_at",
+        )
+        .bind(&row.app_id)
+        .bind(row.text_size)
+        .bind(row.data_size)
+        .bind(row.bss_size)
+        .bind(row.memory_image_size)
+        .bind(row.entry)
+        .bind(&row.syscalls)
+        .bind(row.baseline_cycles)
+        .bind(row.created_at)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_app_analysis(
+        &self,
+        namespace: &str,
+        app_id: &str,
+    ) -> Result<Option<AppAnalysisRow>> {
+        let row = sqlx::query_as::<_, AppAnalysisRow>(
+            "SELECT app_id, text_size, data_size, bss_size, memory_image_size, entry, syscalls, \
+             baseline_cycles, created_at FROM app_analyses WHERE app_id = $1 \
+             AND EXISTS (SELECT 1 FROM app_namespaces \
+             WHERE app_namespaces.app_id = app_analyses.app_id AND namespace = $2)",
+        )
+        .bind(app_id)
+        .bind(namespace)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn recent_apps(&self, limit: usize) -> Result<Vec<(String, Option<String>)>> {
+        let rows = sqlx::query_as(
+            "SELECT apps.app_id, apps.elf_hash FROM apps \
+             JOIN tasks ON tasks.app_id = apps.app_id \
+             GROUP BY apps.app_id, apps.elf_hash ORDER BY MAX(tasks.created_at) DESC LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn list_app_metas(
+        &self,
+        filter: &AppFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AppMetaRow>> {
+        // the tags are matched as a whole in the comma-separated list
+        let rows = sqlx::query_as::<_, AppMetaRow>(
+            "SELECT app_id, elf_hash, created_at, info, app_name, description, owner, tags, \
+             toolchain_version FROM (\
+             SELECT apps.app_id, apps.elf_hash, n.created_at, n.info, n.app_name, n.description, \
+             n.owner, n.tags, n.toolchain_version FROM apps \
+             JOIN app_namespaces n ON n.app_id = apps.app_id WHERE n.namespace = $1 \
+             UNION ALL SELECT app_id, elf_hash, created_at, info, app_name, description, owner, \
+             tags, toolchain_version FROM apps WHERE $1::TEXT IS NULL) AS metas \
+             WHERE ($2::TEXT IS NULL OR owner = $2) AND ($3::TEXT IS NULL OR app_name = $3) \
+             AND ($4::TEXT IS NULL OR strpos(',' || tags || ',', ',' || $4 || ',') > 0) \
+             ORDER BY created_at, app_id LIMIT $5 OFFSET $6",
+        )
+        .bind(&filter.namespace)
+        .bind(&filter.owner)
+        .bind(&filter.name)
+        .bind(&filter.tag)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn insert_app_version(
+        &self,
+        namespace: &str,
+        name: &str,
+        app_id: &str,
+        created_at: u64,
+    ) -> Result<u32> {
+        // the next version is allocated in the same statement
+        let (version,): (i64,) = sqlx::query_as(
+            "INSERT INTO app_versions (namespace, name, version, app_id, created_at) \
+             SELECT $1, $2, COALESCE(MAX(version), 0) + 1, $3, $4 FROM app_versions \
+             WHERE namespace = $1 AND name = $2 \
+             RETURNING version",
+        )
+        .bind(namespace)
+        .bind(name)
+        .bind(app_id)
+        .bind(created_at as i64)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(version as u32)
+    }
+
+    async fn get_app_version(
+        &self,
+        namespace: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<Option<AppVersionRow>> {
+        let row = match version {
+            Some(version) => {
+                sqlx::query_as::<_, AppVersionRow>(
+                    "SELECT name, version, app_id, created_at FROM app_versions \
+                     WHERE namespace = $1 AND name = $2 AND version = $3",
+                )
+                .bind(namespace)
+                .bind(name)
+                .bind(version as i64)
+                .fetch_optional(&self.db_pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, AppVersionRow>(
+                    "SELECT name, version, app_id, created_at FROM app_versions \
+                     WHERE namespace = $1 AND name = $2 ORDER BY version DESC LIMIT 1",
+                )
+                .bind(namespace)
+                .bind(name)
+                .fetch_optional(&self.db_pool)
+                .await?
+            }
+        };
+
+        Ok(row)
+    }
+
+    async fn list_app_versions(&self, namespace: &str, name: &str) -> Result<Vec<AppVersionRow>> {
+        let rows = sqlx::query_as::<_, AppVersionRow>(
+            "SELECT name, version, app_id, created_at FROM app_versions \
+             WHERE namespace = $1 AND name = $2 ORDER BY version",
+        )
+        .bind(namespace)
+        .bind(name)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+#[async_trait]
+impl TaskRepository for PostgresStorage {
+    async fn insert_task(
+        &self,
+        key: &ProvingKey,
+        inputs: Option<&[u8]>,
+        inputs_hash: &str,
+        use_gpu: bool,
+        priority: TaskPriority,
+        created_at: u64,
+        callback_url: Option<&str>,
+        verify_proof: bool,
+        proof_type: ProofType,
+        depends_on: &[String],
+        not_before: Option<u64>,
+        deadline: Option<u64>,
+        allow_cpu_fallback: bool,
+        expected_pv_digest: Option<&[u8]>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO tasks \
+             (namespace, app_id, task_id, inputs, inputs_hash, use_gpu, priority, state, \
+             failure_reason, created_at, updated_at, callback_url, verify_proof, proof_type, \
+             depends_on, not_before, deadline, allow_cpu_fallback, expected_pv_digest) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NULL, $9, $10, $11, $12, $13, $14, $15, \
+             $16, $17, $18) \
+             ON CONFLICT (namespace, app_id, task_id) DO UPDATE SET inputs = EXCLUDED.inputs, \
+             inputs_hash = EXCLUDED.inputs_hash, \
+             use_gpu = EXCLUDED.use_gpu, priority = EXCLUDED.priority, state = EXCLUDED.state, \
+             failure_reason = NULL, \
+             created_at = EXCLUDED.created_at, updated_at = EXCLUDED.updated_at, \
+             callback_url = EXCLUDED.callback_url, verify_proof = EXCLUDED.verify_proof, \
+             proof_type = EXCLUDED.proof_type, depends_on = EXCLUDED.depends_on, \
+             not_before = EXCLUDED.not_before, deadline = EXCLUDED.deadline, \
+             allow_cpu_fallback = EXCLUDED.allow_cpu_fallback, \
+             expected_pv_digest = EXCLUDED.expected_pv_digest",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .bind(inputs)
+        .bind(inputs_hash)
+        .bind(use_gpu)
+        .bind(priority as i32)
+        .bind(TaskState::Queued as i32)
+        .bind(created_at as i64)
+        .bind(unix_timestamp() as i64)
+        .bind(callback_url)
+        .bind(verify_proof)
+        .bind(proof_type as i32)
+        .bind(join_depends_on(depends_on))
+        .bind(not_before.map(|not_before| not_before as i64))
+        .bind(deadline.map(|deadline| deadline as i64))
+        .bind(allow_cpu_fallback)
+        .bind(expected_pv_digest)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_task_state(
+        &self,
+        key: &ProvingKey,
+        state: TaskState,
+        failure_reason: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE tasks SET state = $1, failure_reason = $2, updated_at = $3, \
+             inputs = CASE WHEN $4 THEN NULL ELSE inputs END, \
+             started_at = CASE WHEN $5 THEN $3 ELSE started_at END, \
+             attempts = CASE WHEN $5 THEN attempts + 1 ELSE attempts END \
+             WHERE namespace = $6 AND app_id = $7 AND task_id = $8 \
+             AND state NOT IN ($9, $10, $11)",
+        )
+        .bind(state as i32)
+        .bind(failure_reason)
+        .bind(unix_timestamp() as i64)
+        .bind(is_finished(state))
+        .bind(state == TaskState::Executing)
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .bind(TaskState::Completed as i32)
+        .bind(TaskState::Failed as i32)
+        .bind(TaskState::Cancelled as i32)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn unfinished_tasks(&self) -> Result<Vec<TaskRow>> {
+        let rows = sqlx::query_as::<_, TaskRow>(
+            "SELECT namespace, app_id, task_id, inputs, use_gpu, priority, state, created_at, \
+             callback_url, verify_proof, proof_type, depends_on, not_before, deadline, \
+             allow_cpu_fallback, expected_pv_digest FROM tasks \
+             WHERE state IN ($1, $2, $3) \
+             ORDER BY created_at, namespace, app_id, task_id",
+        )
+        .bind(TaskState::Queued as i32)
+        .bind(TaskState::Executing as i32)
+        .bind(TaskState::Proving as i32)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn get_task(&self, key: &ProvingKey) -> Result<Option<TaskStatusRow>> {
+        let row = sqlx::query_as::<_, TaskStatusRow>(
+            "SELECT state, failure_reason, created_at, updated_at, started_at, attempts, timings, \
+             not_before, deadline FROM tasks WHERE namespace = $1 AND app_id = $2 AND task_id = $3",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn store_task_timings(&self, key: &ProvingKey, timings: &PhaseTimings) -> Result<()> {
+        sqlx::query(
+            "UPDATE tasks SET timings = $1 WHERE namespace = $2 AND app_id = $3 AND task_id = $4",
+        )
+        .bind(timings.encode_to_vec())
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_task_by_inputs(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        inputs_hash: &str,
+        proof_type: ProofType,
+    ) -> Result<Option<(String, TaskState)>> {
+        let row: Option<(String, i32)> = sqlx::query_as(
+            "SELECT task_id, state FROM tasks \
+             WHERE namespace = $1 AND app_id = $2 AND inputs_hash = $3 AND proof_type = $4 \
+             AND state NOT IN ($5, $6) \
+             ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(namespace)
+        .bind(app_id)
+        .bind(inputs_hash)
+        .bind(proof_type as i32)
+        .bind(TaskState::Failed as i32)
+        .bind(TaskState::Cancelled as i32)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.map(|(task_id, state)| (task_id, TaskState::try_from(state).unwrap_or_default())))
+    }
+
+    async fn task_fingerprint(
+        &self,
+        key: &ProvingKey,
+    ) -> Result<Option<(Option<String>, ProofType, TaskState)>> {
+        let row: Option<(Option<String>, i32, i32)> = sqlx::query_as(
+            "SELECT inputs_hash, proof_type, state FROM tasks \
+             WHERE namespace = $1 AND app_id = $2 AND task_id = $3",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.map(|(inputs_hash, proof_type, state)| {
+            (
+                inputs_hash,
+                ProofType::try_from(proof_type).unwrap_or_default(),
+                TaskState::try_from(state).unwrap_or_default(),
+            )
+        }))
+    }
+
+    async fn store_proof(
+        &self,
+        key: &ProvingKey,
+        proof: &[u8],
+        pv_digest: U256,
+        proof_type: ProofType,
+        provenance: &ProofProvenance,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO proofs (namespace, app_id, task_id, proof, pv_digest, proof_type, \
+             prover_version, service_version, elf_hash, inputs_hash, proving_seconds, signature, \
+             created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) \
+             ON CONFLICT (namespace, app_id, task_id) DO UPDATE SET proof = EXCLUDED.proof, \
+             pv_digest = EXCLUDED.pv_digest, created_at = EXCLUDED.created_at, \
+             proof_type = EXCLUDED.proof_type, prover_version = EXCLUDED.prover_version, \
+             service_version = EXCLUDED.service_version, elf_hash = EXCLUDED.elf_hash, \
+             inputs_hash = EXCLUDED.inputs_hash, proving_seconds = EXCLUDED.proving_seconds, \
+             signature = EXCLUDED.signature",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .bind(proof)
+        .bind(pv_digest.to_be_bytes_vec())
+        .bind(proof_type as i32)
+        .bind(&provenance.prover_version)
+        .bind(&provenance.service_version)
+        .bind(&provenance.elf_hash)
+        .bind(&provenance.inputs_hash)
+        .bind(provenance.proving_seconds)
+        .bind(provenance.signature.as_ref().map(Message::encode_to_vec))
+        .bind(provenance.created_at as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_proof(&self, key: &ProvingKey) -> Result<Option<ProofRow>> {
+        let row = sqlx::query_as::<_, ProofRow>(
+            "SELECT proof, pv_digest, created_at, proof_type, prover_version, service_version, \
+             elf_hash, inputs_hash, proving_seconds, signature FROM proofs \
+             WHERE namespace = $1 AND app_id = $2 AND task_id = $3 AND proof IS NOT NULL",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn has_proof(&self, key: &ProvingKey) -> Result<bool> {
+        let row: Option<(i32,)> = sqlx::query_as(
+            "SELECT 1 FROM proofs \
+             WHERE namespace = $1 AND app_id = $2 AND task_id = $3 AND proof IS NOT NULL",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn delete_proof(&self, key: &ProvingKey) -> Result<bool> {
+        let mut tx = self.db_pool.begin().await?;
+        let proofs =
+            sqlx::query("DELETE FROM proofs WHERE namespace = $1 AND app_id = $2 AND task_id = $3")
+                .bind(key.namespace())
+                .bind(key.app_id())
+                .bind(key.task_id())
+                .execute(&mut *tx)
+                .await?;
+        let tasks = sqlx::query(
+            "DELETE FROM tasks WHERE namespace = $1 AND app_id = $2 AND task_id = $3 \
+             AND state IN ($4, $5, $6)",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .bind(TaskState::Completed as i32)
+        .bind(TaskState::Failed as i32)
+        .bind(TaskState::Cancelled as i32)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(proofs.rows_affected() + tasks.rows_affected() > 0)
+    }
+
+    async fn get_cost_estimate(
+        &self,
+        app_id: &str,
+        inputs_hash: &str,
+    ) -> Result<Option<CostEstimateRow>> {
+        let row = sqlx::query_as::<_, CostEstimateRow>(
+            "SELECT cost, total_cycles, pv_digest FROM cost_estimates \
+             WHERE app_id = $1 AND inputs_hash = $2",
+        )
+        .bind(app_id)
+        .bind(inputs_hash)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn store_cost_estimate(
+        &self,
+        app_id: &str,
+        inputs_hash: &str,
+        row: &CostEstimateRow,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO cost_estimates \
+             (app_id, inputs_hash, cost, total_cycles, pv_digest, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (app_id, inputs_hash) DO UPDATE SET cost = EXCLUDED.cost, \
+             total_cycles = EXCLUDED.total_cycles, pv_digest = EXCLUDED.pv_digest, \
+             created_at = EXCLUDED.created_at",
+        )
+        .bind(app_id)
+        .bind(inputs_hash)
+        .bind(row.cost)
+        .bind(row.total_cycles)
+        .bind(&row.pv_digest)
+        .bind(unix_timestamp() as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn expired_tasks(
+        &self,
+        app_id: &str,
+        before: u64,
+        limit: usize,
+    ) -> Result<Vec<ProvingKey>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT namespace, task_id FROM proofs WHERE app_id = $1 AND created_at < $2 \
+             UNION SELECT namespace, task_id FROM tasks \
+             WHERE app_id = $1 AND state IN ($3, $4, $5) AND updated_at < $2 LIMIT $6",
+        )
+        .bind(app_id)
+        .bind(before as i64)
+        .bind(TaskState::Completed as i32)
+        .bind(TaskState::Failed as i32)
+        .bind(TaskState::Cancelled as i32)
+        .bind(limit as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(namespace, task_id)| ProvingKey::new(namespace, app_id.to_string(), task_id))
+            .collect())
+    }
+
+    async fn app_tasks(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ProvingKey>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT task_id FROM proofs WHERE namespace = $1 AND app_id = $2 \
+             UNION SELECT task_id FROM tasks WHERE namespace = $1 AND app_id = $2 \
+             UNION SELECT task_id FROM dead_letters WHERE namespace = $1 AND app_id = $2 \
+             ORDER BY task_id LIMIT $3 OFFSET $4",
+        )
+        .bind(namespace)
+        .bind(app_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(task_id,)| ProvingKey::new(namespace.to_string(), app_id.to_string(), task_id))
+            .collect())
+    }
+
+    async fn store_uploaded_inputs(
+        &self,
+        namespace: &str,
+        inputs_hash: &str,
+        inputs: Option<&[u8]>,
+        size: usize,
+        created_at: u64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO uploaded_inputs (namespace, inputs_hash, inputs, size, created_at) \
+             VALUES ($1, $2, $3, $4, $5) ON CONFLICT (namespace, inputs_hash) DO UPDATE SET \
+             inputs = EXCLUDED.inputs, size = EXCLUDED.size, created_at = EXCLUDED.created_at",
+        )
+        .bind(namespace)
+        .bind(inputs_hash)
+        .bind(inputs)
+        .bind(size as i64)
+        .bind(created_at as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_uploaded_inputs(
+        &self,
+        namespace: &str,
+        inputs_hash: &str,
+    ) -> Result<Option<UploadedInputsRow>> {
+        let row = sqlx::query_as::<_, UploadedInputsRow>(
+            "SELECT inputs, size FROM uploaded_inputs WHERE namespace = $1 AND inputs_hash = $2",
+        )
+        .bind(namespace)
+        .bind(inputs_hash)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn expired_uploaded_inputs(
+        &self,
+        before: u64,
+        limit: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query_as(
+            "SELECT namespace, inputs_hash FROM uploaded_inputs WHERE created_at < $1 LIMIT $2",
+        )
+        .bind(before as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn delete_uploaded_inputs(&self, namespace: &str, inputs_hash: &str) -> Result<bool> {
+        let res =
+            sqlx::query("DELETE FROM uploaded_inputs WHERE namespace = $1 AND inputs_hash = $2")
+                .bind(namespace)
+                .bind(inputs_hash)
+                .execute(&self.db_pool)
+                .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl TokenRepository for PostgresStorage {
+    async fn insert_token(&self, row: &TokenRow) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO tokens (token_id, subject, namespace, scopes, created_at, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(&row.token_id)
+        .bind(&row.subject)
+        .bind(&row.namespace)
+        .bind(&row.scopes)
+        .bind(row.created_at)
+        .bind(row.expires_at)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_token(&self, token_id: &str) -> Result<bool> {
+        let res = sqlx::query("UPDATE tokens SET revoked = TRUE WHERE token_id = $1")
+            .bind(token_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn revoked_tokens(&self, now: u64) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT token_id FROM tokens WHERE revoked AND expires_at > $1")
+                .bind(now as i64)
+                .fetch_all(&self.db_pool)
+                .await?;
+
+        Ok(rows.into_iter().map(|(token_id,)| token_id).collect())
+    }
+}
+
+#[async_trait]
+impl DeadLetterRepository for PostgresStorage {
+    async fn insert_dead_letter(
+        &self,
+        key: &ProvingKey,
+        failure_reason: &str,
+        attempts: u32,
+        failed_at: u64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO dead_letters \
+             (namespace, app_id, task_id, inputs, use_gpu, priority, callback_url, verify_proof, \
+             proof_type, depends_on, allow_cpu_fallback, expected_pv_digest, failure_reason, \
+             attempts, failed_at) \
+             SELECT namespace, app_id, task_id, inputs, use_gpu, priority, callback_url, \
+             verify_proof, proof_type, depends_on, allow_cpu_fallback, expected_pv_digest, \
+             $1, $2, $3 FROM tasks \
+             WHERE namespace = $4 AND app_id = $5 AND task_id = $6 \
+             AND state NOT IN ($7, $8, $9) \
+             ON CONFLICT (namespace, app_id, task_id) DO UPDATE SET inputs = EXCLUDED.inputs, \
+             use_gpu = EXCLUDED.use_gpu, priority = EXCLUDED.priority, \
+             callback_url = EXCLUDED.callback_url, verify_proof = EXCLUDED.verify_proof, \
+             proof_type = EXCLUDED.proof_type, depends_on = EXCLUDED.depends_on, \
+             allow_cpu_fallback = EXCLUDED.allow_cpu_fallback, \
+             expected_pv_digest = EXCLUDED.expected_pv_digest, \
+             failure_reason = EXCLUDED.failure_reason, \
+             attempts = EXCLUDED.attempts, failed_at = EXCLUDED.failed_at",
+        )
+        .bind(failure_reason)
+        .bind(attempts as i32)
+        .bind(failed_at as i64)
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .bind(TaskState::Completed as i32)
+        .bind(TaskState::Failed as i32)
+        .bind(TaskState::Cancelled as i32)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_dead_letters(
+        &self,
+        namespace: &str,
+        app_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<DeadLetterRow>> {
+        let rows = sqlx::query_as::<_, DeadLetterRow>(
+            "SELECT namespace, app_id, task_id, \
+             COALESCE(LENGTH(inputs), 0)::BIGINT AS inputs_size, use_gpu, priority, callback_url, \
+             verify_proof, proof_type, depends_on, allow_cpu_fallback, expected_pv_digest, \
+             failure_reason, attempts, failed_at \
+             FROM dead_letters \
+             WHERE namespace = $1 AND ($2::TEXT IS NULL OR app_id = $2) \
+             ORDER BY failed_at, app_id, task_id LIMIT $3 OFFSET $4",
+        )
+        .bind(namespace)
+        .bind(app_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn get_dead_letter(&self, key: &ProvingKey) -> Result<Option<DeadLetterRow>> {
+        let row = sqlx::query_as::<_, DeadLetterRow>(
+            "SELECT namespace, app_id, task_id, \
+             COALESCE(LENGTH(inputs), 0)::BIGINT AS inputs_size, use_gpu, priority, callback_url, \
+             verify_proof, proof_type, depends_on, allow_cpu_fallback, expected_pv_digest, \
+             failure_reason, attempts, failed_at \
+             FROM dead_letters \
+             WHERE namespace = $1 AND app_id = $2 AND task_id = $3",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    async fn dead_letter_inputs(&self, key: &ProvingKey) -> Result<Option<Vec<u8>>> {
+        let row: Option<(Option<Vec<u8>>,)> = sqlx::query_as(
+            "SELECT inputs FROM dead_letters \
+             WHERE namespace = $1 AND app_id = $2 AND task_id = $3",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.and_then(|(inputs,)| inputs))
+    }
+
+    async fn delete_dead_letter(&self, key: &ProvingKey) -> Result<bool> {
+        let res = sqlx::query(
+            "DELETE FROM dead_letters \
+             WHERE namespace = $1 AND app_id = $2 AND task_id = $3",
+        )
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl UsageRepository for PostgresStorage {
+    async fn insert_usage(&self, usage: &UsageRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO usage_records \
+             (namespace, app_id, task_id, cycles, chunks, gpu_seconds, stored_bytes, recorded_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(usage.key.namespace())
+        .bind(usage.key.app_id())
+        .bind(usage.key.task_id())
+        .bind(usage.cycles as i64)
+        .bind(usage.chunks as i64)
+        .bind(usage.gpu_seconds)
+        .bind(usage.stored_bytes as i64)
+        .bind(usage.recorded_at as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn app_usage(
+        &self,
+        namespace: &str,
+        app_id: Option<&str>,
+        signer: Option<&str>,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<AppUsageRow>> {
+        let rows = sqlx::query_as::<_, AppUsageRow>(
+            "SELECT app_id, COUNT(*) AS tasks, SUM(cycles)::BIGINT AS cycles, \
+             SUM(chunks)::BIGINT AS chunks, SUM(gpu_seconds) AS gpu_seconds, \
+             SUM(stored_bytes)::BIGINT AS stored_bytes \
+             FROM usage_records u \
+             WHERE namespace = $1 AND ($2::TEXT IS NULL OR app_id = $2) \
+             AND ($3::TEXT IS NULL OR EXISTS (SELECT 1 FROM signed_tasks s \
+             WHERE s.namespace = u.namespace AND s.app_id = u.app_id \
+             AND s.task_id = u.task_id AND s.signer = $3)) \
+             AND recorded_at >= $4 AND recorded_at < $5 \
+             GROUP BY app_id ORDER BY app_id",
+        )
+        .bind(namespace)
+        .bind(app_id)
+        .bind(signer)
+        .bind(start as i64)
+        .bind(end as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn stored_bytes(&self, namespace: &str, app_id: &str) -> Result<u64> {
+        let bytes: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(stored_bytes), 0)::BIGINT FROM ( \
+             SELECT MAX(u.stored_bytes) AS stored_bytes FROM usage_records u \
+             JOIN proofs p ON p.namespace = u.namespace AND p.app_id = u.app_id \
+             AND p.task_id = u.task_id \
+             WHERE u.namespace = $1 AND u.app_id = $2 GROUP BY u.task_id) AS task_bytes",
+        )
+        .bind(namespace)
+        .bind(app_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(bytes.max(0) as u64)
+    }
+
+    async fn insert_signed_task(
+        &self,
+        signer: &str,
+        nonce: u64,
+        key: &ProvingKey,
+        created_at: u64,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            "INSERT INTO signed_tasks (signer, nonce, namespace, app_id, task_id, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING",
+        )
+        .bind(signer)
+        .bind(nonce as i64)
+        .bind(key.namespace())
+        .bind(key.app_id())
+        .bind(key.task_id())
+        .bind(created_at as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn add_calibration(&self, sample: &CalibrationSample, updated_at: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO calibrations \
+             (backend, tasks, cycles, chunks, proving_seconds, device_seconds, updated_at) \
+             VALUES ($1, 1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (backend) DO UPDATE SET tasks = calibrations.tasks + 1, \
+             cycles = calibrations.cycles + excluded.cycles, \
+             chunks = calibrations.chunks + excluded.chunks, \
+             proving_seconds = calibrations.proving_seconds + excluded.proving_seconds, \
+             device_seconds = calibrations.device_seconds + excluded.device_seconds, \
+             updated_at = excluded.updated_at",
+        )
+        .bind(sample.backend.name())
+        .bind(sample.cycles as i64)
+        .bind(sample.chunks as i64)
+        .bind(sample.proving_seconds)
+        .bind(sample.device_seconds)
+        .bind(updated_at as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn calibrations(&self) -> Result<Vec<CalibrationRow>> {
+        let rows = sqlx::query_as::<_, CalibrationRow>(
+            "SELECT backend, tasks, cycles, chunks, proving_seconds, device_seconds \
+             FROM calibrations ORDER BY backend",
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.db_pool).await?;
+
+        Ok(())
+    }
+}
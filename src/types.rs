@@ -1,10 +1,70 @@
-use pico_vm::configs::{
-    config::StarkGenericConfig,
-    stark_config::{KoalaBearBn254Poseidon2, KoalaBearPoseidon2},
+use anyhow::Result;
+use pico_vm::{
+    compiler::riscv::program::Program,
+    configs::{
+        config::StarkGenericConfig,
+        stark_config::{KoalaBearBn254Poseidon2, KoalaBearPoseidon2},
+    },
+    emulator::stdin::{EmulatorStdin, EmulatorStdinBuilder},
 };
-use sqlx::{Pool, Sqlite};
+use serde::Serialize;
 
-pub type DbPool = Pool<Sqlite>;
 pub type SC = KoalaBearPoseidon2;
 pub type Val = <KoalaBearPoseidon2 as StarkGenericConfig>::Val;
 pub type EmbedSC = KoalaBearBn254Poseidon2;
+
+// names of the stark configs and the on-chain proof system reported in proof metadata
+pub const SC_NAME: &str = "KoalaBearPoseidon2";
+pub const EMBED_SC_NAME: &str = "KoalaBearBn254Poseidon2";
+pub const ONCHAIN_PROOF_SYSTEM: &str = "groth16";
+
+// version of the prover setting up the app keys, bump it with the pico-vm dependency so the keys
+// cached by the older prover are not loaded
+pub const PROVER_VERSION: &str = "pico-vm-1.1.8";
+
+// builder of the serialized inputs of a proving task, the values are written to the stdin in order
+// and read by the guest program in the same order
+pub struct InputsBuilder {
+    stdin: EmulatorStdinBuilder<Vec<u8>, SC>,
+}
+
+impl Default for InputsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputsBuilder {
+    pub fn new() -> Self {
+        Self {
+            stdin: EmulatorStdin::<Program, Vec<u8>>::new_builder::<SC>(),
+        }
+    }
+
+    // write a value serialized by bincode, it's read by `read_as` of the guest
+    pub fn write<T: Serialize>(&mut self, value: &T) -> &mut Self {
+        self.stdin.write(value);
+        self
+    }
+
+    // write the raw bytes, it's read by `read_vec` of the guest
+    pub fn write_slice(&mut self, bytes: &[u8]) -> &mut Self {
+        self.stdin.write_slice(bytes);
+        self
+    }
+
+    // serialized inputs of `ProveTaskRequest` and `EstimateCostRequest`
+    pub fn build(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(&self.stdin)?)
+    }
+}
+
+// serialize the values as the inputs of a proving task, each value is written to the stdin in order
+pub fn build_inputs<T: Serialize>(values: &[T]) -> Result<Vec<u8>> {
+    let mut builder = InputsBuilder::new();
+    for value in values {
+        builder.write(value);
+    }
+
+    builder.build()
+}
@@ -1,10 +1,90 @@
+use anyhow::{Result, bail};
 use clap::ValueEnum;
-use tonic::{Request, Status};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use tonic::{
+    Request, Status,
+    metadata::{AsciiMetadataValue, MetadataValue},
+    service::Interceptor,
+};
+use tracing::error;
+
+// metadata key of the API key
+pub const API_KEY_METADATA_KEY: &str = "x-api-key";
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum AuthMethod {
     None,
     Bearer,
+    ApiKey,
+    // bearer JWT with the per-app scopes
+    Jwt,
+}
+
+// API keys accepted by the server, they're looked up by the sha256 digests to not leak the keys by
+// the timing of comparison
+#[derive(Clone, Debug, Default)]
+pub struct ApiKeyStore {
+    digests: HashSet<[u8; 32]>,
+}
+
+impl ApiKeyStore {
+    // load the keys and the key file of one key per line, the empty lines and # comments are
+    // ignored
+    pub fn load(keys: &[String], file: Option<&Path>) -> Result<Self> {
+        let content = file
+            .map(fs::read_to_string)
+            .transpose()?
+            .unwrap_or_default();
+        let digests: HashSet<_> = keys
+            .iter()
+            .map(String::as_str)
+            .chain(content.lines())
+            .map(str::trim)
+            .filter(|key| !key.is_empty() && !key.starts_with('#'))
+            .map(|key| Sha256::digest(key).into())
+            .collect();
+        if digests.is_empty() {
+            bail!("no API key configured");
+        }
+
+        Ok(Self { digests })
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.digests
+            .contains(&<[u8; 32]>::from(Sha256::digest(key)))
+    }
+}
+
+// client-side interceptor injecting the API key in the metadata of every call
+#[derive(Clone, Debug)]
+pub struct ApiKeyInterceptor {
+    api_key: Option<AsciiMetadataValue>,
+}
+
+impl ApiKeyInterceptor {
+    // no key is injected if not set
+    pub fn new(api_key: Option<&str>) -> Result<Self> {
+        let api_key = api_key.map(str::parse).transpose()?;
+
+        Ok(Self { api_key })
+    }
+}
+
+impl Interceptor for ApiKeyInterceptor {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(api_key) = &self.api_key {
+            req.metadata_mut()
+                .insert(API_KEY_METADATA_KEY, api_key.clone());
+        }
+
+        Ok(req)
+    }
 }
 
 pub trait AuthConfig {
@@ -12,6 +92,11 @@ pub trait AuthConfig {
 
     fn bearer_token(&self) -> Option<&String>;
 
+    // accepted API keys of the server, the client sends the first one
+    fn api_keys(&self) -> &[String];
+
+    fn api_keys_file(&self) -> Option<&PathBuf>;
+
     fn validate_auth(&self) -> Result<(), String> {
         match self.auth_method() {
             AuthMethod::None => Ok(()),
@@ -22,15 +107,32 @@ pub trait AuthConfig {
                     Ok(())
                 }
             }
+            AuthMethod::ApiKey => self
+                .api_key_store()
+                .map(|_| ())
+                .map_err(|e| format!("Invalid API keys when auth_method is 'api-key': {e}")),
+            AuthMethod::Jwt => Ok(()),
         }
     }
 
+    fn api_key_store(&self) -> Result<ApiKeyStore> {
+        ApiKeyStore::load(self.api_keys(), self.api_keys_file().map(PathBuf::as_path))
+    }
+
     // Server-side authentication check.
     fn server_auth_interceptor(
         &self,
-    ) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone + Send + 'static {
+    ) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static {
         let auth_method = *self.auth_method();
         let bearer_token = self.bearer_token().cloned();
+        // all the calls are rejected if the keys fail to load
+        let api_keys = match auth_method {
+            AuthMethod::ApiKey => self
+                .api_key_store()
+                .inspect_err(|e| error!("[auth] failed to load API keys: {e}"))
+                .unwrap_or_default(),
+            _ => ApiKeyStore::default(),
+        };
 
         move |req: Request<()>| match auth_method {
             AuthMethod::None => Ok(req),
@@ -48,6 +150,16 @@ pub trait AuthConfig {
                     _ => Err(Status::unauthenticated("Invalid or missing auth token")),
                 }
             }
+            AuthMethod::ApiKey => match req
+                .metadata()
+                .get(API_KEY_METADATA_KEY)
+                .and_then(|key| key.to_str().ok())
+            {
+                Some(key) if api_keys.contains(key) => Ok(req),
+                _ => Err(Status::unauthenticated("Invalid or missing API key")),
+            },
+            // verified by the JWT authority of the service
+            AuthMethod::Jwt => Ok(req),
         }
     }
 
@@ -57,10 +169,12 @@ pub trait AuthConfig {
     ) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone + Send + 'static {
         let auth_method = *self.auth_method();
         let bearer_token = self.bearer_token().cloned();
+        let api_key = self.api_keys().first().cloned();
 
         move |mut req: Request<()>| match auth_method {
             AuthMethod::None => Ok(req),
-            AuthMethod::Bearer => {
+            // the JWT is sent as the bearer token
+            AuthMethod::Bearer | AuthMethod::Jwt => {
                 let token = bearer_token
                     .as_ref()
                     .ok_or_else(|| Status::unauthenticated("Bearer token not configured"))?;
@@ -71,6 +185,17 @@ pub trait AuthConfig {
                 req.metadata_mut().insert("authorization", header);
                 Ok(req)
             }
+            AuthMethod::ApiKey => {
+                let api_key = api_key
+                    .as_ref()
+                    .ok_or_else(|| Status::unauthenticated("API key not configured"))?;
+                let header: MetadataValue<_> = api_key
+                    .parse()
+                    .map_err(|_| Status::unauthenticated("Invalid API key format"))?;
+
+                req.metadata_mut().insert(API_KEY_METADATA_KEY, header);
+                Ok(req)
+            }
         }
     }
 }
@@ -86,6 +211,14 @@ macro_rules! impl_auth_config {
             fn bearer_token(&self) -> Option<&String> {
                 self.bearer_token.as_ref()
             }
+
+            fn api_keys(&self) -> &[String] {
+                &self.api_keys
+            }
+
+            fn api_keys_file(&self) -> Option<&std::path::PathBuf> {
+                self.api_keys_file.as_ref()
+            }
         }
     };
 }
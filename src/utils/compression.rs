@@ -0,0 +1,31 @@
+use crate::InputsCompression;
+use anyhow::{Result, bail};
+use std::io::Read;
+
+// compress the serialized inputs by zstd, the level 0 is the default level of zstd
+pub fn compress_inputs(inputs: &[u8], level: i32) -> Result<Vec<u8>> {
+    Ok(zstd::encode_all(inputs, level)?)
+}
+
+// decompress the inputs by their compression, it fails once the decompressed size exceeds the
+// limit instead of inflating a malicious frame in memory
+pub fn decompress_inputs(
+    compression: InputsCompression,
+    inputs: Vec<u8>,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    match compression {
+        InputsCompression::Uncompressed => Ok(inputs),
+        InputsCompression::Zstd => {
+            let mut decompressed = vec![];
+            zstd::Decoder::new(&inputs[..])?
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            if decompressed.len() > max_size {
+                bail!("decompressed inputs exceeded the limit of {max_size} bytes");
+            }
+
+            Ok(decompressed)
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use crate::{storage::SharedStorage, utils::time::unix_timestamp};
+use anyhow::{Result, bail};
+use dashmap::DashSet;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::{task::JoinHandle, time::sleep};
+use tonic::{Request, Status};
+use tracing::{info, warn};
+
+// the revoked tokens are synced from the database for the other replicas
+const REVOCATION_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+// actions granted by the scopes of a token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    // register the applications
+    Register,
+    // submit and cancel the proving tasks, and estimate the cost
+    Prove,
+    // query the applications, tasks and proofs
+    Read,
+    // deregister the applications and delete the proofs
+    Delete,
+    // claim and report the tasks as a remote worker
+    Work,
+    // issue and revoke the tokens, it grants all the actions
+    Admin,
+}
+
+impl Action {
+    const ALL: [Action; 6] = [
+        Action::Register,
+        Action::Prove,
+        Action::Read,
+        Action::Delete,
+        Action::Work,
+        Action::Admin,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Register => "register",
+            Action::Prove => "prove",
+            Action::Read => "read",
+            Action::Delete => "delete",
+            Action::Work => "work",
+            Action::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// claims of a token, the scopes are ACTION:APP_ID, the app ID is * or omitted for all the apps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    // token ID for revocation
+    pub jti: String,
+    pub iat: u64,
+    pub exp: u64,
+    pub scopes: Vec<String>,
+    // the only namespace accessible by the token if bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+impl Claims {
+    // check if the action is granted for the app, or for all the apps if not specified
+    pub fn allows(&self, action: Action, app_id: Option<&str>) -> bool {
+        self.scopes.iter().any(|scope| {
+            let (name, scope_app) = scope.split_once(':').unwrap_or((scope, "*"));
+            // admin of an app is rejected on issuing, it's never granted to all the actions
+            if name == Action::Admin.name() {
+                return scope_app == "*";
+            }
+            name == action.name()
+                && (scope_app == "*"
+                    || app_id.is_some_and(|app_id| app_key(app_id) == app_key(scope_app)))
+        })
+    }
+
+    // check if the action across the namespaces is granted, e.g. draining the queue or claiming
+    // the tasks of any namespace. it's never granted to the token bound to a namespace
+    pub fn allows_global(&self, action: Action) -> bool {
+        self.namespace.is_none() && self.allows(action, None)
+    }
+}
+
+// check the scopes to issue, the action must be known
+pub fn validate_scopes(scopes: &[String]) -> Result<()> {
+    if scopes.is_empty() {
+        bail!("no scope");
+    }
+    for scope in scopes {
+        let (name, app_id) = scope.split_once(':').unwrap_or((scope, "*"));
+        if !Action::ALL.iter().any(|action| action.name() == name) {
+            bail!("unknown action of scope {scope}");
+        }
+        if app_id.is_empty() {
+            bail!("empty app ID of scope {scope}");
+        }
+        // admin grants all the actions of all the apps, it can't be limited to an app
+        if name == Action::Admin.name() && app_id != "*" {
+            bail!("admin scope {scope} can't be bound to an app");
+        }
+    }
+
+    Ok(())
+}
+
+// signer and verifier of the HS256 tokens with the revoked token IDs
+#[derive(Clone)]
+pub struct JwtAuthority {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+    // IDs of the revoked tokens not expired yet
+    revoked: Arc<DashSet<String>>,
+}
+
+impl JwtAuthority {
+    pub fn new(secret: &[u8]) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+
+        Self {
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            validation,
+            revoked: Arc::new(DashSet::new()),
+        }
+    }
+
+    // sign a token of the subject with the scopes, it's bound to the namespace if set
+    pub fn issue(
+        &self,
+        subject: &str,
+        namespace: Option<String>,
+        scopes: Vec<String>,
+        ttl: Duration,
+    ) -> Result<(String, Claims)> {
+        validate_scopes(&scopes)?;
+        let iat = unix_timestamp();
+        let claims = Claims {
+            sub: subject.to_string(),
+            jti: hex::encode(rand::random::<[u8; 16]>()),
+            iat,
+            exp: iat + ttl.as_secs(),
+            scopes,
+            namespace,
+        };
+        let token =
+            jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)?;
+
+        Ok((token, claims))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims, Status> {
+        let claims = jsonwebtoken::decode::<Claims>(token, &self.decoding_key, &self.validation)
+            .map_err(|e| Status::unauthenticated(format!("Invalid token: {e}")))?
+            .claims;
+        if self.revoked.contains(&claims.jti) {
+            return Err(Status::unauthenticated("Token is revoked"));
+        }
+
+        Ok(claims)
+    }
+
+    pub fn revoke(&self, token_id: &str) {
+        self.revoked.insert(token_id.to_string());
+    }
+
+    // server-side interceptor verifying the bearer token, the claims are attached to the request
+    // for the scope checks of the handlers. the global action is required for all the calls if set
+    pub fn interceptor(
+        &self,
+        required: Option<Action>,
+    ) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static {
+        let authority = self.clone();
+
+        move |mut req: Request<()>| {
+            let token = req
+                .metadata()
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+            let claims = authority.verify(token)?;
+            if let Some(action) = required {
+                if !claims.allows_global(action) {
+                    return Err(Status::permission_denied(format!(
+                        "token of {} is not allowed to {action}",
+                        claims.sub
+                    )));
+                }
+            }
+            req.extensions_mut().insert(claims);
+
+            Ok(req)
+        }
+    }
+
+    // load the revoked tokens and keep them synced in background
+    pub async fn start_revocation_sync(&self, storage: SharedStorage) -> Result<JoinHandle<()>> {
+        self.sync_revoked(&storage).await?;
+        info!("[jwt] loaded {} revoked tokens", self.revoked.len());
+
+        let authority = self.clone();
+        Ok(tokio::spawn(async move {
+            loop {
+                sleep(REVOCATION_SYNC_INTERVAL).await;
+                if let Err(e) = authority.sync_revoked(&storage).await {
+                    warn!("[jwt] failed to sync revoked tokens: {e}");
+                }
+            }
+        }))
+    }
+
+    // the expired tokens are rejected by the expiration, so they're dropped from the revoked set
+    async fn sync_revoked(&self, storage: &SharedStorage) -> Result<()> {
+        let revoked = storage.revoked_tokens(unix_timestamp()).await?;
+        self.revoked.retain(|token_id| revoked.contains(token_id));
+        for token_id in revoked {
+            self.revoked.insert(token_id);
+        }
+
+        Ok(())
+    }
+}
+
+// the app ID may be given with or without the 0x prefix
+fn app_key(app_id: &str) -> &str {
+    app_id.strip_prefix("0x").unwrap_or(app_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    fn claims(scopes: &[&str]) -> Claims {
+        Claims {
+            sub: "test".to_string(),
+            jti: "jti".to_string(),
+            iat: 0,
+            exp: u64::MAX,
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            namespace: None,
+        }
+    }
+
+    fn scopes(scopes: &[&str]) -> Vec<String> {
+        scopes.iter().map(|scope| scope.to_string()).collect()
+    }
+
+    #[test]
+    fn test_allows_app_scope() {
+        let claims = claims(&["prove:0xabc", "read"]);
+        assert!(claims.allows(Action::Prove, Some("0xabc")));
+        // the app ID is matched with or without the 0x prefix
+        assert!(claims.allows(Action::Prove, Some("abc")));
+        assert!(!claims.allows(Action::Prove, Some("0xdef")));
+        // the app scope doesn't grant the action of all the apps
+        assert!(!claims.allows(Action::Prove, None));
+        assert!(claims.allows(Action::Read, Some("0xdef")));
+        assert!(claims.allows(Action::Read, None));
+        assert!(!claims.allows(Action::Delete, Some("0xabc")));
+    }
+
+    #[test]
+    fn test_allows_admin() {
+        let admin = claims(&["admin"]);
+        assert!(admin.allows(Action::Admin, None));
+        assert!(admin.allows(Action::Delete, Some("0xabc")));
+        assert!(claims(&["admin:*"]).allows(Action::Work, None));
+
+        // the admin of an app issued before the validation is not a global admin
+        let app_admin = claims(&["admin:0xabc"]);
+        assert!(!app_admin.allows(Action::Admin, None));
+        assert!(!app_admin.allows(Action::Delete, Some("0xabc")));
+        assert!(!app_admin.allows(Action::Prove, Some("0xdef")));
+    }
+
+    #[test]
+    fn test_allows_global() {
+        assert!(claims(&["admin"]).allows_global(Action::Admin));
+        assert!(claims(&["work"]).allows_global(Action::Work));
+        assert!(!claims(&["read"]).allows_global(Action::Work));
+
+        let mut bound = claims(&["admin", "work"]);
+        bound.namespace = Some("tenant".to_string());
+        // the actions of the namespace are still granted
+        assert!(bound.allows(Action::Admin, None));
+        assert!(bound.allows(Action::Prove, Some("0xabc")));
+        assert!(!bound.allows_global(Action::Admin));
+        assert!(!bound.allows_global(Action::Work));
+    }
+
+    #[test]
+    fn test_validate_scopes() {
+        assert!(validate_scopes(&scopes(&["prove:0xabc", "read", "admin"])).is_ok());
+        assert!(validate_scopes(&scopes(&["admin:*"])).is_ok());
+        assert!(validate_scopes(&[]).is_err());
+        assert!(validate_scopes(&scopes(&["mint"])).is_err());
+        assert!(validate_scopes(&scopes(&["prove:"])).is_err());
+        assert!(validate_scopes(&scopes(&["admin:0xabc"])).is_err());
+    }
+
+    #[test]
+    fn test_issue_and_verify() {
+        let authority = JwtAuthority::new(b"secret");
+        let (token, issued) = authority
+            .issue("test", None, scopes(&["read"]), Duration::from_secs(60))
+            .unwrap();
+        let verified = authority.verify(&token).unwrap();
+        assert_eq!(verified.jti, issued.jti);
+        assert_eq!(verified.scopes, issued.scopes);
+
+        // the token of another secret or a revoked one is rejected
+        assert!(JwtAuthority::new(b"other").verify(&token).is_err());
+        authority.revoke(&issued.jti);
+        assert!(authority.verify(&token).is_err());
+
+        assert!(
+            authority
+                .issue(
+                    "test",
+                    None,
+                    scopes(&["admin:0xabc"]),
+                    Duration::from_secs(60)
+                )
+                .is_err()
+        );
+    }
+
+    fn request(token: Option<&str>) -> Request<()> {
+        let mut req = Request::new(());
+        if let Some(token) = token {
+            let value = format!("Bearer {token}").parse().unwrap();
+            req.metadata_mut().insert("authorization", value);
+        }
+
+        req
+    }
+
+    #[test]
+    fn test_interceptor() {
+        let authority = JwtAuthority::new(b"secret");
+        let (token, issued) = authority
+            .issue("test", None, scopes(&["read"]), Duration::from_secs(60))
+            .unwrap();
+
+        // the claims are attached for the scope checks of the handlers
+        let interceptor = authority.interceptor(None);
+        let req = interceptor(request(Some(&token))).unwrap();
+        assert_eq!(req.extensions().get::<Claims>().unwrap().jti, issued.jti);
+
+        let status = interceptor(request(None)).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        let status = interceptor(request(Some("invalid"))).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // the global action is required for all the calls
+        let worker_interceptor = authority.interceptor(Some(Action::Work));
+        let status = worker_interceptor(request(Some(&token))).unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+}
@@ -1 +1,8 @@
 pub mod auth;
+pub mod compression;
+pub mod jwt;
+pub mod net;
+pub mod signing;
+pub mod telemetry;
+pub mod time;
+pub mod tls;
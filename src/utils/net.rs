@@ -0,0 +1,94 @@
+use std::net::IpAddr;
+use tokio::net::lookup_host;
+use url::{Host, Url};
+
+// the host of a URL fetched or posted by the service must not resolve to a loopback, link-local,
+// private or unspecified address unless allowed, so the clients can't reach the service's own
+// network
+pub async fn check_public_host(url: &Url, allowed_hosts: &[String]) -> Result<(), String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("URL {url} has no host"))?;
+    if allowed_hosts
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    {
+        return Ok(());
+    }
+
+    let addrs = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(80);
+            lookup_host((domain, port))
+                .await
+                .map_err(|e| format!("failed to resolve host {host}: {e}"))?
+                .map(|addr| addr.ip())
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    if addrs.is_empty() {
+        return Err(format!("host {host} has no address"));
+    }
+    if let Some(ip) = addrs.iter().find(|ip| !is_public(ip)) {
+        return Err(format!(
+            "host {host} resolves to the non-public address {ip}"
+        ));
+    }
+
+    Ok(())
+}
+
+// the loopback, link-local, private, shared (CGNAT), broadcast and unspecified addresses reach the
+// service's own network
+fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(&IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_public_host() {
+        let check = |url: &str| {
+            let url = Url::parse(url).unwrap();
+            async move { check_public_host(&url, &["callback.internal".to_string()]).await }
+        };
+        assert!(check("https://1.1.1.1/path").await.is_ok());
+        assert!(check("http://callback.internal/path").await.is_ok());
+        for url in [
+            "http://127.0.0.1/path",
+            "http://10.0.0.1/path",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/path",
+            "http://0.0.0.0/path",
+            "http://[::1]/path",
+            "http://[fd00::1]/path",
+            "http://[::ffff:192.168.0.1]/path",
+        ] {
+            assert!(check(url).await.is_err(), "{url}");
+        }
+    }
+}
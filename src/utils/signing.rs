@@ -0,0 +1,284 @@
+use crate::{
+    ProofEnvelope, ProofSignature, ProveTaskRequest, SignatureScheme, SignedProveTaskRequest,
+};
+use alloy_primitives::{Address, B256, Signature, keccak256};
+use anyhow::{Result, anyhow, bail};
+use ed25519_dalek::Signer;
+use prost::Message;
+
+// domain of the signed digest, so the signatures of the other messages are never accepted
+const SIGNING_DOMAIN: &[u8] = b"pico-proving-service/ProveTask/v1";
+
+// domain of the proof envelopes signed by the service
+const PROOF_SIGNING_DOMAIN: &[u8] = b"pico-proving-service/Proof/v1";
+
+// digest signed by the key, it binds the payload to the nonce and the expiry
+pub fn signing_digest(payload: &[u8], nonce: u64, expires_at: u64) -> B256 {
+    let mut message = Vec::with_capacity(SIGNING_DOMAIN.len() + 48);
+    message.extend_from_slice(SIGNING_DOMAIN);
+    message.extend_from_slice(keccak256(payload).as_slice());
+    message.extend_from_slice(&nonce.to_be_bytes());
+    message.extend_from_slice(&expires_at.to_be_bytes());
+
+    keccak256(message)
+}
+
+// digest of the proof envelope signed by the service key
+pub fn proof_digest(envelope: &ProofEnvelope) -> B256 {
+    let mut message = Vec::with_capacity(PROOF_SIGNING_DOMAIN.len() + 32);
+    message.extend_from_slice(PROOF_SIGNING_DOMAIN);
+    message.extend_from_slice(keccak256(envelope.encode_to_vec()).as_slice());
+
+    keccak256(message)
+}
+
+// verify the signature of the service over the envelope and return the signer, it must be the
+// signer of GetServiceKey to attribute the proof to the service
+pub fn verify_proof_signature(
+    envelope: &ProofEnvelope,
+    signature: &ProofSignature,
+) -> Result<String> {
+    let signer = recover_signer(
+        signature.scheme(),
+        proof_digest(envelope),
+        &signature.signature,
+        &signature.public_key,
+    )?;
+    if signer != signature.signer {
+        bail!("proof signed by {signer}, not {}", signature.signer);
+    }
+
+    Ok(signer)
+}
+
+// verify the signed request not expired at the timestamp and return its signer, the checksummed
+// address of a secp256k1 key or `ed25519:` with the hex of the public key
+pub fn verify_signer(req: &SignedProveTaskRequest, now: u64) -> Result<String> {
+    if req.expires_at <= now {
+        bail!("request expired at {}", req.expires_at);
+    }
+    let digest = signing_digest(&req.payload, req.nonce, req.expires_at);
+
+    recover_signer(req.scheme(), digest, &req.signature, &req.public_key)
+}
+
+fn recover_signer(
+    scheme: SignatureScheme,
+    digest: B256,
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<String> {
+    match scheme {
+        SignatureScheme::Secp256k1 => {
+            if !public_key.is_empty() {
+                bail!("public_key must not be set for secp256k1");
+            }
+            let signature = Signature::from_raw(signature)
+                .map_err(|e| anyhow!("invalid secp256k1 signature: {e}"))?;
+            let address = signature
+                .recover_address_from_prehash(&digest)
+                .map_err(|e| anyhow!("failed to recover the signer: {e}"))?;
+            Ok(address.to_checksum(None))
+        }
+        SignatureScheme::Ed25519 => {
+            let key = ed25519_dalek::VerifyingKey::try_from(public_key)
+                .map_err(|e| anyhow!("invalid ed25519 public key: {e}"))?;
+            let signature = ed25519_dalek::Signature::from_slice(signature)
+                .map_err(|e| anyhow!("invalid ed25519 signature: {e}"))?;
+            key.verify_strict(digest.as_slice(), &signature)
+                .map_err(|e| anyhow!("signature mismatch: {e}"))?;
+            Ok(format!("ed25519:{}", hex::encode(key.as_bytes())))
+        }
+    }
+}
+
+// private key signing the prove requests of the clients, or the proofs of the service
+pub enum RequestSigner {
+    Secp256k1(k256::ecdsa::SigningKey),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl RequestSigner {
+    // parse the hex of the 32 bytes private key, the 0x prefix is optional
+    pub fn from_hex(scheme: SignatureScheme, key: &str) -> Result<Self> {
+        let bytes = hex::decode(key.trim().trim_start_matches("0x"))?;
+        let signer = match scheme {
+            SignatureScheme::Secp256k1 => Self::Secp256k1(
+                k256::ecdsa::SigningKey::from_slice(&bytes)
+                    .map_err(|e| anyhow!("invalid secp256k1 key: {e}"))?,
+            ),
+            SignatureScheme::Ed25519 => {
+                let bytes: [u8; 32] = bytes
+                    .try_into()
+                    .map_err(|_| anyhow!("ed25519 key must be 32 bytes"))?;
+                Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(&bytes))
+            }
+        };
+
+        Ok(signer)
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            Self::Secp256k1(_) => SignatureScheme::Secp256k1,
+            Self::Ed25519(_) => SignatureScheme::Ed25519,
+        }
+    }
+
+    // SEC1 compressed secp256k1 public key, or the ed25519 public key
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            Self::Secp256k1(key) => key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+            Self::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+        }
+    }
+
+    // the signer of the key in the same form as `verify_signer`
+    pub fn signer(&self) -> String {
+        match self {
+            Self::Secp256k1(key) => Address::from_public_key(key.verifying_key()).to_checksum(None),
+            Self::Ed25519(key) => {
+                format!("ed25519:{}", hex::encode(key.verifying_key().as_bytes()))
+            }
+        }
+    }
+
+    pub fn sign(
+        &self,
+        req: &ProveTaskRequest,
+        nonce: u64,
+        expires_at: u64,
+    ) -> Result<SignedProveTaskRequest> {
+        let payload = req.encode_to_vec();
+        let (scheme, signature, public_key) =
+            self.sign_digest(signing_digest(&payload, nonce, expires_at))?;
+
+        Ok(SignedProveTaskRequest {
+            payload,
+            scheme: scheme.into(),
+            signature,
+            public_key,
+            nonce,
+            expires_at,
+        })
+    }
+
+    pub fn sign_proof(&self, envelope: &ProofEnvelope) -> Result<ProofSignature> {
+        let (scheme, signature, public_key) = self.sign_digest(proof_digest(envelope))?;
+
+        Ok(ProofSignature {
+            scheme: scheme.into(),
+            signature,
+            public_key,
+            signer: self.signer(),
+        })
+    }
+
+    // the scheme, the signature and the public key to be sent with it
+    fn sign_digest(&self, digest: B256) -> Result<(SignatureScheme, Vec<u8>, Vec<u8>)> {
+        let signed = match self {
+            Self::Secp256k1(key) => {
+                let (signature, recovery_id) = key
+                    .sign_prehash_recoverable(digest.as_slice())
+                    .map_err(|e| anyhow!("failed to sign: {e}"))?;
+                let signature = Signature::from((signature, recovery_id));
+                (
+                    SignatureScheme::Secp256k1,
+                    signature.as_bytes().to_vec(),
+                    vec![],
+                )
+            }
+            Self::Ed25519(key) => (
+                SignatureScheme::Ed25519,
+                key.sign(digest.as_slice()).to_bytes().to_vec(),
+                key.verifying_key().to_bytes().to_vec(),
+            ),
+        };
+
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+    fn signers() -> Vec<RequestSigner> {
+        [SignatureScheme::Secp256k1, SignatureScheme::Ed25519]
+            .into_iter()
+            .map(|scheme| RequestSigner::from_hex(scheme, KEY).unwrap())
+            .collect()
+    }
+
+    fn request() -> ProveTaskRequest {
+        ProveTaskRequest {
+            app_id: "app".to_string(),
+            task_id: "task".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        for signer in signers() {
+            let signed = signer.sign(&request(), 1, 100).unwrap();
+            assert_eq!(signed.scheme(), signer.scheme());
+            assert_eq!(verify_signer(&signed, 99).unwrap(), signer.signer());
+        }
+    }
+
+    #[test]
+    fn test_verify_expired() {
+        for signer in signers() {
+            let signed = signer.sign(&request(), 1, 100).unwrap();
+            assert!(verify_signer(&signed, 100).is_err());
+            assert!(verify_signer(&signed, 101).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_tampered() {
+        for signer in signers() {
+            let signed = signer.sign(&request(), 1, 100).unwrap();
+            // a tampered secp256k1 request recovers another signer
+            let mut tampered = signed.clone();
+            tampered.payload.push(0);
+            assert_ne!(verify_signer(&tampered, 99).ok(), Some(signer.signer()));
+            let mut tampered = signed.clone();
+            tampered.nonce += 1;
+            assert_ne!(verify_signer(&tampered, 99).ok(), Some(signer.signer()));
+            // the expiry is signed, so it can't be extended
+            let mut tampered = signed;
+            tampered.expires_at += 1;
+            assert_ne!(verify_signer(&tampered, 99).ok(), Some(signer.signer()));
+        }
+    }
+
+    #[test]
+    fn test_sign_proof() {
+        for signer in signers() {
+            let envelope = ProofEnvelope {
+                app_id: "app".to_string(),
+                task_id: "task".to_string(),
+                ..Default::default()
+            };
+            let signature = signer.sign_proof(&envelope).unwrap();
+            assert_eq!(
+                verify_proof_signature(&envelope, &signature).unwrap(),
+                signer.signer()
+            );
+
+            let tampered = ProofEnvelope {
+                task_id: "other".to_string(),
+                ..envelope
+            };
+            assert!(verify_proof_signature(&tampered, &signature).is_err());
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::client::REQUEST_ID_METADATA_KEY;
+use anyhow::{Result, bail};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::TracerProvider as _,
+};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use pico_vm::machine::logger::setup_logger;
+use std::{collections::HashMap, env};
+use tonic::{
+    Request, Status,
+    codegen::http,
+    metadata::{MetadataKey, MetadataMap, MetadataValue},
+    service::Interceptor,
+};
+use tracing::{Span, info_span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+// endpoint of the OTLP collector by gRPC, the traces are only exported if it's set
+const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+// format of the logs, text (default) or json
+const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+// setup the logger and the OTLP exporter of the spans if the endpoint is set, the returned
+// provider should be shut down before exit to flush the pending spans. the JSON logs carry the
+// fields of the current spans, e.g. the request ID and the task key
+pub fn setup_tracing(service_name: &'static str) -> Result<Option<SdkTracerProvider>> {
+    let json = match env::var(LOG_FORMAT_ENV).as_deref() {
+        Ok("json") => true,
+        Ok("text") | Err(_) => false,
+        Ok(format) => bail!("unknown {LOG_FORMAT_ENV} {format}, expected text or json"),
+    };
+    let endpoint = env::var(OTLP_ENDPOINT_ENV).ok();
+    if !json && endpoint.is_none() {
+        setup_logger();
+        return Ok(None);
+    }
+
+    let provider = match endpoint {
+        Some(endpoint) => {
+            let exporter = SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint)
+                .build()?;
+            let provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(Resource::builder().with_service_name(service_name).build())
+                .build();
+            global::set_text_map_propagator(TraceContextPropagator::new());
+            global::set_tracer_provider(provider.clone());
+            Some(provider)
+        }
+        None => None,
+    };
+    let otel = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name)));
+    let (text, json) = if json {
+        let layer = tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(true);
+        (None, Some(layer))
+    } else {
+        (Some(tracing_subscriber::fmt::layer()), None)
+    };
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(text)
+        .with(json)
+        .with(otel)
+        .try_init()?;
+
+    Ok(provider)
+}
+
+// span of a gRPC request with the request ID, it's the child of the trace context propagated by
+// the client if any
+pub fn request_span<B>(req: &http::Request<B>) -> Span {
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(req.headers()))
+    });
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let span = info_span!("grpc", method = req.uri().path(), request_id);
+    span.set_parent(parent);
+
+    span
+}
+
+// inject the trace context of the span into the metadata of a request
+pub fn inject_metadata(span: &Span, metadata: &mut MetadataMap) {
+    let cx = span.context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut MetadataInjector(metadata))
+    });
+}
+
+// trace context of the span to be carried by a message, e.g. a task claimed by a remote worker
+pub fn trace_context(span: &Span) -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    let cx = span.context();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&cx, &mut carrier));
+
+    carrier
+}
+
+// set the parent of the span by the trace context carried by a message
+pub fn set_remote_parent(span: &Span, carrier: &HashMap<String, String>) {
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(carrier));
+    span.set_parent(parent);
+}
+
+// client-side interceptor propagating the trace context of the current span
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceInterceptor;
+
+impl Interceptor for TraceInterceptor {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        inject_metadata(&Span::current(), req.metadata_mut());
+
+        Ok(req)
+    }
+}
+
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    // the invalid keys and values are skipped
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::try_from(&value),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{Result, bail};
+use clap::Args;
+use std::{fs, path::PathBuf};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+// certificates of the transport, the server presents its certificate to the clients, and the
+// clients present theirs to the server for mTLS
+#[derive(Debug, Args, Clone, Default)]
+pub struct TlsConfig {
+    #[clap(
+        long,
+        env = "TLS_CERT",
+        requires = "tls_key",
+        help = "PEM certificate (chain) of this end, TLS is enabled on the server if set"
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "TLS_KEY",
+        requires = "tls_cert",
+        help = "PEM private key of TLS_CERT"
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "TLS_CA",
+        help = "PEM CA certificate to verify the peer, the server requires the client certificates (mTLS) if set"
+    )]
+    pub tls_ca: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "TLS_DOMAIN",
+        help = "Domain name to verify the server certificate, default to the host of the address"
+    )]
+    pub tls_domain: Option<String>,
+}
+
+impl TlsConfig {
+    // none if the server certificate is not set
+    pub fn server_tls_config(&self) -> Result<Option<ServerTlsConfig>> {
+        let Some(identity) = self.identity()? else {
+            if self.tls_ca.is_some() {
+                bail!("TLS_CA requires TLS_CERT and TLS_KEY on the server");
+            }
+            return Ok(None);
+        };
+
+        let mut tls = ServerTlsConfig::new().identity(identity);
+        if let Some(ca) = self.ca_certificate()? {
+            tls = tls.client_ca_root(ca);
+        }
+
+        Ok(Some(tls))
+    }
+
+    // none if the CA is not set, the client certificate is presented for mTLS if set
+    pub fn client_tls_config(&self) -> Result<Option<ClientTlsConfig>> {
+        let identity = self.identity()?;
+        let Some(ca) = self.ca_certificate()? else {
+            if identity.is_some() {
+                bail!("TLS_CA is required to verify the server certificate");
+            }
+            return Ok(None);
+        };
+
+        let mut tls = ClientTlsConfig::new().ca_certificate(ca);
+        if let Some(identity) = identity {
+            tls = tls.identity(identity);
+        }
+        if let Some(domain) = &self.tls_domain {
+            tls = tls.domain_name(domain);
+        }
+
+        Ok(Some(tls))
+    }
+
+    fn identity(&self) -> Result<Option<Identity>> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => {
+                Ok(Some(Identity::from_pem(fs::read(cert)?, fs::read(key)?)))
+            }
+            (None, None) => Ok(None),
+            _ => bail!("TLS_CERT and TLS_KEY must be set together"),
+        }
+    }
+
+    fn ca_certificate(&self) -> Result<Option<Certificate>> {
+        let ca = self.tls_ca.as_ref().map(fs::read).transpose()?;
+
+        Ok(ca.map(Certificate::from_pem))
+    }
+}
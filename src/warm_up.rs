@@ -0,0 +1,165 @@
+use crate::{
+    ProofType, TaskPriority, WarmUpState, WarmUpStatus,
+    key_cache::KeyCache,
+    proving::{
+        backend::{ProveOptions, SharedProverBackend},
+        memory::MemoryBudget,
+    },
+    proving_queue::{ProvingKey, ProvingTask},
+    utils::time::unix_timestamp,
+};
+use anyhow::{Result, anyhow};
+use dashmap::{DashMap, mapref::entry::Entry};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info, info_span, warn};
+
+// task ID of the smoke proofs, they're not persisted or tracked by the task statuses
+const SMOKE_TASK_ID: &str = "warm-up-smoke";
+
+// buffered progress messages of a smoke proof, they're not subscribed
+const PROGRESS_CHANNEL_CAPACITY: usize = 16;
+
+// warm-ups of the registered apps, the program and keys are compiled and set up on registration,
+// so the warm-up loads them into the key cache and runs the smoke proof if requested. the statuses
+// are kept in memory by the namespace and the app
+pub struct WarmUps {
+    key_cache: Arc<KeyCache>,
+    prover_backend: SharedProverBackend,
+    prover_count: usize,
+    memory_budget: Option<MemoryBudget>,
+    statuses: DashMap<(String, String), WarmUpStatus>,
+}
+
+impl WarmUps {
+    pub fn new(
+        key_cache: Arc<KeyCache>,
+        prover_backend: SharedProverBackend,
+        prover_count: usize,
+        memory_budget: Option<MemoryBudget>,
+    ) -> Self {
+        Self {
+            key_cache,
+            prover_backend,
+            prover_count,
+            memory_budget,
+            statuses: DashMap::new(),
+        }
+    }
+
+    // the status of the last warm-up, cold if never warmed up
+    pub fn status(&self, namespace: &str, app_id: &str) -> WarmUpStatus {
+        self.statuses
+            .get(&status_key(namespace, app_id))
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    // warm up the app in background, no-op if it's warming up already
+    pub fn start(self: &Arc<Self>, namespace: &str, app_id: &str, smoke_inputs: Option<Vec<u8>>) {
+        let key = status_key(namespace, app_id);
+        match self.statuses.entry(key.clone()) {
+            Entry::Occupied(entry) if entry.get().state() == WarmUpState::Warming => {
+                info!("[warm-up] app {app_id} of namespace {namespace} is warming up already");
+                return;
+            }
+            entry => {
+                entry.insert(WarmUpStatus {
+                    state: WarmUpState::Warming.into(),
+                    started_at: unix_timestamp(),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let warm_ups = self.clone();
+        let span = info_span!("warm_up", namespace = &key.0, app_id = &key.1);
+        tokio::spawn(
+            async move {
+                let res = warm_ups.warm_up(&key.0, &key.1, smoke_inputs).await;
+                let mut status = warm_ups.statuses.entry(key.clone()).or_default();
+                status.finished_at = Some(unix_timestamp());
+                match res {
+                    Ok(smoke) => {
+                        info!("[warm-up] app {} of namespace {} is warm", key.1, key.0);
+                        status.set_state(WarmUpState::Warm);
+                        if let Some((cycles, secs)) = smoke {
+                            status.smoke_cycles = Some(cycles);
+                            status.smoke_proving_secs = Some(secs);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("[warm-up] failed to warm up app {}: {e}", key.1);
+                        status.set_state(WarmUpState::WarmUpFailed);
+                        status.failure_reason = Some(e.to_string());
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    // drop the status of a deregistered app
+    pub fn remove(&self, namespace: &str, app_id: &str) {
+        self.statuses.remove(&status_key(namespace, app_id));
+    }
+
+    // return the cycles and proving seconds of the smoke proof if run
+    async fn warm_up(
+        &self,
+        namespace: &str,
+        app_id: &str,
+        smoke_inputs: Option<Vec<u8>>,
+    ) -> Result<Option<(u64, f64)>> {
+        let keys = self
+            .key_cache
+            .get(namespace, app_id)
+            .await?
+            .ok_or_else(|| anyhow!("app {app_id} is deregistered"))?;
+        let Some(inputs) = smoke_inputs else {
+            return Ok(None);
+        };
+
+        info!("[warm-up] running the smoke proof of app {app_id}");
+        let key = ProvingKey::new(
+            namespace.to_string(),
+            app_id.to_string(),
+            SMOKE_TASK_ID.to_string(),
+        );
+        let task = ProvingTask::new(
+            key,
+            keys.program,
+            keys.pk,
+            keys.vk,
+            Some(inputs),
+            false,
+            false,
+            TaskPriority::default(),
+            None,
+            ProofType::Core,
+            CancellationToken::new(),
+            broadcast::channel(PROGRESS_CHANNEL_CAPACITY).0,
+            info_span!("smoke_proof"),
+        );
+        // no checkpoint since the smoke proof is not resumed
+        let opts = ProveOptions {
+            prover_count: self.prover_count,
+            gpu_devices: &[],
+            checkpoint_store: None,
+            memory_budget: self.memory_budget,
+            preempt_token: None,
+        };
+        let info = self
+            .prover_backend
+            .prove(task, opts, Box::new(|| {}))
+            .await?;
+
+        Ok(Some((info.total_cycles, info.proving_seconds)))
+    }
+}
+
+fn status_key(namespace: &str, app_id: &str) -> (String, String) {
+    let app_id = app_id.strip_prefix("0x").unwrap_or(app_id);
+    (namespace.to_string(), app_id.to_string())
+}
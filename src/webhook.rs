@@ -0,0 +1,256 @@
+use crate::{
+    proving_queue::ProvingKey,
+    retry::RetryPolicy,
+    utils::{net::check_public_host, time::unix_timestamp},
+};
+use alloy_primitives::U256;
+use clap::Args;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::{sync::Arc, time::Duration};
+use tokio::time::sleep;
+use tracing::{info, warn};
+use url::Url;
+
+// headers of the callback requests, the signature is HMAC-SHA256 of "{timestamp}.{body}"
+pub const TIMESTAMP_HEADER: &str = "x-pico-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-pico-signature";
+
+// maximum backoff between the delivery attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Args, Clone)]
+pub struct WebhookConfig {
+    #[clap(
+        long,
+        env = "WEBHOOK_SECRET",
+        help = "Secret to sign the task callbacks by HMAC-SHA256, unsigned if not set"
+    )]
+    pub webhook_secret: Option<String>,
+
+    #[clap(
+        long,
+        env = "WEBHOOK_MAX_ATTEMPTS",
+        default_value = "5",
+        help = "Maximum attempts to deliver a task callback"
+    )]
+    pub webhook_max_attempts: u32,
+
+    #[clap(
+        long,
+        env = "WEBHOOK_BACKOFF_SECS",
+        default_value = "2",
+        help = "Initial backoff (seconds) between the callback attempts, doubled for each attempt"
+    )]
+    pub webhook_backoff_secs: u64,
+
+    #[clap(
+        long,
+        env = "WEBHOOK_TIMEOUT_SECS",
+        default_value = "10",
+        help = "Timeout (seconds) of a callback request"
+    )]
+    pub webhook_timeout_secs: u64,
+
+    #[clap(
+        long,
+        env = "WEBHOOK_ALLOWED_HOSTS",
+        value_delimiter = ',',
+        help = "Hosts of the callback URLs allowed to resolve to the loopback, link-local or private addresses, separated by comma"
+    )]
+    pub webhook_allowed_hosts: Vec<String>,
+}
+
+// final status of a task notified by the callback
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallbackStatus {
+    Completed,
+    Failed,
+}
+
+// JSON payload of the callback of a finished task
+#[derive(Clone, Debug, Serialize)]
+pub struct TaskCallback {
+    pub namespace: String,
+    pub app_id: String,
+    pub task_id: String,
+    pub status: CallbackStatus,
+    // URI of the proof in the artifact store, the proof is fetched by GetProof if none
+    pub proof_uri: Option<String>,
+    // hex of the public values digest if completed
+    pub pv_digest: Option<String>,
+    pub failure_reason: Option<String>,
+    pub finished_at: u64,
+}
+
+impl TaskCallback {
+    pub fn completed(key: &ProvingKey, proof_uri: Option<String>, pv_digest: U256) -> Self {
+        Self {
+            pv_digest: Some(format!("0x{}", hex::encode(pv_digest.to_be_bytes_vec()))),
+            proof_uri,
+            ..Self::new(key, CallbackStatus::Completed)
+        }
+    }
+
+    pub fn failed(key: &ProvingKey, failure_reason: &str) -> Self {
+        Self {
+            failure_reason: Some(failure_reason.to_string()),
+            ..Self::new(key, CallbackStatus::Failed)
+        }
+    }
+
+    fn new(key: &ProvingKey, status: CallbackStatus) -> Self {
+        Self {
+            namespace: key.namespace().to_string(),
+            app_id: key.app_id().to_string(),
+            task_id: key.task_id().to_string(),
+            status,
+            proof_uri: None,
+            pv_digest: None,
+            failure_reason: None,
+            finished_at: unix_timestamp(),
+        }
+    }
+}
+
+// deliver the callbacks of the finished tasks in the background with retries
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    allowed_hosts: Arc<[String]>,
+    secret: Option<Arc<[u8]>>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+}
+
+impl WebhookNotifier {
+    pub fn new(cfg: &WebhookConfig) -> Self {
+        // the redirects are not followed, since the redirected URL is not checked
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("the webhook client is built without TLS customization");
+
+        Self {
+            client,
+            allowed_hosts: cfg.webhook_allowed_hosts.clone().into(),
+            secret: cfg
+                .webhook_secret
+                .as_ref()
+                .map(|secret| Arc::from(secret.as_bytes())),
+            timeout: Duration::from_secs(cfg.webhook_timeout_secs),
+            retry_policy: RetryPolicy::new(
+                cfg.webhook_max_attempts,
+                Duration::from_secs(cfg.webhook_backoff_secs),
+                MAX_BACKOFF,
+                &[],
+            ),
+        }
+    }
+
+    // post the callback to the URL, the failure is only logged after the last attempt
+    pub fn notify(&self, url: String, callback: TaskCallback) {
+        let body = match serde_json::to_vec(&callback) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!(
+                    "[webhook] failed to encode callback of task {}: {e}",
+                    callback.task_id
+                );
+                return;
+            }
+        };
+        let client = self.client.clone();
+        let allowed_hosts = self.allowed_hosts.clone();
+        let secret = self.secret.clone();
+        let timeout = self.timeout;
+        let retry_policy = self.retry_policy.clone();
+        tokio::spawn(async move {
+            for attempt in 1..=retry_policy.max_attempts {
+                // the host is resolved again since its addresses may change after the submission
+                if let Err(e) = check_callback_url(&url, &allowed_hosts).await {
+                    warn!(
+                        "[webhook] dropped callback of task {} to {url}: {e}",
+                        callback.task_id
+                    );
+                    return;
+                }
+                let timestamp = unix_timestamp().to_string();
+                let mut req = client
+                    .post(&url)
+                    .timeout(timeout)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(TIMESTAMP_HEADER, &timestamp);
+                if let Some(secret) = &secret {
+                    req = req.header(SIGNATURE_HEADER, sign(secret, &timestamp, &body));
+                }
+                let error = match req.body(body.clone()).send().await {
+                    Ok(resp) if resp.status().is_success() => {
+                        info!(
+                            "[webhook] delivered callback of task {} to {url}",
+                            callback.task_id
+                        );
+                        return;
+                    }
+                    Ok(resp) => format!("status {}", resp.status()),
+                    Err(e) => e.to_string(),
+                };
+                warn!(
+                    "[webhook] failed to deliver callback of task {} to {url}, attempt {attempt}/{}: {error}",
+                    callback.task_id, retry_policy.max_attempts,
+                );
+                if attempt < retry_policy.max_attempts {
+                    sleep(retry_policy.backoff(attempt)).await;
+                }
+            }
+        });
+    }
+}
+
+// signature of the callback as "sha256=HEX"
+fn sign(secret: &[u8], timestamp: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts the keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// the callback URL must be an absolute HTTP or HTTPS URL, and its host must not resolve to a
+// loopback, link-local, private or unspecified address unless the host is allowed
+pub async fn check_callback_url(url: &str, allowed_hosts: &[String]) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| format!("invalid callback URL: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "unsupported scheme {} of callback URL, expected http or https",
+            url.scheme()
+        ));
+    }
+    check_public_host(&url, allowed_hosts)
+        .await
+        .map_err(|e| format!("invalid callback URL: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_callback_url() {
+        assert!(
+            check_callback_url("https://1.1.1.1/hook", &[])
+                .await
+                .is_ok()
+        );
+        assert!(check_callback_url("ftp://1.1.1.1/hook", &[]).await.is_err());
+        assert!(check_callback_url("not a url", &[]).await.is_err());
+        assert!(
+            check_callback_url("http://127.0.0.1/hook", &[])
+                .await
+                .is_err()
+        );
+    }
+}